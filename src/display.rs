@@ -1,11 +1,50 @@
 use owo_colors::OwoColorize;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// `--quiet`: only headers, warnings, errors, and summaries are printed.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `--yes`: answer every confirmation prompt with yes.
+pub fn set_assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+/// Ask a yes/no question before a destructive step. `--yes` confirms without
+/// asking; without it, a non-terminal stdin is an error rather than a silent
+/// yes or no, so unattended runs must opt in explicitly.
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        print_info(&format!("{question} yes (--yes)"));
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("{question} — stdin is not a terminal; pass --yes to confirm");
+    }
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
 
 pub fn print_header(text: &str) {
     println!("{}", text.bold());
 }
 
 pub fn print_success(text: &str) {
-    println!("{} {}", "✓".green().bold(), text);
+    if !is_quiet() {
+        print_summary_success(text);
+    }
 }
 
 pub fn print_error(text: &str) {
@@ -17,5 +56,17 @@ pub fn print_warning(text: &str) {
 }
 
 pub fn print_info(text: &str) {
+    if !is_quiet() {
+        print_summary(text);
+    }
+}
+
+/// Like `print_info`, but still printed in quiet mode (end-of-run totals).
+pub fn print_summary(text: &str) {
     println!("{} {}", "·".dimmed(), text);
 }
+
+/// Like `print_success`, but still printed in quiet mode (end-of-run totals).
+pub fn print_summary_success(text: &str) {
+    println!("{} {}", "✓".green().bold(), text);
+}
@@ -10,7 +10,9 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, Instant};
 
+use crate::display;
 use crate::notify::{self, Notifier};
+use crate::progress;
 
 // MARK: - Parallel download types
 
@@ -57,17 +59,122 @@ impl DiskSpaceGate {
     }
 }
 
+/// `--pause-on-battery`: blocks while the Mac runs on battery below `below_pct`.
+pub struct BatteryGate {
+    below_pct: u8,
+    notifier: Option<std::sync::Arc<Notifier>>,
+}
+
+impl BatteryGate {
+    pub fn new(below_pct: u8, notifier: Option<std::sync::Arc<Notifier>>) -> Self {
+        Self {
+            below_pct,
+            notifier,
+        }
+    }
+
+    /// Block until on AC power or charged to `below_pct`. Polls every 60s.
+    pub fn wait(&self, part: usize) {
+        let mut paused = false;
+        loop {
+            match battery_status() {
+                Some(status) if status.on_battery && status.percent < self.below_pct => {
+                    if !paused {
+                        paused = true;
+                        let msg = format!(
+                            "On battery at {}% — pausing before part {part} until plugged in",
+                            status.percent
+                        );
+                        display::print_warning(&format!("  [{part:02}] {msg}"));
+                        notify::notify(self.notifier.as_deref(), &format!("photoferry: {msg}"));
+                    }
+                    std::thread::sleep(Duration::from_secs(60));
+                }
+                // On AC, charged, or can't tell (no battery) — proceed
+                _ => {
+                    if paused {
+                        display::print_info(&format!("  [{part:02}] Power OK — resuming"));
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct BatteryStatus {
+    on_battery: bool,
+    percent: u8,
+}
+
+/// Power source via `pmset -g batt`; None without a battery or on failure.
+fn battery_status() -> Option<BatteryStatus> {
+    let output = Command::new("pmset")
+        .args(["-g", "batt"])
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    parse_pmset_batt(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `pmset -g batt`:
+/// `Now drawing from 'Battery Power'` then ` -InternalBattery-0 (id=…)\t85%; discharging; …`.
+fn parse_pmset_batt(output: &str) -> Option<BatteryStatus> {
+    let on_battery = output.lines().next()?.contains("'Battery Power'");
+    let percent = output.lines().skip(1).find_map(|line| {
+        let (before, _) = line.split_once('%')?;
+        let digits = before.rsplit(|c: char| !c.is_ascii_digit()).next()?;
+        digits.parse().ok()
+    })?;
+    Some(BatteryStatus {
+        on_battery,
+        percent,
+    })
+}
+
 /// Returns available disk space in GB for the filesystem containing `path`.
-/// Uses `df -k` — returns None if the command fails or output is unparseable.
+/// Uses `df -k -P` — returns None if the command fails or output is unparseable.
 pub fn available_space_gb(path: &Path) -> Option<u64> {
-    let output = Command::new("df").arg("-k").arg(path).output().ok()?;
+    let output = Command::new("df")
+        .args(["-k", "-P"])
+        .arg(path)
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let data_line = stdout.lines().nth(1)?;
-    let fields: Vec<&str> = data_line.split_whitespace().collect();
-    let avail_kb: u64 = fields.get(3)?.parse().ok()?;
+    let avail_kb = parse_df_available_kb(&stdout)?;
     Some(avail_kb / (1024 * 1024))
 }
 
+/// Parse the "Available" KB value from single-filesystem `df -k` output.
+///
+/// The column is located by its header position relative to the first size
+/// column, so filesystem names containing spaces and rows wrapped onto a
+/// second line (non-POSIX `df` with long device names) still parse.
+fn parse_df_available_kb(output: &str) -> Option<u64> {
+    let mut lines = output.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<&str> = lines.next()?.split_whitespace().collect();
+    // Header: Filesystem <size> <used> <avail> ... — offset of avail from the size column.
+    let avail_offset = header
+        .iter()
+        .position(|h| h.to_ascii_lowercase().starts_with("avail"))
+        .and_then(|i| i.checked_sub(1))
+        .unwrap_or(2);
+
+    // Join the remaining lines: a wrapped row puts the device name on its own line.
+    let data = lines.collect::<Vec<_>>().join(" ");
+    let fields: Vec<&str> = data.split_whitespace().collect();
+    // The device name may contain spaces; the numeric columns start at the
+    // first run of three consecutive integers (size, used, avail).
+    let first_numeric = (0..fields.len()).find(|&i| {
+        fields
+            .get(i..i + 3)
+            .is_some_and(|w| w.iter().all(|f| f.parse::<u64>().is_ok()))
+    })?;
+    fields.get(first_numeric + avail_offset)?.parse().ok()
+}
+
 const COOKIES_SALT: &[u8] = b"saltysalt";
 const COOKIES_ITERATIONS: u32 = 1003;
 const COOKIES_KEY_LEN: usize = 16;
@@ -83,6 +190,16 @@ pub struct DownloadProgress {
     /// Download attempts per part (Google allows max 5 per export).
     #[serde(default)]
     pub attempts: HashMap<usize, usize>,
+    /// Hex SHA-1 of each part's completed HTTP download, checked before
+    /// trusting a zip already on disk.
+    #[serde(default)]
+    pub sha1: HashMap<usize, String>,
+    /// Download or import failures per part, across runs.
+    #[serde(default)]
+    pub failure_counts: HashMap<usize, u32>,
+    /// Parts that hit `--max-part-retries`; skipped unless `--force`.
+    #[serde(default)]
+    pub given_up: Vec<usize>,
 }
 
 impl DownloadProgress {
@@ -101,6 +218,9 @@ impl DownloadProgress {
                 completed: Vec::new(),
                 failed: Vec::new(),
                 attempts: HashMap::new(),
+                sha1: HashMap::new(),
+                failure_counts: HashMap::new(),
+                given_up: Vec::new(),
             }),
             Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
         }
@@ -117,14 +237,29 @@ impl DownloadProgress {
             self.completed.push(i);
         }
         self.failed.retain(|&x| x != i);
+        self.given_up.retain(|&x| x != i);
+        self.failure_counts.remove(&i);
         let _ = self.save(dir);
     }
 
-    pub fn mark_failed(&mut self, i: usize, dir: &Path) {
+    /// Record a failure of part `i`. Returns true if this was failure number
+    /// `max_failures`, after which the part is given up.
+    pub fn mark_failed(&mut self, i: usize, max_failures: u32, dir: &Path) -> bool {
         if !self.failed.contains(&i) {
             self.failed.push(i);
         }
+        let count = self.failure_counts.entry(i).or_insert(0);
+        *count += 1;
+        let newly_given_up = *count >= max_failures && !self.given_up.contains(&i);
+        if newly_given_up {
+            self.given_up.push(i);
+        }
         let _ = self.save(dir);
+        newly_given_up
+    }
+
+    pub fn is_given_up(&self, i: usize) -> bool {
+        self.given_up.contains(&i)
     }
 
     pub fn is_completed(&self, i: usize) -> bool {
@@ -140,6 +275,32 @@ impl DownloadProgress {
         result
     }
 
+    pub fn record_sha1(&mut self, i: usize, sha1: &str, dir: &Path) {
+        self.sha1.insert(i, sha1.to_string());
+        let _ = self.save(dir);
+    }
+
+    /// Parts in `start..=end` recorded as neither completed nor failed.
+    pub fn unaccounted_parts(&self, start: usize, end: usize) -> Vec<usize> {
+        (start..=end)
+            .filter(|i| !self.completed.contains(i) && !self.failed.contains(i))
+            .collect()
+    }
+
+    /// Failed parts outside `start..=end`, which a run over that range never
+    /// retries (e.g. after narrowing `--start`/`--end` on resume).
+    pub fn failed_outside(&self, start: usize, end: usize) -> Vec<usize> {
+        let mut parts: Vec<usize> = self
+            .failed
+            .iter()
+            .copied()
+            .filter(|i| !(start..=end).contains(i) && !self.completed.contains(i))
+            .collect();
+        parts.sort_unstable();
+        parts.dedup();
+        parts
+    }
+
     /// How many of Google's 5 download attempts remain for part `i`.
     pub fn attempts_remaining(&self, i: usize) -> usize {
         5usize.saturating_sub(*self.attempts.get(&i).unwrap_or(&0))
@@ -163,6 +324,18 @@ fn progress_path(dir: &Path, job_id: &str) -> PathBuf {
 
 /// Extract Google cookies from Chrome on macOS using Keychain AES key.
 pub fn get_chrome_cookies() -> Result<HashMap<String, String>> {
+    with_chrome_cookies_db(read_cookies)
+}
+
+/// Every Google cookie row Chrome has, without values (`photoferry cookies`).
+pub fn chrome_cookie_report() -> Result<Vec<CookieInfo>> {
+    let rows = with_chrome_cookies_db(read_cookie_rows)?;
+    Ok(rows.into_iter().map(|(info, _)| info).collect())
+}
+
+fn with_chrome_cookies_db<T>(
+    read: impl FnOnce(&Path, &[u8; COOKIES_KEY_LEN]) -> Result<T>,
+) -> Result<T> {
     let key = derive_aes_key()?;
     let cookies_db = find_chrome_cookies_db()?;
 
@@ -170,11 +343,48 @@ pub fn get_chrome_cookies() -> Result<HashMap<String, String>> {
     let tmp = std::env::temp_dir().join("photoferry-cookies-tmp.db");
     std::fs::copy(&cookies_db, &tmp).context("Failed to copy Chrome cookies DB")?;
 
-    let result = read_cookies(&tmp, &key);
+    let result = read(&tmp, &key);
     let _ = std::fs::remove_file(&tmp);
     result
 }
 
+/// A Google cookie's name and host, and whether HTTP downloads can use it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieInfo {
+    pub name: String,
+    pub host_key: String,
+    pub status: CookieStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieStatus {
+    Usable,
+    Expired,
+    /// Empty, or failed to decrypt with the Keychain key
+    Unreadable,
+}
+
+/// Cookies Google wants before it serves a Takeout download instead of a
+/// sign-in page. `__Secure-*` stands for any of the `__Secure-` variants.
+pub const AUTH_COOKIES: &[&str] = &["SID", "SSID", "HSID", "SAPISID", "__Secure-*"];
+
+/// The [`AUTH_COOKIES`] missing from the usable cookie names.
+pub fn missing_auth_cookies<'a>(
+    usable: impl IntoIterator<Item = &'a str> + Clone,
+) -> Vec<&'static str> {
+    AUTH_COOKIES
+        .iter()
+        .copied()
+        .filter(|required| {
+            let mut names = usable.clone().into_iter();
+            match required.strip_suffix('*') {
+                Some(prefix) => !names.any(|n| n.starts_with(prefix)),
+                None => !names.any(|n| n == *required),
+            }
+        })
+        .collect()
+}
+
 fn derive_aes_key() -> Result<[u8; COOKIES_KEY_LEN]> {
     let output = Command::new("security")
         .args(["find-generic-password", "-s", "Chrome Safe Storage", "-w"])
@@ -223,7 +433,36 @@ fn find_chrome_cookies_db() -> Result<PathBuf> {
     )
 }
 
+/// Seconds between the Windows epoch (1601-01-01), which Chrome's
+/// `expires_utc` counts from, and the Unix epoch.
+const CHROME_EPOCH_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// Current time as Chrome stores it: microseconds since 1601-01-01.
+fn chrome_now_utc() -> i64 {
+    let unix_micros = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or(0);
+    unix_micros + CHROME_EPOCH_OFFSET_SECS * 1_000_000
+}
+
+/// `expires_utc` of 0 marks a session cookie, which never expires on its own.
+fn cookie_expired(expires_utc: i64, now_utc: i64) -> bool {
+    expires_utc != 0 && expires_utc <= now_utc
+}
+
 fn read_cookies(db_path: &Path, key: &[u8; COOKIES_KEY_LEN]) -> Result<HashMap<String, String>> {
+    Ok(read_cookie_rows(db_path, key)?
+        .into_iter()
+        .filter_map(|(info, value)| Some((info.name, value?)))
+        .collect())
+}
+
+/// Every Google cookie row, with its decrypted value when usable.
+fn read_cookie_rows(
+    db_path: &Path,
+    key: &[u8; COOKIES_KEY_LEN],
+) -> Result<Vec<(CookieInfo, Option<String>)>> {
     let conn = Connection::open(db_path).context("Failed to open cookies DB")?;
 
     // Chrome 130+ (DB meta version ≥ 24) prefixes decrypted values with
@@ -243,24 +482,38 @@ fn read_cookies(db_path: &Path, key: &[u8; COOKIES_KEY_LEN]) -> Result<HashMap<S
     // host_key values: '.google.com', 'takeout.google.com', 'google.com'
     let mut stmt = conn
         .prepare(
-            "SELECT name, encrypted_value, host_key FROM cookies \
+            "SELECT name, encrypted_value, host_key, expires_utc FROM cookies \
              WHERE host_key IN ('.google.com', 'google.com', 'takeout.google.com', \
              '.takeout.google.com', 'accounts.google.com', '.accounts.google.com')",
         )
         .context("Failed to query cookies")?;
 
-    let mut cookies = HashMap::new();
+    let mut cookies = Vec::new();
     let mut rows = stmt.query([]).context("Failed to execute cookie query")?;
+    let now_utc = chrome_now_utc();
 
     while let Some(row) = rows.next().context("Error reading cookie row")? {
         let name: String = row.get(0)?;
         let encrypted: Vec<u8> = row.get(1)?;
         let host_key: String = row.get(2)?;
-        if let Ok(value) = decrypt_cookie_value(&encrypted, key, db_version, &host_key)
-            && !value.is_empty()
-        {
-            cookies.insert(name, value);
-        }
+        // Stale auth cookies make Google bounce downloads to a sign-in page
+        let expires_utc: i64 = row.get(3)?;
+        let (status, value) = if cookie_expired(expires_utc, now_utc) {
+            (CookieStatus::Expired, None)
+        } else {
+            match decrypt_cookie_value(&encrypted, key, db_version, &host_key) {
+                Ok(value) if !value.is_empty() => (CookieStatus::Usable, Some(value)),
+                _ => (CookieStatus::Unreadable, None),
+            }
+        };
+        cookies.push((
+            CookieInfo {
+                name,
+                host_key,
+                status,
+            },
+            value,
+        ));
     }
 
     Ok(cookies)
@@ -355,8 +608,40 @@ pub fn build_client(cookies: &HashMap<String, String>) -> Result<Client> {
         .context("Failed to build HTTP client")
 }
 
-/// Download Takeout part `i` to `dir`. Returns the local path.
-/// Skips if file already exists and matches Content-Length.
+/// A downloaded Takeout part.
+pub struct DownloadedZip {
+    pub path: PathBuf,
+    /// Hex SHA-1 of the whole file; None for Chrome downloads
+    pub sha1: Option<String>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Feed a file's bytes into `hasher`.
+fn hash_file_into(hasher: &mut Sha1, path: &Path) -> Result<()> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+fn sha1_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha1::new();
+    hash_file_into(&mut hasher, path)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Download Takeout part `i` to `dir`.
+/// Skips if file already exists and matches Content-Length — and
+/// `expected_sha1`, when an earlier download recorded one.
 /// Resumes partial downloads using Range header.
 pub fn download_zip(
     client: &Client,
@@ -364,7 +649,8 @@ pub fn download_zip(
     user_id: &str,
     i: usize,
     dir: &Path,
-) -> Result<PathBuf> {
+    expected_sha1: Option<&str>,
+) -> Result<DownloadedZip> {
     use indicatif::{ProgressBar, ProgressStyle};
 
     let url = build_url(job_id, user_id, i);
@@ -407,8 +693,31 @@ pub fn download_zip(
     if dest.exists() && content_length > 0 {
         let on_disk = dest.metadata()?.len();
         if on_disk == content_length {
-            println!("  [{i:02}] {filename} — already downloaded, skipping");
-            return Ok(dest);
+            match expected_sha1 {
+                None => {
+                    println!("  [{i:02}] {filename} — already downloaded, skipping");
+                    return Ok(DownloadedZip {
+                        path: dest,
+                        sha1: None,
+                    });
+                }
+                Some(expected) => {
+                    let actual = sha1_file(&dest)?;
+                    if actual.eq_ignore_ascii_case(expected) {
+                        println!(
+                            "  [{i:02}] {filename} — already downloaded (checksum OK), skipping"
+                        );
+                        return Ok(DownloadedZip {
+                            path: dest,
+                            sha1: Some(actual),
+                        });
+                    }
+                    // Same size, different bytes: resuming would keep them
+                    println!("  [{i:02}] {filename} — checksum mismatch, re-downloading");
+                    std::fs::remove_file(&dest)
+                        .with_context(|| format!("Failed to remove {}", dest.display()))?;
+                }
+            }
         }
     }
 
@@ -460,7 +769,11 @@ pub fn download_zip(
         .map(|n| n + effective_resume_pos)
         .unwrap_or(0);
 
-    let pb = ProgressBar::new(total);
+    let pb = if display::is_quiet() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total)
+    };
     pb.set_position(effective_resume_pos);
     pb.set_style(
         ProgressStyle::with_template(
@@ -470,6 +783,12 @@ pub fn download_zip(
         .progress_chars("##-"),
     );
 
+    // Hash what's already on disk so the result covers the whole zip
+    let mut hasher = Sha1::new();
+    if effective_resume_pos > 0 {
+        hash_file_into(&mut hasher, &dest)?;
+    }
+
     let file = std::fs::OpenOptions::new()
         .write(true)
         .create(true)
@@ -479,34 +798,49 @@ pub fn download_zip(
         .with_context(|| format!("Failed to open {}", dest.display()))?;
     let mut writer = std::io::BufWriter::new(file);
 
+    // Validate ZIP magic bytes as soon as the first 4 are known — catch HTML
+    // error pages before writing gigabytes of them. A resumed partial
+    // already has its header on disk.
+    let mut header: Vec<u8> = Vec::with_capacity(4);
+    if effective_resume_pos > 0 {
+        std::fs::File::open(&dest)?
+            .take(4)
+            .read_to_end(&mut header)?;
+    }
+    let mut magic_ok = zip_magic_ok(&header);
+
     let mut stream = resp;
     let mut buf = vec![0u8; 1024 * 1024]; // 1 MB chunks
     loop {
+        if magic_ok == Some(false) {
+            pb.finish_and_clear();
+            let _ = std::fs::remove_file(&dest);
+            bail!(
+                "Downloaded file is not a valid ZIP (got {:?}) — auth may have expired",
+                &header[..4]
+            );
+        }
         let n = stream.read(&mut buf)?;
         if n == 0 {
             break;
         }
+        if magic_ok.is_none() {
+            let need = 4 - header.len();
+            header.extend_from_slice(&buf[..n.min(need)]);
+            magic_ok = zip_magic_ok(&header);
+            if magic_ok == Some(false) {
+                continue;
+            }
+        }
         writer.write_all(&buf[..n])?;
+        hasher.update(&buf[..n]);
         pb.inc(n as u64);
+        progress::emit("download", pb.position(), total, Some(&filename));
     }
     writer.flush()?;
     drop(writer);
     pb.finish_and_clear();
 
-    // Validate ZIP magic bytes — catch HTML error pages saved as .zip
-    let mut file = std::fs::File::open(&dest)?;
-    let mut magic = [0u8; 4];
-    if file.read_exact(&mut magic).is_ok()
-        && !magic.starts_with(b"PK\x03\x04")
-        && !magic.starts_with(b"PK\x05\x06")
-    {
-        let _ = std::fs::remove_file(&dest);
-        bail!(
-            "Downloaded file is not a valid ZIP (got {:?}) — auth may have expired",
-            &magic
-        );
-    }
-
     let final_size = dest.metadata()?.len();
     println!(
         "  [{i:02}] Done → {} ({}MB)",
@@ -514,13 +848,69 @@ pub fn download_zip(
         final_size / 1024 / 1024
     );
 
-    Ok(dest)
+    Ok(DownloadedZip {
+        path: dest,
+        sha1: Some(hex(&hasher.finalize())),
+    })
+}
+
+/// Whether `header` starts like a ZIP: a local file header, or an
+/// end-of-central-directory record for an empty archive. None until 4 bytes
+/// are known.
+fn zip_magic_ok(header: &[u8]) -> Option<bool> {
+    let magic = header.get(..4)?;
+    Some(magic == b"PK\x03\x04" || magic == b"PK\x05\x06")
+}
+
+// MARK: - Part probing
+
+/// Result of a HEAD request against one Takeout part.
+pub struct PartProbe {
+    pub filename: String,
+    pub content_length: Option<u64>,
+}
+
+/// HEAD Takeout part `i` without downloading it.
+/// Returns Ok(None) when the part does not exist (404), Err on auth redirects
+/// or other failures.
+pub fn probe_part(client: &Client, job_id: &str, user_id: &str, i: usize) -> Result<Option<PartProbe>> {
+    let url = build_url(job_id, user_id, i);
+    let head = client.head(&url).send().context("HEAD request failed")?;
+
+    if head.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if head.status().is_client_error() || head.status().is_server_error() {
+        bail!("HEAD part {i} → {} (auth issue? re-login to Google in Chrome)", head.status());
+    }
+
+    let content_type = head
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if content_type.contains("text/html") {
+        bail!("HEAD part {i} returned text/html (auth redirect to {})", head.url());
+    }
+
+    let filename = extract_filename(&head).unwrap_or_else(|| format!("takeout-part-{i:03}.zip"));
+    let content_length = head
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+
+    Ok(Some(PartProbe {
+        filename,
+        content_length,
+    }))
 }
 
 // MARK: - Hybrid download
 
 /// Try downloading via HTTP first (fast), fall back to Chrome (reliable/auth) if needed.
 /// Accepts a pre-built client (cookies extracted on main thread to avoid Keychain prompts).
+#[allow(clippy::too_many_arguments)]
 pub fn download_hybrid(
     client: Option<&Client>,
     job_id: &str,
@@ -529,11 +919,12 @@ pub fn download_hybrid(
     dir: &Path,
     notifier: Option<&Notifier>,
     scraped_url: Option<&str>,
-) -> Result<PathBuf> {
+    expected_sha1: Option<&str>,
+) -> Result<DownloadedZip> {
     // 1. If we have a client, try HTTP download
     if let Some(client) = client {
-        match download_zip(client, job_id, user_id, i, dir) {
-            Ok(path) => return Ok(path),
+        match download_zip(client, job_id, user_id, i, dir, expected_sha1) {
+            Ok(downloaded) => return Ok(downloaded),
             Err(e) => {
                 let err_msg = e.to_string();
                 let is_auth_error = err_msg.contains("text/html")
@@ -556,7 +947,8 @@ pub fn download_hybrid(
         .unwrap_or_else(|| build_url(job_id, user_id, i));
 
     // 3. Fallback to Chrome
-    download_via_chrome_with_url(&url, i, dir, notifier)
+    let path = download_via_chrome_with_url(&url, i, dir, notifier)?;
+    Ok(DownloadedZip { path, sha1: None })
 }
 
 /// Extract Chrome cookies and build an HTTP client.
@@ -690,6 +1082,15 @@ pub fn scrape_takeout_urls() -> HashMap<usize, String> {
 
 // MARK: - Chrome-delegated download
 
+/// Periodic notification that a Chrome download is still making progress.
+fn chrome_heartbeat_message(part: usize, bytes: u64, elapsed: Duration) -> String {
+    format!(
+        "photoferry: Part {part} downloading in Chrome — {:.1}GB after {}m",
+        bytes as f64 / 1024.0 / 1024.0 / 1024.0,
+        elapsed.as_secs() / 60
+    )
+}
+
 /// Download Takeout part by opening a URL in Chrome with proper referrer.
 /// Chrome handles passkey/re-auth challenges natively.
 /// Watches the download directory for the completed zip file.
@@ -761,12 +1162,14 @@ fn download_via_chrome_with_url(
 
     let poll_interval = Duration::from_secs(5);
     let progress_interval = Duration::from_secs(30);
+    let heartbeat_interval = Duration::from_secs(15 * 60);
     let stall_timeout = Duration::from_secs(120); // 2 min stall = retry
     let timeout = Duration::from_secs(7200); // 2h max per part
     let max_retries = 3;
     let start = Instant::now();
     let mut crdownload_seen = false;
     let mut last_progress = Instant::now() - progress_interval;
+    let mut last_heartbeat = Instant::now();
     let mut last_size: u64 = 0;
     let mut last_size_change = Instant::now();
     let mut retries = 0;
@@ -810,6 +1213,16 @@ fn download_via_chrome_with_url(
                 .filter_map(|p| p.metadata().ok())
                 .map(|m| m.len())
                 .sum();
+            // Chrome doesn't expose the expected size, so total stays 0
+            let partial = crdownloads[0].file_stem().map(|s| s.to_string_lossy());
+            progress::emit("download", current_size, 0, partial.as_deref());
+            if last_heartbeat.elapsed() >= heartbeat_interval {
+                last_heartbeat = Instant::now();
+                notify::notify(
+                    notifier,
+                    &chrome_heartbeat_message(i, current_size, start.elapsed()),
+                );
+            }
 
             if current_size != last_size {
                 last_size = current_size;
@@ -822,10 +1235,12 @@ fn download_via_chrome_with_url(
                         max_retries
                     );
                 }
-                println!(
-                    "  [{i:02}] Download stalled for {}s — deleting and retrying ({retries}/{max_retries})",
+                let msg = format!(
+                    "Download stalled for {}s — deleting and retrying ({retries}/{max_retries})",
                     stall_timeout.as_secs()
                 );
+                println!("  [{i:02}] {msg}");
+                notify::notify(notifier, &format!("photoferry: Part {i} {msg}"));
                 // Delete only OUR stalled .crdownload files
                 for cd in &crdownloads {
                     let _ = std::fs::remove_file(cd);
@@ -859,18 +1274,23 @@ fn download_via_chrome_with_url(
                 // Check no .crdownload files remain (Chrome renames atomically on completion)
                 if crdownloads.is_empty() || (crdownload_seen && crdownloads.is_empty()) {
                     let size = zip_path.metadata()?.len();
-                    println!(
-                        "  [{i:02}] Chrome download complete → {} ({:.1}GB)",
+                    let msg = format!(
+                        "Chrome download complete → {} ({:.1}GB)",
                         zip_path.file_name().unwrap_or_default().to_string_lossy(),
                         size as f64 / 1024.0 / 1024.0 / 1024.0
                     );
+                    println!("  [{i:02}] {msg}");
+                    notify::notify(notifier, &format!("photoferry: Part {i} {msg}"));
                     return Ok(zip_path.to_path_buf());
                 }
             }
         }
 
         // Show progress for active downloads
-        if crdownload_seen && last_progress.elapsed() >= progress_interval {
+        if crdownload_seen
+            && last_progress.elapsed() >= progress_interval
+            && !display::is_quiet()
+        {
             for cd in &crdownloads {
                 if let Ok(meta) = cd.metadata() {
                     let gb = meta.len() as f64 / 1024.0 / 1024.0 / 1024.0;
@@ -900,7 +1320,90 @@ fn extract_filename(resp: &reqwest::blocking::Response) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{DownloadProgress, progress_path};
+    use super::{
+        BatteryStatus, COOKIES_KEY_LEN, CookieStatus, DownloadProgress, chrome_heartbeat_message,
+        chrome_now_utc, missing_auth_cookies, parse_df_available_kb, parse_pmset_batt,
+        progress_path, read_cookie_rows, read_cookies, sha1_file, zip_magic_ok,
+    };
+
+    #[test]
+    fn sha1_file_hashes_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part.zip");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha1_file(&path).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn load_accepts_progress_without_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = progress_path(dir.path(), "job-123");
+        std::fs::write(
+            path,
+            r#"{"job_id":"job-123","user_id":"u","completed":[0],"failed":[]}"#,
+        )
+        .unwrap();
+        let progress = DownloadProgress::load(dir.path(), "job-123").unwrap();
+        assert!(progress.sha1.is_empty());
+    }
+
+    #[test]
+    fn read_cookies_skips_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("Cookies");
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE meta (key TEXT, value TEXT); \
+             INSERT INTO meta VALUES ('version', '23'); \
+             CREATE TABLE cookies (name TEXT, encrypted_value BLOB, host_key TEXT, expires_utc INTEGER);",
+        )
+        .unwrap();
+        let now = chrome_now_utc();
+        let hour = 3_600_000_000i64;
+        for (name, expires) in [
+            ("__Secure-1PSID", now + hour),
+            ("__Host-GAPS", 0),
+            ("SID", now - hour),
+        ] {
+            conn.execute(
+                "INSERT INTO cookies VALUES (?1, ?2, '.google.com', ?3)",
+                rusqlite::params![name, b"value".to_vec(), expires],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let cookies = read_cookies(&db, &[0u8; COOKIES_KEY_LEN]).unwrap();
+        assert_eq!(
+            cookies.get("__Secure-1PSID").map(String::as_str),
+            Some("value")
+        );
+        assert_eq!(
+            cookies.get("__Host-GAPS").map(String::as_str),
+            Some("value")
+        );
+        assert!(!cookies.contains_key("SID"));
+
+        let rows = read_cookie_rows(&db, &[0u8; COOKIES_KEY_LEN]).unwrap();
+        let sid = rows.iter().find(|(info, _)| info.name == "SID").unwrap();
+        assert_eq!(sid.0.status, CookieStatus::Expired);
+        assert_eq!(sid.0.host_key, ".google.com");
+        assert!(sid.1.is_none());
+    }
+
+    #[test]
+    fn missing_auth_cookies_names_each_gap() {
+        assert_eq!(
+            missing_auth_cookies(["SID", "HSID"]),
+            vec!["SSID", "SAPISID", "__Secure-*"]
+        );
+        assert!(
+            missing_auth_cookies(["SID", "SSID", "HSID", "SAPISID", "__Secure-3PSID"]).is_empty()
+        );
+    }
 
     #[test]
     fn progress_path_is_unique_for_distinct_jobs_with_same_prefix() {
@@ -910,6 +1413,54 @@ mod tests {
         assert_ne!(p1, p2);
     }
 
+    #[test]
+    fn unaccounted_parts_lists_gaps_in_range() {
+        let progress = DownloadProgress {
+            job_id: "job".to_string(),
+            user_id: "user".to_string(),
+            completed: vec![0, 1, 4],
+            failed: vec![2, 9],
+            attempts: Default::default(),
+            sha1: Default::default(),
+            failure_counts: Default::default(),
+            given_up: Default::default(),
+        };
+        assert_eq!(progress.unaccounted_parts(0, 5), vec![3, 5]);
+        assert!(progress.unaccounted_parts(0, 2).is_empty());
+    }
+
+    #[test]
+    fn failed_outside_lists_failures_the_range_skips() {
+        let progress = DownloadProgress {
+            job_id: "job".to_string(),
+            user_id: "user".to_string(),
+            completed: vec![0, 7],
+            failed: vec![9, 2, 7, 12],
+            ..Default::default()
+        };
+        assert_eq!(progress.failed_outside(3, 10), vec![2, 12]);
+        assert!(progress.failed_outside(0, 12).is_empty());
+    }
+
+    #[test]
+    fn part_is_given_up_after_max_failures_until_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut progress = DownloadProgress::load(dir.path(), "job-123").unwrap();
+        assert!(!progress.mark_failed(3, 2, dir.path()));
+        assert!(!progress.is_given_up(3));
+        assert!(progress.mark_failed(3, 2, dir.path()));
+        // Already given up: not reported again
+        assert!(!progress.mark_failed(3, 2, dir.path()));
+
+        let reloaded = DownloadProgress::load(dir.path(), "job-123").unwrap();
+        assert!(reloaded.is_given_up(3));
+        assert_eq!(reloaded.failure_counts.get(&3), Some(&3));
+
+        progress.mark_completed(3, dir.path());
+        assert!(!progress.is_given_up(3));
+        assert!(progress.failure_counts.is_empty());
+    }
+
     #[test]
     fn load_errors_on_corrupt_progress_file() {
         let dir = tempfile::tempdir().unwrap();
@@ -917,4 +1468,79 @@ mod tests {
         std::fs::write(path, "{bad-json").unwrap();
         assert!(DownloadProgress::load(dir.path(), "job-123").is_err());
     }
+
+    #[test]
+    fn parse_df_posix_output() {
+        let out = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                   /dev/disk3s5 971350180 512340120 420123456 55% /System/Volumes/Data\n";
+        assert_eq!(parse_df_available_kb(out), Some(420123456));
+    }
+
+    #[test]
+    fn parse_df_wrapped_long_device_name() {
+        let out = "Filesystem     1K-blocks      Used Available Use% Mounted on\n\
+                   /dev/mapper/very--long--volume--group-root\n\
+                   \x20              102400000  51200000  46080000  53% /\n";
+        assert_eq!(parse_df_available_kb(out), Some(46080000));
+    }
+
+    #[test]
+    fn parse_df_device_name_with_spaces() {
+        let out = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                   map auto_home 0 0 0 100% /System/Volumes/Data/home\n";
+        assert_eq!(parse_df_available_kb(out), Some(0));
+    }
+
+    #[test]
+    fn parse_pmset_battery_and_ac() {
+        let on_battery = "Now drawing from 'Battery Power'\n \
+                          -InternalBattery-0 (id=4653155)\t42%; discharging; 3:05 remaining present: true\n";
+        assert_eq!(
+            parse_pmset_batt(on_battery),
+            Some(BatteryStatus {
+                on_battery: true,
+                percent: 42
+            })
+        );
+        let on_ac = "Now drawing from 'AC Power'\n \
+                     -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+        assert_eq!(
+            parse_pmset_batt(on_ac),
+            Some(BatteryStatus {
+                on_battery: false,
+                percent: 100
+            })
+        );
+        // Desktop Mac: no battery line
+        assert_eq!(parse_pmset_batt("Now drawing from 'AC Power'\n"), None);
+        assert_eq!(parse_pmset_batt(""), None);
+    }
+
+    #[test]
+    fn chrome_heartbeat_reports_size_and_elapsed_minutes() {
+        let msg = chrome_heartbeat_message(
+            7,
+            3 * 1024 * 1024 * 1024 / 2,
+            std::time::Duration::from_secs(1830),
+        );
+        assert_eq!(
+            msg,
+            "photoferry: Part 7 downloading in Chrome — 1.5GB after 30m"
+        );
+    }
+
+    #[test]
+    fn zip_magic_needs_four_bytes() {
+        assert_eq!(zip_magic_ok(b""), None);
+        assert_eq!(zip_magic_ok(b"PK\x03"), None);
+        assert_eq!(zip_magic_ok(b"PK\x03\x04rest"), Some(true));
+        assert_eq!(zip_magic_ok(b"PK\x05\x06"), Some(true));
+        assert_eq!(zip_magic_ok(b"<!DOCTYPE html>"), Some(false));
+    }
+
+    #[test]
+    fn parse_df_unparseable_output() {
+        assert_eq!(parse_df_available_kb(""), None);
+        assert_eq!(parse_df_available_kb("df: /nope: No such file or directory\n"), None);
+    }
 }
@@ -2,16 +2,30 @@
 
 use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
-use swift_rs::{Bool, SRString, swift};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use swift_rs::{Bool, Int, SRString, swift};
 
 // MARK: - FFI declarations
 
 swift!(fn photoferry_check_access() -> SRString);
 swift!(fn photoferry_import_photo(path: &SRString, metadata_json: &SRString, is_video: Bool) -> SRString);
 swift!(fn photoferry_import_live_photo(photo_path: &SRString, video_path: &SRString, metadata_json: &SRString) -> SRString);
+swift!(fn photoferry_import_motion_photo(path: &SRString, metadata_json: &SRString) -> SRString);
+swift!(fn photoferry_import_photo_resource(path: &SRString, metadata_json: &SRString) -> SRString);
+swift!(fn photoferry_gif_to_video(gif_path: &SRString, movie_path: &SRString) -> Bool);
 swift!(fn photoferry_create_album(title: &SRString) -> SRString);
 swift!(fn photoferry_add_to_album(album_id: &SRString, asset_id: &SRString) -> Bool);
+swift!(fn photoferry_set_album_key_photo(album_id: &SRString, asset_id: &SRString) -> Bool);
+swift!(fn photoferry_album_exists(album_id: &SRString) -> SRString);
 swift!(fn photoferry_verify_assets(identifiers_json: &SRString) -> SRString);
+swift!(fn photoferry_icloud_upload_status(identifiers_json: &SRString) -> SRString);
+swift!(fn photoferry_find_by_filename(filenames_json: &SRString) -> SRString);
+swift!(fn photoferry_delete_assets(identifiers_json: &SRString) -> SRString);
+swift!(fn photoferry_set_creation_date(asset_id: &SRString, date: &SRString) -> Bool);
+swift!(fn photoferry_timezone_at(latitude: f64, longitude: f64) -> SRString);
+swift!(fn photoferry_timezone_offset(identifier: &SRString, epoch: Int) -> Int);
 
 // MARK: - Types
 
@@ -27,6 +41,9 @@ pub struct PhotoMetadata {
     pub altitude: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// Sidecar `description`, often a Google auto-caption. The Swift side
+    /// never writes it: PhotoKit has no caption setter, so imports get no
+    /// caption either way
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(rename = "isFavorite", skip_serializing_if = "Option::is_none")]
@@ -56,6 +73,32 @@ pub struct AssetVerifyResult {
     pub creation_date: Option<String>,
     #[serde(rename = "hasPairedVideo")]
     pub has_paired_video: bool,
+    /// Whether the asset has a photo resource; a Live Photo without one only
+    /// kept its video
+    #[serde(rename = "hasStill")]
+    pub has_still: bool,
+    /// Present, but its originals are only in iCloud (Optimize Storage)
+    #[serde(rename = "inICloudOnly")]
+    pub in_icloud_only: bool,
+}
+
+/// How many of a set of assets iCloud Photos knows about yet.
+#[derive(Debug, Deserialize)]
+pub struct ICloudUploadStatus {
+    /// False before macOS 12 (no cloud identifiers to check)
+    pub available: bool,
+    pub synced: usize,
+    /// No cloud identifier yet: still uploading, or iCloud Photos is off
+    pub pending: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilenameAsset {
+    pub filename: String,
+    #[serde(rename = "localIdentifier")]
+    pub local_identifier: String,
+    #[serde(rename = "creationDate")]
+    pub creation_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -104,8 +147,47 @@ pub fn import_live_photo(
     Ok(result)
 }
 
+pub fn import_motion_photo(path: &str, metadata: Option<&PhotoMetadata>) -> Result<ImportResult> {
+    let path_sr: SRString = path.into();
+    let meta_json = match metadata {
+        Some(m) => serde_json::to_string(m)?,
+        None => String::new(),
+    };
+    let meta_sr: SRString = meta_json.as_str().into();
+
+    let json = unsafe { photoferry_import_motion_photo(&path_sr, &meta_sr) };
+    let result: ImportResult = serde_json::from_str(json.as_str())?;
+    Ok(result)
+}
+
+/// Import a photo as the asset's original resource, byte for byte, so depth
+/// and portrait data embedded in it survive (`--preserve-depth`).
+pub fn import_photo_resource(path: &str, metadata: Option<&PhotoMetadata>) -> Result<ImportResult> {
+    let path_sr: SRString = path.into();
+    let meta_json = match metadata {
+        Some(m) => serde_json::to_string(m)?,
+        None => String::new(),
+    };
+    let meta_sr: SRString = meta_json.as_str().into();
+
+    let json = unsafe { photoferry_import_photo_resource(&path_sr, &meta_sr) };
+    let result: ImportResult = serde_json::from_str(json.as_str())?;
+    Ok(result)
+}
+
+/// Render an animated GIF into a QuickTime movie at `movie_path`.
+pub fn gif_to_video(gif_path: &str, movie_path: &str) -> Result<()> {
+    let gif_sr: SRString = gif_path.into();
+    let movie_sr: SRString = movie_path.into();
+    let ok = unsafe { photoferry_gif_to_video(&gif_sr, &movie_sr) };
+    if !ok {
+        bail!("Could not convert GIF to video");
+    }
+    Ok(())
+}
+
 pub fn create_album(title: &str) -> Result<String> {
-    let title_sr: SRString = title.into();
+    let title_sr: SRString = crate::takeout::sanitize_album_title(title).as_str().into();
     let json = unsafe { photoferry_create_album(&title_sr) };
     let result: AlbumResult = serde_json::from_str(json.as_str())?;
 
@@ -117,6 +199,45 @@ pub fn create_album(title: &str) -> Result<String> {
         .ok_or_else(|| anyhow::anyhow!("No album ID returned"))
 }
 
+/// Album ids created (or found) this process, by title.
+static ALBUM_IDS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// `create_album`, once per title per process. The lock is held across the
+/// Swift call, so concurrent requests for one title create one album.
+pub fn get_or_create_album(title: &str) -> Result<String> {
+    get_or_create_in(ALBUM_IDS.get_or_init(Default::default), title, create_album)
+}
+
+fn get_or_create_in(
+    cache: &Mutex<HashMap<String, String>>,
+    title: &str,
+    create: impl FnOnce(&str) -> Result<String>,
+) -> Result<String> {
+    let mut ids = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = ids.get(title) {
+        return Ok(id.clone());
+    }
+    // Failures aren't cached; the caller decides whether to retry
+    let id = create(title)?;
+    ids.insert(title.to_string(), id.clone());
+    Ok(id)
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumLookup {
+    exists: bool,
+    title: Option<String>,
+}
+
+/// Title of the album with this local identifier, or None if there is no such
+/// album. Untitled albums come back as an empty title.
+pub fn album_title(album_id: &str) -> Result<Option<String>> {
+    let album_sr: SRString = album_id.into();
+    let json = unsafe { photoferry_album_exists(&album_sr) };
+    let lookup: AlbumLookup = serde_json::from_str(json.as_str())?;
+    Ok(lookup.exists.then(|| lookup.title.unwrap_or_default()))
+}
+
 pub fn verify_assets(local_ids: &[&str]) -> Result<Vec<AssetVerifyResult>> {
     let ids_json = serde_json::to_string(local_ids)?;
     let ids_sr: SRString = ids_json.as_str().into();
@@ -125,9 +246,422 @@ pub fn verify_assets(local_ids: &[&str]) -> Result<Vec<AssetVerifyResult>> {
     Ok(results)
 }
 
+/// Best-effort iCloud upload status for `local_ids`, from whether each has
+/// a cloud identifier yet. PhotoKit exposes no upload queue.
+pub fn icloud_upload_status(local_ids: &[&str]) -> Result<ICloudUploadStatus> {
+    let ids_json = serde_json::to_string(local_ids)?;
+    let ids_sr: SRString = ids_json.as_str().into();
+    let json = unsafe { photoferry_icloud_upload_status(&ids_sr) };
+    let status: ICloudUploadStatus = serde_json::from_str(json.as_str())?;
+    Ok(status)
+}
+
+/// [`verify_assets`], splitting a failed batch in half and retrying each half
+/// until the identifiers the Swift side rejects are isolated. Returns the
+/// results for the rest plus the identifiers that failed on their own; errors
+/// only if nothing verifies (e.g. Photos access is gone).
+pub fn verify_assets_isolating(
+    local_ids: &[&str],
+) -> Result<(Vec<AssetVerifyResult>, Vec<String>)> {
+    let err = match verify_assets(local_ids) {
+        Ok(results) => return Ok((results, Vec::new())),
+        Err(e) => e,
+    };
+    let (results, unverifiable) = bisect_batches(local_ids, &mut |ids| verify_assets(ids));
+    if results.is_empty() {
+        return Err(err);
+    }
+    Ok((results, unverifiable))
+}
+
+fn bisect_batches<T>(
+    ids: &[&str],
+    call: &mut dyn FnMut(&[&str]) -> Result<Vec<T>>,
+) -> (Vec<T>, Vec<String>) {
+    if ids.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    match call(ids) {
+        Ok(results) => (results, Vec::new()),
+        Err(_) if ids.len() == 1 => (Vec::new(), vec![ids[0].to_string()]),
+        Err(_) => {
+            let (left, right) = ids.split_at(ids.len() / 2);
+            let (mut results, mut failed) = bisect_batches(left, call);
+            let (more, more_failed) = bisect_batches(right, call);
+            results.extend(more);
+            failed.extend(more_failed);
+            (results, failed)
+        }
+    }
+}
+
+pub fn find_by_filename(filenames: &[&str]) -> Result<Vec<FilenameAsset>> {
+    let names_json = serde_json::to_string(filenames)?;
+    let names_sr: SRString = names_json.as_str().into();
+    let json = unsafe { photoferry_find_by_filename(&names_sr) };
+    let results: Vec<FilenameAsset> = serde_json::from_str(json.as_str())?;
+    Ok(results)
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteResult {
+    deleted: Option<usize>,
+    error: Option<String>,
+}
+
+/// Move assets to Photos' Recently Deleted. Returns how many were found and
+/// deleted; Photos asks the user to confirm.
+pub fn delete_assets(local_ids: &[&str]) -> Result<usize> {
+    let ids_json = serde_json::to_string(local_ids)?;
+    let ids_sr: SRString = ids_json.as_str().into();
+    let json = unsafe { photoferry_delete_assets(&ids_sr) };
+    let result: DeleteResult = serde_json::from_str(json.as_str())?;
+
+    if let Some(err) = result.error {
+        bail!("Failed to delete assets: {}", err);
+    }
+    Ok(result.deleted.unwrap_or(0))
+}
+
 pub fn add_to_album(album_id: &str, asset_id: &str) -> Result<bool> {
     let album_sr: SRString = album_id.into();
     let asset_sr: SRString = asset_id.into();
     let success: Bool = unsafe { photoferry_add_to_album(&album_sr, &asset_sr) };
     Ok(success)
 }
+
+pub fn set_album_key_photo(album_id: &str, asset_id: &str) -> Result<bool> {
+    let album_sr: SRString = album_id.into();
+    let asset_sr: SRString = asset_id.into();
+    let success: Bool = unsafe { photoferry_set_album_key_photo(&album_sr, &asset_sr) };
+    Ok(success)
+}
+
+/// Set an existing asset's creation date (ISO 8601). False if the asset is
+/// gone, the date doesn't parse, or Photos rejects the change.
+pub fn set_creation_date(asset_id: &str, date: &str) -> Result<bool> {
+    let asset_sr: SRString = asset_id.into();
+    let date_sr: SRString = date.into();
+    let success: Bool = unsafe { photoferry_set_creation_date(&asset_sr, &date_sr) };
+    Ok(success)
+}
+
+/// Time zones looked up this process, by coordinate rounded to 0.1° (about
+/// 11 km). Failed lookups are kept too, so a dead network isn't retried for
+/// every photo from the same place.
+static TIMEZONES: OnceLock<TimezoneCache> = OnceLock::new();
+
+type TimezoneCache = Mutex<HashMap<(i32, i32), Option<String>>>;
+
+/// IANA time zone at a coordinate (`--timezone-from-gps`), reverse geocoded
+/// at most once per rounded coordinate. None when the lookup fails.
+pub fn timezone_at(latitude: f64, longitude: f64) -> Option<String> {
+    timezone_at_in(
+        TIMEZONES.get_or_init(Default::default),
+        latitude,
+        longitude,
+        |lat, lon| {
+            let identifier = unsafe { photoferry_timezone_at(lat, lon) };
+            Some(identifier.to_string()).filter(|id| !id.is_empty())
+        },
+    )
+}
+
+fn timezone_at_in(
+    cache: &TimezoneCache,
+    latitude: f64,
+    longitude: f64,
+    lookup: impl FnOnce(f64, f64) -> Option<String>,
+) -> Option<String> {
+    let key = (
+        (latitude * 10.0).round() as i32,
+        (longitude * 10.0).round() as i32,
+    );
+    let mut zones = cache.lock().unwrap_or_else(|e| e.into_inner());
+    zones
+        .entry(key)
+        .or_insert_with(|| lookup(latitude, longitude))
+        .clone()
+}
+
+/// Seconds east of UTC in an IANA time zone at a Unix time.
+pub fn timezone_offset(identifier: &str, epoch: i64) -> Option<i32> {
+    let identifier_sr: SRString = identifier.into();
+    let offset = unsafe { photoferry_timezone_offset(&identifier_sr, epoch as Int) };
+    i32::try_from(offset).ok()
+}
+
+// MARK: - Importer trait
+
+/// The Photos calls the import pipelines make. `run --dry-run` and tests
+/// drive the same pipeline with an implementation that never touches PhotoKit.
+pub trait Importer {
+    fn check_access(&self) -> Result<AccessResult>;
+    fn import_photo(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+        is_video: bool,
+    ) -> Result<ImportResult>;
+    fn import_live_photo(
+        &self,
+        photo_path: &str,
+        video_path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult>;
+    fn import_motion_photo(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult>;
+    fn import_photo_resource(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult>;
+    fn gif_to_video(&self, gif_path: &str, movie_path: &str) -> Result<()>;
+    fn verify_assets(&self, local_ids: &[&str]) -> Result<Vec<AssetVerifyResult>>;
+    /// Seconds east of UTC at a location and Unix time; None if unknown
+    fn utc_offset_at(&self, latitude: f64, longitude: f64, epoch: i64) -> Option<i32>;
+    fn create_album(&self, title: &str) -> Result<String>;
+    fn album_title(&self, album_id: &str) -> Result<Option<String>>;
+    fn add_to_album(&self, album_id: &str, asset_id: &str) -> Result<bool>;
+    fn set_album_key_photo(&self, album_id: &str, asset_id: &str) -> Result<bool>;
+}
+
+/// The system Photos library, through the Swift bridge.
+pub struct PhotosImporter;
+
+impl Importer for PhotosImporter {
+    fn check_access(&self) -> Result<AccessResult> {
+        check_access()
+    }
+
+    fn import_photo(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+        is_video: bool,
+    ) -> Result<ImportResult> {
+        import_photo(path, metadata, is_video)
+    }
+
+    fn import_live_photo(
+        &self,
+        photo_path: &str,
+        video_path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult> {
+        import_live_photo(photo_path, video_path, metadata)
+    }
+
+    fn import_motion_photo(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult> {
+        import_motion_photo(path, metadata)
+    }
+
+    fn import_photo_resource(
+        &self,
+        path: &str,
+        metadata: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult> {
+        import_photo_resource(path, metadata)
+    }
+
+    fn gif_to_video(&self, gif_path: &str, movie_path: &str) -> Result<()> {
+        gif_to_video(gif_path, movie_path)
+    }
+
+    fn verify_assets(&self, local_ids: &[&str]) -> Result<Vec<AssetVerifyResult>> {
+        verify_assets(local_ids)
+    }
+
+    fn utc_offset_at(&self, latitude: f64, longitude: f64, epoch: i64) -> Option<i32> {
+        timezone_offset(&timezone_at(latitude, longitude)?, epoch)
+    }
+
+    fn create_album(&self, title: &str) -> Result<String> {
+        get_or_create_album(title)
+    }
+
+    fn album_title(&self, album_id: &str) -> Result<Option<String>> {
+        album_title(album_id)
+    }
+
+    fn add_to_album(&self, album_id: &str, asset_id: &str) -> Result<bool> {
+        add_to_album(album_id, asset_id)
+    }
+
+    fn set_album_key_photo(&self, album_id: &str, asset_id: &str) -> Result<bool> {
+        set_album_key_photo(album_id, asset_id)
+    }
+}
+
+/// `run --dry-run`: every call succeeds with a placeholder id, so the pipeline
+/// reports what a real run would import without changing the library.
+#[derive(Default)]
+pub struct DryRunImporter {
+    next_id: Cell<usize>,
+}
+
+impl DryRunImporter {
+    fn placeholder(&self, prefix: &str) -> String {
+        let n = self.next_id.get() + 1;
+        self.next_id.set(n);
+        format!("dry-run-{prefix}-{n}")
+    }
+
+    fn imported(&self) -> Result<ImportResult> {
+        Ok(ImportResult {
+            success: true,
+            local_identifier: Some(self.placeholder("asset")),
+            error: None,
+        })
+    }
+}
+
+impl Importer for DryRunImporter {
+    fn check_access(&self) -> Result<AccessResult> {
+        Ok(AccessResult {
+            authorized: true,
+            status: "dry-run".to_string(),
+        })
+    }
+
+    fn import_photo(&self, _: &str, _: Option<&PhotoMetadata>, _: bool) -> Result<ImportResult> {
+        self.imported()
+    }
+
+    fn import_live_photo(
+        &self,
+        _: &str,
+        _: &str,
+        _: Option<&PhotoMetadata>,
+    ) -> Result<ImportResult> {
+        self.imported()
+    }
+
+    fn import_motion_photo(&self, _: &str, _: Option<&PhotoMetadata>) -> Result<ImportResult> {
+        self.imported()
+    }
+
+    fn import_photo_resource(&self, _: &str, _: Option<&PhotoMetadata>) -> Result<ImportResult> {
+        self.imported()
+    }
+
+    /// Nothing is imported, so there's nothing to convert.
+    fn gif_to_video(&self, _: &str, _: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Placeholder ids aren't in Photos; the pipeline doesn't check them.
+    fn verify_assets(&self, _: &[&str]) -> Result<Vec<AssetVerifyResult>> {
+        Ok(Vec::new())
+    }
+
+    /// No network lookups on a dry run; dates stay in UTC.
+    fn utc_offset_at(&self, _: f64, _: f64, _: i64) -> Option<i32> {
+        None
+    }
+
+    fn create_album(&self, _: &str) -> Result<String> {
+        Ok(self.placeholder("album"))
+    }
+
+    /// Assumes the album exists; its id stands in for the title.
+    fn album_title(&self, album_id: &str) -> Result<Option<String>> {
+        Ok(Some(album_id.to_string()))
+    }
+
+    fn add_to_album(&self, _: &str, _: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn set_album_key_photo(&self, _: &str, _: &str) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+// MARK: - Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn timezone_lookups_are_cached_by_rounded_coordinate() {
+        let cache = Mutex::new(HashMap::new());
+        let lookups = AtomicUsize::new(0);
+        let lookup = |lat: f64, _: f64| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            (lat > 0.0).then(|| "Asia/Tokyo".to_string())
+        };
+        assert_eq!(
+            timezone_at_in(&cache, 35.6812, 139.7671, lookup).as_deref(),
+            Some("Asia/Tokyo")
+        );
+        // A few hundred meters away: same cell
+        assert_eq!(
+            timezone_at_in(&cache, 35.6841, 139.7702, lookup).as_deref(),
+            Some("Asia/Tokyo")
+        );
+        assert_eq!(timezone_at_in(&cache, -33.86, 151.21, lookup), None);
+        assert_eq!(timezone_at_in(&cache, -33.86, 151.21, lookup), None);
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn concurrent_requests_for_one_title_create_one_album() {
+        let cache = Mutex::new(HashMap::new());
+        let creations = AtomicUsize::new(0);
+        let create = |title: &str| {
+            creations.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            Ok(format!("id-{title}"))
+        };
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    assert_eq!(get_or_create_in(&cache, "Trip", create).unwrap(), "id-Trip");
+                });
+            }
+        });
+        assert_eq!(creations.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            get_or_create_in(&cache, "Other", create).unwrap(),
+            "id-Other"
+        );
+        assert_eq!(creations.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn bisect_batches_isolates_failing_ids() {
+        let ids = ["a", "b", "bad", "c", "d", "worse", "e"];
+        let mut calls = 0;
+        let (results, failed) = bisect_batches(&ids, &mut |batch| {
+            calls += 1;
+            if batch.iter().any(|id| *id == "bad" || *id == "worse") {
+                bail!("invalid_input");
+            }
+            Ok(batch.iter().map(|id| id.to_string()).collect())
+        });
+        assert_eq!(results, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(failed, vec!["bad", "worse"]);
+        assert!(calls < ids.len() * 2);
+    }
+
+    #[test]
+    fn failed_creation_is_not_cached() {
+        let cache = Mutex::new(HashMap::new());
+        assert!(get_or_create_in(&cache, "Trip", |_| bail!("no access")).is_err());
+        assert_eq!(
+            get_or_create_in(&cache, "Trip", |_| Ok("id".to_string())).unwrap(),
+            "id"
+        );
+    }
+}
@@ -4,18 +4,89 @@ mod importer;
 mod manifest;
 mod metadata;
 mod notify;
+mod progress;
 mod sidecar;
 mod takeout;
 
 use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::{HashMap, HashSet};
-use std::io::BufReader;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
-const STRICT_EXTENSIONS_ABORT: &str = "STRICT_EXTENSIONS_ABORT";
+/// Errors that callers (and the exit status) match on rather than just report.
+#[derive(Debug)]
+enum ProcessError {
+    /// `--strict-extensions` found files with unrecognized extensions.
+    StrictExtensionsAbort { count: usize, examples: Vec<String> },
+    /// Photos access is denied or limited.
+    PhotosAccess(String),
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessError::StrictExtensionsAbort { count, examples } => {
+                let examples = if examples.is_empty() {
+                    "<none>".to_string()
+                } else {
+                    examples.join(", ")
+                };
+                write!(
+                    f,
+                    "Unknown extensions detected ({count}). Examples: {examples}. Re-run without --strict-extensions to proceed."
+                )
+            }
+            ProcessError::PhotosAccess(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Process exit status, so scripts can branch on how a command ended. Any
+/// other error exits with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success = 0,
+    PermissionDenied = 2,
+    StrictExtensionsAbort = 3,
+    /// Ran to the end, but some files or zips failed to import
+    CompletedWithFailures = 4,
+    /// Some Takeout parts failed to download
+    DownloadFailures = 5,
+}
+
+impl Outcome {
+    fn exit_code(self) -> u8 {
+        self as u8
+    }
+
+    fn error_exit_code(err: &anyhow::Error) -> u8 {
+        match err.downcast_ref::<ProcessError>() {
+            Some(ProcessError::StrictExtensionsAbort { .. }) => {
+                Self::StrictExtensionsAbort.exit_code()
+            }
+            Some(ProcessError::PhotosAccess(_)) => Self::PermissionDenied.exit_code(),
+            None => 1,
+        }
+    }
+
+    /// `download`: download failures outrank import failures.
+    fn for_download(failed_downloads: usize, failed_imports: usize) -> Self {
+        if failed_downloads > 0 {
+            Self::DownloadFailures
+        } else if failed_imports > 0 {
+            Self::CompletedWithFailures
+        } else {
+            Self::Success
+        }
+    }
+}
 
 #[derive(Parser)]
 #[command(
@@ -26,6 +97,18 @@ const STRICT_EXTENSIONS_ABORT: &str = "STRICT_EXTENSIONS_ABORT";
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Hide progress bars and per-file output; print only headers, warnings,
+    /// errors, and final summaries (for logged, unattended runs)
+    #[arg(long, global = true)]
+    quiet: bool,
+    /// Also write newline-delimited JSON progress events
+    /// (`{phase, current, total, current_file}`) to file descriptor N
+    #[arg(long, global = true, value_name = "N")]
+    progress_fd: Option<u32>,
+    /// Answer yes to confirmation prompts (currently: `clean` removing files).
+    /// Required when stdin isn't a terminal
+    #[arg(long, short = 'y', global = true)]
+    yes: bool,
 }
 
 #[derive(Subcommand)]
@@ -43,27 +126,32 @@ enum Commands {
         /// Simulate without importing
         #[arg(long)]
         dry_run: bool,
-        /// Print per-file import results instead of progress bar
-        #[arg(long)]
-        verbose: bool,
-        /// Include trashed items from Takeout
-        #[arg(long)]
-        include_trashed: bool,
         /// Retry only files that previously failed in manifest
         #[arg(long)]
         retry_failed: bool,
-        /// Abort if any unknown file extensions are detected
-        #[arg(long)]
-        strict_extensions: bool,
-        /// Write CSV report of unknown files to PATH
-        #[arg(long)]
-        unknown_report: Option<PathBuf>,
+        /// Only import these albums (repeatable; case-insensitive). Name a year
+        /// folder (e.g. "Photos from 2024") to include it
+        #[arg(long = "album", value_name = "NAME")]
+        albums: Vec<String>,
+        /// After a clean import that verifies, move the zip into PATH
+        #[arg(long, value_name = "PATH")]
+        archive_dir: Option<PathBuf>,
+        /// Write every ZIP entry photoferry sees (path, kind, size, whether it
+        /// would import) to PATH as JSON and exit. No Photos access needed
+        #[arg(long, value_name = "PATH")]
+        dump_index: Option<PathBuf>,
+        /// Scan every zip and create all its albums before importing anything
+        #[arg(long, conflicts_with = "into_album_id")]
+        two_phase: bool,
+        #[command(flatten)]
+        import: ImportArgs,
     },
     /// Import a single file (for testing)
     Import {
         /// Path to photo/video file
         file: PathBuf,
-        /// JSON metadata string
+        /// JSON metadata string (default: read from the file's Takeout sidecar,
+        /// as `run` would)
         #[arg(long)]
         metadata: Option<String>,
     },
@@ -72,12 +160,34 @@ enum Commands {
         /// Source directory containing Takeout zips
         #[arg(default_value = "~/Downloads")]
         dir: PathBuf,
+        /// Also write `{album: [relative_path, ...]}` for every zip to PATH
+        #[arg(long, value_name = "PATH")]
+        json: Option<PathBuf>,
     },
     /// Verify imported photos exist and are correct in Photos library
     Verify {
         /// Directory containing manifest files
         #[arg(default_value = "~/Downloads")]
         dir: PathBuf,
+        /// If a manifest's zip is missing, find it among renamed zips and update the manifest
+        #[arg(long)]
+        relink: bool,
+        /// Verify only this manifest file (its zip is looked up next to it)
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+        /// Spot-check a random PCT% of each manifest's assets and extrapolate
+        #[arg(long, value_name = "PCT", value_parser = parse_sample_pct)]
+        sample: Option<f64>,
+        /// Seed for `--sample`, to re-check the same assets (default: random, printed)
+        #[arg(long, requires = "sample")]
+        seed: Option<u64>,
+        /// Count a creation date within N seconds of the expected one as a match
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        date_tolerance_secs: u64,
+        /// Write every problem asset (`{zip, path, local_id, reason}`) to PATH
+        /// as JSON, for review before retrying
+        #[arg(long, value_name = "PATH")]
+        export_missing: Option<PathBuf>,
     },
     /// Re-import assets that verify as missing from Photos library
     RetryMissing {
@@ -87,6 +197,33 @@ enum Commands {
         /// Print per-file import results
         #[arg(long)]
         verbose: bool,
+        /// With --verbose, print only every Nth success line (failures are
+        /// always printed)
+        #[arg(long, value_name = "N", requires = "verbose")]
+        progress_every: Option<NonZeroUsize>,
+        /// If a manifest's zip is missing, find it among renamed zips and update the manifest
+        #[arg(long)]
+        relink: bool,
+        /// Record a failed Live Photo import as failed instead of importing the
+        /// still alone
+        #[arg(long)]
+        no_live_fallback: bool,
+        /// What to do when Photos may still hold the asset being re-imported
+        #[arg(long, value_enum, default_value_t = OnConflict::Duplicate)]
+        on_conflict: OnConflict,
+        /// Count a creation date within N seconds of the expected one as a match
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        date_tolerance_secs: u64,
+    },
+    /// Correct the creation date of assets that verify with the wrong date,
+    /// in place (no re-import, so no duplicates)
+    FixDates {
+        /// Directory containing manifest files
+        #[arg(default_value = "~/Downloads")]
+        dir: PathBuf,
+        /// Count a creation date within N seconds of the expected one as a match
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        date_tolerance_secs: u64,
     },
     /// Re-import Live Photo fallbacks (still-only) as Live Photos
     RetryLivePhotoFallbacks {
@@ -96,6 +233,18 @@ enum Commands {
         /// Print per-file import results
         #[arg(long)]
         verbose: bool,
+        /// What to do with the still-only asset already in Photos
+        #[arg(long, value_enum, default_value_t = OnConflict::Duplicate)]
+        on_conflict: OnConflict,
+    },
+    /// Rebuild a lost or corrupt manifest by finding the zip's files in Photos
+    /// (by filename and creation date)
+    RepairManifest {
+        /// Takeout zip whose manifest to rebuild
+        zip: PathBuf,
+        /// Directory the manifest lives in (default: the zip's directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
     },
     /// Download Takeout zips from Google, import, and delete
     Download {
@@ -120,30 +269,357 @@ enum Commands {
         /// Download only, skip import
         #[arg(long)]
         download_only: bool,
-        /// Print per-file import results
-        #[arg(long)]
-        verbose: bool,
-        /// Include trashed items from Takeout
-        #[arg(long)]
-        include_trashed: bool,
-        /// Abort if any unknown file extensions are detected
-        #[arg(long)]
-        strict_extensions: bool,
-        /// Write CSV report of unknown files to PATH
-        #[arg(long)]
-        unknown_report: Option<PathBuf>,
+        #[command(flatten)]
+        import: ImportArgs,
         /// Keep zip files after successful import+verify (default: delete)
         #[arg(long)]
         keep_zips: bool,
+        /// Move zip files into PATH after successful import+verify instead of deleting
+        #[arg(long, value_name = "PATH", conflicts_with = "keep_zips")]
+        archive_dir: Option<PathBuf>,
+        /// Shell command to run after each part, with `PHOTOFERRY_PART`,
+        /// `PHOTOFERRY_STATUS` (downloaded, verified, kept, download-failed,
+        /// import-failed) and `PHOTOFERRY_DIR` set; a failing hook only warns
+        #[arg(long, value_name = "CMD")]
+        after_part: Option<String>,
         /// File with pre-scraped download URLs (one per line, with rapt tokens)
         #[arg(long)]
         urls_file: Option<PathBuf>,
+        /// Give up on a part after it fails (download or import) this many
+        /// times across runs
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        max_part_retries: u32,
+        /// Retry parts that were given up on
+        #[arg(long)]
+        force: bool,
+        /// Pause between parts while on battery below PCT% (default: any
+        /// battery level), until plugged in
+        #[arg(long, value_name = "PCT", num_args = 0..=1, default_missing_value = "100")]
+        pause_on_battery: Option<u8>,
+    },
+    /// Re-verify zips a download kept and delete the ones Photos now holds
+    VerifyKept {
+        /// Directory containing manifests and Takeout zips
+        #[arg(default_value = "~/Downloads")]
+        dir: PathBuf,
+        /// Delete verified zips (only once Photos has finished uploading them
+        /// to iCloud); without it, only reports what would be deleted
+        #[arg(long)]
+        icloud_confirmed: bool,
+    },
+    /// Remove photoferry's manifests, progress files and temp dirs once a
+    /// migration is complete
+    Clean {
+        /// Directory containing manifests and Takeout zips
+        #[arg(default_value = "~/Downloads")]
+        dir: PathBuf,
+        /// List what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove even if manifests have failed imports or downloads have
+        /// incomplete parts
+        #[arg(long)]
+        force: bool,
+    },
+    /// List failed imports across all manifests, grouped by error
+    ListFailed {
+        /// Directory containing manifests
+        #[arg(default_value = "~/Downloads")]
+        dir: PathBuf,
+        /// Only failures whose error message contains this text
+        #[arg(long, value_name = "TEXT")]
+        error_contains: Option<String>,
+    },
+    /// List the Google cookies photoferry can read from Chrome (names only,
+    /// never values) and which sign-in cookies are missing
+    Cookies,
+    /// List Takeout parts and their sizes via HTTP HEAD (no download)
+    Probe {
+        /// Google Takeout job ID
+        #[arg(long)]
+        job: String,
+        /// Google user ID
+        #[arg(long)]
+        user: String,
+        /// Download directory (used to report parts already on disk)
+        #[arg(long, default_value = "~/Downloads")]
+        dir: PathBuf,
     },
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Import options shared by `run` and `download`.
+#[derive(clap::Args, Debug, Clone, Default)]
+struct ImportArgs {
+    /// Print per-file import results instead of progress bar
+    #[arg(long)]
+    verbose: bool,
+    /// Include trashed items from Takeout
+    #[arg(long)]
+    include_trashed: bool,
+    /// Abort if any unknown file extensions are detected
+    #[arg(long)]
+    strict_extensions: bool,
+    /// Write CSV report of unknown files to PATH
+    #[arg(long)]
+    unknown_report: Option<PathBuf>,
+    /// Append to `--unknown-report` PATH even if it isn't an unknown-files
+    /// report
+    #[arg(long, requires = "unknown_report")]
+    force_unknown_report: bool,
+    /// Keep videos out of photo albums: `suffix` (default) adds them to
+    /// `<Album> (Videos)`, `single` puts every video in `Imported Videos`
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "suffix")]
+    split_media_albums: Option<SplitMediaAlbums>,
+    /// Order to import files in: `path` (ZIP layout) or `date` (sidecar photo
+    /// taken time, oldest first, across the whole ZIP)
+    #[arg(long, value_enum, default_value_t)]
+    import_order: ImportOrder,
+    /// Skip media files larger than GB (listed at the end for manual import)
+    #[arg(long, value_name = "GB")]
+    max_file_size: Option<f64>,
+    /// With --verbose, print only every Nth success line (failures are always
+    /// printed)
+    #[arg(long, value_name = "N", requires = "verbose")]
+    progress_every: Option<NonZeroUsize>,
+    /// Create at most N albums (counting albums earlier runs created). Files
+    /// in albums past the cap import without an album
+    #[arg(long, value_name = "N")]
+    limit_albums: Option<usize>,
+    /// Record a failed Live Photo import as failed instead of importing the
+    /// still alone
+    #[arg(long)]
+    no_live_fallback: bool,
+    /// Import photos as their original file, byte for byte, so depth maps and
+    /// portrait data survive (Portrait effects stay editable in Photos)
+    #[arg(long)]
+    preserve_depth: bool,
+    /// Make each album's earliest-dated import its key photo (moves it to the
+    /// front of the album)
+    #[arg(long)]
+    album_key_photo: bool,
+    /// What to do when a fuzzy-matched sidecar's title looks unrelated to the
+    /// media filename: `skip` its metadata, `warn` only, or `off`
+    #[arg(long, value_enum, default_value_t)]
+    fuzzy_title_check: FuzzyTitleCheck,
+    /// Don't import these extensions (comma-separated, e.g. `gif,dng`); they're
+    /// counted apart from unknown files. Skipping `mov` drops Live Photo motion
+    #[arg(long, value_delimiter = ',', value_name = "EXT")]
+    skip_ext: Vec<String>,
+    /// Only import media whose sidecar tags at least N people (no sidecar or
+    /// no `people` counts as 0)
+    #[arg(long, value_name = "N")]
+    min_people: Option<usize>,
+    /// Only import media starred in Google Photos (`favorited` in a sidecar
+    /// matched by exact name)
+    #[arg(long, alias = "import-favorites-only")]
+    favorites_only: bool,
+    /// Record media whose sidecar can't be parsed as failed instead of
+    /// importing it without metadata
+    #[arg(long)]
+    strict_metadata: bool,
+    /// When a folder has both `photo.jpg` and `photo-edited.jpg`: import
+    /// `both`, only the `edited` copy, or only the `original`
+    #[arg(long, value_enum, default_value_t)]
+    edited_policy: EditedPolicy,
+    /// For burst sequences (`_BURST` frames): import `keep-all` frames, or
+    /// `cover-only` (the frame Google marks as cover, else the first)
+    #[arg(long, value_enum, default_value_t)]
+    burst_policy: BurstPolicy,
+    /// Animated GIFs: import as-is (`static`; some macOS versions keep only
+    /// the first frame), convert to a `video`, or `skip` them
+    #[arg(long, value_enum, default_value_t)]
+    gif_policy: GifPolicy,
+    /// Move creation dates of photos with GPS so Photos shows the local time
+    /// where they were taken (reverse geocodes each place; needs network)
+    #[arg(long)]
+    timezone_from_gps: bool,
+    /// Skip `Photos from YYYY` folders and import only album folders
+    #[arg(long)]
+    import_albums_only: bool,
+    /// Put `Photos from YYYY` media in albums by creation date (`2020` or
+    /// `2020-07`); files without a date stay out of albums
+    #[arg(long, value_enum, conflicts_with = "import_albums_only")]
+    date_albums: Option<DateAlbums>,
+    /// Add every imported file to this existing Photos album (its local
+    /// identifier) instead of creating albums from the Takeout folders
+    #[arg(long, value_name = "LOCAL_ID", conflicts_with = "split_media_albums")]
+    into_album_id: Option<String>,
+    /// Keep album folders that share a title separate: the second folder's
+    /// album is named `<Title> (<folder>)` instead of merging into the first
+    #[arg(long)]
+    disambiguate_albums: bool,
+    /// Stop cleanly once this much time has passed (e.g. `90m`, `6h`,
+    /// `1h30m`): the file or part in flight finishes, progress is saved, and
+    /// the next run resumes
+    #[arg(long, value_name = "DURATION", value_parser = parse_runtime)]
+    max_runtime: Option<Duration>,
+    /// Album names to restrict the import to (`run --album`); empty = all
+    #[arg(skip)]
+    album_filter: Vec<String>,
+    /// Stop after Phase 1 and return the ZIP index (`run --dump-index`)
+    #[arg(skip)]
+    dump_index: bool,
+    /// Create every album before importing (`run --two-phase`)
+    #[arg(skip)]
+    two_phase: bool,
+    /// When `--max-runtime` runs out, counted from startup
+    #[arg(skip)]
+    deadline: Option<Instant>,
+}
+
+impl ImportArgs {
+    fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// `--max-runtime`: one or more `<number><unit>` pairs, unit `s`, `m`, `h`
+/// or `d`.
+fn parse_runtime(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration {s:?} (expected e.g. 90m, 6h or 1h30m)");
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return Err(invalid()),
+        };
+        let n: u64 = digits.parse().map_err(|_| invalid())?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// `verify --sample`: a percentage in (0, 100].
+fn parse_sample_pct(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(pct) if pct > 0.0 && pct <= 100.0 => Ok(pct),
+        _ => Err(format!(
+            "invalid percentage {s:?} (expected more than 0, up to 100)"
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum SplitMediaAlbums {
+    /// Videos go to a sibling `<Album> (Videos)` album
+    Suffix,
+    /// All videos go to a single `Imported Videos` album
+    Single,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum ImportOrder {
+    /// Directory by directory, in ZIP order
+    #[default]
+    Path,
+    /// Oldest first by sidecar photo taken time; undated files last
+    Date,
+}
+
+/// Re-imports always create a new asset; this decides what happens to the
+/// one Photos may still have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum OnConflict {
+    /// Leave assets Photos still has (re-checking "missing" ones by filename
+    /// and date) and only import the rest
+    Skip,
+    /// Import, then move the old asset to Recently Deleted
+    Replace,
+    /// Import alongside the old asset
+    #[default]
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum FuzzyTitleCheck {
+    /// Import without the sidecar's metadata
+    #[default]
+    Skip,
+    /// Keep the metadata but count and report the mismatch
+    Warn,
+    /// Trust fuzzy matches as-is
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum EditedPolicy {
+    /// Import the original and the edited copy
+    #[default]
+    Both,
+    /// Import only the edited copy
+    Edited,
+    /// Import only the original
+    Original,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum DateAlbums {
+    /// One album per year (`2020`)
+    Year,
+    /// One album per month (`2020-07`)
+    Month,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum BurstPolicy {
+    /// Import every frame
+    #[default]
+    KeepAll,
+    /// Import only the cover frame
+    CoverOnly,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum GifPolicy {
+    /// Import the GIF file as a photo
+    #[default]
+    Static,
+    /// Convert to a movie and import it as a video
+    Video,
+    /// Leave animated GIFs out
+    Skip,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(outcome) => ExitCode::from(outcome.exit_code()),
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(Outcome::error_exit_code(&e))
+        }
+    }
+}
+
+fn run() -> Result<Outcome> {
+    let mut cli = Cli::parse();
+    display::set_quiet(cli.quiet);
+    display::set_assume_yes(cli.yes);
+    if let Some(fd) = cli.progress_fd {
+        progress::set_progress_fd(fd)?;
+    }
+
+    if let Some(Commands::Run { import, .. } | Commands::Download { import, .. }) =
+        cli.command.as_mut()
+    {
+        import.deadline = import.max_runtime.map(|d| Instant::now() + d);
+    }
 
+    let mut outcome = Outcome::Success;
     match cli.command {
         None => {
             display::print_header("photoferry");
@@ -157,28 +633,72 @@ fn main() -> Result<()> {
             dir,
             once,
             dry_run,
-            verbose,
-            include_trashed,
             retry_failed,
-            strict_extensions,
-            unknown_report,
-        }) => cmd_run(
+            albums,
+            archive_dir,
+            dump_index,
+            two_phase,
+            mut import,
+        }) => {
+            import.album_filter = albums;
+            import.dump_index = dump_index.is_some();
+            import.two_phase = two_phase;
+            outcome = cmd_run(
+                &dir,
+                once,
+                dry_run,
+                retry_failed,
+                &import,
+                archive_dir.as_deref(),
+                dump_index.as_deref(),
+            )?;
+        }
+        Some(Commands::Import { file, metadata }) => cmd_import(&file, metadata.as_deref())?,
+        Some(Commands::Albums { dir, json }) => cmd_albums(&dir, json.as_deref())?,
+        Some(Commands::Verify {
+            dir,
+            relink,
+            manifest,
+            sample,
+            seed,
+            date_tolerance_secs,
+            export_missing,
+        }) => cmd_verify(
             &dir,
-            once,
-            dry_run,
+            relink,
+            manifest.as_deref(),
+            sample,
+            seed,
+            date_tolerance_secs,
+            export_missing.as_deref(),
+        )?,
+        Some(Commands::RetryMissing {
+            dir,
             verbose,
-            include_trashed,
-            retry_failed,
-            strict_extensions,
-            unknown_report.as_deref(),
+            progress_every,
+            relink,
+            no_live_fallback,
+            on_conflict,
+            date_tolerance_secs,
+        }) => cmd_retry_missing(
+            &dir,
+            verbose,
+            progress_every,
+            relink,
+            no_live_fallback,
+            on_conflict,
+            date_tolerance_secs,
         )?,
-        Some(Commands::Import { file, metadata }) => cmd_import(&file, metadata.as_deref())?,
-        Some(Commands::Albums { dir }) => cmd_albums(&dir)?,
-        Some(Commands::Verify { dir }) => cmd_verify(&dir)?,
-        Some(Commands::RetryMissing { dir, verbose }) => cmd_retry_missing(&dir, verbose)?,
-        Some(Commands::RetryLivePhotoFallbacks { dir, verbose }) => {
-            cmd_retry_live_photo_fallbacks(&dir, verbose)?
-        }
+        Some(Commands::FixDates {
+            dir,
+            date_tolerance_secs,
+        }) => cmd_fix_dates(&dir, date_tolerance_secs)?,
+        Some(Commands::RetryLivePhotoFallbacks {
+            dir,
+            verbose,
+            on_conflict,
+        }) => cmd_retry_live_photo_fallbacks(&dir, verbose, on_conflict)?,
+        Some(Commands::RepairManifest { zip, dir }) => cmd_repair_manifest(&zip, dir.as_deref())?,
         Some(Commands::Download {
             job,
             user,
@@ -187,30 +707,51 @@ fn main() -> Result<()> {
             end,
             concurrency,
             download_only,
-            verbose,
-            include_trashed,
-            strict_extensions,
-            unknown_report,
+            import,
             keep_zips,
+            archive_dir,
+            after_part,
             urls_file,
-        }) => cmd_download(
-            &job,
-            &user,
-            &dir,
-            start,
-            end,
-            concurrency,
-            download_only,
-            verbose,
-            include_trashed,
-            strict_extensions,
-            unknown_report.as_deref(),
-            keep_zips,
-            urls_file.as_deref(),
-        )?,
+            max_part_retries,
+            force,
+            pause_on_battery,
+        }) => {
+            outcome = cmd_download(
+                &job,
+                &user,
+                &dir,
+                start,
+                end,
+                concurrency,
+                download_only,
+                &import,
+                keep_zips,
+                archive_dir.as_deref(),
+                after_part.as_deref(),
+                urls_file.as_deref(),
+                max_part_retries,
+                force,
+                pause_on_battery,
+            )?
+        }
+        Some(Commands::VerifyKept {
+            dir,
+            icloud_confirmed,
+        }) => cmd_verify_kept(&dir, icloud_confirmed)?,
+        Some(Commands::Clean {
+            dir,
+            dry_run,
+            force,
+        }) => cmd_clean(&dir, dry_run, force)?,
+        Some(Commands::ListFailed {
+            dir,
+            error_contains,
+        }) => cmd_list_failed(&dir, error_contains.as_deref())?,
+        Some(Commands::Cookies) => cmd_cookies()?,
+        Some(Commands::Probe { job, user, dir }) => cmd_probe(&job, &user, &dir)?,
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
 fn cmd_check() -> Result<()> {
@@ -237,23 +778,28 @@ fn cmd_run(
     dir: &Path,
     once: bool,
     dry_run: bool,
-    verbose: bool,
-    include_trashed: bool,
     retry_failed: bool,
-    strict_extensions: bool,
-    unknown_report: Option<&Path>,
-) -> Result<()> {
+    import: &ImportArgs,
+    archive_dir: Option<&Path>,
+    dump_index: Option<&Path>,
+) -> Result<Outcome> {
     let dir = expand_tilde(dir);
-    if dry_run {
+    let archive_dir = archive_dir.map(expand_tilde);
+    let dump_index = dump_index.map(expand_tilde);
+    // Dumping the index only reads the zips
+    let dry_run = dry_run || dump_index.is_some();
+    if dump_index.is_some() {
+        display::print_header(&format!("Indexing Takeout zips in {}", dir.display()));
+    } else if dry_run {
         display::print_header(&format!("Dry run — scanning {}", dir.display()));
     } else {
         display::print_header(&format!("Processing Takeout zips from {}", dir.display()));
     }
 
-    let zips = takeout::find_takeout_zips(&dir)?;
+    let zips = repack_tarballs(takeout::find_takeout_zips(&dir)?)?;
     if zips.is_empty() {
         display::print_info("No Takeout zips found.");
-        return Ok(());
+        return Ok(Outcome::Success);
     }
 
     display::print_info(&format!("Found {} zip(s)", zips.len()));
@@ -266,37 +812,73 @@ fn cmd_run(
         display::print_success(&format!("Photos access: {} (authorized)", access.status));
     }
 
+    if import.two_phase && dump_index.is_none() {
+        create_albums_up_front(
+            zips_to_process,
+            &dir,
+            dry_run,
+            import,
+            &importer::PhotosImporter,
+        )?;
+    }
+
     let mut total_summary = ImportSummary::default();
+    let mut index_dumps: Vec<ZipIndexDump> = Vec::new();
+    let mut zips_skipped = 0usize;
 
-    for zip_path in zips_to_process {
+    for (n, zip_path) in zips_to_process.iter().enumerate() {
+        if import.past_deadline() {
+            display::print_warning(&format!(
+                "--max-runtime reached — {} zip(s) not started; re-run to continue",
+                zips_to_process.len() - n
+            ));
+            break;
+        }
         display::print_header(&format!(
             "Processing {}",
             zip_path.file_name().unwrap_or_default().to_string_lossy()
         ));
-        match process_one_zip(
-            zip_path,
-            &dir,
-            dry_run,
-            verbose,
-            include_trashed,
-            retry_failed,
-            strict_extensions,
-            unknown_report,
-        ) {
+        match process_one_zip(zip_path, &dir, dry_run, retry_failed, import) {
+            Ok(summary) if dump_index.is_some() => {
+                index_dumps.push(ZipIndexDump {
+                    zip: zip_path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    entries: summary.index,
+                });
+            }
             Ok(summary) => {
                 print_import_summary(&summary);
                 total_summary.merge(&summary);
+                if let Some(archive_dir) = archive_dir.as_deref()
+                    && !dry_run
+                    && summary.failed == 0
+                    && summary.oversized_skipped.is_empty()
+                    && summary.deadline_remaining == 0
+                    && verify_zip_manifest(zip_path, &dir)
+                {
+                    match archive_zip(zip_path, archive_dir) {
+                        Ok(dest) => display::print_success(&format!(
+                            "Verified + archived to {}",
+                            dest.display()
+                        )),
+                        Err(e) => display::print_warning(&format!(
+                            "Verified OK but could not archive zip: {e:#}"
+                        )),
+                    }
+                }
             }
             Err(e) => {
-                let msg = e.to_string();
-                if msg.starts_with(STRICT_EXTENSIONS_ABORT) {
-                    let cleaned = msg
-                        .strip_prefix(STRICT_EXTENSIONS_ABORT)
-                        .unwrap_or(&msg)
-                        .trim_start_matches(':')
-                        .trim();
-                    return Err(anyhow::anyhow!(cleaned.to_string()));
+                // Fatal for the whole run: every later zip would fail the same way
+                if let Some(
+                    ProcessError::StrictExtensionsAbort { .. } | ProcessError::PhotosAccess(_),
+                ) = e.downcast_ref()
+                {
+                    return Err(e);
                 }
+                zips_skipped += 1;
                 display::print_error(&format!(
                     "Skipping {} — {}",
                     zip_path.file_name().unwrap_or_default().to_string_lossy(),
@@ -306,75 +888,382 @@ fn cmd_run(
         }
     }
 
+    if let Some(dump_path) = dump_index.as_deref() {
+        let json = serde_json::to_string_pretty(&index_dumps)?;
+        std::fs::write(dump_path, json)
+            .with_context(|| format!("Failed to write {}", dump_path.display()))?;
+        display::print_success(&format!(
+            "Wrote index of {} zip(s) to {}",
+            index_dumps.len(),
+            dump_path.display()
+        ));
+    }
+
     // Print totals if multiple zips processed
-    if !dry_run && zips_to_process.len() > 1 {
+    if dump_index.is_none() && zips_to_process.len() > 1 {
         println!();
         display::print_header("Total across all zips");
+        if dry_run {
+            let totals = total_summary.dry_run;
+            display::print_summary(&format!(
+                "Would import: {} | Already done: {} | Would retry: {}",
+                totals.would_import, totals.already_done, totals.would_retry_failed
+            ));
+        }
         print_import_summary(&total_summary);
     }
 
-    Ok(())
+    if zips_skipped > 0 || total_summary.failed > 0 {
+        return Ok(Outcome::CompletedWithFailures);
+    }
+    Ok(Outcome::Success)
 }
 
 /// Process a single Takeout zip. Streams directory-by-directory from the ZIP
 /// to avoid extracting the entire archive (peak disk: ~one directory vs full ZIP).
-#[allow(clippy::too_many_arguments)]
 fn process_one_zip(
     zip_path: &Path,
     manifest_dir: &Path,
     dry_run: bool,
-    verbose: bool,
-    include_trashed: bool,
     retry_failed: bool,
-    strict_extensions: bool,
-    unknown_report: Option<&Path>,
+    import: &ImportArgs,
 ) -> Result<ImportSummary> {
+    let library: &dyn importer::Importer = if dry_run {
+        &importer::DryRunImporter::default()
+    } else {
+        &importer::PhotosImporter
+    };
     process_zip_streaming(
         zip_path,
         manifest_dir,
         dry_run,
-        verbose,
-        include_trashed,
         retry_failed,
-        strict_extensions,
-        unknown_report,
+        import,
+        library,
     )
 }
 
+// MARK: - Two-phase album creation
+
+/// Every album title importing `zip_path` would add files to, after album
+/// routing. Reads only directory names and `metadata.json` files.
+fn zip_album_titles(
+    zip_path: &Path,
+    import: &ImportArgs,
+    registry: &mut manifest::AlbumRegistry,
+) -> Result<BTreeSet<String>> {
+    let file = std::fs::File::open(zip_path)
+        .with_context(|| format!("Cannot open ZIP: {}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
+    let prefix = takeout::detect_content_root(archive.file_names()).prefix;
+
+    // Directory → (metadata.json index, media paths)
+    let mut dirs: BTreeMap<PathBuf, (Option<usize>, Vec<PathBuf>)> = BTreeMap::new();
+    for i in 0..archive.len() {
+        let Some(name) = archive.name_for_index(i) else {
+            continue;
+        };
+        let rel = PathBuf::from(name.strip_prefix(prefix.as_str()).unwrap_or(name));
+        let dir = rel.parent().map(Path::to_path_buf).unwrap_or_default();
+        if rel.file_name().and_then(|n| n.to_str()) == Some("metadata.json") {
+            dirs.entry(dir).or_default().0 = Some(i);
+        } else if takeout::media_type_from_path(&rel).is_some() {
+            dirs.entry(dir).or_default().1.push(rel);
+        }
+    }
+
+    let mut titles = BTreeSet::new();
+    for (dir, (metadata_index, media)) in dirs {
+        if media.is_empty() {
+            continue;
+        }
+        let is_year = takeout::is_year_folder(&dir);
+        let mut album = None;
+        if let Some(index) = metadata_index.filter(|_| !is_year) {
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut archive.by_index(index)?, &mut bytes)?;
+            album = metadata::parse_sidecar(&bytes)
+                .ok()
+                .and_then(|m| m.album_data)
+                .map(|a| takeout::sanitize_album_title(&a.title))
+                .filter(|title| !title.is_empty());
+        }
+        if !album_filter_matches(
+            &import.album_filter,
+            album.as_deref(),
+            is_year.then_some(dir.as_path()),
+        ) || (is_year && import.import_albums_only)
+        {
+            continue;
+        }
+        if let Some(title) = album.take() {
+            album = Some(if import.disambiguate_albums {
+                let folder = dir.file_name().unwrap_or_default().to_string_lossy();
+                registry.disambiguate(&title, &folder)?
+            } else {
+                title
+            });
+        }
+
+        // Live Photo videos import with their photo, not on their own
+        let live_pairs = takeout::detect_live_photo_pairs(&media);
+        for path in &media {
+            if live_pairs.values().any(|v| v == path) {
+                continue;
+            }
+            let Some(media_type) = takeout::media_type_from_path(path) else {
+                continue;
+            };
+            if let Some(title) =
+                album_for_media(album.as_deref(), media_type, import.split_media_albums)
+            {
+                titles.insert(title);
+            }
+        }
+    }
+    Ok(titles)
+}
+
+/// `run --two-phase`: create every album the zips need before any import, so
+/// album ids are settled up front. Dry runs only count them.
+fn create_albums_up_front(
+    zips: &[PathBuf],
+    manifest_dir: &Path,
+    dry_run: bool,
+    import: &ImportArgs,
+    library: &dyn importer::Importer,
+) -> Result<()> {
+    display::print_header("Creating albums before import");
+    let mut registry = if dry_run {
+        manifest::AlbumRegistry::load_read_only(manifest_dir)?
+    } else {
+        manifest::AlbumRegistry::load(manifest_dir)?
+    };
+    let mut titles = BTreeSet::new();
+    for zip_path in zips {
+        titles.extend(zip_album_titles(zip_path, import, &mut registry)?);
+    }
+
+    let mut new_titles: Vec<&String> = titles
+        .iter()
+        .filter(|t| registry.get(t).is_none())
+        .collect();
+    let existing = titles.len() - new_titles.len();
+    if let Some(limit) = import.limit_albums {
+        let room = limit.saturating_sub(registry.album_count());
+        if new_titles.len() > room {
+            display::print_info(&format!(
+                "--limit-albums {limit}: creating {room} of {} new albums",
+                new_titles.len()
+            ));
+            new_titles.truncate(room);
+        }
+    }
+    if dry_run {
+        display::print_summary(&format!(
+            "Albums: {} would be created, {existing} already exist",
+            new_titles.len()
+        ));
+        return Ok(());
+    }
+    let mut album_ids: HashMap<String, Option<String>> = HashMap::new();
+    let mut created = 0usize;
+    for title in new_titles {
+        if ensure_album(library, &mut album_ids, &mut registry, title).is_some() {
+            created += 1;
+        }
+    }
+    display::print_summary(&format!(
+        "Albums: {created} created, {existing} already exist"
+    ));
+    Ok(())
+}
+
 // MARK: - Streaming ZIP processor
 
+/// Files imported between Photos access re-checks in `process_zip_streaming`.
+const ACCESS_RECHECK_INTERVAL: usize = 200;
+
+/// Manifest records buffered before `process_zip_streaming` appends them to
+/// the manifest's journal: the most a crash can lose.
+const MANIFEST_FLUSH_INTERVAL: usize = 250;
+
 /// Entry metadata collected during Phase 1 (ZIP indexing).
 struct ZipEntry {
     index: usize,
     relative_path: String,
     filename: String,
+    /// Uncompressed size, for the byte-weighted progress bar
+    size: u64,
+    /// CRC-32 from the ZIP directory, to tell same-named copies apart
+    crc32: u32,
     /// false if filtered out by already_imported / retry_failed
     should_import: bool,
 }
 
-#[derive(Default)]
-struct ZipDirGroup {
-    media: Vec<ZipEntry>,
-    json: Vec<ZipEntry>,
+/// Phase 1's view of one ZIP entry, written out by `run --dump-index`.
+#[derive(Debug, Clone, serde::Serialize)]
+struct IndexEntry {
+    path: String,
+    dir: String,
+    kind: IndexEntryKind,
+    size_bytes: u64,
+    should_import: bool,
 }
 
-/// Stream-process a ZIP file one directory at a time.
-///
-/// Phase 1: Index all ZIP entries by parent directory (no disk I/O).
-/// Phase 2: For each directory, extract its files to a temp dir, run sidecar
-///           matching / live-photo detection / import, then delete the temp files.
-/// Phase 3: Write merged manifest.
-#[allow(clippy::too_many_arguments)]
-fn process_zip_streaming(
-    zip_path: &Path,
-    manifest_dir: &Path,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum IndexEntryKind {
+    Photo,
+    Video,
+    Json,
+    /// Takeout's own archive_browser.html / readme files
+    Bookkeeping,
+    /// Media excluded by `--skip-ext`
+    Excluded,
+    Unknown,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ZipIndexDump {
+    zip: String,
+    entries: Vec<IndexEntry>,
+}
+
+/// `run --dry-run`: media paths by what a real run would do with them (before
+/// `--album` filtering, which needs sidecars).
+#[derive(Debug, Default)]
+struct DryRunLists {
+    would_import: Vec<String>,
+    already_done: Vec<String>,
+    would_retry_failed: Vec<String>,
+}
+
+impl DryRunLists {
+    fn counts(&self) -> DryRunCounts {
+        DryRunCounts {
+            would_import: self.would_import.len(),
+            already_done: self.already_done.len(),
+            would_retry_failed: self.would_retry_failed.len(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct DryRunCounts {
+    would_import: usize,
+    already_done: usize,
+    would_retry_failed: usize,
+}
+
+/// What Phase 1 makes of a ZIP entry's name alone.
+enum ZipEntryClass {
+    /// Outside the Photos folder of a multi-product export
+    OtherProduct,
+    Entry(ClassifiedEntry),
+}
+
+struct ClassifiedEntry {
+    relative: String,
+    dir_key: String,
+    filename: String,
+    /// Lowercase, no dot
+    ext: String,
+    kind: IndexEntryKind,
+}
+
+/// Classify one ZIP entry by path and extension; None for entries with no
+/// file name.
+fn classify_zip_entry(
+    name: &str,
+    content_root: &takeout::ContentRoot,
+    skip_ext: &[String],
+) -> Option<ZipEntryClass> {
+    let relative = match name.strip_prefix(&content_root.prefix) {
+        Some(rest) => rest.to_string(),
+        None if content_root.other_products => return Some(ZipEntryClass::OtherProduct),
+        None => name.to_string(),
+    };
+    let path = Path::new(&relative);
+    let dir_key = path
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let filename = path.file_name()?.to_string_lossy().to_string();
+    let ext = Path::new(&filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let media_type = takeout::classify_extension(&ext);
+    let kind = if ext == "json" {
+        IndexEntryKind::Json
+    } else if media_type.is_some() && skip_ext_matches(skip_ext, &ext) {
+        IndexEntryKind::Excluded
+    } else if let Some(media_type) = media_type {
+        match media_type {
+            takeout::MediaType::Photo => IndexEntryKind::Photo,
+            takeout::MediaType::Video => IndexEntryKind::Video,
+        }
+    } else if takeout::is_takeout_bookkeeping(&filename) {
+        IndexEntryKind::Bookkeeping
+    } else {
+        IndexEntryKind::Unknown
+    };
+    Some(ZipEntryClass::Entry(ClassifiedEntry {
+        relative,
+        dir_key,
+        filename,
+        ext,
+        kind,
+    }))
+}
+
+#[derive(Default)]
+struct ZipDirGroup {
+    media: Vec<ZipEntry>,
+    json: Vec<ZipEntry>,
+}
+
+/// Live Photo pairs across a ZIP, by file name only (same rule Phase 2 uses).
+fn count_live_pairs(dirs: &HashMap<String, ZipDirGroup>) -> usize {
+    dirs.values()
+        .map(|group| {
+            let names: Vec<PathBuf> = group
+                .media
+                .iter()
+                .map(|e| PathBuf::from(&e.filename))
+                .collect();
+            takeout::detect_live_photo_pairs(&names).len()
+        })
+        .sum()
+}
+
+/// Stream-process a ZIP file, extracting one file at a time.
+///
+/// Phase 1: Index all ZIP entries by parent directory (no disk I/O).
+/// Phase 2: For each directory, extract its JSON sidecars to a temp dir and run
+///           sidecar matching / live-photo detection to build an import plan.
+/// Phase 3: Extract each planned file (plus its Live Photo video), import it,
+///           then delete it. `--import-order date` sorts the plan first.
+/// Phase 4: Write merged manifest.
+///
+/// `--dry-run` runs all of it against [`importer::DryRunImporter`] and skips
+/// every manifest and album registry write.
+///
+/// The plan keeps every importable file's path and parsed metadata in memory
+/// (never media bytes) — a few hundred bytes per file.
+fn process_zip_streaming(
+    zip_path: &Path,
+    manifest_dir: &Path,
     dry_run: bool,
-    verbose: bool,
-    include_trashed: bool,
     retry_failed: bool,
-    strict_extensions: bool,
-    unknown_report: Option<&Path>,
+    import: &ImportArgs,
+    library: &dyn importer::Importer,
 ) -> Result<ImportSummary> {
+    let verbose = import.verbose;
     let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
     let zip_name = zip_path.file_name().unwrap_or_default().to_string_lossy();
     let manifest_path = manifest_dir.join(format!(".photoferry-manifest-{}.json", zip_stem));
@@ -420,91 +1309,133 @@ fn process_zip_streaming(
     let mut archive = zip::ZipArchive::new(BufReader::new(file))
         .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
 
-    // Detect "Takeout/" wrapper prefix
-    let content_prefix = {
-        let mut prefix = String::new();
-        for i in 0..archive.len().min(20) {
-            if let Ok(entry) = archive.by_index_raw(i) {
-                if !entry.is_dir() && entry.name().starts_with("Takeout/") {
-                    prefix = "Takeout/".to_string();
-                    break;
-                }
-            }
-        }
-        prefix
-    };
+    // Detect "Takeout/" wrapper prefix (or the Photos folder of a multi-product export)
+    let content_root = takeout::detect_content_root(archive.file_names());
+    let mut other_product_files = 0usize;
 
     let mut dirs: HashMap<String, ZipDirGroup> = HashMap::new();
     let mut unknown_stats = takeout::InventoryStats::default();
     let mut total_photos = 0usize;
     let mut total_videos = 0usize;
     let mut total_to_process = 0usize;
+    let mut total_bytes_to_process = 0u64;
+    let mut oversized_skipped: Vec<OversizedFile> = Vec::new();
+    let mut skipped_by_ext = 0usize;
+    let mut index: Vec<IndexEntry> = Vec::new();
+    let mut dry_run_lists = DryRunLists::default();
+    let previously_failed: HashSet<&str> = existing_manifest
+        .as_ref()
+        .map(|m| m.failed.iter().map(|e| e.path.as_str()).collect())
+        .unwrap_or_default();
 
     for i in 0..archive.len() {
         let entry = archive.by_index_raw(i)?;
         if entry.is_dir() {
             continue;
         }
-        let entry_path = entry.name().to_string();
-        let entry_size = entry.size();
+        let (entry_size, crc32) = (entry.size(), entry.crc32());
+        let class = classify_zip_entry(entry.name(), &content_root, &import.skip_ext);
         drop(entry); // release borrow
-
-        let relative = entry_path
-            .strip_prefix(&content_prefix)
-            .unwrap_or(&entry_path)
-            .to_string();
-
-        let path = Path::new(&relative);
-        let dir_key = path
-            .parent()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let Some(fname) = path.file_name() else {
-            continue;
+        let ClassifiedEntry {
+            relative,
+            dir_key,
+            filename,
+            ext,
+            kind,
+        } = match class {
+            Some(ZipEntryClass::Entry(entry)) => entry,
+            Some(ZipEntryClass::OtherProduct) => {
+                other_product_files += 1;
+                continue;
+            }
+            None => continue,
         };
-        let filename = fname.to_string_lossy().to_string();
-        let ext = Path::new(&filename)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_ascii_lowercase();
-
-        if ext == "json" {
-            dirs.entry(dir_key).or_default().json.push(ZipEntry {
-                index: i,
-                relative_path: relative,
-                filename,
-                should_import: false, // JSON entries are never directly imported
-            });
-        } else if let Some(media_type) = takeout::classify_extension(&ext) {
-            // Always count for summary stats
-            match media_type {
-                takeout::MediaType::Photo => total_photos += 1,
-                takeout::MediaType::Video => total_videos += 1,
-            }
-            // Determine whether this file should be imported
-            let dominated = already_imported.contains(&relative)
-                || (retry_failed && !failed_paths.contains(&relative));
-            if !dominated {
-                total_to_process += 1;
-            }
-            // Always add to the group (needed for live-photo pair detection even
-            // when the file itself is already imported)
-            dirs.entry(dir_key).or_default().media.push(ZipEntry {
-                index: i,
-                relative_path: relative,
-                filename,
-                should_import: !dominated,
-            });
-        } else {
-            unknown_stats.unknown_extensions += 1;
-            if unknown_stats.unknown_examples.len() < 5 {
-                unknown_stats.unknown_examples.push(relative.clone());
+
+        // Keep a copy for `--dump-index`; the branches below move these
+        let index_key = import
+            .dump_index
+            .then(|| (relative.clone(), dir_key.clone()));
+
+        let should_import = match kind {
+            IndexEntryKind::Json => {
+                dirs.entry(dir_key).or_default().json.push(ZipEntry {
+                    index: i,
+                    relative_path: relative,
+                    filename,
+                    size: entry_size,
+                    crc32,
+                    should_import: false, // JSON entries are never directly imported
+                });
+                false
+            }
+            IndexEntryKind::Excluded => {
+                skipped_by_ext += 1;
+                false
+            }
+            IndexEntryKind::Photo | IndexEntryKind::Video => {
+                // Always count for summary stats
+                if kind == IndexEntryKind::Photo {
+                    total_photos += 1;
+                } else {
+                    total_videos += 1;
+                }
+                // Determine whether this file should be imported
+                let mut dominated = already_imported.contains(&relative)
+                    || (retry_failed && !failed_paths.contains(&relative));
+                if !dominated && exceeds_max_file_size(entry_size, import.max_file_size) {
+                    oversized_skipped.push(OversizedFile {
+                        path: relative.clone(),
+                        size_bytes: entry_size,
+                    });
+                    dominated = true;
+                }
+                if !dominated {
+                    total_to_process += 1;
+                    total_bytes_to_process += entry_size;
+                }
+                if dry_run {
+                    if already_imported.contains(&relative) {
+                        dry_run_lists.already_done.push(relative.clone());
+                    } else if !dominated && previously_failed.contains(relative.as_str()) {
+                        dry_run_lists.would_retry_failed.push(relative.clone());
+                    } else if !dominated {
+                        dry_run_lists.would_import.push(relative.clone());
+                    }
+                }
+                // Always add to the group (needed for live-photo pair detection even
+                // when the file itself is already imported)
+                dirs.entry(dir_key).or_default().media.push(ZipEntry {
+                    index: i,
+                    relative_path: relative,
+                    filename,
+                    size: entry_size,
+                    crc32,
+                    should_import: !dominated,
+                });
+                !dominated
+            }
+            IndexEntryKind::Bookkeeping => false,
+            IndexEntryKind::Unknown => {
+                unknown_stats.unknown_extensions += 1;
+                if unknown_stats.unknown_examples.len() < 5 {
+                    unknown_stats.unknown_examples.push(relative.clone());
+                }
+                unknown_stats.unknown_files.push(takeout::UnknownFile {
+                    path: PathBuf::from(&relative),
+                    ext,
+                    size_bytes: entry_size,
+                });
+                false
             }
-            unknown_stats.unknown_files.push(takeout::UnknownFile {
-                path: PathBuf::from(&relative),
-                ext,
+        };
+
+        if let Some((path, dir)) = index_key {
+            index.push(IndexEntry {
+                path,
+                dir,
+                kind,
                 size_bytes: entry_size,
+                should_import,
             });
         }
     }
@@ -512,6 +1443,20 @@ fn process_zip_streaming(
     // Phase 1 summary
     display::print_info(&format!("Photos: {}", total_photos));
     display::print_info(&format!("Videos: {}", total_videos));
+    let live_pairs = count_live_pairs(&dirs);
+    if live_pairs > 0 {
+        display::print_info(&format!(
+            "Live Photo pairs: {live_pairs} (standalone photos: {}, standalone videos: {})",
+            total_photos - live_pairs,
+            total_videos - live_pairs
+        ));
+    }
+    if other_product_files > 0 {
+        display::print_info(&format!(
+            "Other Takeout products: {} files outside {} (skipping)",
+            other_product_files, content_root.prefix
+        ));
+    }
     if !already_imported.is_empty() {
         display::print_info(&format!(
             "Already imported: {} (skipping)",
@@ -531,29 +1476,74 @@ fn process_zip_streaming(
         }
     }
 
-    if let Some(report_path) = unknown_report {
-        write_unknown_report(report_path, zip_name.as_ref(), &unknown_stats.unknown_files)?;
+    if !oversized_skipped.is_empty() {
+        display::print_warning(&format!(
+            "Over --max-file-size (skipped): {}",
+            oversized_skipped.len()
+        ));
     }
-    if strict_extensions && unknown_stats.unknown_extensions > 0 {
-        let examples = if unknown_stats.unknown_examples.is_empty() {
-            "<none>".to_string()
-        } else {
-            unknown_stats.unknown_examples.join(", ")
-        };
-        return Err(anyhow::anyhow!(format!(
-            "{STRICT_EXTENSIONS_ABORT}: Unknown extensions detected ({}). Examples: {}. Re-run without --strict-extensions to proceed.",
-            unknown_stats.unknown_extensions, examples
-        )));
+    if skipped_by_ext > 0 {
+        display::print_info(&format!("Excluded by --skip-ext: {skipped_by_ext}"));
+    }
+
+    if import.dump_index {
+        return Ok(ImportSummary {
+            index,
+            ..Default::default()
+        });
+    }
+
+    if let Some(report_path) = import.unknown_report.as_deref() {
+        write_unknown_report(
+            report_path,
+            zip_name.as_ref(),
+            &unknown_stats.unknown_files,
+            import.force_unknown_report,
+        )?;
+    }
+    if import.strict_extensions && unknown_stats.unknown_extensions > 0 {
+        return Err(ProcessError::StrictExtensionsAbort {
+            count: unknown_stats.unknown_extensions,
+            examples: unknown_stats.unknown_examples,
+        }
+        .into());
     }
     if dry_run {
-        return Ok(ImportSummary::default());
+        let counts = dry_run_lists.counts();
+        display::print_info(&format!(
+            "Dry run: {} would import | {} already done | {} would retry (previously failed)",
+            counts.would_import, counts.already_done, counts.would_retry_failed
+        ));
+        if verbose {
+            for (mark, paths) in [
+                ("+", &dry_run_lists.would_import),
+                ("↻", &dry_run_lists.would_retry_failed),
+                ("=", &dry_run_lists.already_done),
+            ] {
+                for path in paths {
+                    println!("  {mark} {path}");
+                }
+            }
+        }
     }
+    let dry_run_counts = dry_run_lists.counts();
     if total_to_process == 0 {
         display::print_warning("No media files to import.");
-        return Ok(ImportSummary::default());
+        return Ok(ImportSummary {
+            oversized_skipped,
+            skipped_by_ext,
+            dry_run: dry_run_counts,
+            simulated: dry_run,
+            ..Default::default()
+        });
     }
 
-    // ── Phase 2: Process each directory ──────────────────────────────────
+    // ── Phase 2: Plan imports, one directory at a time ──────────────────
+    //
+    // Only JSON sidecars are extracted here. Sidecar matching and Live Photo
+    // pairing work on file names, so media stays in the ZIP until its turn to
+    // import. The plan holds paths and parsed metadata (no media bytes), which
+    // is what lets `--import-order date` sort across the whole ZIP.
 
     // Clean stale temp dir
     if tmp_dir.exists() {
@@ -561,19 +1551,58 @@ fn process_zip_streaming(
     }
 
     let start = Instant::now();
-    let mut summary = ImportSummary::default();
-    let mut album_ids: HashMap<String, String> = HashMap::new();
-    let mut all_imported: Vec<(String, String, Option<String>, bool)> = Vec::new();
-    let mut all_failed: Vec<(String, String)> = Vec::new();
-    let mut all_live_fallbacks: Vec<(String, String, String)> = Vec::new();
+    let mut summary = ImportSummary {
+        oversized_skipped,
+        skipped_by_ext,
+        dry_run: dry_run_counts,
+        simulated: dry_run,
+        ..Default::default()
+    };
+    // Album title → Photos album id (None if creation failed; not retried this zip)
+    let mut album_ids: HashMap<String, Option<String>> = HashMap::new();
+    let mut album_registry = if dry_run {
+        manifest::AlbumRegistry::load_read_only(manifest_dir)?
+    } else {
+        manifest::AlbumRegistry::load(manifest_dir)?
+    };
+    // `--album-key-photo`: album title → its earliest-dated import so far
+    let mut earliest_in_album = BTreeMap::new();
+    // `--into-album-id`: every file goes to one existing album, so seed the
+    // cache with it and never create albums
+    let into_album = match import.into_album_id.as_deref() {
+        Some(album_id) => {
+            let Some(title) = library.album_title(album_id)? else {
+                bail!("--into-album-id {album_id}: no such album in Photos");
+            };
+            album_ids.insert(title.clone(), Some(album_id.to_string()));
+            Some(title)
+        }
+        None => None,
+    };
+    let mut records = manifest::ManifestAppender::new(
+        &manifest_path,
+        &zip_name,
+        MANIFEST_FLUSH_INTERVAL,
+        !dry_run,
+    );
+    records.set_layout(manifest::LayoutInfo {
+        content_prefix: content_root.prefix.clone(),
+        directories: dirs.len(),
+        standard_takeout: takeout::is_standard_takeout(
+            &content_root,
+            dirs.keys().map(String::as_str),
+        ),
+    });
 
-    let pb = if verbose {
+    let pb = if verbose || display::is_quiet() {
         ProgressBar::hidden()
     } else {
-        let pb = ProgressBar::new(total_to_process as u64);
+        // Weighted by bytes, not files: a directory of thumbnails followed by
+        // one of large videos would otherwise wreck the ETA
+        let pb = ProgressBar::new(total_bytes_to_process);
         pb.set_style(
             ProgressStyle::with_template(
-                "[{bar:40}] {pos}/{len} {per_sec:.1}/s ETA {eta} {msg}",
+                "[{bar:40}] {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta} {msg}",
             )
             .unwrap_or_else(|_| ProgressStyle::default_bar())
             .progress_chars("##-"),
@@ -581,6 +1610,35 @@ fn process_zip_streaming(
         pb
     };
 
+    struct PlannedImport {
+        zip_index: usize,
+        relative_path: String,
+        filename: String,
+        media_type: takeout::MediaType,
+        photo_metadata: Option<importer::PhotoMetadata>,
+        album: Option<String>,
+        /// Paired Live Photo video: ZIP index and relative path
+        live_video: Option<(usize, String)>,
+        /// `album` comes from `--date-albums`, not a Takeout album
+        date_album: bool,
+        /// Where Phase 3 extracts the media and paired video (see
+        /// [`temp_extract_path`]); manifests keep the relative paths
+        disk_path: PathBuf,
+        video_disk: Option<PathBuf>,
+        /// Relative path of the sidecar `photo_metadata` was parsed from
+        sidecar_path: Option<String>,
+        /// How that sidecar was matched (`run --dry-run --verbose` reports it)
+        sidecar_strength: Option<sidecar::SidecarMatchStrength>,
+        /// Media plus paired video bytes; what the progress bar advances by
+        size_bytes: u64,
+        /// The media entry's CRC-32
+        crc32: u32,
+        /// Other copies of the same file in this zip (relative path, album):
+        /// recorded against this file's asset and added to their albums
+        copies: Vec<(String, Option<String>)>,
+    }
+    let mut plan: Vec<PlannedImport> = Vec::new();
+
     let mut dir_keys: Vec<String> = dirs.keys().cloned().collect();
     dir_keys.sort();
 
@@ -597,88 +1655,134 @@ fn process_zip_streaming(
         } else {
             tmp_dir.join(dir_key)
         };
-        std::fs::create_dir_all(&extract_dir)?;
+        // Over the path limits it can't be created: its media still imports
+        // under shortened names (see `temp_extract_path`), without sidecars
+        let dir_created = match std::fs::create_dir_all(&extract_dir) {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidFilename => {
+                pb.println(format!(
+                    "  ! {dir_key}: path too long for sidecars — importing without metadata"
+                ));
+                false
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         // Extract JSON sidecars
         let mut json_paths = Vec::new();
-        for je in &group.json {
+        for je in group.json.iter().filter(|_| dir_created) {
             let dest = extract_dir.join(&je.filename);
-            let mut zf = archive.by_index(je.index)?;
-            let mut out = std::fs::File::create(&dest)?;
-            std::io::copy(&mut zf, &mut out)?;
+            // A corrupt sidecar only costs its media the metadata
+            if let Err(e) = extract_zip_entry(&mut archive, je.index, &dest) {
+                pb.println(format!(
+                    "  ! Could not extract {} — {e:#}",
+                    je.relative_path
+                ));
+                let _ = std::fs::remove_file(&dest);
+                continue;
+            }
             json_paths.push(dest);
         }
 
-        // Extract ALL media (including already-imported, needed for live-pair detection)
-        struct ExtractedMedia {
-            disk_path: PathBuf,
-            relative_path: String,
-            should_import: bool,
-        }
-        let mut media_map: Vec<ExtractedMedia> = Vec::new();
-        for me in &group.media {
-            let dest = extract_dir.join(&me.filename);
-            let mut zf = archive.by_index(me.index)?;
-            let mut out = std::fs::File::create(&dest)?;
-            std::io::copy(&mut zf, &mut out)?;
-            media_map.push(ExtractedMedia {
-                disk_path: dest,
-                relative_path: me.relative_path.clone(),
-                should_import: me.should_import,
-            });
-        }
+        // Where each media file will land once extracted (ALL media, including
+        // already-imported, needed for live-pair detection)
+        let media_paths: Vec<PathBuf> = group
+            .media
+            .iter()
+            .map(|me| extract_dir.join(&me.filename))
+            .collect();
 
         // ── Per-directory analysis (mirrors scan_directory logic) ────────
 
         // Album detection
         let dir_path = Path::new(dir_key);
-        let album = takeout::detect_album(dir_path, &json_paths);
+        let album = match takeout::detect_album_with_original(dir_path, &json_paths) {
+            Some((title, original)) => {
+                if verbose && title != original {
+                    pb.println(format!("  ~ album {original:?} imported as {title:?}"));
+                }
+                Some(title)
+            }
+            None => None,
+        };
         let is_year = takeout::is_year_folder(dir_path);
         let effective_album = if is_year { None } else { album };
 
-        // Ensure album exists in Photos.app
-        if let Some(ref album_name) = effective_album {
-            if !album_ids.contains_key(album_name) {
-                match importer::create_album(album_name) {
-                    Ok(id) => {
-                        album_ids.insert(album_name.clone(), id);
-                    }
-                    Err(e) => {
-                        display::print_warning(&format!(
-                            "Failed to create album '{}': {}",
-                            album_name, e
-                        ));
-                    }
-                }
-            }
+        if !album_filter_matches(
+            &import.album_filter,
+            effective_album.as_deref(),
+            is_year.then_some(dir_path),
+        ) {
+            let skipped: Vec<&ZipEntry> = group.media.iter().filter(|e| e.should_import).collect();
+            summary.skipped_by_album_filter += skipped.len();
+            pb.inc(skipped.iter().map(|e| e.size).sum());
+            let _ = std::fs::remove_dir_all(&tmp_dir);
+            continue;
+        }
+        if is_year && import.import_albums_only {
+            let skipped: Vec<&ZipEntry> = group.media.iter().filter(|e| e.should_import).collect();
+            summary.skipped_year_folder += skipped.len();
+            pb.inc(skipped.iter().map(|e| e.size).sum());
+            let _ = std::fs::remove_dir_all(&tmp_dir);
+            continue;
         }
+        let effective_album = match effective_album {
+            Some(title) if import.disambiguate_albums => {
+                let folder = dir_path.file_name().unwrap_or_default().to_string_lossy();
+                Some(album_registry.disambiguate(&title, &folder)?)
+            }
+            other => other,
+        };
 
         // Sidecar candidates
-        let all_disk_files: Vec<PathBuf> = json_paths
-            .iter()
-            .chain(media_map.iter().map(|m| &m.disk_path))
-            .cloned()
-            .collect();
+        let all_disk_files: Vec<PathBuf> = json_paths.iter().chain(&media_paths).cloned().collect();
         let json_candidates = sidecar::collect_json_candidates(&all_disk_files);
 
+        // Sidecars no media claims (matched against ALL media, so an
+        // already-imported file's sidecar isn't counted)
+        for orphan in sidecar::orphaned_sidecars(&media_paths, &json_candidates) {
+            summary.orphaned_sidecars += 1;
+            if verbose {
+                let rel = orphan.strip_prefix(&tmp_dir).unwrap_or(orphan);
+                pb.println(format!("  ? {} — sidecar matches no media", rel.display()));
+            }
+        }
+
         // Live Photo pairs (uses ALL media files including already-imported)
-        let disk_media_paths: Vec<PathBuf> =
-            media_map.iter().map(|m| m.disk_path.clone()).collect();
-        let live_pairs = takeout::detect_live_photo_pairs(&disk_media_paths);
+        let live_pairs = takeout::detect_live_photo_pairs(&media_paths);
+
+        // Originals/edited copies left out by `--edited-policy` (again ALL
+        // media: the other half may have been imported already)
+        let edited_skips = edited_policy_skips(&media_paths, import.edited_policy);
+
+        // Burst frames often share one sidecar, so only some get its metadata
+        let bursts = takeout::detect_burst_sequences(&media_paths);
+        if verbose {
+            for (key, frames) in &bursts {
+                pb.println(format!(
+                    "  ~ burst {key} in {}: {} frames (metadata may cover only some)",
+                    dir_path.display(),
+                    frames.len()
+                ));
+            }
+        }
+        let burst_skips: HashSet<&PathBuf> = if import.burst_policy == BurstPolicy::CoverOnly {
+            bursts
+                .values()
+                .flat_map(|f| f.iter().skip(1).copied())
+                .collect()
+        } else {
+            HashSet::new()
+        };
 
         // Truncation collision detection
         let mut truncation_counts: HashMap<String, Vec<PathBuf>> = HashMap::new();
-        for em in &media_map {
-            let name = em
-                .disk_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-            if let Some(trunc) = sidecar::truncated_media_base(name) {
+        for (me, disk_path) in group.media.iter().zip(&media_paths) {
+            if let Some(trunc) = sidecar::truncated_media_base(&me.filename) {
                 truncation_counts
                     .entry(trunc)
                     .or_default()
-                    .push(em.disk_path.clone());
+                    .push(disk_path.clone());
             }
         }
         let ambiguous_truncations: HashSet<String> = truncation_counts
@@ -687,305 +1791,736 @@ fn process_zip_streaming(
             .map(|(k, _)| k.clone())
             .collect();
 
-        // ── Import each media file ──────────────────────────────────────
-
-        for em in &media_map {
-            // Skip already-imported (they were extracted only for live-pair detection)
-            if !em.should_import {
+        for (me, disk_path) in group.media.iter().zip(&media_paths) {
+            // Skip already-imported (they were indexed only for live-pair detection)
+            if !me.should_import {
                 continue;
             }
 
-            let filename = em
-                .disk_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .into_owned();
-            pb.set_message(filename.clone());
-
-            let ext = em
-                .disk_path
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("");
-            let Some(media_type) = takeout::classify_extension(ext) else {
-                pb.inc(1);
+            let Some(media_type) = takeout::media_type_from_path(disk_path) else {
+                pb.inc(me.size);
                 continue;
             };
 
             // Skip videos that are Live Photo pair components
             if media_type == takeout::MediaType::Video
-                && live_pairs.values().any(|v| v == &em.disk_path)
+                && live_pairs.values().any(|v| v == disk_path)
             {
-                // Not counted in total_to_process, so don't increment pb
+                // Its bytes are advanced along with the paired photo
                 continue;
             }
 
             // Sidecar matching
-            let sidecar_match = if sidecar::truncated_media_base(&filename)
+            let sidecar_match = if sidecar::truncated_media_base(&me.filename)
                 .as_ref()
                 .is_some_and(|t| ambiguous_truncations.contains(t))
             {
-                None // truncation collision — skip sidecar
+                // Truncation collision — any match could be the wrong file's
+                // sidecar, so import without metadata
+                summary.ambiguous_truncation_skipped += 1;
+                if verbose {
+                    pb.println(format!(
+                        "  ? {} — truncated name collides with another file, no sidecar used",
+                        me.relative_path
+                    ));
+                }
+                None
             } else {
-                sidecar::find_sidecar_with_strength(&em.disk_path, &json_candidates)
+                sidecar::find_sidecar_with_strength(disk_path, &json_candidates)
             };
 
             let sidecar_path = sidecar_match.as_ref().map(|m| m.path.clone());
             let sidecar_strength = sidecar_match.as_ref().map(|m| m.strength);
-            let takeout_meta = sidecar_path.as_ref().and_then(|sp| {
-                let bytes = std::fs::read(sp).ok()?;
-                metadata::parse_sidecar(&bytes).ok()
+            let parsed = sidecar_path.as_ref().map(|sp| {
+                std::fs::read(sp)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|bytes| metadata::parse_sidecar(&bytes))
             });
+            let mut takeout_meta = match parsed {
+                Some(Ok(meta)) => Some(meta),
+                Some(Err(e)) => {
+                    summary.unparseable_sidecars += 1;
+                    let err = format!("Unparseable sidecar: {e:#}");
+                    if verbose {
+                        pb.println(format!("  ? {} — {err}", me.relative_path));
+                    }
+                    if import.strict_metadata {
+                        summary.record_failure(ImportFailure {
+                            path: me.relative_path.clone(),
+                            error: err.clone(),
+                        });
+                        records.failed(manifest::ManifestFailure {
+                            path: me.relative_path.clone(),
+                            error: err,
+                        })?;
+                        pb.inc(me.size);
+                        continue;
+                    }
+                    None
+                }
+                None => None,
+            };
 
-            // Trashed check
-            let is_trashed = takeout_meta.as_ref().is_some_and(|m| m.is_trashed());
-            let is_strong =
-                sidecar_strength == Some(sidecar::SidecarMatchStrength::Strong);
-            if is_trashed && is_strong && !include_trashed {
-                pb.inc(1);
-                continue;
+            // A fuzzy match whose title names some other file grabbed the wrong
+            // sidecar; its date/GPS would be silently wrong
+            if sidecar_strength == Some(sidecar::SidecarMatchStrength::Fuzzy)
+                && import.fuzzy_title_check != FuzzyTitleCheck::Off
+                && let Some(title) = takeout_meta.as_ref().and_then(|m| m.title.as_deref())
+                && !sidecar::title_matches_media(title, &me.filename)
+            {
+                summary.fuzzy_title_mismatches += 1;
+                if verbose {
+                    pb.println(format!(
+                        "  ? {} — fuzzy sidecar is titled '{}'{}",
+                        me.relative_path,
+                        title,
+                        if import.fuzzy_title_check == FuzzyTitleCheck::Skip {
+                            ", metadata not used"
+                        } else {
+                            ""
+                        }
+                    ));
+                }
+                if import.fuzzy_title_check == FuzzyTitleCheck::Skip {
+                    takeout_meta = None;
+                }
             }
 
-            let photo_metadata = takeout_meta.as_ref().map(|m| m.to_photo_metadata());
-
-            let live_photo_pair = if media_type == takeout::MediaType::Photo {
-                live_pairs.get(&em.disk_path).cloned()
+            // Trashed check
+            let is_trashed = takeout_meta.as_ref().is_some_and(|m| m.is_trashed());
+            let is_strong = sidecar_strength == Some(sidecar::SidecarMatchStrength::Strong);
+            let live_entry = if media_type == takeout::MediaType::Photo {
+                live_pairs.get(disk_path).and_then(|video| {
+                    group
+                        .media
+                        .iter()
+                        .zip(&media_paths)
+                        .find(|(_, p)| *p == video)
+                        .map(|(ve, _)| ve)
+                })
             } else {
                 None
             };
+            let size_bytes = me.size + live_entry.map_or(0, |ve| ve.size);
 
-            // Import the file
-            let path_str = match em.disk_path.to_str() {
-                Some(p) => p,
-                None => {
-                    let err = "Invalid UTF-8 file path".to_string();
-                    summary.failed.push(ImportFailure {
-                        path: em.relative_path.clone(),
-                        error: err.clone(),
-                    });
-                    all_failed.push((em.relative_path.clone(), err));
-                    pb.inc(1);
-                    continue;
+            if is_trashed && is_strong && !import.include_trashed {
+                pb.inc(size_bytes);
+                continue;
+            }
+            if edited_skips.contains(disk_path) {
+                summary.skipped_by_edited_policy += 1;
+                pb.inc(size_bytes);
+                continue;
+            }
+            if burst_skips.contains(disk_path) {
+                summary.skipped_burst_frames += 1;
+                pb.inc(size_bytes);
+                continue;
+            }
+            if let Some(min_people) = import.min_people
+                && takeout_meta.as_ref().map_or(0, |m| m.people_count()) < min_people
+            {
+                summary.skipped_by_people += 1;
+                pb.inc(size_bytes);
+                continue;
+            }
+            // A fuzzy match's favorite flag may belong to another file
+            let is_favorite = is_strong
+                && takeout_meta
+                    .as_ref()
+                    .is_some_and(|m| m.favorited == Some(true));
+            if import.favorites_only && !is_favorite {
+                summary.skipped_not_favorite += 1;
+                pb.inc(size_bytes);
+                continue;
+            }
+            let live_video = live_entry.map(|ve| (ve.index, ve.relative_path.clone()));
+            let (disk_path, shortened) = temp_extract_path(&tmp_dir, &me.relative_path);
+            let video_disk = live_video.as_ref().map(|(_, rel)| {
+                let (path, video_shortened) = temp_extract_path(&tmp_dir, rel);
+                summary.shortened_paths += usize::from(video_shortened);
+                path
+            });
+            if shortened {
+                summary.shortened_paths += 1;
+                if verbose {
+                    pb.println(format!(
+                        "  ~ {} — path too long to extract as-is, using a shortened temp name",
+                        me.relative_path
+                    ));
                 }
-            };
+            }
+            let sidecar_strength = sidecar_strength.filter(|_| takeout_meta.is_some());
+            let sidecar_path = sidecar_path
+                .filter(|_| takeout_meta.is_some())
+                .and_then(|sp| {
+                    Some(
+                        sp.strip_prefix(&tmp_dir)
+                            .ok()?
+                            .to_string_lossy()
+                            .into_owned(),
+                    )
+                });
 
-            let mut used_live_fallback = false;
-            let import_result = if let Some(ref video_disk) = live_photo_pair {
-                let live_result = match video_disk.to_str() {
-                    Some(vstr) => {
-                        importer::import_live_photo(path_str, vstr, photo_metadata.as_ref())
-                    }
-                    None => Err(anyhow::anyhow!("Invalid UTF-8 in Live Photo video path")),
-                };
-                match live_result {
-                    Ok(r) if r.success => Ok(r),
-                    Ok(r) => {
-                        let live_err = r
-                            .error
-                            .clone()
-                            .unwrap_or_else(|| "Live Photo import failed".to_string());
-                        match importer::import_photo(path_str, photo_metadata.as_ref(), false) {
-                            Ok(fb) if fb.success => {
-                                used_live_fallback = true;
-                                Ok(fb)
-                            }
-                            Ok(fb) => {
-                                let fb_err = fb
-                                    .error
-                                    .unwrap_or_else(|| "Fallback failed".to_string());
-                                Ok(importer::ImportResult {
-                                    success: false,
-                                    local_identifier: None,
-                                    error: Some(format!(
-                                        "Live Photo failed ({live_err}); fallback failed ({fb_err})"
-                                    )),
-                                })
-                            }
-                            Err(e) => Err(anyhow::anyhow!(
-                                "Live Photo failed ({live_err}); fallback error: {e}"
-                            )),
-                        }
-                    }
-                    Err(err) => {
-                        match importer::import_photo(path_str, photo_metadata.as_ref(), false) {
-                            Ok(fb) if fb.success => {
-                                used_live_fallback = true;
-                                Ok(fb)
-                            }
-                            Ok(fb) => {
-                                let fb_err = fb
-                                    .error
-                                    .unwrap_or_else(|| "Fallback failed".to_string());
-                                Ok(importer::ImportResult {
-                                    success: false,
-                                    local_identifier: None,
-                                    error: Some(format!(
-                                        "Live Photo error ({err}); fallback failed ({fb_err})"
-                                    )),
-                                })
-                            }
-                            Err(e) => Err(anyhow::anyhow!(
-                                "Live Photo error ({err}); fallback error: {e}"
-                            )),
-                        }
-                    }
+            let mut photo_metadata = takeout_meta.as_ref().map(|m| m.to_photo_metadata());
+            if import.timezone_from_gps
+                && let Some(meta) = photo_metadata.as_mut()
+                && meta.latitude.is_some()
+            {
+                if localize_creation_date(meta, library) {
+                    summary.dates_localized += 1;
+                } else {
+                    summary.dates_not_localized += 1;
                 }
-            } else {
-                let is_video = matches!(media_type, takeout::MediaType::Video);
-                importer::import_photo(path_str, photo_metadata.as_ref(), is_video)
+            }
+
+            let date_album = match import.date_albums {
+                Some(granularity) if is_year => date_album_title(
+                    granularity,
+                    photo_metadata
+                        .as_ref()
+                        .and_then(|m| m.creation_date.as_deref()),
+                ),
+                _ => None,
             };
 
-            match import_result {
-                Ok(result) if result.success => {
-                    let Some(local_id) = result.local_identifier.clone() else {
-                        let err =
-                            "import succeeded but no local identifier returned".to_string();
-                        summary.failed.push(ImportFailure {
-                            path: em.relative_path.clone(),
-                            error: err.clone(),
-                        });
-                        all_failed.push((em.relative_path.clone(), err));
-                        pb.inc(1);
-                        continue;
-                    };
+            plan.push(PlannedImport {
+                zip_index: me.index,
+                relative_path: me.relative_path.clone(),
+                filename: me.filename.clone(),
+                media_type,
+                photo_metadata,
+                date_album: date_album.is_some(),
+                album: date_album.or_else(|| effective_album.clone()),
+                live_video,
+                disk_path,
+                video_disk,
+                sidecar_path,
+                sidecar_strength,
+                size_bytes,
+                crc32: me.crc32,
+                copies: Vec::new(),
+            });
+        }
 
-                    if used_live_fallback {
-                        summary.live_photo_fallbacks += 1;
-                        if let Some(video_disk) = live_photo_pair.as_ref() {
-                            let video_fname = video_disk
-                                .file_name()
-                                .map(|f| f.to_string_lossy().to_string())
-                                .unwrap_or_default();
-                            let video_rel = if dir_key.is_empty() {
-                                video_fname
-                            } else {
-                                format!("{}/{}", dir_key, video_fname)
-                            };
-                            summary
-                                .live_photo_fallback_entries
-                                .push(LivePhotoFallback {
-                                    photo_path: PathBuf::from(&em.relative_path),
-                                    video_path: PathBuf::from(&video_rel),
-                                    local_id: local_id.clone(),
-                                });
-                            all_live_fallbacks.push((
-                                em.relative_path.clone(),
-                                video_rel,
-                                local_id.clone(),
-                            ));
-                        }
-                        pb.println(format!(
-                            "  ! Live Photo import failed; imported still photo only: {}",
-                            em.relative_path
-                        ));
-                    }
+        // Sidecars are parsed into the plan; drop them before the next directory
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+    }
 
-                    let is_live = live_photo_pair.is_some() && !used_live_fallback;
-                    let creation_date =
-                        photo_metadata.as_ref().and_then(|m| m.creation_date.clone());
+    // Takeout puts a photo in its year folder and in each album it's in, as
+    // separate copies. Same name, size and CRC is the same file: import it once.
+    let mut first_copy: HashMap<(String, u64, u32), usize> = HashMap::new();
+    let mut unique: Vec<PlannedImport> = Vec::with_capacity(plan.len());
+    for item in plan {
+        let key = (item.filename.clone(), item.size_bytes, item.crc32);
+        if let Some(&first) = first_copy.get(&key) {
+            let kept = &mut unique[first];
+            if kept.photo_metadata.is_none() {
+                kept.photo_metadata = item.photo_metadata;
+                kept.sidecar_path = item.sidecar_path;
+                kept.sidecar_strength = item.sidecar_strength;
+            }
+            kept.copies.push((item.relative_path, item.album));
+            summary.duplicate_copies += 1;
+            pb.inc(item.size_bytes);
+        } else {
+            first_copy.insert(key, unique.len());
+            unique.push(item);
+        }
+    }
+    let mut plan = unique;
+
+    if import.import_order == ImportOrder::Date {
+        // Stable sort: files with the same (or no) date keep path order
+        plan.sort_by_key(|item| {
+            import_date_key(
+                item.photo_metadata
+                    .as_ref()
+                    .and_then(|m| m.creation_date.as_deref()),
+            )
+        });
+    }
 
-                    summary.imported.push(ImportedFile {
-                        path: PathBuf::from(&em.relative_path),
-                        local_id: local_id.clone(),
-                        album: effective_album.clone(),
-                        creation_date: creation_date.clone(),
-                        is_live_photo: is_live,
-                    });
-                    all_imported.push((
-                        em.relative_path.clone(),
-                        local_id.clone(),
-                        creation_date,
-                        is_live,
-                    ));
+    // ── Phase 3: Extract and import each planned file ───────────────────
+
+    // `--into-album-id` creates no albums, so the cap doesn't apply
+    let album_limit = import.limit_albums.filter(|_| into_album.is_none());
+
+    for (n, item) in plan.iter().enumerate() {
+        // Access can be revoked or downgraded to "limited" while a long import
+        // runs, after which every import fails — re-check and stop early.
+        if n.is_multiple_of(ACCESS_RECHECK_INTERVAL)
+            && let Err(e) = library
+                .check_access()
+                .and_then(|access| ensure_full_photos_access(&access, "import"))
+        {
+            pb.finish_and_clear();
+            let _ = std::fs::remove_dir_all(&tmp_dir);
+            records.flush()?;
+            // Keep the `ProcessError::PhotosAccess` underneath so callers stop
+            // the whole run and exit with the permission-denied code
+            return Err(e.context(format!(
+                "Photos access check failed after {n} of {} files — aborting; progress saved, re-run to resume",
+                plan.len()
+            )));
+        }
+        if import.past_deadline() {
+            summary.deadline_remaining = plan.len() - n;
+            break;
+        }
 
-                    // Album assignment
-                    if let Some(album_name) = effective_album.as_ref()
-                        && let Some(album_id) = album_ids.get(album_name)
-                        && let Some(actual_id) = result.local_identifier.as_deref()
-                    {
-                        match importer::add_to_album(album_id, actual_id) {
-                            Ok(true) => {}
-                            Ok(false) => {
-                                pb.println(format!(
-                                    "  ! Failed to add '{}' to album '{}'",
-                                    filename, album_name
-                                ));
-                            }
-                            Err(e) => {
-                                pb.println(format!(
-                                    "  ! Failed to add '{}' to album '{}': {}",
-                                    filename, album_name, e
-                                ));
-                            }
-                        }
-                    }
+        pb.set_message(item.filename.clone());
+        progress::emit(
+            "import",
+            pb.position(),
+            total_bytes_to_process,
+            Some(&item.relative_path),
+        );
 
+        let disk_path = item.disk_path.clone();
+        let video_disk = item.video_disk.clone();
+        // A dry run imports nothing, so extracts nothing; the checks below
+        // that look inside a file read its ZIP entry instead
+        let extracted = if dry_run {
+            Ok(())
+        } else {
+            extract_zip_entry(&mut archive, item.zip_index, &disk_path).and_then(|()| {
+                match (item.live_video.as_ref(), video_disk.as_deref()) {
+                    (Some((video_index, _)), Some(dest)) => {
+                        extract_zip_entry(&mut archive, *video_index, dest)
+                    }
+                    _ => Ok(()),
+                }
+            })
+        };
+        if let Err(e) = extracted {
+            remove_extracted(&disk_path, video_disk.as_deref());
+            // Out of disk fails every file after it; stop instead
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|io| io.kind() == std::io::ErrorKind::StorageFull)
+            {
+                pb.finish_and_clear();
+                let _ = std::fs::remove_dir_all(&tmp_dir);
+                records.flush()?;
+                return Err(
+                    e.context("Disk full while extracting — progress saved, re-run to resume")
+                );
+            }
+            // A corrupt entry (bad CRC, truncated data) fails only this file
+            let err = format!("Extraction failed: {e:#}");
+            summary.record_failure(ImportFailure {
+                path: item.relative_path.clone(),
+                error: err.clone(),
+            });
+            records.failed(manifest::ManifestFailure {
+                path: item.relative_path.clone(),
+                error: err.clone(),
+            })?;
+            if verbose {
+                pb.println(format!("  ! {} — {}", item.filename, err));
+            }
+            pb.inc(item.size_bytes);
+            continue;
+        }
+        let live_photo_pair = video_disk;
+        let photo_metadata = &item.photo_metadata;
+        let target_album = into_album.clone().or_else(|| {
+            album_for_media(
+                item.album.as_deref(),
+                item.media_type,
+                import.split_media_albums,
+            )
+        });
+
+        // Animated GIFs (see `--gif-policy`)
+        let mut gif_movie: Option<PathBuf> = None;
+        let animated_gif = item.media_type == takeout::MediaType::Photo
+            && if dry_run {
+                archive
+                    .by_index(item.zip_index)
+                    .is_ok_and(|entry| takeout::is_animated_gif_in(&disk_path, entry))
+            } else {
+                takeout::is_animated_gif(&disk_path)
+            };
+        if animated_gif {
+            match import.gif_policy {
+                GifPolicy::Static => summary.animated_gifs_static += 1,
+                GifPolicy::Skip => {
+                    summary.skipped_animated_gifs += 1;
                     if verbose {
-                        let label = if live_photo_pair.is_some() {
-                            let vname = live_photo_pair
-                                .as_ref()
-                                .and_then(|p| p.file_name())
-                                .map(|n| n.to_string_lossy().into_owned())
-                                .unwrap_or_default();
-                            format!("{}+{}", filename, vname)
-                        } else {
-                            filename.clone()
-                        };
-                        display::print_success(&format!(
-                            "[{}/{}] {} -> {}",
-                            summary.imported.len(),
-                            total_to_process,
-                            label,
-                            local_id
+                        pb.println(format!(
+                            "  - {} — animated GIF skipped (--gif-policy skip)",
+                            item.filename
                         ));
                     }
+                    pb.inc(item.size_bytes);
+                    remove_extracted(&disk_path, None);
+                    continue;
                 }
-                Ok(result) => {
-                    let err = result
+                GifPolicy::Video => {
+                    let movie = disk_path.with_extension("gif.mov");
+                    let converted = match (disk_path.to_str(), movie.to_str()) {
+                        // Nothing extracted to convert
+                        _ if dry_run => Ok(()),
+                        (Some(gif), Some(mov)) => library.gif_to_video(gif, mov),
+                        _ => Err(anyhow::anyhow!("Invalid UTF-8 file path")),
+                    };
+                    match converted {
+                        Ok(()) => {
+                            summary.animated_gifs_as_video += 1;
+                            gif_movie = Some(movie);
+                        }
+                        Err(e) => {
+                            // A failed conversion can leave a partial movie
+                            let _ = std::fs::remove_file(&movie);
+                            summary.animated_gifs_static += 1;
+                            pb.println(format!(
+                                "  ! {} — {e}; importing the GIF as-is",
+                                item.filename
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Import the file
+        let path_str = match disk_path.to_str() {
+            Some(p) => p,
+            None => {
+                let err = "Invalid UTF-8 file path".to_string();
+                summary.record_failure(ImportFailure {
+                    path: item.relative_path.clone(),
+                    error: err.clone(),
+                });
+                records.failed(manifest::ManifestFailure {
+                    path: item.relative_path.clone(),
+                    error: err,
+                })?;
+                pb.inc(item.size_bytes);
+                remove_extracted(&disk_path, live_photo_pair.as_deref());
+                continue;
+            }
+        };
+
+        let mut used_live_fallback = false;
+        let import_started = Instant::now();
+        let motion_photo = live_photo_pair.is_none()
+            && item.media_type == takeout::MediaType::Photo
+            && if dry_run {
+                archive
+                    .by_index(item.zip_index)
+                    .is_ok_and(takeout::is_motion_photo_in)
+            } else {
+                takeout::is_motion_photo(&disk_path)
+            };
+        let import_result = if let Some(ref video_disk) = live_photo_pair {
+            let live_result = match video_disk.to_str() {
+                Some(vstr) => library.import_live_photo(path_str, vstr, photo_metadata.as_ref()),
+                None => Err(anyhow::anyhow!("Invalid UTF-8 in Live Photo video path")),
+            };
+            match live_result {
+                Ok(r) if r.success => Ok(r),
+                // --no-live-fallback: fail loudly rather than import a degraded still
+                Ok(r) if import.no_live_fallback => Ok(r),
+                Err(err) if import.no_live_fallback => Err(err),
+                Ok(r) => {
+                    let live_err = r
                         .error
-                        .unwrap_or_else(|| "unknown error".to_string());
-                    summary.failed.push(ImportFailure {
-                        path: em.relative_path.clone(),
+                        .clone()
+                        .unwrap_or_else(|| "Live Photo import failed".to_string());
+                    match library.import_photo(path_str, photo_metadata.as_ref(), false) {
+                        Ok(fb) if fb.success => {
+                            used_live_fallback = true;
+                            Ok(fb)
+                        }
+                        Ok(fb) => {
+                            let fb_err = fb.error.unwrap_or_else(|| "Fallback failed".to_string());
+                            Ok(importer::ImportResult {
+                                success: false,
+                                local_identifier: None,
+                                error: Some(format!(
+                                    "Live Photo failed ({live_err}); fallback failed ({fb_err})"
+                                )),
+                            })
+                        }
+                        Err(e) => Err(anyhow::anyhow!(
+                            "Live Photo failed ({live_err}); fallback error: {e}"
+                        )),
+                    }
+                }
+                Err(err) => match library.import_photo(path_str, photo_metadata.as_ref(), false) {
+                    Ok(fb) if fb.success => {
+                        used_live_fallback = true;
+                        Ok(fb)
+                    }
+                    Ok(fb) => {
+                        let fb_err = fb.error.unwrap_or_else(|| "Fallback failed".to_string());
+                        Ok(importer::ImportResult {
+                            success: false,
+                            local_identifier: None,
+                            error: Some(format!(
+                                "Live Photo error ({err}); fallback failed ({fb_err})"
+                            )),
+                        })
+                    }
+                    Err(e) => Err(anyhow::anyhow!(
+                        "Live Photo error ({err}); fallback error: {e}"
+                    )),
+                },
+            }
+        } else if motion_photo {
+            match library.import_motion_photo(path_str, photo_metadata.as_ref()) {
+                Ok(r) if r.success => Ok(r),
+                Ok(r) if import.no_live_fallback => Ok(r),
+                Err(err) if import.no_live_fallback => Err(err),
+                // Import the still, as before motion photos were recognised
+                _ => {
+                    let fallback = library.import_photo(path_str, photo_metadata.as_ref(), false);
+                    used_live_fallback = fallback.as_ref().is_ok_and(|r| r.success);
+                    fallback
+                }
+            }
+        } else if let Some(movie) = gif_movie.as_deref() {
+            match movie.to_str() {
+                Some(mov) => library.import_photo(mov, photo_metadata.as_ref(), true),
+                None => Err(anyhow::anyhow!("Invalid UTF-8 file path")),
+            }
+        } else if import.preserve_depth && item.media_type == takeout::MediaType::Photo {
+            library.import_photo_resource(path_str, photo_metadata.as_ref())
+        } else {
+            let is_video = matches!(item.media_type, takeout::MediaType::Video);
+            library.import_photo(path_str, photo_metadata.as_ref(), is_video)
+        };
+        let import_time = import_time_label(import_started.elapsed());
+        if let Some(movie) = gif_movie.as_deref() {
+            let _ = std::fs::remove_file(movie);
+        }
+
+        match import_result {
+            Ok(result) if result.success => {
+                let Some(local_id) = result.local_identifier.clone() else {
+                    let err = "import succeeded but no local identifier returned".to_string();
+                    summary.record_failure(ImportFailure {
+                        path: item.relative_path.clone(),
                         error: err.clone(),
                     });
-                    all_failed.push((em.relative_path.clone(), err.clone()));
-                    if verbose {
-                        pb.println(format!("  ! {} — {}", filename, err));
+                    records.failed(manifest::ManifestFailure {
+                        path: item.relative_path.clone(),
+                        error: err,
+                    })?;
+                    pb.inc(item.size_bytes);
+                    remove_extracted(&disk_path, live_photo_pair.as_deref());
+                    continue;
+                };
+
+                if used_live_fallback && motion_photo {
+                    // No separate video for `retry-live-photo-fallbacks` to pair
+                    summary.motion_photo_fallbacks += 1;
+                    pb.println(format!(
+                        "  ! Motion photo import failed; imported still photo only: {}",
+                        item.relative_path
+                    ));
+                } else if used_live_fallback {
+                    summary.live_photo_fallbacks += 1;
+                    if let Some((_, video_rel)) = item.live_video.as_ref() {
+                        records.live_photo_fallback(manifest::ManifestLivePhotoFallback {
+                            photo_path: item.relative_path.clone(),
+                            video_path: video_rel.clone(),
+                            local_id: local_id.clone(),
+                        })?;
                     }
+                    pb.println(format!(
+                        "  ! Live Photo import failed; imported still photo only: {}",
+                        item.relative_path
+                    ));
                 }
-                Err(error) => {
-                    let err = error.to_string();
-                    summary.failed.push(ImportFailure {
-                        path: em.relative_path.clone(),
-                        error: err.clone(),
+
+                let is_live = (live_photo_pair.is_some() || motion_photo) && !used_live_fallback;
+                let creation_date = photo_metadata
+                    .as_ref()
+                    .and_then(|m| m.creation_date.clone());
+
+                // Album assignment — only record the album if the asset was added to it
+                let assigned_album = target_album.as_deref().and_then(|album_name| {
+                    if !album_within_limit(album_limit, &album_registry, album_name) {
+                        summary.album_limit_unassigned += 1;
+                        return None;
+                    }
+                    add_to_named_album(
+                        library,
+                        &mut album_ids,
+                        &mut album_registry,
+                        album_name,
+                        &local_id,
+                        &item.filename,
+                        &pb,
+                    )
+                });
+                if item.date_album && assigned_album.is_some() {
+                    summary.date_album_assigned += 1;
+                }
+
+                summary.imported += 1;
+                if import.album_key_photo
+                    && let Some(album) = assigned_album.as_deref()
+                {
+                    note_earliest_in_album(
+                        &mut earliest_in_album,
+                        album,
+                        creation_date.as_deref(),
+                        &local_id,
+                    );
+                }
+                if !dry_run
+                    && self_check_due(summary.imported)
+                    && !findable_by_id(library, &local_id)
+                {
+                    summary.unstable_ids += 1;
+                    pb.println(format!(
+                        "  ! {} — imported, but Photos can't find it by the id it returned ({local_id}); its identifier changed after import",
+                        item.filename
+                    ));
+                }
+                records.imported(manifest::ManifestEntry {
+                    path: item.relative_path.clone(),
+                    local_id: local_id.clone(),
+                    creation_date: creation_date.clone(),
+                    is_live_photo: Some(is_live),
+                    album: assigned_album.clone(),
+                    sidecar_path: item.sidecar_path.clone(),
+                })?;
+
+                // Other copies share the asset; each adds it to its own album
+                let mut albums_added: HashSet<String> = assigned_album.into_iter().collect();
+                for (copy_path, copy_album) in &item.copies {
+                    let copy_target = into_album.clone().or_else(|| {
+                        album_for_media(
+                            copy_album.as_deref(),
+                            item.media_type,
+                            import.split_media_albums,
+                        )
                     });
-                    all_failed.push((em.relative_path.clone(), err.clone()));
-                    if verbose {
-                        pb.println(format!("  ! {} — {}", filename, err));
+                    let copy_assigned = match copy_target {
+                        Some(album_name) if albums_added.contains(&album_name) => Some(album_name),
+                        Some(album_name)
+                            if !album_within_limit(album_limit, &album_registry, &album_name) =>
+                        {
+                            summary.album_limit_unassigned += 1;
+                            None
+                        }
+                        Some(album_name) => add_to_named_album(
+                            library,
+                            &mut album_ids,
+                            &mut album_registry,
+                            &album_name,
+                            &local_id,
+                            &item.filename,
+                            &pb,
+                        ),
+                        None => None,
+                    };
+                    if let Some(album_name) = copy_assigned.as_ref() {
+                        albums_added.insert(album_name.clone());
                     }
+                    records.imported(manifest::ManifestEntry {
+                        path: copy_path.clone(),
+                        local_id: local_id.clone(),
+                        creation_date: creation_date.clone(),
+                        is_live_photo: Some(is_live),
+                        album: copy_assigned,
+                        sidecar_path: item.sidecar_path.clone(),
+                    })?;
                 }
-            }
 
-            pb.inc(1);
+                if verbose && verbose_success_due(summary.imported, import.progress_every) {
+                    let label = if live_photo_pair.is_some() {
+                        let vname = live_photo_pair
+                            .as_ref()
+                            .and_then(|p| p.file_name())
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        format!("{}+{}", item.filename, vname)
+                    } else {
+                        item.filename.clone()
+                    };
+                    display::print_success(&format!(
+                        "[{}/{}] {} -> {} {import_time}",
+                        summary.imported, total_to_process, label, local_id
+                    ));
+                    if dry_run {
+                        let sidecar = item.sidecar_path.as_deref().zip(item.sidecar_strength);
+                        println!(
+                            "      {}",
+                            dry_run_detail(
+                                sidecar,
+                                photo_metadata.as_ref(),
+                                target_album.as_deref()
+                            )
+                        );
+                    }
+                }
+            }
+            Ok(result) => {
+                let err = result.error.unwrap_or_else(|| "unknown error".to_string());
+                summary.record_failure(ImportFailure {
+                    path: item.relative_path.clone(),
+                    error: err.clone(),
+                });
+                records.failed(manifest::ManifestFailure {
+                    path: item.relative_path.clone(),
+                    error: err.clone(),
+                })?;
+                if verbose {
+                    pb.println(format!("  ! {} — {} {import_time}", item.filename, err));
+                }
+            }
+            Err(error) => {
+                let err = error.to_string();
+                summary.record_failure(ImportFailure {
+                    path: item.relative_path.clone(),
+                    error: err.clone(),
+                });
+                records.failed(manifest::ManifestFailure {
+                    path: item.relative_path.clone(),
+                    error: err.clone(),
+                })?;
+                if verbose {
+                    pb.println(format!("  ! {} — {} {import_time}", item.filename, err));
+                }
+            }
         }
 
-        // Clean up this directory's files before processing the next
-        let _ = std::fs::remove_dir_all(&tmp_dir);
+        pb.inc(item.size_bytes);
+        remove_extracted(&disk_path, live_photo_pair.as_deref());
     }
 
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+
+    progress::emit("import", pb.position(), total_bytes_to_process, None);
     pb.finish_and_clear();
     summary.elapsed = start.elapsed();
 
-    // ── Phase 3: Write manifest ─────────────────────────────────────────
+    if import.album_key_photo {
+        for (album, (_, local_id)) in &earliest_in_album {
+            let Some(Some(album_id)) = album_ids.get(album) else {
+                continue;
+            };
+            match library.set_album_key_photo(album_id, local_id) {
+                Ok(true) => {}
+                Ok(false) => {
+                    display::print_warning(&format!("Could not set key photo for album '{album}'"))
+                }
+                Err(e) => display::print_warning(&format!(
+                    "Could not set key photo for album '{album}': {e}"
+                )),
+            }
+        }
+    }
+
+    // ── Phase 4: Write manifest ─────────────────────────────────────────
 
-    manifest::merge_and_write(
-        &manifest_path,
-        &zip_name,
-        &all_imported,
-        &all_failed,
-        &all_live_fallbacks,
-    )?;
+    records.finish()?;
 
     Ok(summary)
 }
@@ -999,7 +2534,31 @@ fn cmd_import(file: &Path, metadata_json: Option<&str>) -> Result<()> {
 
     let metadata = match metadata_json {
         Some(json) => Some(serde_json::from_str::<importer::PhotoMetadata>(json)?),
-        None => None,
+        None => match discover_sidecar_metadata(file)? {
+            Some((sidecar, parsed)) => {
+                let strength = match sidecar.strength {
+                    sidecar::SidecarMatchStrength::Strong => "strong",
+                    sidecar::SidecarMatchStrength::Fuzzy => "fuzzy",
+                };
+                display::print_info(&format!(
+                    "Sidecar: {} ({strength} match)",
+                    sidecar.path.display()
+                ));
+                match parsed {
+                    Ok(meta) => Some(meta),
+                    Err(e) => {
+                        display::print_warning(&format!(
+                            "Unparseable sidecar ({e}) — importing without metadata"
+                        ));
+                        None
+                    }
+                }
+            }
+            None => {
+                display::print_info("No sidecar found — importing without metadata");
+                None
+            }
+        },
     };
 
     let is_video = match takeout::media_type_from_path(file) {
@@ -1028,17 +2587,45 @@ fn cmd_import(file: &Path, metadata_json: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-fn cmd_albums(dir: &Path) -> Result<()> {
+/// `import` without `--metadata`: the sidecar `run` would match for `file`
+/// among the JSON files beside it, and the metadata parsed from it.
+fn discover_sidecar_metadata(
+    file: &Path,
+) -> Result<Option<(sidecar::SidecarMatch, Result<importer::PhotoMetadata>)>> {
+    let dir = match file.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read directory: {}", dir.display()))?
+    {
+        files.push(entry?.path());
+    }
+    let candidates = sidecar::collect_json_candidates(&files);
+    let media = dir.join(file.file_name().unwrap_or_default());
+    let Some(found) = sidecar::find_sidecar_with_strength(&media, &candidates) else {
+        return Ok(None);
+    };
+    let parsed = std::fs::read(&found.path)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| metadata::parse_sidecar(&bytes))
+        .map(|m| m.to_photo_metadata());
+    Ok(Some((found, parsed)))
+}
+
+fn cmd_albums(dir: &Path, json: Option<&Path>) -> Result<()> {
     let dir = expand_tilde(dir);
     display::print_header(&format!("Scanning albums in {}", dir.display()));
 
-    let zips = takeout::find_takeout_zips(&dir)?;
+    let zips = repack_tarballs(takeout::find_takeout_zips(&dir)?)?;
     if zips.is_empty() {
         display::print_info("No Takeout zips found.");
         return Ok(());
     }
 
     let mut all_albums = Vec::new();
+    let mut album_files: BTreeMap<String, Vec<String>> = BTreeMap::new();
 
     for zip_path in &zips {
         let extract_dir = dir.join(format!(
@@ -1065,6 +2652,9 @@ fn cmd_albums(dir: &Path) -> Result<()> {
             }
         };
         all_albums.extend(inventory.albums);
+        if json.is_some() {
+            group_album_files(&inventory.files, &content_root, &mut album_files);
+        }
 
         std::fs::remove_dir_all(&extract_dir)?;
     }
@@ -1072,6 +2662,20 @@ fn cmd_albums(dir: &Path) -> Result<()> {
     all_albums.sort();
     all_albums.dedup();
 
+    if let Some(json_path) = json.map(expand_tilde) {
+        for paths in album_files.values_mut() {
+            paths.sort();
+            paths.dedup();
+        }
+        std::fs::write(&json_path, serde_json::to_string_pretty(&album_files)?)
+            .with_context(|| format!("Failed to write {}", json_path.display()))?;
+        display::print_success(&format!(
+            "Wrote files of {} album(s) to {}",
+            album_files.len(),
+            json_path.display()
+        ));
+    }
+
     if all_albums.is_empty() {
         display::print_info("No albums detected.");
     } else {
@@ -1084,6 +2688,49 @@ fn cmd_albums(dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Add each album's files (and Live Photo videos), relative to `root` as in
+/// manifests, to `albums`.
+fn group_album_files(
+    files: &[takeout::MediaFile],
+    root: &Path,
+    albums: &mut BTreeMap<String, Vec<String>>,
+) {
+    let relative = |p: &Path| {
+        p.strip_prefix(root)
+            .unwrap_or(p)
+            .to_string_lossy()
+            .into_owned()
+    };
+    for file in files {
+        let Some(album) = file.album.as_ref() else {
+            continue;
+        };
+        let paths = albums.entry(album.clone()).or_default();
+        paths.push(relative(&file.path));
+        if let Some(video) = file.live_photo_pair.as_deref() {
+            paths.push(relative(video));
+        }
+    }
+}
+
+/// Record a failed part and say so if it has now used up `--max-part-retries`.
+fn record_part_failure(
+    progress: &std::sync::Mutex<downloader::DownloadProgress>,
+    part: usize,
+    max_part_retries: u32,
+    dir: &Path,
+) {
+    if progress
+        .lock()
+        .unwrap()
+        .mark_failed(part, max_part_retries, dir)
+    {
+        display::print_warning(&format!(
+            "  [{part:02}] Failed {max_part_retries} times — giving up; later runs skip it unless --force"
+        ));
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 /// Load download URLs from a file (one URL per line). Parses &i=N to index by part number.
 fn load_urls_file(path: &Path) -> Result<HashMap<usize, String>> {
@@ -1113,17 +2760,20 @@ fn cmd_download(
     end: usize,
     concurrency: usize,
     download_only: bool,
-    verbose: bool,
-    include_trashed: bool,
-    strict_extensions: bool,
-    unknown_report: Option<&Path>,
+    import: &ImportArgs,
     keep_zips: bool,
+    archive_dir: Option<&Path>,
+    after_part: Option<&str>,
     urls_file: Option<&Path>,
-) -> Result<()> {
+    max_part_retries: u32,
+    force: bool,
+    pause_on_battery: Option<u8>,
+) -> Result<Outcome> {
     use std::collections::VecDeque;
     use std::sync::{Arc, Mutex, mpsc};
 
     let dir = expand_tilde(dir);
+    let archive_dir = archive_dir.map(expand_tilde);
     std::fs::create_dir_all(&dir)?;
     let concurrency = concurrency.max(1);
 
@@ -1137,6 +2787,14 @@ fn cmd_download(
     if !download_only && keep_zips {
         display::print_info("--keep-zips: ZIPs will be kept after import+verify.");
     }
+    if let Some(archive_dir) = archive_dir.as_deref()
+        && !download_only
+    {
+        display::print_info(&format!(
+            "--archive-dir: ZIPs will be moved to {} after import+verify.",
+            archive_dir.display()
+        ));
+    }
 
     // Check Photos access up front (unless download-only)
     if !download_only {
@@ -1155,6 +2813,11 @@ fn cmd_download(
     for i in start..=end {
         if progress.is_completed(i) {
             display::print_info(&format!("  [{i:02}] Already done, skipping"));
+        } else if progress.is_given_up(i) && !force {
+            display::print_warning(&format!(
+                "  [{i:02}] Given up after {} failures — skipping (--force to retry)",
+                progress.failure_counts.get(&i).copied().unwrap_or(0)
+            ));
         } else if progress.attempts_remaining(i) == 0 {
             display::print_warning(&format!(
                 "  [{i:02}] Exhausted (5 download attempts) — skipping. Re-export to reset."
@@ -1167,7 +2830,7 @@ fn cmd_download(
     let total_remaining = work.len();
     if total_remaining == 0 {
         display::print_success("All parts already completed");
-        return Ok(());
+        return Ok(Outcome::Success);
     }
 
     // Pipeline stats for ETA
@@ -1183,6 +2846,11 @@ fn cmd_download(
     let mut total_imported = 0usize;
     let mut total_failed_dl = 0usize;
     let mut total_failed_import = 0usize;
+    let mut total_verified = 0usize;
+    let mut zips_deleted = 0usize;
+    let mut zips_archived = 0usize;
+    let mut zips_kept = 0usize;
+    let mut parts_not_started = 0usize;
 
     // Extract cookies on main thread (Keychain may need interactive access)
     let mut http_client = downloader::try_build_http_client().map(Arc::new);
@@ -1203,6 +2871,8 @@ fn cmd_download(
         // Each concurrent download needs ~55GB. Gate must ensure enough space for all workers.
         let min_free_gb = 55 * concurrency as u64;
         let gate = Arc::new(downloader::DiskSpaceGate::new(dir.clone(), min_free_gb));
+        let battery = pause_on_battery
+            .map(|pct| Arc::new(downloader::BatteryGate::new(pct, notifier.clone())));
         let (tx, rx) = mpsc::channel::<downloader::DownloadEvent>();
 
         // Spawn N download worker threads
@@ -1210,6 +2880,7 @@ fn cmd_download(
         for _ in 0..concurrency {
             let queue = Arc::clone(&work_queue);
             let gate = Arc::clone(&gate);
+            let battery = battery.clone();
             let tx = tx.clone();
             let notifier = notifier.clone();
             let http_client = http_client.clone();
@@ -1218,9 +2889,14 @@ fn cmd_download(
             let job_id = job_id.to_string();
             let user_id = user_id.to_string();
             let dir = dir.clone();
+            let deadline = import.deadline;
 
             handles.push(std::thread::spawn(move || {
                 loop {
+                    // Past --max-runtime: leave the rest queued
+                    if deadline.is_some_and(|d| Instant::now() >= d) {
+                        break;
+                    }
                     let part = {
                         let mut q = queue.lock().unwrap();
                         q.pop_front()
@@ -1232,13 +2908,17 @@ fn cmd_download(
                         let mut p = progress.lock().unwrap();
                         let attempt = p.record_attempt(part, &dir);
                         let remaining = 5usize.saturating_sub(attempt);
-                        if remaining > 0 {
+                        if remaining > 0 && !display::is_quiet() {
                             println!("  [{part:02}] Download attempt {attempt}/5 ({remaining} remaining)");
                         }
                     }
 
                     gate.wait(part);
+                    if let Some(battery) = battery.as_deref() {
+                        battery.wait(part);
+                    }
                     let start_time = std::time::Instant::now();
+                    let expected_sha1 = progress.lock().unwrap().sha1.get(&part).cloned();
 
                     match downloader::download_hybrid(
                         http_client.as_deref(),
@@ -1248,8 +2928,13 @@ fn cmd_download(
                         &dir,
                         notifier.as_deref(),
                         scraped_urls.get(&part).map(|s| s.as_str()),
+                        expected_sha1.as_deref(),
                     ) {
-                        Ok(zip_path) => {
+                        Ok(downloaded) => {
+                            if let Some(sha1) = downloaded.sha1.as_deref() {
+                                progress.lock().unwrap().record_sha1(part, sha1, &dir);
+                            }
+                            let zip_path = downloaded.path;
                             let size = zip_path.metadata().map(|m| m.len()).unwrap_or(0);
                             let _ = tx.send(downloader::DownloadEvent::Completed {
                                 part,
@@ -1289,13 +2974,14 @@ fn cmd_download(
                         ));
                         progress.lock().unwrap().mark_completed(part, &dir);
                         total_imported += 1;
-                        stats.record_part(size, duration);
+                        stats.record_part(part, size, duration);
                         let eta = stats.eta_string();
                         display::print_info(&format!("  {eta}"));
                         notify::notify(
                             notifier.as_deref(),
                             &format!("photoferry: Part {part} downloaded ({size_gb:.1}GB). {eta}"),
                         );
+                        run_after_part_hook(after_part, part, "downloaded", &dir);
                         continue;
                     }
 
@@ -1304,49 +2990,74 @@ fn cmd_download(
                         "  [{part:02}] Importing {}...",
                         zip_path.file_name().unwrap_or_default().to_string_lossy()
                     ));
-                    match process_one_zip(
-                        &zip_path,
-                        &dir,
-                        false,
-                        verbose,
-                        include_trashed,
-                        false,
-                        strict_extensions,
-                        unknown_report,
-                    ) {
+                    match process_one_zip(&zip_path, &dir, false, false, import) {
                         Ok(summary) => {
-                            let imported_count = summary.imported.len();
+                            let imported_count = summary.imported;
                             print_import_summary(&summary);
                             total_imported += imported_count;
-                            let had_failures = !summary.failed.is_empty();
+                            let had_failures = summary.failed > 0;
+                            let mut part_status = "kept";
                             if had_failures {
-                                total_failed_import += summary.failed.len();
+                                total_failed_import += summary.failed;
+                                zips_kept += 1;
                                 display::print_warning(&format!(
                                     "  [{part:02}] {} files failed — zip kept for retry",
-                                    summary.failed.len()
+                                    summary.failed
                                 ));
                                 notify::notify(
                                     notifier.as_deref(),
                                     &format!(
                                         "photoferry: Part {part} imported with {} failures — zip kept",
-                                        summary.failed.len()
+                                        summary.failed
                                     ),
                                 );
+                            } else if let Some(reason) = zip_keep_reason(&summary) {
+                                zips_kept += 1;
+                                display::print_warning(&format!("  [{part:02}] {reason}"));
                             } else {
                                 if verify_zip_manifest(&zip_path, &dir) {
+                                    total_verified += 1;
+                                    part_status = "verified";
                                     progress.lock().unwrap().mark_completed(part, &dir);
-                                    match verify_success_action(keep_zips) {
+                                    match verify_success_action(keep_zips, archive_dir.is_some()) {
                                         VerifySuccessAction::KeepZipAndMarkCompleted => {
+                                            zips_kept += 1;
                                             display::print_warning(&format!(
                                                 "  [{part:02}] Verify passed — keeping zip (--keep-zips)"
                                             ));
                                         }
+                                        VerifySuccessAction::MoveZipAndMarkCompleted => {
+                                            let archive_dir =
+                                                archive_dir.as_deref().unwrap_or(&dir);
+                                            match archive_zip(&zip_path, archive_dir) {
+                                                Ok(dest) => {
+                                                    zips_archived += 1;
+                                                    display::print_success(&format!(
+                                                        "  [{part:02}] Verified + archived to {}",
+                                                        dest.display()
+                                                    ));
+                                                }
+                                                Err(e) => {
+                                                    zips_kept += 1;
+                                                    display::print_warning(&format!(
+                                                        "  [{part:02}] Verified OK but could not archive zip: {e:#}"
+                                                    ));
+                                                }
+                                            }
+                                        }
                                         VerifySuccessAction::DeleteZipAndMarkCompleted => {
+                                            warn_if_icloud_pending(
+                                                &zip_path,
+                                                &dir,
+                                                &format!("  [{part:02}] "),
+                                            );
                                             if let Err(e) = std::fs::remove_file(&zip_path) {
+                                                zips_kept += 1;
                                                 display::print_warning(&format!(
                                                     "  [{part:02}] Verified OK but could not delete zip: {e}"
                                                 ));
                                             } else {
+                                                zips_deleted += 1;
                                                 display::print_success(&format!(
                                                     "  [{part:02}] Verified + deleted {}",
                                                     zip_path.file_name().unwrap_or_default().to_string_lossy()
@@ -1355,6 +3066,7 @@ fn cmd_download(
                                         }
                                     }
                                 } else {
+                                    zips_kept += 1;
                                     display::print_warning(&format!(
                                         "  [{part:02}] Import OK but verify failed — keeping zip"
                                     ));
@@ -1366,32 +3078,37 @@ fn cmd_download(
                                     ),
                                 );
                             }
-                            stats.record_part(size, duration);
+                            stats.record_part(part, size, duration);
                             let eta = stats.eta_string();
                             display::print_info(&format!("  {eta}"));
+                            run_after_part_hook(after_part, part, part_status, &dir);
                         }
                         Err(e) => {
-                            let msg = e.to_string();
-                            if msg.starts_with(STRICT_EXTENSIONS_ABORT) {
-                                let cleaned = msg
-                                    .strip_prefix(STRICT_EXTENSIONS_ABORT)
-                                    .unwrap_or(&msg)
-                                    .trim_start_matches(':')
-                                    .trim();
+                            // Without Photos access every later part fails too —
+                            // stop queuing parts; downloads already on disk resume next run
+                            if let Some(ProcessError::PhotosAccess(_)) = e.downcast_ref() {
+                                work_queue.lock().unwrap().clear();
+                                return Err(e);
+                            }
+                            if let Some(abort @ ProcessError::StrictExtensionsAbort { .. }) =
+                                e.downcast_ref()
+                            {
                                 // Can't abort workers mid-flight — log and continue
                                 display::print_error(&format!(
-                                    "  [{part:02}] Strict extensions abort: {cleaned}"
+                                    "  [{part:02}] Strict extensions abort: {abort}"
                                 ));
                             }
                             display::print_error(&format!(
                                 "  [{part:02}] Import failed: {e} — zip kept"
                             ));
-                            progress.lock().unwrap().mark_failed(part, &dir);
+                            record_part_failure(&progress, part, max_part_retries, &dir);
                             total_failed_import += 1;
+                            zips_kept += 1;
                             notify::notify(
                                 notifier.as_deref(),
                                 &format!("photoferry: FAILED part {part} import — {e}"),
                             );
+                            run_after_part_hook(after_part, part, "import-failed", &dir);
                         }
                     }
                 }
@@ -1399,12 +3116,13 @@ fn cmd_download(
                     display::print_error(&format!(
                         "  [{part:02}] Download failed: {error} — skipping"
                     ));
-                    progress.lock().unwrap().mark_failed(part, &dir);
+                    record_part_failure(&progress, part, max_part_retries, &dir);
                     total_failed_dl += 1;
                     notify::notify(
                         notifier.as_deref(),
                         &format!("photoferry: FAILED part {part} download — {error}"),
                     );
+                    run_after_part_hook(after_part, part, "download-failed", &dir);
                 }
             }
         }
@@ -1413,16 +3131,26 @@ fn cmd_download(
         for h in handles {
             let _ = h.join();
         }
+        parts_not_started = work_queue.lock().unwrap().len();
     } else {
         // ── Serial hybrid downloads ──────────────────────────────────
 
         let gate = downloader::DiskSpaceGate::new(dir.clone(), 20);
+        let battery =
+            pause_on_battery.map(|pct| downloader::BatteryGate::new(pct, notifier.clone()));
 
-        for i in work {
+        for (n, &i) in work.iter().enumerate() {
+            if import.past_deadline() {
+                parts_not_started = work.len() - n;
+                break;
+            }
             println!();
             display::print_header(&format!("Part {i}/{end}"));
 
             gate.wait(i);
+            if let Some(battery) = battery.as_ref() {
+                battery.wait(i);
+            }
             let part_start = std::time::Instant::now();
 
             // Record attempt before download
@@ -1430,12 +3158,13 @@ fn cmd_download(
                 let mut p = progress.lock().unwrap();
                 let attempt = p.record_attempt(i, &dir);
                 let remaining = 5usize.saturating_sub(attempt);
-                if remaining > 0 {
+                if remaining > 0 && !display::is_quiet() {
                     println!("  [{i:02}] Download attempt {attempt}/5 ({remaining} remaining)");
                 }
             }
 
             // Download
+            let expected_sha1 = progress.lock().unwrap().sha1.get(&i).cloned();
             let zip_path = match downloader::download_hybrid(
                 http_client.as_deref(),
                 job_id,
@@ -1444,18 +3173,25 @@ fn cmd_download(
                 &dir,
                 notifier.as_deref(),
                 scraped_urls.get(&i).map(|s| s.as_str()),
+                expected_sha1.as_deref(),
             ) {
-                Ok(p) => p,
+                Ok(downloaded) => {
+                    if let Some(sha1) = downloaded.sha1.as_deref() {
+                        progress.lock().unwrap().record_sha1(i, sha1, &dir);
+                    }
+                    downloaded.path
+                }
                 Err(e) => {
                     display::print_error(&format!(
                         "  [{i:02}] Download failed: {e} — skipping"
                     ));
-                    progress.lock().unwrap().mark_failed(i, &dir);
+                    record_part_failure(&progress, i, max_part_retries, &dir);
                     total_failed_dl += 1;
                     notify::notify(
                         notifier.as_deref(),
                         &format!("photoferry: FAILED part {i} download — {e}"),
                     );
+                    run_after_part_hook(after_part, i, "download-failed", &dir);
                     continue;
                 }
             };
@@ -1477,7 +3213,7 @@ fn cmd_download(
                 ));
                 progress.lock().unwrap().mark_completed(i, &dir);
                 total_imported += 1;
-                stats.record_part(zip_size, part_start.elapsed());
+                stats.record_part(i, zip_size, part_start.elapsed());
                 let eta = stats.eta_string();
                 display::print_info(&format!("  {eta}"));
                 notify::notify(
@@ -1487,6 +3223,7 @@ fn cmd_download(
                         zip_size as f64 / 1024.0 / 1024.0 / 1024.0
                     ),
                 );
+                run_after_part_hook(after_part, i, "downloaded", &dir);
                 continue;
             }
 
@@ -1495,42 +3232,66 @@ fn cmd_download(
                 "  [{i:02}] Importing {}...",
                 zip_path.file_name().unwrap_or_default().to_string_lossy()
             ));
-            match process_one_zip(
-                &zip_path,
-                &dir,
-                false,
-                verbose,
-                include_trashed,
-                false,
-                strict_extensions,
-                unknown_report,
-            ) {
+            match process_one_zip(&zip_path, &dir, false, false, import) {
                 Ok(summary) => {
-                    let imported_count = summary.imported.len();
+                    let imported_count = summary.imported;
                     print_import_summary(&summary);
                     total_imported += imported_count;
-                    let had_failures = !summary.failed.is_empty();
+                    let had_failures = summary.failed > 0;
+                    let mut part_status = "kept";
                     if had_failures {
-                        total_failed_import += summary.failed.len();
+                        total_failed_import += summary.failed;
+                        zips_kept += 1;
                         display::print_warning(&format!(
                             "  [{i:02}] {} files failed — zip kept for retry",
-                            summary.failed.len()
+                            summary.failed
                         ));
+                    } else if let Some(reason) = zip_keep_reason(&summary) {
+                        zips_kept += 1;
+                        display::print_warning(&format!("  [{i:02}] {reason}"));
                     } else {
                         if verify_zip_manifest(&zip_path, &dir) {
+                            total_verified += 1;
+                            part_status = "verified";
                             progress.lock().unwrap().mark_completed(i, &dir);
-                            match verify_success_action(keep_zips) {
+                            match verify_success_action(keep_zips, archive_dir.is_some()) {
                                 VerifySuccessAction::KeepZipAndMarkCompleted => {
+                                    zips_kept += 1;
                                     display::print_warning(&format!(
                                         "  [{i:02}] Verify passed — keeping zip (--keep-zips)"
                                     ));
                                 }
-                                VerifySuccessAction::DeleteZipAndMarkCompleted => {
+                                VerifySuccessAction::MoveZipAndMarkCompleted => {
+                                    let archive_dir = archive_dir.as_deref().unwrap_or(&dir);
+                                    match archive_zip(&zip_path, archive_dir) {
+                                        Ok(dest) => {
+                                            zips_archived += 1;
+                                            display::print_success(&format!(
+                                                "  [{i:02}] Verified + archived to {}",
+                                                dest.display()
+                                            ));
+                                        }
+                                        Err(e) => {
+                                            zips_kept += 1;
+                                            display::print_warning(&format!(
+                                                "  [{i:02}] Verified OK but could not archive zip: {e:#}"
+                                            ));
+                                        }
+                                    }
+                                }
+                                VerifySuccessAction::DeleteZipAndMarkCompleted => {
+                                    warn_if_icloud_pending(
+                                        &zip_path,
+                                        &dir,
+                                        &format!("  [{i:02}] "),
+                                    );
                                     if let Err(e) = std::fs::remove_file(&zip_path) {
+                                        zips_kept += 1;
                                         display::print_warning(&format!(
                                             "  [{i:02}] Verified OK but could not delete zip: {e}"
                                         ));
                                     } else {
+                                        zips_deleted += 1;
                                         display::print_success(&format!(
                                             "  [{i:02}] Verified + deleted {}",
                                             zip_path
@@ -1542,12 +3303,13 @@ fn cmd_download(
                                 }
                             }
                         } else {
+                            zips_kept += 1;
                             display::print_warning(&format!(
                                 "  [{i:02}] Import OK but verify failed — keeping zip"
                             ));
                         }
                     }
-                    stats.record_part(zip_size, part_start.elapsed());
+                    stats.record_part(i, zip_size, part_start.elapsed());
                     let eta = stats.eta_string();
                     display::print_info(&format!("  {eta}"));
                     notify::notify(
@@ -1556,26 +3318,24 @@ fn cmd_download(
                             "photoferry: Part {i} imported — {imported_count} files. {eta}"
                         ),
                     );
+                    run_after_part_hook(after_part, i, part_status, &dir);
                 }
                 Err(e) => {
-                    let msg = e.to_string();
-                    if msg.starts_with(STRICT_EXTENSIONS_ABORT) {
-                        let cleaned = msg
-                            .strip_prefix(STRICT_EXTENSIONS_ABORT)
-                            .unwrap_or(&msg)
-                            .trim_start_matches(':')
-                            .trim();
-                        return Err(anyhow::anyhow!(cleaned.to_string()));
+                    if let Some(
+                        ProcessError::StrictExtensionsAbort { .. } | ProcessError::PhotosAccess(_),
+                    ) = e.downcast_ref()
+                    {
+                        return Err(e);
                     }
-                    display::print_error(&format!(
-                        "  [{i:02}] Import failed: {e} — zip kept"
-                    ));
-                    progress.lock().unwrap().mark_failed(i, &dir);
+                    display::print_error(&format!("  [{i:02}] Import failed: {e} — zip kept"));
+                    record_part_failure(&progress, i, max_part_retries, &dir);
                     total_failed_import += 1;
+                    zips_kept += 1;
                     notify::notify(
                         notifier.as_deref(),
                         &format!("photoferry: FAILED part {i} import — {e}"),
                     );
+                    run_after_part_hook(after_part, i, "import-failed", &dir);
                 }
             }
         }
@@ -1586,11 +3346,11 @@ fn cmd_download(
     println!();
     display::print_header("Download run complete");
     let progress = progress.lock().unwrap();
-    display::print_info(&format!("Parts completed: {}", progress.completed.len()));
+    display::print_summary(&format!("Parts completed: {}", progress.completed.len()));
     if download_only {
-        display::print_info(&format!("Downloaded: {total_imported}"));
+        display::print_summary(&format!("Downloaded: {total_imported}"));
     } else {
-        display::print_info(&format!("Photos imported: {total_imported}"));
+        display::print_summary(&format!("Photos imported: {total_imported}"));
     }
     if total_failed_dl > 0 {
         display::print_error(&format!("Download failures: {total_failed_dl}"));
@@ -1598,10 +3358,80 @@ fn cmd_download(
     if total_failed_import > 0 {
         display::print_warning(&format!("Import failures: {total_failed_import}"));
     }
+    if parts_not_started > 0 {
+        display::print_warning(&format!(
+            "Stopped at --max-runtime: {parts_not_started} part(s) not started — re-run to continue"
+        ));
+    }
+    let given_up: Vec<String> = (start..=end)
+        .filter(|&i| progress.is_given_up(i))
+        .map(|i| i.to_string())
+        .collect();
+    if !given_up.is_empty() {
+        display::print_warning(&format!(
+            "Given up (failed {max_part_retries}+ times; investigate manually): parts {}",
+            given_up.join(", ")
+        ));
+    }
+    if let Some(throughput) = stats.throughput_summary() {
+        for (part, mbps) in stats.part_throughput() {
+            display::print_info(&format!("  [{part:02}] {mbps:.1} MB/s"));
+        }
+        display::print_summary(&throughput);
+    }
     let all_ok = total_failed_dl == 0 && total_failed_import == 0;
     if all_ok {
-        display::print_success("All parts completed successfully");
+        display::print_summary_success("All parts completed successfully");
+    }
+
+    // ── Reconciliation ───────────────────────────────────────────────
+
+    println!();
+    display::print_header("Reconciliation");
+    let requested = end.saturating_sub(start) + 1;
+    let completed_in_range = (start..=end).filter(|&i| progress.is_completed(i)).count();
+    let failed_in_range = (start..=end)
+        .filter(|&i| !progress.is_completed(i) && progress.failed.contains(&i))
+        .count();
+    display::print_summary(&format!(
+        "Parts {start}–{end}: {completed_in_range}/{requested} completed, {failed_in_range} failed"
+    ));
+    if !download_only {
+        display::print_summary(&format!(
+            "This run: {total_verified} parts verified | {zips_deleted} zips deleted | {zips_kept} zips kept"
+        ));
+        if zips_archived > 0 {
+            display::print_summary(&format!("Zips archived: {zips_archived}"));
+        }
+    }
+    let gaps = progress.unaccounted_parts(start, end);
+    if gaps.is_empty() {
+        display::print_summary_success("Every requested part is either completed or failed");
+    } else {
+        let list: Vec<String> = gaps.iter().map(|i| i.to_string()).collect();
+        display::print_warning(&format!(
+            "{} parts neither completed nor failed: {}",
+            gaps.len(),
+            list.join(", ")
+        ));
     }
+    let skipped_failures = progress.failed_outside(start, end);
+    if !skipped_failures.is_empty() {
+        let list: Vec<String> = skipped_failures.iter().map(|i| i.to_string()).collect();
+        display::print_warning(&format!(
+            "{} failed parts outside --start {start} --end {end} were not retried: {} (widen the range to retry them)",
+            skipped_failures.len(),
+            list.join(", ")
+        ));
+    }
+    let totals = manifest_totals(&dir);
+    display::print_summary(&format!(
+        "All manifests in {}: {} zips, {} imported, {} failed",
+        dir.display(),
+        totals.manifests,
+        totals.imported,
+        totals.failed
+    ));
 
     // Final summary notification
     let summary_msg = format!(
@@ -1613,1260 +3443,6603 @@ fn cmd_download(
     );
     notify::notify(notifier.as_deref(), &summary_msg);
 
-    Ok(())
+    Ok(Outcome::for_download(total_failed_dl, total_failed_import))
 }
 
-// MARK: - Helpers
+#[derive(Debug, Default)]
+struct ManifestTotals {
+    manifests: usize,
+    imported: usize,
+    failed: usize,
+}
 
-fn write_unknown_report(
-    report_path: &Path,
-    zip_name: &str,
-    unknown_files: &[takeout::UnknownFile],
-) -> Result<()> {
-    if unknown_files.is_empty() {
-        return Ok(());
-    }
-    let mut needs_header = true;
-    if let Ok(meta) = std::fs::metadata(report_path) {
-        if meta.len() > 0 {
-            needs_header = false;
+/// Sum imported/failed counts over every manifest in `dir`. Unreadable
+/// manifests are skipped with a warning.
+fn manifest_totals(dir: &Path) -> ManifestTotals {
+    let mut totals = ManifestTotals::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return totals;
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let is_manifest = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"));
+        if !is_manifest {
+            continue;
+        }
+        match manifest::read_manifest_strict(&path) {
+            Ok(Some(m)) => {
+                totals.manifests += 1;
+                totals.imported += m.imported.len();
+                totals.failed += m.failed.len();
+            }
+            Ok(None) => {}
+            Err(e) => display::print_warning(&format!("Skipping {}: {e}", path.display())),
         }
     }
+    totals
+}
 
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(report_path)?;
+/// `verify-kept`: verify every imported zip still in `dir` and, with
+/// `--icloud-confirmed`, delete those that pass and whose manifest covers all
+/// their media. Safe to re-run.
+fn cmd_verify_kept(dir: &Path, icloud_confirmed: bool) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Re-verifying kept zips in {}", dir.display()));
 
-    if needs_header {
-        use std::io::Write;
-        writeln!(file, "zip,relative_path,ext,size_bytes")?;
-    }
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "verify")?;
 
-    use std::io::Write;
-    for entry in unknown_files {
-        let rel = entry.path.to_string_lossy().replace('"', "\"\"");
-        let ext = entry.ext.replace('"', "\"\"");
-        writeln!(file, "\"{zip_name}\",\"{rel}\",\"{ext}\",{}", entry.size_bytes)?;
+    let mut verified = 0usize;
+    let mut deleted = 0usize;
+    let mut kept = 0usize;
+    for zip_path in takeout::find_takeout_zips(&dir)? {
+        // Never repacked, so never imported
+        if takeout::is_tarball(&zip_path) {
+            continue;
+        }
+        let zip_name = zip_path.file_name().unwrap_or_default().to_string_lossy();
+        let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+        let manifest_path = dir.join(format!(".photoferry-manifest-{zip_stem}.json"));
+        let manifest = match manifest::read_manifest_strict(&manifest_path) {
+            Ok(Some(m)) => m,
+            // Never imported: not a kept zip
+            Ok(None) => continue,
+            Err(e) => {
+                kept += 1;
+                display::print_warning(&format!(
+                    "{zip_name}: manifest unreadable ({e}) — keeping zip"
+                ));
+                continue;
+            }
+        };
+        display::print_info(&zip_name);
+        if !manifest.failed.is_empty() {
+            kept += 1;
+            display::print_warning(&format!(
+                "  {} failed imports — keeping zip (run again after a retry)",
+                manifest.failed.len()
+            ));
+            continue;
+        }
+        // Download keeps partly imported zips too; those must stay until done
+        match zip_media_unaccounted(&manifest, &zip_path) {
+            Ok(0) => {}
+            Ok(missing) => {
+                kept += 1;
+                display::print_warning(&format!(
+                    "  {missing} media files not in the manifest — keeping zip (import it again to finish)"
+                ));
+                continue;
+            }
+            Err(e) => {
+                kept += 1;
+                display::print_warning(&format!("  Could not count media ({e:#}) — keeping zip"));
+                continue;
+            }
+        }
+        if !verify_zip_manifest(&zip_path, &dir) {
+            kept += 1;
+            display::print_warning("  Verify failed — keeping zip");
+            continue;
+        }
+        verified += 1;
+        if !icloud_confirmed {
+            display::print_success("  Verified — pass --icloud-confirmed to delete");
+            continue;
+        }
+        warn_if_icloud_pending(&zip_path, &dir, "  ");
+        match std::fs::remove_file(&zip_path) {
+            Ok(()) => {
+                deleted += 1;
+                display::print_success(&format!("  Verified + deleted {zip_name}"));
+            }
+            Err(e) => {
+                kept += 1;
+                display::print_warning(&format!("  Verified OK but could not delete zip: {e}"));
+            }
+        }
     }
+
+    display::print_header("Re-verify complete");
+    display::print_summary(&format!("Verified: {verified}"));
+    display::print_summary(&format!("Deleted: {deleted}"));
+    display::print_summary(&format!("Kept: {kept}"));
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum VerifySuccessAction {
-    KeepZipAndMarkCompleted,
-    DeleteZipAndMarkCompleted,
+/// Examples printed per error by `list-failed`.
+const LIST_FAILED_EXAMPLES: usize = 5;
+
+/// Failures grouped by error message, most frequent first. Each path is
+/// prefixed with its zip.
+fn group_failures(
+    manifests: &[manifest::ImportManifest],
+    error_contains: Option<&str>,
+) -> Vec<(String, Vec<String>)> {
+    let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for m in manifests {
+        for failure in &m.failed {
+            if error_contains.is_some_and(|needle| !failure.error.contains(needle)) {
+                continue;
+            }
+            groups
+                .entry(failure.error.as_str())
+                .or_default()
+                .push(format!("{}: {}", m.zip, failure.path));
+        }
+    }
+    let mut groups: Vec<(String, Vec<String>)> = groups
+        .into_iter()
+        .map(|(error, paths)| (error.to_string(), paths))
+        .collect();
+    // Stable: equal counts stay in error-message order
+    groups.sort_by_key(|(_, paths)| std::cmp::Reverse(paths.len()));
+    groups
 }
 
-fn verify_success_action(keep_zips: bool) -> VerifySuccessAction {
-    if keep_zips {
-        VerifySuccessAction::KeepZipAndMarkCompleted
-    } else {
-        VerifySuccessAction::DeleteZipAndMarkCompleted
+/// `list-failed`: every manifest's failed imports, grouped so systematic
+/// failures cluster. Read-only.
+fn cmd_list_failed(dir: &Path, error_contains: Option<&str>) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Failed imports in {}", dir.display()));
+
+    let mut manifest_paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
+        })
+        .collect();
+    manifest_paths.sort();
+    let mut manifests = Vec::new();
+    for path in &manifest_paths {
+        match manifest::read_manifest_strict(path) {
+            Ok(Some(m)) => manifests.push(m),
+            Ok(None) => {}
+            Err(e) => display::print_warning(&format!("Skipping {}: {e}", path.display())),
+        }
     }
-}
 
-#[derive(Debug)]
-struct ImportFailure {
-    path: String,
-    error: String,
+    let groups = group_failures(&manifests, error_contains);
+    if groups.is_empty() {
+        display::print_success("No failed imports");
+        return Ok(());
+    }
+    let mut total = 0usize;
+    for (error, paths) in &groups {
+        total += paths.len();
+        println!();
+        display::print_error(&format!("{} × {error}", paths.len()));
+        for path in paths.iter().take(LIST_FAILED_EXAMPLES) {
+            display::print_info(&format!("  {path}"));
+        }
+        if paths.len() > LIST_FAILED_EXAMPLES {
+            display::print_info(&format!(
+                "  … and {} more",
+                paths.len() - LIST_FAILED_EXAMPLES
+            ));
+        }
+    }
+    println!();
+    display::print_summary(&format!(
+        "{total} failed imports, {} distinct errors, across {} manifests",
+        groups.len(),
+        manifests.len()
+    ));
+    Ok(())
 }
 
-#[derive(Debug)]
-struct ImportedFile {
-    path: PathBuf,
-    local_id: String,
-    album: Option<String>,
-    creation_date: Option<String>,
-    is_live_photo: bool,
+/// Everything photoferry writes into `dir`: manifests, download progress,
+/// the album registry and leftover extraction dirs.
+fn clean_targets(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut targets: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(".photoferry-"))
+        })
+        .collect();
+    targets.sort();
+    Ok(targets)
 }
 
-#[derive(Debug)]
-struct LivePhotoFallback {
-    photo_path: PathBuf,
-    video_path: PathBuf,
-    local_id: String,
+/// Reasons not to delete `targets`: manifests with failed (or unreadable)
+/// entries and download progress files with failed parts.
+fn clean_blockers(targets: &[PathBuf]) -> Vec<String> {
+    let mut blockers = Vec::new();
+    for path in targets {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.ends_with(".json") {
+            continue;
+        }
+        if name.starts_with(".photoferry-manifest-") {
+            match manifest::read_manifest_strict(path) {
+                Ok(Some(m)) if !m.failed.is_empty() => {
+                    blockers.push(format!("{name}: {} failed imports", m.failed.len()))
+                }
+                Ok(_) => {}
+                Err(e) => blockers.push(format!("{name}: unreadable ({e})")),
+            }
+        } else if name.starts_with(".photoferry-download-") {
+            let progress = std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| Ok(serde_json::from_str::<downloader::DownloadProgress>(&data)?));
+            match progress {
+                Ok(p) if !p.failed.is_empty() => {
+                    let mut parts = p.failed.clone();
+                    parts.sort_unstable();
+                    let parts: Vec<String> = parts.iter().map(|i| i.to_string()).collect();
+                    blockers.push(format!("{name}: incomplete parts {}", parts.join(", ")));
+                }
+                Ok(_) => {}
+                Err(e) => blockers.push(format!("{name}: unreadable ({e})")),
+            }
+        }
+    }
+    blockers
 }
 
-#[derive(Debug, Default)]
-struct ImportSummary {
-    imported: Vec<ImportedFile>,
-    failed: Vec<ImportFailure>,
-    elapsed: std::time::Duration,
-    live_photo_fallbacks: usize,
-    live_photo_fallback_entries: Vec<LivePhotoFallback>,
-}
+/// `clean`: remove photoferry's state files from `dir` after asking. Refuses
+/// while anything is unresolved, unless `--force`.
+fn cmd_clean(dir: &Path, dry_run: bool, force: bool) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Cleaning photoferry files in {}", dir.display()));
 
-impl ImportSummary {
-    fn merge(&mut self, other: &ImportSummary) {
-        self.imported
-            .extend(other.imported.iter().map(|file| ImportedFile {
-                path: file.path.clone(),
-                local_id: file.local_id.clone(),
-                album: file.album.clone(),
-                creation_date: file.creation_date.clone(),
-                is_live_photo: file.is_live_photo,
-            }));
-        self.failed
-            .extend(other.failed.iter().map(|f| ImportFailure {
-                path: f.path.clone(),
-                error: f.error.clone(),
-            }));
-        self.elapsed += other.elapsed;
-        self.live_photo_fallbacks += other.live_photo_fallbacks;
-        self.live_photo_fallback_entries
-            .extend(other.live_photo_fallback_entries.iter().map(|e| LivePhotoFallback {
-                photo_path: e.photo_path.clone(),
-                video_path: e.video_path.clone(),
-                local_id: e.local_id.clone(),
-            }));
+    let targets = clean_targets(&dir)?;
+    if targets.is_empty() {
+        display::print_info("Nothing to clean");
+        return Ok(());
+    }
+    for path in &targets {
+        display::print_info(&path.file_name().unwrap_or_default().to_string_lossy());
     }
-}
 
-fn import_inventory(inventory: &takeout::TakeoutInventory, verbose: bool) -> ImportSummary {
-    let total = inventory.files.len();
-    let mut summary = ImportSummary::default();
-    let start = Instant::now();
-    let mut album_ids: HashMap<String, String> = HashMap::new();
+    let blockers = clean_blockers(&targets);
+    for blocker in &blockers {
+        display::print_warning(blocker);
+    }
+    if !blockers.is_empty() && !force {
+        bail!(
+            "Migration not complete ({} unresolved) — retry first, or pass --force to remove anyway",
+            blockers.len()
+        );
+    }
+    if dry_run {
+        display::print_summary(&format!("Would remove {} files", targets.len()));
+        return Ok(());
+    }
 
-    if total == 0 {
-        display::print_warning("No media files found to import.");
-        return summary;
+    if !display::confirm(&format!("Remove {} files?", targets.len()))? {
+        display::print_info("Aborted — nothing removed");
+        return Ok(());
     }
 
-    for album in inventory.albums.iter().cloned().collect::<HashSet<_>>() {
-        match importer::create_album(&album) {
-            Ok(album_id) => {
-                album_ids.insert(album, album_id);
+    let mut removed = 0usize;
+    for path in &targets {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => display::print_warning(&format!("Could not remove {}: {e}", path.display())),
+        }
+    }
+    display::print_summary(&format!("Removed: {removed}/{}", targets.len()));
+    Ok(())
+}
+
+fn cmd_cookies() -> Result<()> {
+    display::print_header("Reading Google cookies from Chrome");
+    let mut cookies = downloader::chrome_cookie_report()?;
+    if cookies.is_empty() {
+        display::print_warning("No Google cookies found — log into Google in Chrome and retry");
+        return Ok(());
+    }
+    cookies.sort_by(|a, b| (&a.host_key, &a.name).cmp(&(&b.host_key, &b.name)));
+
+    for cookie in &cookies {
+        let line = format!("{} ({})", cookie.name, cookie.host_key);
+        match cookie.status {
+            downloader::CookieStatus::Usable => display::print_success(&line),
+            downloader::CookieStatus::Expired => {
+                display::print_warning(&format!("{line} — expired"))
             }
-            Err(err) => {
-                display::print_warning(&format!("Failed to create album '{}': {}", album, err));
+            downloader::CookieStatus::Unreadable => {
+                display::print_warning(&format!("{line} — could not decrypt"))
             }
         }
     }
 
-    let pb = if verbose {
-        ProgressBar::hidden()
+    let usable = cookies
+        .iter()
+        .filter(|c| c.status == downloader::CookieStatus::Usable)
+        .map(|c| c.name.as_str());
+    let missing = downloader::missing_auth_cookies(usable);
+    println!();
+    if missing.is_empty() {
+        display::print_summary_success("All sign-in cookies present");
     } else {
-        let pb = ProgressBar::new(total as u64);
-        let style =
-            ProgressStyle::with_template("[{bar:40}] {pos}/{len} {per_sec:.1}/s ETA {eta} {msg}")
-                .unwrap_or_else(|_| ProgressStyle::default_bar())
-                .progress_chars("##-");
-        pb.set_style(style);
-        pb
+        display::print_error(&format!(
+            "Missing sign-in cookies: {} — sign out of Google in Chrome and back in",
+            missing.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Upper bound on parts probed, in case Google never returns a 404.
+const MAX_PROBE_PARTS: usize = 1000;
+
+fn cmd_probe(job_id: &str, user_id: &str, dir: &Path) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Probing Takeout parts for job {job_id}"));
+
+    let Some(client) = downloader::try_build_http_client() else {
+        bail!("Probe needs Chrome cookies for HTTP HEAD requests — log into Google in Chrome and retry");
     };
 
-    for (index, file) in inventory.files.iter().enumerate() {
-        let filename = file
-            .path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .into_owned();
-        pb.set_message(filename.clone());
+    let mut parts: Vec<(usize, downloader::PartProbe)> = Vec::new();
+    for i in 0..MAX_PROBE_PARTS {
+        match downloader::probe_part(&client, job_id, user_id, i) {
+            Ok(Some(probe)) => parts.push((i, probe)),
+            Ok(None) => {
+                display::print_info(&format!("Part {i}: not found (404) — end of export"));
+                break;
+            }
+            Err(e) if i == 0 => return Err(e.context("Could not probe the first part")),
+            Err(e) => {
+                display::print_info(&format!("Part {i}: {e} — treating as end of export"));
+                break;
+            }
+        }
+    }
 
-        let path = match file.path.to_str() {
-            Some(p) => p,
+    if parts.is_empty() {
+        display::print_warning("No parts found.");
+        return Ok(());
+    }
+
+    println!();
+    println!("{:>4}  {:<48}  {:>9}  Local", "Part", "Filename", "Size");
+    let mut total_bytes = 0u64;
+    let mut unknown_sizes = 0usize;
+    for (i, probe) in &parts {
+        let size = match probe.content_length {
+            Some(len) => {
+                total_bytes += len;
+                format!("{:.2}GB", len as f64 / 1024.0 / 1024.0 / 1024.0)
+            }
             None => {
-                let err = "Invalid UTF-8 file path".to_string();
-                summary.failed.push(ImportFailure {
-                    path: file.path.display().to_string(),
-                    error: err.clone(),
-                });
-                if verbose {
-                    pb.println(format!(
-                        "  ! [{}/{}] {} — {}",
-                        index + 1,
-                        total,
-                        filename,
-                        err
-                    ));
-                }
-                pb.inc(1);
-                continue;
+                unknown_sizes += 1;
+                "?".to_string()
             }
         };
-
-        let mut used_live_fallback = false;
-        let import_result = if let Some(ref video_path) = file.live_photo_pair {
-            let live_result = match video_path.to_str() {
-                Some(video_str) => {
-                    importer::import_live_photo(path, video_str, file.metadata.as_ref())
-                }
-                None => Err(anyhow::anyhow!("Invalid UTF-8 in Live Photo video path")),
-            };
-
-            match live_result {
-                Ok(result) if result.success => Ok(result),
-                Ok(result) => {
-                    let live_err = result
-                        .error
-                        .clone()
-                        .unwrap_or_else(|| "Live Photo import failed".to_string());
-                    match importer::import_photo(path, file.metadata.as_ref(), false) {
-                        Ok(fallback) if fallback.success => {
-                            used_live_fallback = true;
-                            Ok(fallback)
-                        }
-                        Ok(fallback) => {
-                            let fb_err = fallback
-                                .error
-                                .unwrap_or_else(|| "Fallback photo import failed".to_string());
-                            Ok(importer::ImportResult {
-                                success: false,
-                                local_identifier: None,
-                                error: Some(format!(
-                                    "Live Photo failed ({live_err}); fallback failed ({fb_err})"
-                                )),
-                            })
-                        }
-                        Err(e) => Err(anyhow::anyhow!(
-                            "Live Photo failed ({live_err}); fallback error: {e}"
-                        )),
-                    }
-                }
-                Err(err) => match importer::import_photo(path, file.metadata.as_ref(), false) {
-                    Ok(fallback) if fallback.success => {
-                        used_live_fallback = true;
-                        Ok(fallback)
-                    }
-                    Ok(fallback) => {
-                        let fb_err = fallback
-                            .error
-                            .unwrap_or_else(|| "Fallback photo import failed".to_string());
-                        Ok(importer::ImportResult {
-                            success: false,
-                            local_identifier: None,
-                            error: Some(format!(
-                                "Live Photo error ({err}); fallback failed ({fb_err})"
-                            )),
-                        })
-                    }
-                    Err(e) => Err(anyhow::anyhow!(
-                        "Live Photo error ({err}); fallback error: {e}"
-                    )),
-                },
-            }
-        } else {
-            let is_video = matches!(file.media_type, takeout::MediaType::Video);
-            importer::import_photo(path, file.metadata.as_ref(), is_video)
+        let local = match (dir.join(&probe.filename).metadata(), probe.content_length) {
+            (Ok(meta), Some(len)) if meta.len() == len => "downloaded",
+            (Ok(_), _) => "partial",
+            (Err(_), _) => "-",
         };
+        println!("{i:>4}  {:<48}  {size:>9}  {local}", probe.filename);
+    }
+    println!();
 
-        match import_result {
-            Ok(result) if result.success => {
-                let Some(local_id) = result.local_identifier.clone() else {
-                    let err = "import succeeded but no local identifier returned".to_string();
-                    summary.failed.push(ImportFailure {
-                        path: file.path.display().to_string(),
-                        error: err.clone(),
-                    });
-                    if verbose {
-                        pb.println(format!(
-                            "  ! [{}/{}] {} — {}",
-                            index + 1,
-                            total,
-                            filename,
-                            err
-                        ));
-                    }
-                    pb.inc(1);
-                    continue;
-                };
-                if used_live_fallback {
-                    summary.live_photo_fallbacks += 1;
-                    if let Some(video_path) = file.live_photo_pair.as_ref() {
-                        summary.live_photo_fallback_entries.push(LivePhotoFallback {
-                            photo_path: file.path.clone(),
-                            video_path: video_path.clone(),
-                            local_id: local_id.clone(),
-                        });
-                    }
-                    pb.println(format!(
-                        "  ! Live Photo import failed; imported still photo only: {}",
-                        file.path.display()
-                    ));
-                }
+    display::print_info(&format!("Parts: {} (indices 0–{})", parts.len(), parts.len() - 1));
+    display::print_info(&format!(
+        "Total: {:.1}GB ({total_bytes} bytes)",
+        total_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+    ));
+    if unknown_sizes > 0 {
+        display::print_warning(&format!(
+            "{unknown_sizes} part(s) did not report Content-Length; total is a lower bound"
+        ));
+    }
+    display::print_info(&format!(
+        "Download all with: photoferry download --job {job_id} --user {user_id} --end {}",
+        parts.len() - 1
+    ));
 
-                summary.imported.push(ImportedFile {
-                    path: file.path.clone(),
-                    local_id: local_id.clone(),
-                    album: file.album.clone(),
-                    creation_date: file.metadata.as_ref().and_then(|m| m.creation_date.clone()),
-                    is_live_photo: file.live_photo_pair.is_some() && !used_live_fallback,
-                });
+    Ok(())
+}
 
-                if let Some(album_name) = file.album.as_ref()
-                    && let Some(album_id) = album_ids.get(album_name)
-                {
-                    if let Some(actual_local_id) = result.local_identifier.as_deref() {
-                        match importer::add_to_album(album_id, actual_local_id) {
-                            Ok(true) => {}
-                            Ok(false) => {
-                                pb.println(format!(
-                                    "  ! Failed to add '{}' to album '{}'",
-                                    filename, album_name
-                                ));
-                            }
-                            Err(err) => {
-                                pb.println(format!(
-                                    "  ! Failed to add '{}' to album '{}': {}",
-                                    filename, album_name, err
-                                ));
-                            }
-                        }
-                    } else {
-                        pb.println(format!(
-                            "  ! No local identifier for '{}'; skipping album assignment",
-                            filename
-                        ));
-                    }
-                }
+// MARK: - Helpers
 
-                if verbose {
-                    let label = if file.live_photo_pair.is_some() {
-                        let video_name = file
-                            .live_photo_pair
-                            .as_ref()
-                            .and_then(|p| p.file_name())
-                            .map(|n| n.to_string_lossy().into_owned())
-                            .unwrap_or_default();
-                        format!("{}+{}", filename, video_name)
-                    } else {
-                        filename.clone()
-                    };
-                    display::print_success(&format!(
-                        "[{}/{}] {} -> {}",
-                        index + 1,
-                        total,
-                        label,
-                        local_id
-                    ));
-                }
-            }
-            Ok(result) => {
-                let err = result.error.unwrap_or_else(|| "unknown error".to_string());
-                summary.failed.push(ImportFailure {
-                    path: file.path.display().to_string(),
-                    error: err.clone(),
-                });
-                if verbose {
-                    pb.println(format!(
-                        "  ! [{}/{}] {} — {}",
-                        index + 1,
-                        total,
-                        filename,
-                        err
-                    ));
-                }
+/// Swap each `.tgz`/`.tar.gz` export for the ZIP it repacks into, so the rest
+/// of the pipeline only ever sees ZIPs.
+fn repack_tarballs(zips: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    zips.into_iter()
+        .map(|path| {
+            if !takeout::is_tarball(&path) {
+                return Ok(path);
             }
-            Err(error) => {
-                let err = error.to_string();
-                summary.failed.push(ImportFailure {
-                    path: file.path.display().to_string(),
-                    error: err.clone(),
-                });
-                if verbose {
-                    pb.println(format!(
-                        "  ! [{}/{}] {} — {}",
-                        index + 1,
-                        total,
-                        filename,
-                        err
-                    ));
-                }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !takeout::tarball_zip_path(&path).exists() {
+                ensure_space_to_repack(&path)?;
             }
+            display::print_info(&format!("Repacking {name} as a zip (one-time)..."));
+            let zip_path = takeout::repack_tarball_as_zip(&path)?;
+            display::print_success(&format!(
+                "{name} → {} — the tarball can be deleted",
+                zip_path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            Ok(zip_path)
+        })
+        .collect()
+}
+
+/// The repacked zip is a second copy about the tarball's size (gzip barely
+/// shrinks media), so refuse when the disk can't hold it. Unknown free space
+/// doesn't block the repack.
+fn ensure_space_to_repack(tar_path: &Path) -> Result<()> {
+    let dir = tar_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let need_gb = std::fs::metadata(tar_path)?
+        .len()
+        .div_ceil(1024 * 1024 * 1024);
+    if let Some(free_gb) = downloader::available_space_gb(dir)
+        && free_gb < need_gb
+    {
+        bail!(
+            "Repacking {} needs about {need_gb}GB free for the zip; only {free_gb}GB free",
+            tar_path.display()
+        );
+    }
+    Ok(())
+}
+
+type ZipReader = zip::ZipArchive<BufReader<std::fs::File>>;
+
+fn extract_zip_entry(archive: &mut ZipReader, index: usize, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut zf = archive.by_index(index)?;
+    let mut out = std::fs::File::create(dest)?;
+    std::io::copy(&mut zf, &mut out)?;
+    Ok(())
+}
+
+/// Longest file name macOS (APFS, HFS+) accepts, in bytes
+const MAX_NAME_BYTES: usize = 255;
+/// macOS `PATH_MAX`
+const MAX_PATH_BYTES: usize = 1024;
+/// Stem bytes kept in a shortened temp name, so Photos still shows something
+/// recognizable as the original file name
+const SHORTENED_STEM_BYTES: usize = 100;
+
+/// Where to extract `relative` under `tmp_dir`: the same relative path, or,
+/// when a component or the whole path would be over the filesystem's limits
+/// (long album names plus long file names), a shortened name in
+/// `.photoferry-long/` that keeps the extension and is unique per relative
+/// path. The second value says whether it was shortened.
+fn temp_extract_path(tmp_dir: &Path, relative: &str) -> (PathBuf, bool) {
+    let full = tmp_dir.join(relative);
+    let fits = full.as_os_str().len() < MAX_PATH_BYTES
+        && full
+            .components()
+            .all(|c| c.as_os_str().len() <= MAX_NAME_BYTES);
+    if fits {
+        return (full, false);
+    }
+
+    let rel_path = Path::new(relative);
+    let name = rel_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+        _ => (name.as_str(), None),
+    };
+    let mut end = stem.len().min(SHORTENED_STEM_BYTES);
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut hasher = std::hash::DefaultHasher::new();
+    std::hash::Hash::hash(relative, &mut hasher);
+    let hash = std::hash::Hasher::finish(&hasher);
+    let short = match ext {
+        Some(ext) => format!("{}-{hash:016x}.{ext}", &stem[..end]),
+        None => format!("{}-{hash:016x}", &stem[..end]),
+    };
+    (tmp_dir.join(".photoferry-long").join(short), true)
+}
+
+/// `--date-albums`: album title for a year-folder file from its creation
+/// date, or None if it has no usable date.
+fn date_album_title(granularity: DateAlbums, creation_date: Option<&str>) -> Option<String> {
+    let date = chrono::DateTime::parse_from_rfc3339(creation_date?).ok()?;
+    let format = match granularity {
+        DateAlbums::Year => "%Y",
+        DateAlbums::Month => "%Y-%m",
+    };
+    Some(date.format(format).to_string())
+}
+
+/// Every Nth import (and the first) is looked up again right away, to catch
+/// Photos handing back an identifier that doesn't last.
+const SELF_CHECK_EVERY: usize = 100;
+
+fn self_check_due(imported: usize) -> bool {
+    imported % SELF_CHECK_EVERY == 1
+}
+
+/// Whether Photos finds `local_id` straight after importing it. A lookup
+/// error says nothing about the id, so it counts as found.
+fn findable_by_id(library: &dyn importer::Importer, local_id: &str) -> bool {
+    match library.verify_assets(&[local_id]) {
+        Ok(results) => results
+            .iter()
+            .any(|r| r.local_identifier == local_id && r.found),
+        Err(_) => true,
+    }
+}
+
+/// `--progress-every N`: whether the `n`th successful import (1-based) gets
+/// its verbose line.
+fn verbose_success_due(n: usize, every: Option<NonZeroUsize>) -> bool {
+    every.is_none_or(|every| n.is_multiple_of(every.get()))
+}
+
+fn remove_extracted(media: &Path, live_video: Option<&Path>) {
+    let _ = std::fs::remove_file(media);
+    if let Some(video) = live_video {
+        let _ = std::fs::remove_file(video);
+    }
+}
+
+/// `run --dry-run --verbose`: the sidecar and metadata a file would be
+/// imported with.
+fn dry_run_detail(
+    sidecar: Option<(&str, sidecar::SidecarMatchStrength)>,
+    metadata: Option<&importer::PhotoMetadata>,
+    album: Option<&str>,
+) -> String {
+    let mut parts = vec![match sidecar {
+        Some((path, sidecar::SidecarMatchStrength::Strong)) => format!("sidecar {path}"),
+        Some((path, sidecar::SidecarMatchStrength::Fuzzy)) => format!("sidecar {path} (fuzzy)"),
+        None => "no sidecar".to_string(),
+    }];
+    let date = metadata.and_then(|m| m.creation_date.as_deref());
+    parts.push(format!("date {}", date.unwrap_or("none")));
+    if let Some(m) = metadata
+        && let (Some(lat), Some(lon)) = (m.latitude, m.longitude)
+    {
+        parts.push(format!("GPS {lat:.5},{lon:.5}"));
+    }
+    if metadata.and_then(|m| m.is_favorite) == Some(true) {
+        parts.push("favorite".to_string());
+    }
+    if let Some(album) = album {
+        parts.push(format!("album {album:?}"));
+    }
+    parts.join(" | ")
+}
+
+/// Imports slower than this are flagged in `--verbose` output.
+const SLOW_IMPORT: Duration = Duration::from_secs(10);
+
+/// `--verbose`: how long one file took to import, flagged when slow.
+fn import_time_label(elapsed: Duration) -> String {
+    let millis = elapsed.as_millis();
+    if elapsed >= SLOW_IMPORT {
+        format!("({millis} ms, slow)")
+    } else {
+        format!("({millis} ms)")
+    }
+}
+
+/// Sort key for `--import-order date`: dated files oldest first, undated last.
+fn import_date_key(creation_date: Option<&str>) -> (bool, i64) {
+    match creation_date.and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok()) {
+        Some(dt) => (false, dt.timestamp()),
+        None => (true, 0),
+    }
+}
+
+const VIDEOS_ALBUM_SUFFIX: &str = " (Videos)";
+const SINGLE_VIDEOS_ALBUM: &str = "Imported Videos";
+
+/// The album a file should be added to, after `--split-media-albums` routing.
+fn album_for_media(
+    album: Option<&str>,
+    media_type: takeout::MediaType,
+    split: Option<SplitMediaAlbums>,
+) -> Option<String> {
+    match (media_type, split) {
+        (takeout::MediaType::Video, Some(SplitMediaAlbums::Suffix)) => {
+            album.map(|a| format!("{a}{VIDEOS_ALBUM_SUFFIX}"))
+        }
+        (takeout::MediaType::Video, Some(SplitMediaAlbums::Single)) => {
+            Some(SINGLE_VIDEOS_ALBUM.to_string())
         }
+        _ => album.map(str::to_string),
+    }
+}
 
-        pb.inc(1);
+/// Whether a directory passes `run --album`. An empty filter passes everything;
+/// year folders only pass when named explicitly.
+fn album_filter_matches(
+    filter: &[String],
+    album: Option<&str>,
+    year_folder: Option<&Path>,
+) -> bool {
+    if filter.is_empty() {
+        return true;
     }
+    let name = album.or_else(|| year_folder.and_then(|d| d.file_name()?.to_str()));
+    let Some(name) = name.map(|n| n.trim().to_lowercase()) else {
+        return false;
+    };
+    filter.iter().any(|f| f.trim().to_lowercase() == name)
+}
 
-    pb.finish_and_clear();
-    summary.elapsed = start.elapsed();
-    summary
+/// `--album-key-photo`: keep `local_id` as `album`'s key photo if it's the
+/// earliest-dated import in it so far. Undated imports, and dates that don't
+/// parse, are never picked.
+fn note_earliest_in_album(
+    earliest: &mut BTreeMap<String, (chrono::DateTime<chrono::FixedOffset>, String)>,
+    album: &str,
+    creation_date: Option<&str>,
+    local_id: &str,
+) {
+    let Some(date) = creation_date.and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+    else {
+        return;
+    };
+    match earliest.get_mut(album) {
+        Some(current) if (date, local_id) < (current.0, current.1.as_str()) => {
+            *current = (date, local_id.to_string());
+        }
+        Some(_) => {}
+        None => {
+            earliest.insert(album.to_string(), (date, local_id.to_string()));
+        }
+    }
 }
 
-fn print_import_summary(summary: &ImportSummary) {
-    let secs = summary.elapsed.as_secs();
-    let elapsed_str = if secs >= 60 {
-        format!("{}m {}s", secs / 60, secs % 60)
-    } else {
-        format!("{}s", secs)
-    };
+/// Add an imported asset to the album titled `album_name`, creating the album
+/// if needed. Returns the title if the asset was added.
+fn add_to_named_album(
+    library: &dyn importer::Importer,
+    album_ids: &mut HashMap<String, Option<String>>,
+    registry: &mut manifest::AlbumRegistry,
+    album_name: &str,
+    local_id: &str,
+    filename: &str,
+    pb: &ProgressBar,
+) -> Option<String> {
+    let album_id = ensure_album(library, album_ids, registry, album_name)?;
+    match library.add_to_album(&album_id, local_id) {
+        Ok(true) => Some(album_name.to_string()),
+        Ok(false) => {
+            pb.println(format!(
+                "  ! Failed to add '{}' to album '{}'",
+                filename, album_name
+            ));
+            None
+        }
+        Err(e) => {
+            pb.println(format!(
+                "  ! Failed to add '{}' to album '{}': {}",
+                filename, album_name, e
+            ));
+            None
+        }
+    }
+}
+
+/// `--limit-albums`: whether `title` may be used — it already exists, or
+/// there's room to create it.
+fn album_within_limit(
+    limit: Option<usize>,
+    registry: &manifest::AlbumRegistry,
+    title: &str,
+) -> bool {
+    limit.is_none_or(|n| registry.get(title).is_some() || registry.album_count() < n)
+}
+
+/// Look up an album id by title — this run's cache first, then the persisted
+/// registry — creating the album only if neither knows it.
+/// Failed creations are cached as None so they are not retried for every file.
+fn ensure_album(
+    library: &dyn importer::Importer,
+    album_ids: &mut HashMap<String, Option<String>>,
+    registry: &mut manifest::AlbumRegistry,
+    title: &str,
+) -> Option<String> {
+    if let Some(cached) = album_ids.get(title) {
+        return cached.clone();
+    }
+    let id = if let Some(known) = registry.get(title) {
+        Some(known.to_string())
+    } else {
+        match library.create_album(title) {
+            Ok(id) => {
+                if let Err(e) = registry.insert(title, &id) {
+                    display::print_warning(&format!(
+                        "Failed to save album id for '{}': {}",
+                        title, e
+                    ));
+                }
+                Some(id)
+            }
+            Err(e) => {
+                display::print_warning(&format!("Failed to create album '{}': {}", title, e));
+                None
+            }
+        }
+    };
+    album_ids.insert(title.to_string(), id.clone());
+    id
+}
+
+const UNKNOWN_REPORT_HEADER: &str = "zip,relative_path,ext,size_bytes";
+
+/// Append `unknown_files` to the `--unknown-report` CSV. Refuses to append to
+/// an existing file that doesn't start with the report header (likely some
+/// unrelated file) unless `force`.
+fn write_unknown_report(
+    report_path: &Path,
+    zip_name: &str,
+    unknown_files: &[takeout::UnknownFile],
+    force: bool,
+) -> Result<()> {
+    if unknown_files.is_empty() {
+        return Ok(());
+    }
+    let mut needs_header = true;
+    if let Ok(meta) = std::fs::metadata(report_path) {
+        if meta.len() > 0 {
+            needs_header = false;
+            if !force && !is_unknown_report(report_path)? {
+                anyhow::bail!(
+                    "--unknown-report {} exists and isn't an unknown-files report — \
+                     pick another path, or pass --force-unknown-report to append anyway",
+                    report_path.display()
+                );
+            }
+        }
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(report_path)?;
+
+    if needs_header {
+        use std::io::Write;
+        writeln!(file, "{UNKNOWN_REPORT_HEADER}")?;
+    }
+
+    use std::io::Write;
+    for entry in unknown_files {
+        let rel = entry.path.to_string_lossy().replace('"', "\"\"");
+        let ext = entry.ext.replace('"', "\"\"");
+        writeln!(file, "\"{zip_name}\",\"{rel}\",\"{ext}\",{}", entry.size_bytes)?;
+    }
+    Ok(())
+}
+
+/// Whether the file at `path` starts with [`UNKNOWN_REPORT_HEADER`].
+fn is_unknown_report(path: &Path) -> Result<bool> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let mut first_line = String::new();
+    BufReader::new(file).read_line(&mut first_line)?;
+    Ok(first_line.trim_end() == UNKNOWN_REPORT_HEADER)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+enum VerifySuccessAction {
+    KeepZipAndMarkCompleted,
+    MoveZipAndMarkCompleted,
+    DeleteZipAndMarkCompleted,
+}
+
+fn verify_success_action(keep_zips: bool, archive: bool) -> VerifySuccessAction {
+    if keep_zips {
+        VerifySuccessAction::KeepZipAndMarkCompleted
+    } else if archive {
+        VerifySuccessAction::MoveZipAndMarkCompleted
+    } else {
+        VerifySuccessAction::DeleteZipAndMarkCompleted
+    }
+}
+
+/// Why `download` keeps a part's zip although nothing failed: some of its
+/// media wasn't imported, and Takeout parts can't be downloaded again, so
+/// deleting the zip would lose it. None when the whole zip went in.
+fn zip_keep_reason(summary: &ImportSummary) -> Option<String> {
+    if !summary.oversized_skipped.is_empty() {
+        return Some(format!(
+            "{} files over --max-file-size — zip kept for manual import",
+            summary.oversized_skipped.len()
+        ));
+    }
+    if summary.deadline_remaining > 0 {
+        return Some("Stopped at --max-runtime — zip kept to resume".to_string());
+    }
+    let left_out: Vec<String> = [
+        (summary.skipped_not_favorite, "--favorites-only"),
+        (summary.skipped_by_people, "--min-people"),
+        (summary.skipped_by_ext, "--skip-ext"),
+        (summary.skipped_burst_frames, "--burst-policy cover-only"),
+        (summary.skipped_by_edited_policy, "--edited-policy"),
+        (summary.skipped_year_folder, "--import-albums-only"),
+        (summary.skipped_animated_gifs, "--gif-policy skip"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, flag)| format!("{count} by {flag}"))
+    .collect();
+    (!left_out.is_empty()).then(|| {
+        format!(
+            "Files left out of the import ({}) — zip kept",
+            left_out.join(", ")
+        )
+    })
+}
+
+/// Move a verified zip into `archive_dir`, out of the way of `find_takeout_zips`.
+/// Falls back to copy + delete when `archive_dir` is on another filesystem.
+fn archive_zip(zip_path: &Path, archive_dir: &Path) -> Result<PathBuf> {
+    let file_name = zip_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file: {}", zip_path.display()))?;
+    std::fs::create_dir_all(archive_dir)
+        .with_context(|| format!("Failed to create {}", archive_dir.display()))?;
+    let dest = archive_dir.join(file_name);
+    if dest.exists() {
+        bail!("{} already exists", dest.display());
+    }
+
+    match std::fs::rename(zip_path, &dest) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            // Copy under a temp name so an interrupted copy never looks like an archived zip
+            let partial = dest.with_extension("zip.partial");
+            std::fs::copy(zip_path, &partial)
+                .with_context(|| format!("Failed to copy to {}", partial.display()))?;
+            std::fs::rename(&partial, &dest)?;
+            std::fs::remove_file(zip_path)
+                .with_context(|| format!("Copied but failed to remove {}", zip_path.display()))?;
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to move to {}", dest.display()));
+        }
+    }
+    Ok(dest)
+}
+
+#[derive(Debug, Clone)]
+struct ImportFailure {
+    path: String,
+    error: String,
+}
+
+/// Failures an [`ImportSummary`] keeps for the end-of-run report; the
+/// manifest (and `list-failed`) has all of them.
+const FAILURES_LISTED: usize = 20;
+
+/// One file `import_inventory` imported, for the caller to record.
+#[derive(Debug)]
+struct ImportedFile {
+    path: PathBuf,
+    local_id: String,
+    album: Option<String>,
+    creation_date: Option<String>,
+    is_live_photo: bool,
+}
+
+/// A zip's (or run's) results. Counts only, so memory doesn't grow with the
+/// zip: per-file records go to the manifest as they happen.
+#[derive(Debug, Default)]
+struct ImportSummary {
+    imported: usize,
+    failed: usize,
+    /// The first [`FAILURES_LISTED`] failures
+    failures: Vec<ImportFailure>,
+    elapsed: std::time::Duration,
+    live_photo_fallbacks: usize,
+    /// Motion photos imported as a plain still; not in the manifest's
+    /// fallbacks, since there's no separate video to retry with
+    motion_photo_fallbacks: usize,
+    skipped_by_album_filter: usize,
+    /// Files whose truncated name collided with another's, so no sidecar was matched
+    ambiguous_truncation_skipped: usize,
+    /// Media skipped for exceeding `--max-file-size`; left in the zip
+    oversized_skipped: Vec<OversizedFile>,
+    /// Media excluded by `--skip-ext`
+    skipped_by_ext: usize,
+    /// Media with fewer tagged people than `--min-people`
+    skipped_by_people: usize,
+    /// Non-favorites left out by `--favorites-only`
+    skipped_not_favorite: usize,
+    /// Originals or edited copies left out by `--edited-policy`
+    skipped_by_edited_policy: usize,
+    /// Non-cover burst frames left out by `--burst-policy cover-only`
+    skipped_burst_frames: usize,
+    /// Animated GIFs imported as GIF files (may show as a still)
+    animated_gifs_static: usize,
+    /// Animated GIFs converted and imported as videos
+    animated_gifs_as_video: usize,
+    /// Animated GIFs left out by `--gif-policy skip`
+    skipped_animated_gifs: usize,
+    /// Files extracted under a shortened temp name (path over filesystem limits)
+    shortened_paths: usize,
+    /// Year-folder files added to a `--date-albums` album
+    date_album_assigned: usize,
+    /// Self-checked imports Photos couldn't find by their returned id
+    unstable_ids: usize,
+    /// Creation dates moved to their GPS location's local time
+    dates_localized: usize,
+    /// Photos with GPS whose time zone couldn't be looked up
+    dates_not_localized: usize,
+    /// Year-folder media left out by `--import-albums-only`
+    skipped_year_folder: usize,
+    /// Fuzzy sidecar matches whose title didn't match the media filename
+    fuzzy_title_mismatches: usize,
+    /// Imports left out of their album by `--limit-albums`
+    album_limit_unassigned: usize,
+    /// JSON sidecars that matched no media file
+    orphaned_sidecars: usize,
+    /// Matched sidecars that failed to parse (media failed under
+    /// `--strict-metadata`, else imported without metadata)
+    unparseable_sidecars: usize,
+    /// Extra copies of a file already in this zip, imported once
+    duplicate_copies: usize,
+    /// Planned files left unimported because `--max-runtime` ran out
+    deadline_remaining: usize,
+    /// Phase 1 ZIP index, filled only for `run --dump-index`
+    index: Vec<IndexEntry>,
+    /// What `run --dry-run` found a real run would do
+    dry_run: DryRunCounts,
+    /// Produced by `run --dry-run`: nothing was imported
+    simulated: bool,
+}
+
+#[derive(Debug, Clone)]
+struct OversizedFile {
+    path: String,
+    size_bytes: u64,
+}
+
+/// Media in one directory that `--edited-policy` leaves out: originals that
+/// have an edited copy (`edited`), or edited copies of an original present
+/// alongside (`original`). Unpaired files are always kept.
+fn edited_policy_skips(media: &[PathBuf], policy: EditedPolicy) -> HashSet<&PathBuf> {
+    if policy == EditedPolicy::Both {
+        return HashSet::new();
+    }
+    let stem = |p: &Path| p.file_stem().and_then(|s| s.to_str()).map(str::to_string);
+    let stems: HashSet<String> = media.iter().filter_map(|p| stem(p)).collect();
+    let originals_with_edits: HashSet<&str> = stems
+        .iter()
+        .filter_map(|s| sidecar::edited_original_stem(s))
+        .filter(|base| stems.contains(*base))
+        .collect();
+    media
+        .iter()
+        .filter(|p| {
+            let Some(s) = stem(p) else {
+                return false;
+            };
+            match policy {
+                EditedPolicy::Edited => originals_with_edits.contains(s.as_str()),
+                EditedPolicy::Original => sidecar::edited_original_stem(&s)
+                    .is_some_and(|base| originals_with_edits.contains(base)),
+                EditedPolicy::Both => false,
+            }
+        })
+        .collect()
+}
+
+/// Whether `ext` (lowercase, no dot) is listed in `--skip-ext`, which may
+/// be given with dots or in any case.
+fn skip_ext_matches(skip_ext: &[String], ext: &str) -> bool {
+    skip_ext
+        .iter()
+        .any(|s| s.trim().trim_start_matches('.').eq_ignore_ascii_case(ext))
+}
+
+/// Whether a ZIP entry of `size_bytes` is over the `--max-file-size` limit (GB).
+fn exceeds_max_file_size(size_bytes: u64, max_gb: Option<f64>) -> bool {
+    max_gb.is_some_and(|gb| size_bytes as f64 > gb * 1024.0 * 1024.0 * 1024.0)
+}
+
+impl ImportSummary {
+    fn record_failure(&mut self, failure: ImportFailure) {
+        self.failed += 1;
+        if self.failures.len() < FAILURES_LISTED {
+            self.failures.push(failure);
+        }
+    }
+
+    fn merge(&mut self, other: &ImportSummary) {
+        self.imported += other.imported;
+        self.failed += other.failed;
+        let room = FAILURES_LISTED.saturating_sub(self.failures.len());
+        self.failures
+            .extend(other.failures.iter().take(room).cloned());
+        self.elapsed += other.elapsed;
+        self.live_photo_fallbacks += other.live_photo_fallbacks;
+        self.motion_photo_fallbacks += other.motion_photo_fallbacks;
+        self.skipped_by_album_filter += other.skipped_by_album_filter;
+        self.skipped_by_ext += other.skipped_by_ext;
+        self.skipped_by_people += other.skipped_by_people;
+        self.skipped_not_favorite += other.skipped_not_favorite;
+        self.skipped_by_edited_policy += other.skipped_by_edited_policy;
+        self.skipped_burst_frames += other.skipped_burst_frames;
+        self.animated_gifs_static += other.animated_gifs_static;
+        self.animated_gifs_as_video += other.animated_gifs_as_video;
+        self.skipped_animated_gifs += other.skipped_animated_gifs;
+        self.dates_localized += other.dates_localized;
+        self.shortened_paths += other.shortened_paths;
+        self.date_album_assigned += other.date_album_assigned;
+        self.unstable_ids += other.unstable_ids;
+        self.dates_not_localized += other.dates_not_localized;
+        self.skipped_year_folder += other.skipped_year_folder;
+        self.dry_run.would_import += other.dry_run.would_import;
+        self.dry_run.already_done += other.dry_run.already_done;
+        self.dry_run.would_retry_failed += other.dry_run.would_retry_failed;
+        self.simulated |= other.simulated;
+        self.ambiguous_truncation_skipped += other.ambiguous_truncation_skipped;
+        self.fuzzy_title_mismatches += other.fuzzy_title_mismatches;
+        self.album_limit_unassigned += other.album_limit_unassigned;
+        self.orphaned_sidecars += other.orphaned_sidecars;
+        self.unparseable_sidecars += other.unparseable_sidecars;
+        self.deadline_remaining += other.deadline_remaining;
+        self.duplicate_copies += other.duplicate_copies;
+        self.oversized_skipped
+            .extend(other.oversized_skipped.iter().cloned());
+    }
+}
+
+fn import_inventory(
+    inventory: &takeout::TakeoutInventory,
+    verbose: bool,
+    progress_every: Option<NonZeroUsize>,
+    no_live_fallback: bool,
+    album_registry: &mut manifest::AlbumRegistry,
+    library: &dyn importer::Importer,
+) -> (ImportSummary, Vec<ImportedFile>, Vec<ImportFailure>) {
+    let total = inventory.files.len();
+    let mut summary = ImportSummary::default();
+    let mut imported = Vec::new();
+    let mut failed = Vec::new();
+    let start = Instant::now();
+    let mut album_ids: HashMap<String, Option<String>> = HashMap::new();
+
+    if total == 0 {
+        display::print_warning("No media files found to import.");
+        return (summary, imported, failed);
+    }
+
+    for album in inventory.albums.iter().cloned().collect::<HashSet<_>>() {
+        ensure_album(library, &mut album_ids, album_registry, &album);
+    }
+
+    let pb = if verbose || display::is_quiet() {
+        ProgressBar::hidden()
+    } else {
+        let pb = ProgressBar::new(total as u64);
+        let style =
+            ProgressStyle::with_template("[{bar:40}] {pos}/{len} {per_sec:.1}/s ETA {eta} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_bar())
+                .progress_chars("##-");
+        pb.set_style(style);
+        pb
+    };
+
+    for (index, file) in inventory.files.iter().enumerate() {
+        let filename = file
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        pb.set_message(filename.clone());
+
+        let path = match file.path.to_str() {
+            Some(p) => p,
+            None => {
+                let err = "Invalid UTF-8 file path".to_string();
+                failed.push(ImportFailure {
+                    path: file.path.display().to_string(),
+                    error: err.clone(),
+                });
+                if verbose {
+                    pb.println(format!(
+                        "  ! [{}/{}] {} — {}",
+                        index + 1,
+                        total,
+                        filename,
+                        err
+                    ));
+                }
+                pb.inc(1);
+                continue;
+            }
+        };
+
+        let mut used_live_fallback = false;
+        let import_result = if let Some(ref video_path) = file.live_photo_pair {
+            let live_result = match video_path.to_str() {
+                Some(video_str) => {
+                    library.import_live_photo(path, video_str, file.metadata.as_ref())
+                }
+                None => Err(anyhow::anyhow!("Invalid UTF-8 in Live Photo video path")),
+            };
+
+            match live_result {
+                Ok(result) if result.success => Ok(result),
+                // --no-live-fallback: fail loudly rather than import a degraded still
+                Ok(result) if no_live_fallback => Ok(result),
+                Err(err) if no_live_fallback => Err(err),
+                Ok(result) => {
+                    let live_err = result
+                        .error
+                        .clone()
+                        .unwrap_or_else(|| "Live Photo import failed".to_string());
+                    match library.import_photo(path, file.metadata.as_ref(), false) {
+                        Ok(fallback) if fallback.success => {
+                            used_live_fallback = true;
+                            Ok(fallback)
+                        }
+                        Ok(fallback) => {
+                            let fb_err = fallback
+                                .error
+                                .unwrap_or_else(|| "Fallback photo import failed".to_string());
+                            Ok(importer::ImportResult {
+                                success: false,
+                                local_identifier: None,
+                                error: Some(format!(
+                                    "Live Photo failed ({live_err}); fallback failed ({fb_err})"
+                                )),
+                            })
+                        }
+                        Err(e) => Err(anyhow::anyhow!(
+                            "Live Photo failed ({live_err}); fallback error: {e}"
+                        )),
+                    }
+                }
+                Err(err) => match library.import_photo(path, file.metadata.as_ref(), false) {
+                    Ok(fallback) if fallback.success => {
+                        used_live_fallback = true;
+                        Ok(fallback)
+                    }
+                    Ok(fallback) => {
+                        let fb_err = fallback
+                            .error
+                            .unwrap_or_else(|| "Fallback photo import failed".to_string());
+                        Ok(importer::ImportResult {
+                            success: false,
+                            local_identifier: None,
+                            error: Some(format!(
+                                "Live Photo error ({err}); fallback failed ({fb_err})"
+                            )),
+                        })
+                    }
+                    Err(e) => Err(anyhow::anyhow!(
+                        "Live Photo error ({err}); fallback error: {e}"
+                    )),
+                },
+            }
+        } else {
+            let is_video = matches!(file.media_type, takeout::MediaType::Video);
+            library.import_photo(path, file.metadata.as_ref(), is_video)
+        };
+
+        match import_result {
+            Ok(result) if result.success => {
+                let Some(local_id) = result.local_identifier.clone() else {
+                    let err = "import succeeded but no local identifier returned".to_string();
+                    failed.push(ImportFailure {
+                        path: file.path.display().to_string(),
+                        error: err.clone(),
+                    });
+                    if verbose {
+                        pb.println(format!(
+                            "  ! [{}/{}] {} — {}",
+                            index + 1,
+                            total,
+                            filename,
+                            err
+                        ));
+                    }
+                    pb.inc(1);
+                    continue;
+                };
+                if used_live_fallback {
+                    summary.live_photo_fallbacks += 1;
+                    pb.println(format!(
+                        "  ! Live Photo import failed; imported still photo only: {}",
+                        file.path.display()
+                    ));
+                }
+
+                imported.push(ImportedFile {
+                    path: file.path.clone(),
+                    local_id: local_id.clone(),
+                    album: file.album.clone(),
+                    creation_date: file.metadata.as_ref().and_then(|m| m.creation_date.clone()),
+                    is_live_photo: file.live_photo_pair.is_some() && !used_live_fallback,
+                });
+
+                if let Some(album_name) = file.album.as_ref()
+                    && let Some(Some(album_id)) = album_ids.get(album_name)
+                {
+                    if let Some(actual_local_id) = result.local_identifier.as_deref() {
+                        match library.add_to_album(album_id, actual_local_id) {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                pb.println(format!(
+                                    "  ! Failed to add '{}' to album '{}'",
+                                    filename, album_name
+                                ));
+                            }
+                            Err(err) => {
+                                pb.println(format!(
+                                    "  ! Failed to add '{}' to album '{}': {}",
+                                    filename, album_name, err
+                                ));
+                            }
+                        }
+                    } else {
+                        pb.println(format!(
+                            "  ! No local identifier for '{}'; skipping album assignment",
+                            filename
+                        ));
+                    }
+                }
+
+                if verbose && verbose_success_due(imported.len(), progress_every) {
+                    let label = if file.live_photo_pair.is_some() {
+                        let video_name = file
+                            .live_photo_pair
+                            .as_ref()
+                            .and_then(|p| p.file_name())
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        format!("{}+{}", filename, video_name)
+                    } else {
+                        filename.clone()
+                    };
+                    display::print_success(&format!(
+                        "[{}/{}] {} -> {}",
+                        index + 1,
+                        total,
+                        label,
+                        local_id
+                    ));
+                }
+            }
+            Ok(result) => {
+                let err = result.error.unwrap_or_else(|| "unknown error".to_string());
+                failed.push(ImportFailure {
+                    path: file.path.display().to_string(),
+                    error: err.clone(),
+                });
+                if verbose {
+                    pb.println(format!(
+                        "  ! [{}/{}] {} — {}",
+                        index + 1,
+                        total,
+                        filename,
+                        err
+                    ));
+                }
+            }
+            Err(error) => {
+                let err = error.to_string();
+                failed.push(ImportFailure {
+                    path: file.path.display().to_string(),
+                    error: err.clone(),
+                });
+                if verbose {
+                    pb.println(format!(
+                        "  ! [{}/{}] {} — {}",
+                        index + 1,
+                        total,
+                        filename,
+                        err
+                    ));
+                }
+            }
+        }
+
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+    summary.elapsed = start.elapsed();
+    summary.imported = imported.len();
+    for failure in &failed {
+        summary.record_failure(failure.clone());
+    }
+    (summary, imported, failed)
+}
+
+fn print_import_summary(summary: &ImportSummary) {
+    let secs = summary.elapsed.as_secs();
+    let elapsed_str = if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    };
+
+    let imported_label = if summary.simulated {
+        "Imported (dry run)"
+    } else {
+        "Imported"
+    };
+    display::print_summary(&format!("{imported_label}: {}", summary.imported));
+    display::print_summary(&format!("Failed: {}", summary.failed));
+    display::print_summary(&format!("Elapsed: {}", elapsed_str));
+    if summary.skipped_by_album_filter > 0 {
+        display::print_summary(&format!(
+            "Skipped (not in --album): {}",
+            summary.skipped_by_album_filter
+        ));
+    }
+    if summary.skipped_by_ext > 0 {
+        display::print_summary(&format!("Skipped (--skip-ext): {}", summary.skipped_by_ext));
+    }
+    if summary.duplicate_copies > 0 {
+        display::print_summary(&format!(
+            "Duplicate copies in zip (imported once, added to each album): {}",
+            summary.duplicate_copies
+        ));
+    }
+    if summary.skipped_year_folder > 0 {
+        display::print_summary(&format!(
+            "Skipped (year folder, --import-albums-only): {}",
+            summary.skipped_year_folder
+        ));
+    }
+    if summary.skipped_by_edited_policy > 0 {
+        display::print_summary(&format!(
+            "Skipped (--edited-policy): {}",
+            summary.skipped_by_edited_policy
+        ));
+    }
+    if summary.unstable_ids > 0 {
+        display::print_warning(&format!(
+            "Imported assets Photos couldn't find by their id right after import: {} (identifier churn, not failed imports; verify will miss them — `photoferry repair-manifest` re-finds them by name and date)",
+            summary.unstable_ids
+        ));
+    }
+    if summary.date_album_assigned > 0 {
+        display::print_summary(&format!(
+            "Added to date albums (--date-albums): {}",
+            summary.date_album_assigned
+        ));
+    }
+    if summary.shortened_paths > 0 {
+        display::print_info(&format!(
+            "Extracted under a shortened name (path too long; manifest keeps the real path): {}",
+            summary.shortened_paths
+        ));
+    }
+    if summary.dates_localized > 0 {
+        display::print_summary(&format!(
+            "Creation dates moved to local time from GPS: {}",
+            summary.dates_localized
+        ));
+    }
+    if summary.dates_not_localized > 0 {
+        display::print_warning(&format!(
+            "Time zone lookups failed (dates left as UTC): {}",
+            summary.dates_not_localized
+        ));
+    }
+    if summary.animated_gifs_static > 0 {
+        display::print_warning(&format!(
+            "Animated GIFs imported as GIF files (some macOS versions show only the first frame; --gif-policy video converts them): {}",
+            summary.animated_gifs_static
+        ));
+    }
+    if summary.animated_gifs_as_video > 0 {
+        display::print_summary(&format!(
+            "Animated GIFs imported as videos: {}",
+            summary.animated_gifs_as_video
+        ));
+    }
+    if summary.skipped_animated_gifs > 0 {
+        display::print_summary(&format!(
+            "Skipped (animated GIFs, --gif-policy skip): {}",
+            summary.skipped_animated_gifs
+        ));
+    }
+    if summary.skipped_burst_frames > 0 {
+        display::print_summary(&format!(
+            "Skipped (burst frames, --burst-policy cover-only): {}",
+            summary.skipped_burst_frames
+        ));
+    }
+    if summary.skipped_by_people > 0 {
+        display::print_summary(&format!(
+            "Skipped (fewer than --min-people): {}",
+            summary.skipped_by_people
+        ));
+    }
+    if summary.skipped_not_favorite > 0 {
+        display::print_summary(&format!(
+            "Skipped (not a favorite): {}",
+            summary.skipped_not_favorite
+        ));
+    }
+    if summary.live_photo_fallbacks > 0 {
+        display::print_warning(&format!(
+            "Live Photo fallbacks (still photo only): {}",
+            summary.live_photo_fallbacks
+        ));
+    }
+    if summary.motion_photo_fallbacks > 0 {
+        display::print_warning(&format!(
+            "Motion photo fallbacks (still photo only, not retried by retry-live-photo-fallbacks): {}",
+            summary.motion_photo_fallbacks
+        ));
+    }
+    if summary.fuzzy_title_mismatches > 0 {
+        display::print_warning(&format!(
+            "Fuzzy sidecar title mismatches (--fuzzy-title-check): {}",
+            summary.fuzzy_title_mismatches
+        ));
+    }
+    if summary.album_limit_unassigned > 0 {
+        display::print_summary(&format!(
+            "Imported without an album (past --limit-albums): {}",
+            summary.album_limit_unassigned
+        ));
+    }
+    if summary.ambiguous_truncation_skipped > 0 {
+        display::print_warning(&format!(
+            "No sidecar (truncated filename collision, dates may be wrong): {}",
+            summary.ambiguous_truncation_skipped
+        ));
+    }
+    if summary.orphaned_sidecars > 0 {
+        display::print_warning(&format!(
+            "Sidecars matching no media (--verbose lists them): {}",
+            summary.orphaned_sidecars
+        ));
+    }
+    if summary.unparseable_sidecars > 0 {
+        display::print_warning(&format!(
+            "Sidecars that failed to parse (--verbose lists them): {}",
+            summary.unparseable_sidecars
+        ));
+    }
+
+    if summary.deadline_remaining > 0 {
+        display::print_warning(&format!(
+            "Stopped at --max-runtime: {} file(s) left for the next run",
+            summary.deadline_remaining
+        ));
+    }
+
+    if summary.failed > 0 {
+        display::print_warning("Failed files:");
+        for failed in &summary.failures {
+            display::print_error(&format!("{} — {}", failed.path, failed.error));
+        }
+        if summary.failed > summary.failures.len() {
+            display::print_warning(&format!(
+                "… and {} more (list-failed shows them all)",
+                summary.failed - summary.failures.len()
+            ));
+        }
+    }
+    if !summary.oversized_skipped.is_empty() {
+        display::print_warning("Skipped over --max-file-size (import manually):");
+        for file in &summary.oversized_skipped {
+            display::print_warning(&format!(
+                "{} ({:.2}GB)",
+                file.path,
+                file.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+            ));
+        }
+    }
+}
+
+/// One asset `verify --export-missing` lists for remediation.
+#[derive(Debug, serde::Serialize)]
+struct VerifyProblem {
+    zip: String,
+    path: String,
+    local_id: String,
+    reason: VerifyProblemReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VerifyProblemReason {
+    Missing,
+    WrongDate,
+    LivePairMissing,
+    LiveStillMissing,
+    /// Photos rejected the identifier
+    Unverifiable,
+}
+
+impl VerifyProblem {
+    fn new(zip: &str, entry: &manifest::ManifestEntry, reason: VerifyProblemReason) -> Self {
+        Self {
+            zip: zip.to_string(),
+            path: entry.path.clone(),
+            local_id: entry.local_id.clone(),
+            reason,
+        }
+    }
+}
+
+/// Zips claimed by more than one manifest (copied or renamed zips), with the
+/// manifests claiming each. Their assets are verified, and counted, once per
+/// manifest.
+fn duplicate_zip_claims(claims: &[(String, PathBuf)]) -> Vec<(&str, Vec<&Path>)> {
+    let mut by_zip: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+    for (zip, manifest_path) in claims {
+        by_zip.entry(zip).or_default().push(manifest_path);
+    }
+    by_zip
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect()
+}
+
+/// Pick the manifests `verify` should check: every manifest in `dir`, or just
+/// `manifest` when given. A named manifest must exist and parse — it never
+/// falls back to the directory scan.
+fn verify_manifest_paths(dir: &Path, manifest: Option<&Path>) -> Result<Vec<PathBuf>> {
+    if let Some(path) = manifest {
+        match manifest::read_manifest_strict(path)? {
+            Some(_) => return Ok(vec![path.to_path_buf()]),
+            None => bail!("Manifest not found: {}", path.display()),
+        }
+    }
+
+    Ok(std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect())
+}
+
+/// Small deterministic PRNG (SplitMix64) for `verify --sample`; reproducible
+/// from the seed alone.
+struct SampleRng(u64);
+
+impl SampleRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Pick `pct`% of `0..len` (at least one when `len > 0`), returned in order.
+fn sample_indices(len: usize, pct: f64, rng: &mut SampleRng) -> Vec<usize> {
+    let count = ((len as f64 * pct / 100.0).ceil() as usize).clamp(len.min(1), len);
+    // Partial Fisher–Yates: the first `count` slots end up a uniform sample
+    let mut indices: Vec<usize> = (0..len).collect();
+    for i in 0..count {
+        let j = i + (rng.next_u64() % (len - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(count);
+    indices.sort_unstable();
+    indices
+}
+
+/// 95% Wilson score upper bound on the failure rate, given `failures` out of
+/// `n` sampled assets.
+fn failure_rate_upper_bound(failures: usize, n: usize) -> f64 {
+    if n == 0 {
+        return 1.0;
+    }
+    let z = 1.96_f64;
+    let n = n as f64;
+    let p = failures as f64 / n;
+    let center = p + z * z / (2.0 * n);
+    let margin = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt();
+    ((center + margin) / (1.0 + z * z / n)).min(1.0)
+}
+
+/// Identifiers per `verify_assets` call during `verify`, so the progress bar
+/// moves while a large manifest is checked.
+const VERIFY_BATCH: usize = 500;
+
+/// Verify `ids` a batch at a time, advancing `pb` after each. `verify` (see
+/// [`importer::verify_assets_isolating`]) already isolates the identifiers
+/// Photos rejects; only those are unverifiable. A batch that still fails
+/// outright means verification itself is broken (e.g. Photos access is
+/// gone), so its error is returned rather than blaming every asset in it.
+fn verify_in_batches<T>(
+    ids: &[&str],
+    pb: &ProgressBar,
+    mut verify: impl FnMut(&[&str]) -> Result<(Vec<T>, Vec<String>)>,
+) -> Result<(Vec<T>, Vec<String>)> {
+    let mut results = Vec::new();
+    let mut unverifiable = Vec::new();
+    for (n, batch) in ids.chunks(VERIFY_BATCH).enumerate() {
+        let (verified, rejected) = verify(batch).with_context(|| {
+            format!(
+                "Verification failed after {} of {} assets",
+                n * VERIFY_BATCH,
+                ids.len()
+            )
+        })?;
+        results.extend(verified);
+        unverifiable.extend(rejected);
+        pb.inc(batch.len() as u64);
+    }
+    Ok((results, unverifiable))
+}
+
+/// Bar over every asset `verify` checks, across manifests. One is drawn per
+/// manifest (so report lines don't tangle with it), picking up the overall
+/// position and elapsed time.
+fn verify_progress_bar(total: u64, done: u64, elapsed: Duration) -> ProgressBar {
+    if display::is_quiet() {
+        return ProgressBar::hidden();
+    }
+    let pb = ProgressBar::new(total)
+        .with_position(done)
+        .with_elapsed(elapsed);
+    pb.set_style(
+        ProgressStyle::with_template("[{bar:40}] {pos}/{len} assets ETA {eta}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("##-"),
+    );
+    pb
+}
+
+fn cmd_verify(
+    dir: &Path,
+    relink: bool,
+    manifest: Option<&Path>,
+    sample: Option<f64>,
+    seed: Option<u64>,
+    date_tolerance_secs: u64,
+    export_missing: Option<&Path>,
+) -> Result<()> {
+    let manifest = manifest.map(expand_tilde);
+    // A named manifest sits next to its zip, so resolve zips from its directory
+    let dir = match manifest.as_deref().and_then(Path::parent) {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        Some(_) => PathBuf::from("."),
+        None => expand_tilde(dir),
+    };
+    display::print_header(&format!("Verifying imports in {}", dir.display()));
+
+    let manifests = verify_manifest_paths(&dir, manifest.as_deref())?;
+
+    if manifests.is_empty() {
+        display::print_info("No manifests found.");
+        return Ok(());
+    }
+
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "verify")?;
+
+    let mut rng = sample.map(|pct| {
+        let seed = seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        display::print_info(&format!("Sampling {pct}% of assets (--seed {seed})"));
+        SampleRng(seed)
+    });
+    let mut total_imported = 0usize;
+    let mut total_checked = 0usize;
+
+    let mut total_verified_ok = 0usize;
+    let mut total_missing = 0usize;
+    let mut total_wrong_date = 0usize;
+    let mut total_unverifiable = 0usize;
+    let mut total_live_photo_pair_missing = 0usize;
+    let mut total_live_photo_still_missing = 0usize;
+    let mut total_offloaded = 0usize;
+    let mut total_live_photo_fallback = 0usize;
+    let mut zip_claims: Vec<(String, PathBuf)> = Vec::new();
+    let mut problem_assets: Vec<VerifyProblem> = Vec::new();
+
+    // Overall progress: every manifest's assets, less what sampling leaves out
+    let mut assets_total: u64 = manifests
+        .iter()
+        .filter_map(|p| manifest::read_manifest_strict(p).ok().flatten())
+        .map(|m| m.imported.len() as u64)
+        .sum();
+    let mut assets_done = 0u64;
+    let verify_start = Instant::now();
+
+    for manifest_path in &manifests {
+        let mut manifest = match manifest::read_manifest_strict(manifest_path) {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                display::print_warning(&format!("Could not read {:?}", manifest_path));
+                continue;
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Refusing to verify with corrupt manifest {}",
+                    manifest_path.display()
+                )));
+            }
+        };
+        let mut manifest_path = manifest_path.clone();
+        if relink && !dir.join(&manifest.zip).exists() {
+            relink_or_warn(&dir, &mut manifest_path, &mut manifest);
+        }
+        zip_claims.push((manifest.zip.clone(), manifest_path));
+
+        display::print_header(&format!("Verifying {}", manifest.zip));
+        let checked: Vec<&manifest::ManifestEntry> = match (sample, rng.as_mut()) {
+            (Some(pct), Some(rng)) => sample_indices(manifest.imported.len(), pct, rng)
+                .into_iter()
+                .map(|i| &manifest.imported[i])
+                .collect(),
+            _ => manifest.imported.iter().collect(),
+        };
+        total_imported += manifest.imported.len();
+        total_checked += checked.len();
+        assets_total =
+            assets_total.saturating_sub((manifest.imported.len() - checked.len()) as u64);
+        if checked.len() == manifest.imported.len() {
+            display::print_info(&format!("Checking {} imported assets...", checked.len()));
+        } else {
+            display::print_info(&format!(
+                "Checking {} of {} imported assets (sampled)...",
+                checked.len(),
+                manifest.imported.len()
+            ));
+        }
+
+        let mut live_photo_paths = HashSet::new();
+        let zip_path = dir.join(&manifest.zip);
+        if zip_path.exists() {
+            warn_if_manifest_exceeds_zip(&manifest, &zip_path);
+            match live_photo_paths_from_zip(&zip_path, &dir) {
+                Ok(paths) => live_photo_paths = paths,
+                Err(e) => display::print_warning(&format!(
+                    "Live Photo fallback scan failed for {}: {}",
+                    manifest.zip, e
+                )),
+            }
+        }
+
+        let ids: Vec<&str> = checked.iter().map(|e| e.local_id.as_str()).collect();
+        let pb = verify_progress_bar(assets_total, assets_done, verify_start.elapsed());
+        let verified = verify_in_batches(&ids, &pb, importer::verify_assets_isolating);
+        assets_done += ids.len() as u64;
+        pb.finish_and_clear();
+        let (results, unverifiable) = verified?;
+
+        let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
+            .iter()
+            .map(|r| (r.local_identifier.as_str(), r))
+            .collect();
+        let unverifiable: HashSet<&str> = unverifiable.iter().map(String::as_str).collect();
+
+        let mut missing = vec![];
+        let mut wrong_date = vec![];
+        let mut live_pair_missing = vec![];
+        let mut live_still_missing = vec![];
+        let mut live_photo_fallback = vec![];
+        // Present and verified, with originals only in iCloud
+        let mut offloaded = 0usize;
+        let mut rejected = 0usize;
+
+        for &entry in &checked {
+            if unverifiable.contains(entry.local_id.as_str()) {
+                display::print_error(&format!(
+                    "UNVERIFIABLE: {} ({}) — Photos rejected the identifier",
+                    entry.path, entry.local_id
+                ));
+                rejected += 1;
+                total_unverifiable += 1;
+                problem_assets.push(VerifyProblem::new(
+                    &manifest.zip,
+                    entry,
+                    VerifyProblemReason::Unverifiable,
+                ));
+                continue;
+            }
+            match result_map.get(entry.local_id.as_str()) {
+                None | Some(importer::AssetVerifyResult { found: false, .. }) => {
+                    missing.push(entry);
+                }
+                Some(result) => {
+                    match live_photo_issue(entry, result) {
+                        Some(LivePhotoIssue::StillMissing) => {
+                            live_still_missing.push(entry);
+                            continue;
+                        }
+                        Some(LivePhotoIssue::PairMissing) => {
+                            live_pair_missing.push(entry);
+                            continue;
+                        }
+                        None => {}
+                    }
+                    if date_mismatch(
+                        entry.creation_date.as_deref(),
+                        result.creation_date.as_deref(),
+                        date_tolerance_secs,
+                    ) {
+                        wrong_date.push((
+                            entry,
+                            result
+                                .creation_date
+                                .clone()
+                                .unwrap_or_else(|| "<missing>".to_string()),
+                        ));
+                        continue;
+                    }
+                    if entry.is_live_photo == Some(false)
+                        && live_photo_paths.contains(&entry.path)
+                    {
+                        live_photo_fallback.push(entry);
+                    }
+                    if result.in_icloud_only {
+                        offloaded += 1;
+                    }
+                    total_verified_ok += 1;
+                }
+            }
+        }
+
+        for e in &missing {
+            display::print_error(&format!("MISSING: {} ({})", e.path, e.local_id));
+            total_missing += 1;
+        }
+        for (e, actual) in &wrong_date {
+            display::print_warning(&format!(
+                "DATE MISMATCH: {} — expected {} got {}",
+                e.path,
+                e.creation_date.as_deref().unwrap_or("?"),
+                actual
+            ));
+            total_wrong_date += 1;
+        }
+        for e in &live_pair_missing {
+            display::print_warning(&format!(
+                "LIVE PHOTO PAIR MISSING: {} ({})",
+                e.path, e.local_id
+            ));
+            total_live_photo_pair_missing += 1;
+        }
+        for e in &live_still_missing {
+            display::print_warning(&format!(
+                "LIVE PHOTO STILL MISSING: {} ({})",
+                e.path, e.local_id
+            ));
+            total_live_photo_still_missing += 1;
+        }
+        for e in &live_photo_fallback {
+            display::print_warning(&format!("LIVE PHOTO FELL BACK: {}", e.path));
+            total_live_photo_fallback += 1;
+        }
+        if export_missing.is_some() {
+            let zip = manifest.zip.as_str();
+            let problems =
+                missing
+                    .iter()
+                    .map(|e| VerifyProblem::new(zip, e, VerifyProblemReason::Missing))
+                    .chain(
+                        wrong_date.iter().map(|(e, _)| {
+                            VerifyProblem::new(zip, e, VerifyProblemReason::WrongDate)
+                        }),
+                    )
+                    .chain(
+                        live_pair_missing.iter().map(|e| {
+                            VerifyProblem::new(zip, e, VerifyProblemReason::LivePairMissing)
+                        }),
+                    )
+                    .chain(live_still_missing.iter().map(|e| {
+                        VerifyProblem::new(zip, e, VerifyProblemReason::LiveStillMissing)
+                    }));
+            problem_assets.extend(problems);
+        }
+
+        display::print_summary(&format!(
+            "Verified: {} | Missing: {} | Wrong date: {} | Live pair missing: {} | Live still missing: {} | Unverifiable: {} | Live fallback: {}",
+            checked.len()
+                - missing.len()
+                - wrong_date.len()
+                - live_pair_missing.len()
+                - live_still_missing.len()
+                - rejected,
+            missing.len(),
+            wrong_date.len(),
+            live_pair_missing.len(),
+            live_still_missing.len(),
+            rejected,
+            live_photo_fallback.len()
+        ));
+        if offloaded > 0 {
+            display::print_info(&format!(
+                "{offloaded} verified assets are in iCloud only (Optimize Storage)"
+            ));
+        }
+        total_offloaded += offloaded;
+    }
+
+    if let Some(export_path) = export_missing {
+        let json = serde_json::to_string_pretty(&problem_assets)?;
+        std::fs::write(export_path, json)
+            .with_context(|| format!("Failed to write {}", export_path.display()))?;
+        display::print_success(&format!(
+            "Wrote {} problem asset(s) to {}",
+            problem_assets.len(),
+            export_path.display()
+        ));
+    }
+
+    for (zip, paths) in duplicate_zip_claims(&zip_claims) {
+        let names: Vec<String> = paths
+            .iter()
+            .map(|p| {
+                p.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        display::print_warning(&format!(
+            "{} manifests claim {zip} ({}) — its assets were verified and counted {} times",
+            paths.len(),
+            names.join(", "),
+            paths.len()
+        ));
+    }
+
+    println!();
+    display::print_header("Total");
+    display::print_summary(&format!("Verified OK: {}", total_verified_ok));
+    if total_offloaded > 0 {
+        display::print_summary(&format!("  of which in iCloud only: {total_offloaded}"));
+    }
+    if total_missing > 0 {
+        display::print_error(&format!("Missing: {}", total_missing));
+    }
+    if total_wrong_date > 0 {
+        display::print_warning(&format!(
+            "Wrong date: {} (`photoferry fix-dates` corrects them in place)",
+            total_wrong_date
+        ));
+    }
+    if total_unverifiable > 0 {
+        display::print_error(&format!("Unverifiable: {}", total_unverifiable));
+    }
+    if total_live_photo_pair_missing > 0 {
+        display::print_warning(&format!(
+            "Live Photo pair missing: {}",
+            total_live_photo_pair_missing
+        ));
+    }
+    if total_live_photo_still_missing > 0 {
+        display::print_warning(&format!(
+            "Live Photo still missing: {}",
+            total_live_photo_still_missing
+        ));
+    }
+    if total_live_photo_fallback > 0 {
+        display::print_warning(&format!(
+            "Live Photo fallbacks (still photo only): {}",
+            total_live_photo_fallback
+        ));
+    }
+    let problems = verify_problem_total(
+        total_missing,
+        total_wrong_date,
+        total_live_photo_pair_missing,
+        total_live_photo_still_missing,
+        total_unverifiable,
+    );
+    if sample.is_some() && total_checked > 0 {
+        let rate = problems as f64 / total_checked as f64;
+        let upper = failure_rate_upper_bound(problems, total_checked);
+        display::print_summary(&format!(
+            "Sampled {total_checked} of {total_imported} assets: ~{:.0} problems estimated overall",
+            rate * total_imported as f64
+        ));
+        display::print_summary(&format!(
+            "95% confidence: at least {:.2}% of imports verify OK",
+            (1.0 - upper) * 100.0
+        ));
+    }
+    if let Some(line) = verify_success_line(problems, sample.is_some()) {
+        display::print_summary_success(line);
+    }
+
+    Ok(())
+}
+
+/// Assets `verify` found a problem with, across every kind it reports.
+fn verify_problem_total(
+    missing: usize,
+    wrong_date: usize,
+    live_pair_missing: usize,
+    live_still_missing: usize,
+    unverifiable: usize,
+) -> usize {
+    missing + wrong_date + live_pair_missing + live_still_missing + unverifiable
+}
+
+/// The closing success line of `verify`, if nothing was wrong.
+fn verify_success_line(problems: usize, sampled: bool) -> Option<&'static str> {
+    match (problems, sampled) {
+        (0, true) => Some("All sampled assets verified successfully"),
+        (0, false) => Some("All assets verified successfully"),
+        _ => None,
+    }
+}
+
+/// Extract just the retried files from `zip_path`, with the sidecars their
+/// manifest entries recorded, their Live Photo videos and album metadata.
+/// None if any entry has no recorded sidecar (older manifests, files
+/// imported without one) or is gone from the zip: scan the whole zip instead.
+fn extract_retry_files(
+    zip_path: &Path,
+    entries: &[manifest::ManifestEntry],
+    extract_dir: &Path,
+) -> Result<Option<Vec<takeout::MediaFile>>> {
+    if entries.iter().any(|e| e.sidecar_path.is_none()) {
+        return Ok(None);
+    }
+    let file = std::fs::File::open(zip_path)
+        .with_context(|| format!("Cannot open ZIP: {}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
+    let prefix = takeout::detect_content_root(archive.file_names()).prefix;
+    let by_relative: HashMap<String, usize> = (0..archive.len())
+        .filter_map(|i| {
+            let name = archive.name_for_index(i)?;
+            let relative = name.strip_prefix(prefix.as_str()).unwrap_or(name);
+            Some((relative.to_string(), i))
+        })
+        .collect();
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let sidecar_rel = entry.sidecar_path.as_deref().unwrap_or_default();
+        let (Some(&media_index), Some(&sidecar_index)) =
+            (by_relative.get(&entry.path), by_relative.get(sidecar_rel))
+        else {
+            return Ok(None);
+        };
+        let rel_path = Path::new(&entry.path);
+        let Some(media_type) = takeout::media_type_from_path(rel_path) else {
+            return Ok(None);
+        };
+        let media_disk = extract_dir.join(&entry.path);
+        extract_zip_entry(&mut archive, media_index, &media_disk)?;
+        let sidecar_disk = extract_dir.join(sidecar_rel);
+        extract_zip_entry(&mut archive, sidecar_index, &sidecar_disk)?;
+        let metadata = metadata::parse_sidecar(&std::fs::read(&sidecar_disk)?)
+            .ok()
+            .map(|m| m.to_photo_metadata());
+
+        let dir_rel = rel_path.parent().unwrap_or(Path::new(""));
+        let album_json = dir_rel.join("metadata.json");
+        let album = match by_relative.get(album_json.to_string_lossy().as_ref()) {
+            Some(&index) if !takeout::is_year_folder(dir_rel) => {
+                let disk = extract_dir.join(&album_json);
+                extract_zip_entry(&mut archive, index, &disk)?;
+                takeout::detect_album(dir_rel, &[disk])
+            }
+            _ => None,
+        };
+
+        let live_photo_pair = if media_type == takeout::MediaType::Photo {
+            let siblings: Vec<PathBuf> = by_relative
+                .keys()
+                .map(PathBuf::from)
+                .filter(|p| p.parent() == Some(dir_rel))
+                .filter(|p| takeout::media_type_from_path(p).is_some())
+                .collect();
+            match takeout::detect_live_photo_pairs(&siblings).get(rel_path) {
+                Some(video_rel) => {
+                    let video_disk = extract_dir.join(video_rel);
+                    let index = by_relative[video_rel.to_string_lossy().as_ref()];
+                    extract_zip_entry(&mut archive, index, &video_disk)?;
+                    Some(video_disk)
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+
+        files.push(takeout::MediaFile {
+            path: media_disk,
+            media_type,
+            metadata,
+            album,
+            live_photo_pair,
+        });
+    }
+    Ok(Some(files))
+}
+
+fn cmd_retry_missing(
+    dir: &Path,
+    verbose: bool,
+    progress_every: Option<NonZeroUsize>,
+    relink: bool,
+    no_live_fallback: bool,
+    on_conflict: OnConflict,
+    date_tolerance_secs: u64,
+) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Retrying missing assets in {}", dir.display()));
+
+    let manifests: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    if manifests.is_empty() {
+        display::print_info("No manifests found.");
+        return Ok(());
+    }
+
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "retry-missing verification")?;
+    let mut album_registry = manifest::AlbumRegistry::load(&dir)?;
+
+    let mut total_reimported = 0usize;
+    let mut total_retry_failed = 0usize;
+    let mut total_missing_unresolved = 0usize;
+    let mut total_kept = 0usize;
+    let mut total_relinked = 0usize;
+    let mut total_replaced = 0usize;
+
+    for manifest_path in &manifests {
+        let mut manifest = match manifest::read_manifest_strict(manifest_path) {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                display::print_warning(&format!("Could not read {:?}", manifest_path));
+                continue;
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Refusing retry-missing with corrupt manifest {}",
+                    manifest_path.display()
+                )));
+            }
+        };
+        let mut manifest_path = manifest_path.clone();
+        if relink && !dir.join(&manifest.zip).exists() {
+            relink_or_warn(&dir, &mut manifest_path, &mut manifest);
+        }
+        if manifest.imported.is_empty() {
+            continue;
+        }
+
+        let ids: Vec<&str> = manifest
+            .imported
+            .iter()
+            .map(|e| e.local_id.as_str())
+            .collect();
+        let results = importer::verify_assets(&ids)?;
+        let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
+            .iter()
+            .map(|r| (r.local_identifier.as_str(), r))
+            .collect();
+        let mut retry_entries: Vec<manifest::ManifestEntry> = manifest
+            .imported
+            .iter()
+            .filter(|entry| match result_map.get(entry.local_id.as_str()) {
+                None | Some(importer::AssetVerifyResult { found: false, .. }) => true,
+                Some(result) => {
+                    if live_photo_issue(entry, result).is_some() {
+                        return true;
+                    }
+                    date_mismatch(
+                        entry.creation_date.as_deref(),
+                        result.creation_date.as_deref(),
+                        date_tolerance_secs,
+                    )
+                }
+            })
+            .cloned()
+            .collect();
+
+        // Old asset ids for `--on-conflict replace`, by manifest path
+        let asset_present = |entry: &manifest::ManifestEntry| {
+            result_map
+                .get(entry.local_id.as_str())
+                .is_some_and(|r| r.found)
+        };
+        let replace_ids: HashMap<String, String> = if on_conflict == OnConflict::Replace {
+            retry_entries
+                .iter()
+                .filter(|entry| asset_present(entry))
+                .map(|entry| (entry.path.clone(), entry.local_id.clone()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        if on_conflict == OnConflict::Skip && !retry_entries.is_empty() {
+            let names: Vec<&str> = retry_entries
+                .iter()
+                .filter(|entry| !asset_present(entry))
+                .filter_map(|entry| Path::new(&entry.path).file_name()?.to_str())
+                .collect();
+            let assets = if names.is_empty() {
+                Vec::new()
+            } else {
+                importer::find_by_filename(&names)?
+            };
+            let skip = skip_conflicts(retry_entries, &result_map, &assets);
+            retry_entries = skip.retry;
+            if !skip.relinks.is_empty() {
+                for entry in &mut manifest.imported {
+                    if let Some(id) = skip.relinks.get(&entry.path) {
+                        entry.local_id = id.clone();
+                    }
+                }
+                manifest::write_manifest(
+                    &manifest_path,
+                    &manifest.zip,
+                    &manifest.imported,
+                    &manifest.failed,
+                    &manifest.live_photo_fallbacks,
+                )?;
+            }
+            if skip.kept > 0 || !skip.relinks.is_empty() {
+                display::print_info(&format!(
+                    "{}: left {} assets still in Photos, relinked {} found by filename",
+                    manifest.zip,
+                    skip.kept,
+                    skip.relinks.len()
+                ));
+            }
+            total_kept += skip.kept;
+            total_relinked += skip.relinks.len();
+        }
+
+        if retry_entries.is_empty() {
+            display::print_info(&format!("{}: no retry-needed assets", manifest.zip));
+            continue;
+        }
+        let (retry_entries, copies) = group_retry_entries(retry_entries);
+
+        let zip_path = dir.join(&manifest.zip);
+        if !zip_path.exists() {
+            let hint = if relink { "" } else { " (renamed? try --relink)" };
+            display::print_warning(&format!(
+                "{}: {} missing assets but zip not found at {}{hint}",
+                manifest.zip,
+                retry_entries.len(),
+                zip_path.display()
+            ));
+            total_missing_unresolved += retry_entries.len();
+            continue;
+        }
+
+        display::print_header(&format!(
+            "{}: retrying {} assets",
+            manifest.zip,
+            retry_entries.len()
+        ));
+
+        let extract_dir = dir.join(format!(
+            ".photoferry-retry-extract-{}",
+            zip_path.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        if extract_dir.exists() {
+            std::fs::remove_dir_all(&extract_dir)?;
+        }
+        std::fs::create_dir_all(&extract_dir)?;
+
+        let targeted = match extract_retry_files(&zip_path, &retry_entries, &extract_dir) {
+            Ok(files) => files,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&extract_dir);
+                return Err(e.context(format!("Failed to extract {}", zip_path.display())));
+            }
+        };
+        let (content_root, retry_files, unresolved) = match targeted {
+            Some(files) => (extract_dir.clone(), files, 0),
+            None => {
+                let content_root = match takeout::extract_zip(&zip_path, &extract_dir) {
+                    Ok(root) => root,
+                    Err(e) => {
+                        let _ = std::fs::remove_dir_all(&extract_dir);
+                        return Err(e.context(format!("Failed to extract {}", zip_path.display())));
+                    }
+                };
+                let inventory = match takeout::scan_directory(
+                    &content_root,
+                    &takeout::ScanOptions::default(),
+                ) {
+                    Ok(inv) => inv,
+                    Err(e) => {
+                        let _ = std::fs::remove_dir_all(&extract_dir);
+                        return Err(e.context(format!(
+                            "Failed to scan extracted content for {}",
+                            zip_path.display()
+                        )));
+                    }
+                };
+
+                let mut by_relative: HashMap<String, takeout::MediaFile> = HashMap::new();
+                for file in &inventory.files {
+                    let rel = file
+                        .path
+                        .strip_prefix(&content_root)
+                        .unwrap_or(&file.path)
+                        .to_string_lossy()
+                        .to_string();
+                    by_relative.insert(rel, file.clone());
+                }
+
+                let mut retry_files = Vec::new();
+                let mut unresolved = 0usize;
+                for entry in &retry_entries {
+                    if let Some(file) = by_relative.get(&entry.path) {
+                        retry_files.push(file.clone());
+                    } else {
+                        display::print_warning(&format!(
+                            "Missing in zip content (cannot retry): {}",
+                            entry.path
+                        ));
+                        unresolved += 1;
+                    }
+                }
+                (content_root, retry_files, unresolved)
+            }
+        };
+
+        if retry_files.is_empty() {
+            total_missing_unresolved += retry_entries.len();
+            let _ = std::fs::remove_dir_all(&extract_dir);
+            continue;
+        }
+
+        let retry_albums: Vec<String> = retry_files
+            .iter()
+            .filter_map(|f| f.album.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let retry_inventory = takeout::TakeoutInventory {
+            files: retry_files,
+            albums: retry_albums,
+            stats: Default::default(),
+        };
+
+        let sidecar_by_path: HashMap<&str, Option<String>> = retry_entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.sidecar_path.clone()))
+            .collect();
+        let (summary, imported, failed) = import_inventory(
+            &retry_inventory,
+            verbose,
+            progress_every,
+            no_live_fallback,
+            &mut album_registry,
+            &importer::PhotosImporter,
+        );
+        print_import_summary(&summary);
+
+        let mut new_imported: Vec<manifest::ManifestEntry> = imported
+            .iter()
+            .map(|file| manifest::ManifestEntry {
+                path: file
+                    .path
+                    .strip_prefix(&content_root)
+                    .unwrap_or(&file.path)
+                    .to_string_lossy()
+                    .to_string(),
+                local_id: file.local_id.clone(),
+                creation_date: file.creation_date.clone(),
+                is_live_photo: Some(file.is_live_photo),
+                album: file.album.clone(),
+                sidecar_path: None,
+            })
+            .map(|entry| manifest::ManifestEntry {
+                sidecar_path: sidecar_by_path.get(entry.path.as_str()).cloned().flatten(),
+                ..entry
+            })
+            .collect();
+        let mut album_ids: HashMap<String, Option<String>> = HashMap::new();
+        let mut copy_entries: Vec<manifest::ManifestEntry> = Vec::new();
+        for entry in &new_imported {
+            let Some(entry_copies) = copies.get(&entry.path) else {
+                continue;
+            };
+            let start = copy_entries.len();
+            copy_entries.extend(entry_copies.iter().cloned());
+            repoint_copies(
+                &importer::PhotosImporter,
+                &mut album_ids,
+                &mut album_registry,
+                &entry.local_id,
+                entry.is_live_photo,
+                &mut copy_entries[start..],
+                entry.album.iter().cloned().collect(),
+            );
+        }
+        // Before adding copies: they share the retried entries' old ids
+        let replaced: Vec<String> = new_imported
+            .iter()
+            .filter_map(|entry| replace_ids.get(&entry.path).cloned())
+            .collect();
+        new_imported.extend(copy_entries);
+        let new_failed: Vec<manifest::ManifestFailure> = failed
+            .iter()
+            .map(|file| {
+                let p = std::path::Path::new(&file.path);
+                manifest::ManifestFailure {
+                    path: p
+                        .strip_prefix(&content_root)
+                        .unwrap_or(p)
+                        .to_string_lossy()
+                        .to_string(),
+                    error: file.error.clone(),
+                }
+            })
+            .collect();
+        manifest::merge_and_write(
+            &manifest_path,
+            &manifest.zip,
+            &new_imported,
+            &new_failed,
+            &[],
+        )?;
+
+        // Only after the new asset is recorded, so a failed import keeps the old one
+        if !replaced.is_empty() {
+            let replaced: Vec<&str> = replaced.iter().map(String::as_str).collect();
+            match importer::delete_assets(&replaced) {
+                Ok(n) => total_replaced += n,
+                Err(e) => display::print_warning(&format!(
+                    "{}: could not delete {} replaced assets ({e:#}); check Photos.app for duplicates",
+                    manifest.zip,
+                    replaced.len()
+                )),
+            }
+        }
+
+        total_reimported += summary.imported;
+        total_retry_failed += summary.failed;
+        total_missing_unresolved += unresolved;
+        std::fs::remove_dir_all(&extract_dir)?;
+    }
+
+    println!();
+    display::print_header("Retry missing summary");
+    display::print_summary(&format!("Re-imported: {}", total_reimported));
+    if total_replaced > 0 {
+        display::print_summary(&format!("Replaced (old asset deleted): {}", total_replaced));
+    }
+    if total_kept > 0 || total_relinked > 0 {
+        display::print_summary(&format!(
+            "Left in Photos: {} | Relinked by filename: {}",
+            total_kept, total_relinked
+        ));
+    }
+    if total_retry_failed > 0 {
+        display::print_warning(&format!("Retry import failures: {}", total_retry_failed));
+    }
+    if total_missing_unresolved > 0 {
+        display::print_warning(&format!(
+            "Still unresolved missing assets: {}",
+            total_missing_unresolved
+        ));
+    }
+
+    Ok(())
+}
+
+/// Indices of manifest entries Photos holds with the wrong creation date, as
+/// `verify` reports them: found, no Live Photo issue, and a date more than
+/// `tolerance_secs` off.
+fn wrong_date_indices(
+    entries: &[manifest::ManifestEntry],
+    results: &HashMap<&str, &importer::AssetVerifyResult>,
+    tolerance_secs: u64,
+) -> Vec<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            results.get(entry.local_id.as_str()).is_some_and(|result| {
+                result.found
+                    && live_photo_issue(entry, result).is_none()
+                    && date_mismatch(
+                        entry.creation_date.as_deref(),
+                        result.creation_date.as_deref(),
+                        tolerance_secs,
+                    )
+            })
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn cmd_fix_dates(dir: &Path, date_tolerance_secs: u64) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!("Fixing wrong dates in {}", dir.display()));
+
+    let manifests = verify_manifest_paths(&dir, None)?;
+    if manifests.is_empty() {
+        display::print_info("No manifests found.");
+        return Ok(());
+    }
+
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "fix-dates")?;
+
+    let mut total_fixed = 0usize;
+    let mut total_failed = 0usize;
+    let mut total_unverifiable = 0usize;
+
+    for manifest_path in &manifests {
+        let mut manifest = match manifest::read_manifest_strict(manifest_path) {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                display::print_warning(&format!("Could not read {:?}", manifest_path));
+                continue;
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Refusing fix-dates with corrupt manifest {}",
+                    manifest_path.display()
+                )));
+            }
+        };
+        if manifest.imported.is_empty() {
+            continue;
+        }
+
+        let ids: Vec<&str> = manifest
+            .imported
+            .iter()
+            .map(|e| e.local_id.as_str())
+            .collect();
+        let pb = verify_progress_bar(ids.len() as u64, 0, Duration::ZERO);
+        let verified = verify_in_batches(&ids, &pb, importer::verify_assets_isolating);
+        pb.finish_and_clear();
+        let (results, unverifiable) = verified?;
+        if !unverifiable.is_empty() {
+            display::print_warning(&format!(
+                "{}: {} asset(s) could not be verified — dates left alone",
+                manifest.zip,
+                unverifiable.len()
+            ));
+            total_unverifiable += unverifiable.len();
+        }
+        let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
+            .iter()
+            .map(|r| (r.local_identifier.as_str(), r))
+            .collect();
+        let wrong = wrong_date_indices(&manifest.imported, &result_map, date_tolerance_secs);
+        if wrong.is_empty() {
+            continue;
+        }
+        display::print_header(&format!(
+            "Fixing {} date(s) in {}",
+            wrong.len(),
+            manifest.zip
+        ));
+
+        let mut fixed: Vec<usize> = Vec::new();
+        for i in wrong {
+            let entry = &manifest.imported[i];
+            let Some(date) = entry.creation_date.as_deref() else {
+                continue;
+            };
+            match importer::set_creation_date(&entry.local_id, date) {
+                Ok(true) => {
+                    display::print_success(&format!("{} → {date}", entry.path));
+                    fixed.push(i);
+                }
+                Ok(false) => {
+                    display::print_error(&format!(
+                        "{} — Photos rejected the date change",
+                        entry.path
+                    ));
+                    total_failed += 1;
+                }
+                Err(e) => {
+                    display::print_error(&format!("{} — {e}", entry.path));
+                    total_failed += 1;
+                }
+            }
+        }
+        if fixed.is_empty() {
+            continue;
+        }
+        total_fixed += fixed.len();
+
+        // Record the date as Photos now stores it, so verify compares like for like
+        let fixed_ids: Vec<&str> = fixed
+            .iter()
+            .map(|&i| manifest.imported[i].local_id.as_str())
+            .collect();
+        let (stored, _) = verify_in_batches(
+            &fixed_ids,
+            &ProgressBar::hidden(),
+            importer::verify_assets_isolating,
+        )?;
+        let stored: HashMap<String, String> = stored
+            .into_iter()
+            .filter_map(|r| Some((r.local_identifier, r.creation_date?)))
+            .collect();
+        for i in fixed {
+            let entry = &mut manifest.imported[i];
+            if let Some(date) = stored.get(&entry.local_id) {
+                entry.creation_date = Some(date.clone());
+            }
+        }
+        manifest::write_manifest(
+            manifest_path,
+            &manifest.zip,
+            &manifest.imported,
+            &manifest.failed,
+            &manifest.live_photo_fallbacks,
+        )?;
+    }
+
+    println!();
+    display::print_header("Fix dates complete");
+    display::print_summary(&format!("Dates fixed: {total_fixed}"));
+    if total_unverifiable > 0 {
+        display::print_warning(&format!("Could not verify: {total_unverifiable}"));
+    }
+    if total_failed > 0 {
+        display::print_warning(&format!("Could not fix: {total_failed}"));
+    } else if total_fixed == 0 && total_unverifiable == 0 {
+        display::print_summary_success("No wrong dates found");
+    }
+
+    Ok(())
+}
+
+fn cmd_retry_live_photo_fallbacks(
+    dir: &Path,
+    verbose: bool,
+    on_conflict: OnConflict,
+) -> Result<()> {
+    let dir = expand_tilde(dir);
+    display::print_header(&format!(
+        "Retrying Live Photo fallbacks in {}",
+        dir.display()
+    ));
+
+    let manifests: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    if manifests.is_empty() {
+        display::print_info("No manifests found.");
+        return Ok(());
+    }
+
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "retry-live-photo-fallbacks")?;
+    let mut album_registry = manifest::AlbumRegistry::load(&dir)?;
+    let mut album_ids: HashMap<String, Option<String>> = HashMap::new();
+
+    let mut total_reimported = 0usize;
+    let mut total_failed = 0usize;
+    let mut total_unresolved = 0usize;
+    let mut total_kept = 0usize;
+    let mut total_replaced = 0usize;
+
+    for manifest_path in &manifests {
+        let mut manifest = match manifest::read_manifest_strict(manifest_path) {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                display::print_warning(&format!("Could not read {:?}", manifest_path));
+                continue;
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Refusing retry-live-photo-fallbacks with corrupt manifest {}",
+                    manifest_path.display()
+                )));
+            }
+        };
+
+        if manifest.live_photo_fallbacks.is_empty() {
+            continue;
+        }
+
+        let zip_path = dir.join(&manifest.zip);
+        if !zip_path.exists() {
+            display::print_warning(&format!(
+                "{}: {} live photo fallbacks but zip not found at {}",
+                manifest.zip,
+                manifest.live_photo_fallbacks.len(),
+                zip_path.display()
+            ));
+            total_unresolved += manifest.live_photo_fallbacks.len();
+            continue;
+        }
+
+        display::print_header(&format!(
+            "{}: retrying {} live photo fallbacks",
+            manifest.zip,
+            manifest.live_photo_fallbacks.len()
+        ));
+
+        let extract_dir = dir.join(format!(
+            ".photoferry-live-retry-extract-{}",
+            zip_path.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        if extract_dir.exists() {
+            std::fs::remove_dir_all(&extract_dir)?;
+        }
+        std::fs::create_dir_all(&extract_dir)?;
+
+        let content_root = match takeout::extract_zip(&zip_path, &extract_dir) {
+            Ok(root) => root,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&extract_dir);
+                return Err(e.context(format!("Failed to extract {}", zip_path.display())));
+            }
+        };
+        let inventory = match takeout::scan_directory(&content_root, &takeout::ScanOptions::default()) {
+            Ok(inv) => inv,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&extract_dir);
+                return Err(e.context(format!(
+                    "Failed to scan extracted content for {}",
+                    zip_path.display()
+                )));
+            }
+        };
+
+        let mut by_relative: HashMap<String, takeout::MediaFile> = HashMap::new();
+        for file in &inventory.files {
+            let rel = file
+                .path
+                .strip_prefix(&content_root)
+                .unwrap_or(&file.path)
+                .to_string_lossy()
+                .to_string();
+            by_relative.insert(rel, file.clone());
+        }
+
+        let mut resolved_paths = HashSet::new();
+        // Still-only asset id → the Live Photo that replaces it
+        let mut updated_imports: HashMap<String, String> = HashMap::new();
+
+        // The still-only asset each fallback was recorded with
+        let still_ids: HashMap<&str, &str> = manifest
+            .imported
+            .iter()
+            .map(|e| (e.path.as_str(), e.local_id.as_str()))
+            .collect();
+        let present_stills: HashSet<String> = if on_conflict == OnConflict::Skip {
+            let ids: Vec<&str> = manifest
+                .live_photo_fallbacks
+                .iter()
+                .filter_map(|f| still_ids.get(f.photo_path.as_str()).copied())
+                .collect();
+            importer::verify_assets(&ids)?
+                .into_iter()
+                .filter(|r| r.found)
+                .map(|r| r.local_identifier)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let mut replaced_stills: Vec<String> = Vec::new();
+
+        for fallback in &manifest.live_photo_fallbacks {
+            if still_ids
+                .get(fallback.photo_path.as_str())
+                .is_some_and(|id| present_stills.contains(*id))
+            {
+                total_kept += 1;
+                continue;
+            }
+            // Another copy of the same file already got its Live Photo
+            if updated_imports.contains_key(&fallback.local_id) {
+                resolved_paths.insert(fallback.photo_path.clone());
+                continue;
+            }
+            let Some(photo_file) = by_relative.get(&fallback.photo_path) else {
+                display::print_warning(&format!(
+                    "Missing photo in zip content: {}",
+                    fallback.photo_path
+                ));
+                total_unresolved += 1;
+                continue;
+            };
+            let video_abs = content_root.join(&fallback.video_path);
+            if !video_abs.exists() {
+                display::print_warning(&format!(
+                    "Missing video in zip content: {}",
+                    fallback.video_path
+                ));
+                total_unresolved += 1;
+                continue;
+            }
+
+            let photo_abs = &photo_file.path;
+            let import_result = importer::import_live_photo(
+                photo_abs.to_str().unwrap_or_default(),
+                video_abs.to_str().unwrap_or_default(),
+                photo_file.metadata.as_ref(),
+            );
+
+            match import_result {
+                Ok(result) if result.success => {
+                    total_reimported += 1;
+                    resolved_paths.insert(fallback.photo_path.clone());
+                    if let Some(local_id) = result.local_identifier {
+                        updated_imports.insert(fallback.local_id.clone(), local_id);
+                    }
+                    if on_conflict == OnConflict::Replace
+                        && let Some(still) = still_ids.get(fallback.photo_path.as_str())
+                    {
+                        replaced_stills.push(still.to_string());
+                    }
+                    if verbose {
+                        display::print_success(&format!(
+                            "Re-imported Live Photo: {}",
+                            fallback.photo_path
+                        ));
+                    }
+                }
+                Ok(result) => {
+                    total_failed += 1;
+                    let err = result.error.unwrap_or_else(|| "unknown error".to_string());
+                    display::print_warning(&format!(
+                        "Live Photo retry failed: {} — {}",
+                        fallback.photo_path, err
+                    ));
+                }
+                Err(err) => {
+                    total_failed += 1;
+                    display::print_warning(&format!(
+                        "Live Photo retry error: {} — {}",
+                        fallback.photo_path, err
+                    ));
+                }
+            }
+        }
+
+        if !resolved_paths.is_empty() {
+            // Update manifest: remove resolved fallbacks and update imported entry to live photo
+            manifest.live_photo_fallbacks.retain(|f| !resolved_paths.contains(&f.photo_path));
+            // Every copy recorded against the still moves to the Live Photo,
+            // which starts out in no album
+            for (still_id, new_id) in &updated_imports {
+                repoint_copies(
+                    &importer::PhotosImporter,
+                    &mut album_ids,
+                    &mut album_registry,
+                    new_id,
+                    Some(true),
+                    manifest
+                        .imported
+                        .iter_mut()
+                        .filter(|entry| entry.local_id == *still_id),
+                    HashSet::new(),
+                );
+            }
+            // Write updated manifest
+            manifest::write_manifest(
+                manifest_path,
+                &manifest.zip,
+                &manifest.imported,
+                &manifest.failed,
+                &manifest.live_photo_fallbacks,
+            )?;
+
+            if !replaced_stills.is_empty() {
+                let ids: Vec<&str> = replaced_stills.iter().map(String::as_str).collect();
+                match importer::delete_assets(&ids) {
+                    Ok(n) => total_replaced += n,
+                    Err(e) => display::print_warning(&format!(
+                        "{}: could not delete {} replaced stills ({e:#}); check Photos.app for duplicates",
+                        manifest.zip,
+                        ids.len()
+                    )),
+                }
+            } else if on_conflict == OnConflict::Duplicate && !updated_imports.is_empty() {
+                display::print_warning(
+                    "Live Photo retries create new assets; check Photos.app for duplicates.",
+                );
+            }
+        }
+
+        std::fs::remove_dir_all(&extract_dir)?;
+    }
+
+    println!();
+    display::print_header("Retry Live Photo fallbacks summary");
+    display::print_summary(&format!("Re-imported: {}", total_reimported));
+    if total_replaced > 0 {
+        display::print_summary(&format!("Replaced stills (deleted): {}", total_replaced));
+    }
+    if total_kept > 0 {
+        display::print_summary(&format!(
+            "Left as still-only (--on-conflict skip): {}",
+            total_kept
+        ));
+    }
+    if total_failed > 0 {
+        display::print_warning(&format!("Retry failures: {}", total_failed));
+    }
+    if total_unresolved > 0 {
+        display::print_warning(&format!(
+            "Unresolved fallbacks (missing in zip): {}",
+            total_unresolved
+        ));
+    }
+
+    Ok(())
+}
+
+/// `--on-conflict skip` for retry-missing.
+#[derive(Debug)]
+struct SkipConflicts {
+    /// Entries Photos really no longer has
+    retry: Vec<manifest::ManifestEntry>,
+    /// Manifest path → asset found again by filename and date
+    relinks: HashMap<String, String>,
+    /// Entries whose asset is still in Photos, just not as recorded
+    kept: usize,
+}
+
+/// Split retry candidates into ones to re-import and ones Photos still has.
+/// Present-but-wrong assets (date, paired video) are kept as they are; a
+/// "missing" id is looked up again by filename, which catches assets whose
+/// id changed (e.g. after an iCloud round trip).
+fn skip_conflicts(
+    entries: Vec<manifest::ManifestEntry>,
+    verified: &HashMap<&str, &importer::AssetVerifyResult>,
+    assets: &[importer::FilenameAsset],
+) -> SkipConflicts {
+    let mut skip = SkipConflicts {
+        retry: Vec::new(),
+        relinks: HashMap::new(),
+        kept: 0,
+    };
+    for entry in entries {
+        if verified
+            .get(entry.local_id.as_str())
+            .is_some_and(|r| r.found)
+        {
+            skip.kept += 1;
+            continue;
+        }
+        let filename = Path::new(&entry.path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        match repair_match(filename, entry.creation_date.as_deref(), assets) {
+            RepairMatch::Found(id) => {
+                skip.relinks.insert(entry.path.clone(), id.to_string());
+            }
+            // Something by that name and date is there; don't add another
+            RepairMatch::Ambiguous(_) => skip.kept += 1,
+            RepairMatch::Missing => skip.retry.push(entry),
+        }
+    }
+    skip
+}
+
+/// Retry candidates, one per asset. A file's year-folder copy and each album
+/// copy are separate manifest entries sharing one `local_id`; re-importing
+/// each would duplicate the asset. Returns the first entry per id, and by its
+/// path, the other entries that share it.
+fn group_retry_entries(
+    entries: Vec<manifest::ManifestEntry>,
+) -> (
+    Vec<manifest::ManifestEntry>,
+    HashMap<String, Vec<manifest::ManifestEntry>>,
+) {
+    let mut first_path: HashMap<String, String> = HashMap::new();
+    let mut retry = Vec::new();
+    let mut copies: HashMap<String, Vec<manifest::ManifestEntry>> = HashMap::new();
+    for entry in entries {
+        match first_path.get(&entry.local_id) {
+            Some(path) => copies.entry(path.clone()).or_default().push(entry),
+            None => {
+                first_path.insert(entry.local_id.clone(), entry.path.clone());
+                retry.push(entry);
+            }
+        }
+    }
+    (retry, copies)
+}
+
+/// Point the copies of a re-imported file at its new asset, adding it to each
+/// copy's album. `albums` are the albums the asset is already in; a copy whose
+/// album add fails is recorded without one, as in `process_zip_streaming`.
+fn repoint_copies<'a>(
+    library: &dyn importer::Importer,
+    album_ids: &mut HashMap<String, Option<String>>,
+    registry: &mut manifest::AlbumRegistry,
+    local_id: &str,
+    is_live_photo: Option<bool>,
+    copies: impl IntoIterator<Item = &'a mut manifest::ManifestEntry>,
+    mut albums: HashSet<String>,
+) {
+    for copy in copies {
+        copy.local_id = local_id.to_string();
+        copy.is_live_photo = is_live_photo;
+        let Some(title) = copy.album.clone() else {
+            continue;
+        };
+        if albums.contains(&title) {
+            continue;
+        }
+        let added = match ensure_album(library, album_ids, registry, &title) {
+            Some(album_id) => match library.add_to_album(&album_id, local_id) {
+                Ok(added) => added,
+                Err(e) => {
+                    display::print_warning(&format!(
+                        "Failed to add '{}' to album '{title}': {e}",
+                        copy.path
+                    ));
+                    false
+                }
+            },
+            None => false,
+        };
+        if added {
+            albums.insert(title);
+        } else {
+            copy.album = None;
+        }
+    }
+}
+
+/// `--timezone-from-gps`: shift `meta`'s creation date to the local time at
+/// its GPS location (see [`metadata::shift_to_local_time`]). False, leaving
+/// the date alone, when there's no date or the time zone lookup failed.
+fn localize_creation_date(
+    meta: &mut importer::PhotoMetadata,
+    library: &dyn importer::Importer,
+) -> bool {
+    let (Some(date), Some(latitude), Some(longitude)) =
+        (meta.creation_date.as_deref(), meta.latitude, meta.longitude)
+    else {
+        return false;
+    };
+    let Ok(epoch) = chrono::DateTime::parse_from_rfc3339(date).map(|d| d.timestamp()) else {
+        return false;
+    };
+    let shifted = library
+        .utc_offset_at(latitude, longitude, epoch)
+        .and_then(|offset| metadata::shift_to_local_time(date, offset, &chrono::Local));
+    match shifted {
+        Some(shifted) => {
+            meta.creation_date = Some(shifted);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether two dates are the same instant, give or take `tolerance_secs`
+/// (`--date-tolerance-secs`). Unparseable dates must match as strings.
+fn dates_match(a: &str, b: &str, tolerance_secs: u64) -> bool {
+    let parsed_a = chrono::DateTime::parse_from_rfc3339(a)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc));
+    let parsed_b = chrono::DateTime::parse_from_rfc3339(b)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc));
+    match (parsed_a, parsed_b) {
+        (Some(da), Some(db)) => (da - db).num_seconds().unsigned_abs() <= tolerance_secs,
+        _ => a.trim() == b.trim(),
+    }
+}
+
+/// What a verified Live Photo is missing. Photos can keep just the video of a
+/// pair whose still failed, leaving a video asset under the Live Photo's id.
+#[derive(Debug, PartialEq, Eq)]
+enum LivePhotoIssue {
+    StillMissing,
+    PairMissing,
+}
+
+fn live_photo_issue(
+    entry: &manifest::ManifestEntry,
+    result: &importer::AssetVerifyResult,
+) -> Option<LivePhotoIssue> {
+    if entry.is_live_photo != Some(true) {
+        return None;
+    }
+    if !result.has_still {
+        Some(LivePhotoIssue::StillMissing)
+    } else if !result.has_paired_video {
+        Some(LivePhotoIssue::PairMissing)
+    } else {
+        None
+    }
+}
+
+fn date_mismatch(expected: Option<&str>, actual: Option<&str>, tolerance_secs: u64) -> bool {
+    match expected {
+        None => false,
+        Some(expected_value) => match actual {
+            Some(actual_value) => !dates_match(expected_value, actual_value, tolerance_secs),
+            None => true,
+        },
+    }
+}
+
+
+/// How a zip's file was found in Photos by `repair-manifest`.
+#[derive(Debug, PartialEq, Eq)]
+enum RepairMatch<'a> {
+    Found(&'a str),
+    /// Several assets share the filename and date; the first is recorded
+    Ambiguous(Vec<&'a str>),
+    Missing,
+}
+
+/// Match a file against Photos assets by filename (case-insensitive) and, when
+/// the sidecar gave one, creation date.
+fn repair_match<'a>(
+    filename: &str,
+    creation_date: Option<&str>,
+    assets: &'a [importer::FilenameAsset],
+) -> RepairMatch<'a> {
+    let mut ids: Vec<&str> = assets
+        .iter()
+        .filter(|a| a.filename.eq_ignore_ascii_case(filename))
+        .filter(|a| !date_mismatch(creation_date, a.creation_date.as_deref(), 0))
+        .map(|a| a.local_identifier.as_str())
+        .collect();
+    ids.sort_unstable();
+    match ids.len() {
+        0 => RepairMatch::Missing,
+        1 => RepairMatch::Found(ids[0]),
+        _ => RepairMatch::Ambiguous(ids),
+    }
+}
+
+fn cmd_repair_manifest(zip: &Path, dir: Option<&Path>) -> Result<()> {
+    let zip_path = expand_tilde(zip);
+    let dir = match dir {
+        Some(d) => expand_tilde(d),
+        None => zip_path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+    let zip_name = zip_path.file_name().unwrap_or_default().to_string_lossy();
+    let manifest_path = dir.join(format!(".photoferry-manifest-{}.json", zip_stem));
+    display::print_header(&format!("Repairing manifest for {zip_name}"));
+
+    // Never overwrite a good record with a guessed one
+    match manifest::read_manifest_strict(&manifest_path) {
+        Ok(Some(_)) => bail!(
+            "Manifest {} is readable — delete it first to rebuild from Photos",
+            manifest_path.display()
+        ),
+        Ok(None) => {}
+        Err(_) => {
+            let backup = manifest_path.with_extension("json.corrupt");
+            std::fs::rename(&manifest_path, &backup)?;
+            display::print_warning(&format!("Corrupt manifest moved to {}", backup.display()));
+        }
+    }
+
+    let access = importer::check_access()?;
+    ensure_full_photos_access(&access, "repair-manifest lookup")?;
+
+    let extract_dir = dir.join(format!(".photoferry-repair-extract-{}", zip_stem));
+    if extract_dir.exists() {
+        std::fs::remove_dir_all(&extract_dir)?;
+    }
+    std::fs::create_dir_all(&extract_dir)?;
+    let inventory = (|| -> Result<(PathBuf, takeout::TakeoutInventory)> {
+        let content_root = takeout::extract_zip(&zip_path, &extract_dir)?;
+        let inventory = takeout::scan_directory(&content_root, &takeout::ScanOptions::default())?;
+        Ok((content_root, inventory))
+    })();
+    let _ = std::fs::remove_dir_all(&extract_dir);
+    let (content_root, inventory) = inventory?;
+    display::print_info(&format!("{} files expected", inventory.files.len()));
+
+    let filenames: Vec<String> = inventory
+        .files
+        .iter()
+        .map(|f| {
+            f.path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    let names: Vec<&str> = filenames.iter().map(String::as_str).collect();
+    let assets = importer::find_by_filename(&names)?;
+
+    let mut entries: Vec<manifest::ManifestEntry> = Vec::new();
+    let mut ambiguous = 0usize;
+    let mut missing = 0usize;
+    for (file, filename) in inventory.files.iter().zip(&filenames) {
+        let rel = file
+            .path
+            .strip_prefix(&content_root)
+            .unwrap_or(&file.path)
+            .to_string_lossy()
+            .to_string();
+        let creation_date = file.metadata.as_ref().and_then(|m| m.creation_date.clone());
+        let local_id = match repair_match(filename, creation_date.as_deref(), &assets) {
+            RepairMatch::Found(id) => id,
+            RepairMatch::Ambiguous(ids) => {
+                ambiguous += 1;
+                display::print_warning(&format!(
+                    "  ? {rel} — {} matching assets, recording {}",
+                    ids.len(),
+                    ids[0]
+                ));
+                ids[0]
+            }
+            RepairMatch::Missing => {
+                missing += 1;
+                continue;
+            }
+        };
+        entries.push(manifest::ManifestEntry {
+            path: rel,
+            local_id: local_id.to_string(),
+            creation_date,
+            is_live_photo: Some(file.live_photo_pair.is_some()),
+            album: None,
+            sidecar_path: None,
+        });
+    }
+
+    manifest::write_manifest(&manifest_path, &zip_name, &entries, &[], &[])?;
+    display::print_summary_success(&format!(
+        "Wrote {} with {} of {} files",
+        manifest_path.display(),
+        entries.len(),
+        inventory.files.len()
+    ));
+    if ambiguous > 0 {
+        display::print_warning(&format!(
+            "Ambiguous (same name and date, first asset recorded): {ambiguous}"
+        ));
+    }
+    if missing > 0 {
+        display::print_summary(&format!(
+            "Not found in Photos: {missing} (a re-run will import them)"
+        ));
+    }
+    Ok(())
+}
+
+/// Before deleting a verified zip: warn if Photos hasn't uploaded all of its
+/// imports to iCloud yet (best effort — see [`importer::icloud_upload_status`]).
+/// Never blocks the deletion; the zip's verify already passed.
+fn warn_if_icloud_pending(zip_path: &Path, manifest_dir: &Path, label: &str) {
+    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+    let manifest_path = manifest_dir.join(format!(".photoferry-manifest-{zip_stem}.json"));
+    let Ok(Some(manifest)) = manifest::read_manifest_strict(&manifest_path) else {
+        return;
+    };
+    let ids: Vec<&str> = manifest
+        .imported
+        .iter()
+        .map(|e| e.local_id.as_str())
+        .collect();
+    if ids.is_empty() {
+        return;
+    }
+    match importer::icloud_upload_status(&ids) {
+        Ok(status) => {
+            if let Some(message) = icloud_pending_message(&status) {
+                display::print_warning(&format!("{label}{message}"));
+            }
+        }
+        Err(e) => display::print_warning(&format!("{label}iCloud upload status unavailable: {e}")),
+    }
+}
+
+fn icloud_pending_message(status: &importer::ICloudUploadStatus) -> Option<String> {
+    if !status.available || status.pending == 0 {
+        return None;
+    }
+    let total = status.synced + status.pending;
+    Some(format!(
+        "{} of {total} imports not in iCloud yet (still uploading, or iCloud Photos is off) — \
+         this zip may be their only other copy",
+        status.pending
+    ))
+}
+
+/// Batch-verify all assets recorded in a zip's manifest exist in Photos Library.
+/// Returns true if all present (safe to delete zip), false if any missing.
+fn verify_zip_manifest(zip_path: &Path, manifest_dir: &Path) -> bool {
+    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+    let manifest_path = manifest_dir.join(format!(".photoferry-manifest-{}.json", zip_stem));
+    let manifest = match manifest::read_manifest_strict(&manifest_path) {
+        Ok(Some(m)) => m,
+        Ok(None) => {
+            display::print_warning("  Verify: manifest missing — refusing to delete zip");
+            return false;
+        }
+        Err(e) => {
+            display::print_warning(&format!(
+                "  Verify: manifest unreadable/corrupt ({e}) — refusing to delete zip"
+            ));
+            return false;
+        }
+    };
+    warn_if_manifest_exceeds_zip(&manifest, zip_path);
+    if manifest.imported.is_empty() {
+        if !manifest.failed.is_empty() {
+            display::print_warning(&format!(
+                "  Verify: {} failed imports — keeping zip",
+                manifest.failed.len()
+            ));
+            return false;
+        }
+        return true;
+    }
+    let ids: Vec<&str> = manifest
+        .imported
+        .iter()
+        .map(|e| e.local_id.as_str())
+        .collect();
+    match importer::verify_assets_isolating(&ids) {
+        Ok((results, unverifiable)) => {
+            let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
+                .iter()
+                .map(|r| (r.local_identifier.as_str(), r))
+                .collect();
+            // Can't tell whether these are in Photos, so they keep the zip too
+            if !unverifiable.is_empty() {
+                display::print_warning(&format!(
+                    "  Verify: Photos could not check {}",
+                    unverifiable.join(", ")
+                ));
+            }
+            let unverifiable: HashSet<&str> = unverifiable.iter().map(String::as_str).collect();
+            let mut missing = 0usize;
+            let mut wrong_date = 0usize;
+            let mut live_pair_missing = 0usize;
+            let mut live_still_missing = 0usize;
+            let mut confirmed = 0usize;
+            for entry in &manifest.imported {
+                if unverifiable.contains(entry.local_id.as_str()) {
+                    continue;
+                }
+                let Some(result) = result_map.get(entry.local_id.as_str()) else {
+                    missing += 1;
+                    continue;
+                };
+                if !result.found {
+                    missing += 1;
+                    continue;
+                }
+                match live_photo_issue(entry, result) {
+                    Some(LivePhotoIssue::StillMissing) => {
+                        live_still_missing += 1;
+                        continue;
+                    }
+                    Some(LivePhotoIssue::PairMissing) => {
+                        live_pair_missing += 1;
+                        continue;
+                    }
+                    None => {}
+                }
+                if date_mismatch(
+                    entry.creation_date.as_deref(),
+                    result.creation_date.as_deref(),
+                    0,
+                ) {
+                    wrong_date += 1;
+                    continue;
+                }
+                confirmed += 1;
+            }
+            if missing > 0
+                || live_pair_missing > 0
+                || live_still_missing > 0
+                || wrong_date > 0
+                || !unverifiable.is_empty()
+            {
+                display::print_warning(&format!(
+                    "  Verify: {}/{} confirmed — {} missing, {} wrong date, {} live pair missing, {} live still missing, {} unverifiable; keeping zip",
+                    confirmed,
+                    manifest.imported.len(),
+                    missing,
+                    wrong_date,
+                    live_pair_missing,
+                    live_still_missing,
+                    unverifiable.len()
+                ));
+                false
+            } else {
+                display::print_success(&format!(
+                    "  Verify: all {} assets confirmed in Photos Library",
+                    confirmed
+                ));
+                true
+            }
+        }
+        Err(e) => {
+            display::print_warning(&format!("  Verify error: {e} — keeping zip as precaution"));
+            false
+        }
+    }
+}
+
+/// `--relink`: [`relink_manifest_zip`], moving `manifest_path` along with a
+/// renamed manifest. A failed relink is only a warning; the manifest is then
+/// handled as if its zip were missing.
+fn relink_or_warn(
+    dir: &Path,
+    manifest_path: &mut PathBuf,
+    manifest: &mut manifest::ImportManifest,
+) {
+    match relink_manifest_zip(dir, manifest_path, manifest) {
+        Ok(Some(relinked)) => *manifest_path = relinked,
+        Ok(None) => {}
+        Err(e) => display::print_warning(&format!(
+            "{}: could not relink — {e:#}",
+            manifest_path.display()
+        )),
+    }
+}
+
+/// Manifest paths sampled when looking for a renamed zip.
+const RELINK_SAMPLE_PATHS: usize = 5;
+
+/// Find the zip a manifest refers to after the user renamed it. A zip in `dir`
+/// matches when it contains every sampled manifest path; on a unique match the
+/// manifest's `zip` is updated, and the manifest file is renamed to the new
+/// zip's stem so `run` still resumes against it. Returns the (possibly new)
+/// manifest path, or None if no single zip matched.
+fn relink_manifest_zip(
+    dir: &Path,
+    manifest_path: &Path,
+    manifest: &mut manifest::ImportManifest,
+) -> Result<Option<PathBuf>> {
+    let samples: Vec<&str> = manifest
+        .imported
+        .iter()
+        .map(|e| e.path.as_str())
+        .chain(manifest.failed.iter().map(|e| e.path.as_str()))
+        .take(RELINK_SAMPLE_PATHS)
+        .collect();
+    if samples.is_empty() {
+        return Ok(None);
+    }
+
+    let mut matches = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+        {
+            continue;
+        }
+        match zip_contains_paths(&path, &samples) {
+            Ok(true) => matches.push(path),
+            Ok(false) => {}
+            Err(e) => {
+                display::print_warning(&format!("Relink: skipping {}: {}", path.display(), e))
+            }
+        }
+    }
+
+    let [zip_path] = matches.as_slice() else {
+        if matches.len() > 1 {
+            display::print_warning(&format!(
+                "Relink: {} zips match {}; leaving manifest unchanged",
+                matches.len(),
+                manifest.zip
+            ));
+        } else {
+            display::print_warning(&format!(
+                "Relink: no zip in {} matches {}",
+                dir.display(),
+                manifest.zip
+            ));
+        }
+        return Ok(None);
+    };
+
+    let zip_name = zip_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+    manifest::relink(manifest_path, &zip_name)?;
+
+    let renamed = dir.join(format!(".photoferry-manifest-{}.json", zip_stem));
+    let new_manifest_path = if renamed != manifest_path && !renamed.exists() {
+        std::fs::rename(manifest_path, &renamed)?;
+        renamed
+    } else {
+        manifest_path.to_path_buf()
+    };
+
+    display::print_success(&format!("Relinked {} → {}", manifest.zip, zip_name));
+    manifest.zip = zip_name;
+    Ok(Some(new_manifest_path))
+}
+
+/// `download --after-part`: run the user's command via `sh -c` once a part is
+/// done. Non-zero exits are warnings; a hook never stops the run.
+fn run_after_part_hook(cmd: Option<&str>, part: usize, status: &str, dir: &Path) {
+    let Some(cmd) = cmd else {
+        return;
+    };
+    let result = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .env("PHOTOFERRY_PART", part.to_string())
+        .env("PHOTOFERRY_STATUS", status)
+        .env("PHOTOFERRY_DIR", dir)
+        .status();
+    match result {
+        Ok(exit) if exit.success() => {}
+        Ok(exit) => display::print_warning(&format!(
+            "  [{part:02}] --after-part hook exited with {exit}"
+        )),
+        Err(e) => display::print_warning(&format!(
+            "  [{part:02}] --after-part hook failed to run: {e}"
+        )),
+    }
+}
+
+/// Media entries in a zip, by extension (what Phase 1 would index).
+fn zip_media_count(zip_path: &Path) -> Result<usize> {
+    let file = std::fs::File::open(zip_path)
+        .with_context(|| format!("Cannot open ZIP: {}", zip_path.display()))?;
+    let archive = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
+    Ok(archive
+        .file_names()
+        .filter(|n| !n.ends_with('/') && takeout::media_type_from_path(Path::new(n)).is_some())
+        .count())
+}
+
+/// Each manifest entry is one media path in its zip, so more entries than
+/// media means double-counting or a zip swapped for a smaller one.
+fn warn_if_manifest_exceeds_zip(manifest: &manifest::ImportManifest, zip_path: &Path) {
+    match zip_media_count(zip_path) {
+        Ok(media) if manifest.imported.len() > media => {
+            display::print_warning(&format!(
+                "  Manifest lists {} imported but {} has only {} media files — was the zip replaced?",
+                manifest.imported.len(),
+                manifest.zip,
+                media
+            ));
+        }
+        Ok(_) => {}
+        Err(e) => display::print_warning(&format!(
+            "  Could not count media in {}: {e:#}",
+            manifest.zip
+        )),
+    }
+}
+
+/// Media files in a zip that its manifest doesn't record as imported — left
+/// out by a filter, `--max-runtime` or `--max-file-size`, or not reached
+/// before a crash. A Live Photo entry stands for its photo and its video.
+fn zip_media_unaccounted(manifest: &manifest::ImportManifest, zip_path: &Path) -> Result<usize> {
+    let covered: usize = manifest
+        .imported
+        .iter()
+        .map(|e| if e.is_live_photo == Some(true) { 2 } else { 1 })
+        .sum();
+    Ok(zip_media_count(zip_path)?.saturating_sub(covered))
+}
+
+/// Whether a zip contains every given Takeout-relative path.
+fn zip_contains_paths(zip_path: &Path, paths: &[&str]) -> Result<bool> {
+    let file = std::fs::File::open(zip_path)
+        .with_context(|| format!("Cannot open ZIP: {}", zip_path.display()))?;
+    let archive = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
+    let root = takeout::detect_content_root(archive.file_names());
+    let names: HashSet<&str> = archive
+        .file_names()
+        .map(|n| n.strip_prefix(root.prefix.as_str()).unwrap_or(n))
+        .collect();
+    Ok(paths.iter().all(|p| names.contains(p)))
+}
+
+fn live_photo_paths_from_zip(zip_path: &Path, manifest_dir: &Path) -> Result<HashSet<String>> {
+    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
+    let extract_dir = manifest_dir.join(format!(
+        ".photoferry-verify-extract-{}",
+        zip_stem
+    ));
+    if extract_dir.exists() {
+        std::fs::remove_dir_all(&extract_dir)?;
+    }
+    std::fs::create_dir_all(&extract_dir)?;
+
+    let result = (|| -> Result<HashSet<String>> {
+        let content_root = takeout::extract_zip(zip_path, &extract_dir)?;
+        let inventory = takeout::scan_directory(&content_root, &takeout::ScanOptions::default())?;
+
+        let mut live_paths = HashSet::new();
+        for file in &inventory.files {
+            if file.live_photo_pair.is_some() {
+                let rel = file
+                    .path
+                    .strip_prefix(&content_root)
+                    .unwrap_or(&file.path)
+                    .to_string_lossy()
+                    .to_string();
+                live_paths.insert(rel);
+            }
+        }
+        Ok(live_paths)
+    })();
+
+    let _ = std::fs::remove_dir_all(&extract_dir);
+    result
+}
+
+fn ensure_full_photos_access(access: &importer::AccessResult, action: &str) -> Result<()> {
+    if !access.authorized {
+        return Err(ProcessError::PhotosAccess(format!(
+            "Photos access: {} — grant in System Settings > Privacy & Security > Photos",
+            access.status
+        ))
+        .into());
+    }
+    if access.status == "limited" {
+        return Err(ProcessError::PhotosAccess(format!(
+            "Photos access is limited — {action} requires full library access for reliable results"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn expand_tilde(path: &Path) -> PathBuf {
+    if let Some(rest) = path.to_str().and_then(|s: &str| s.strip_prefix("~/"))
+        && let Ok(home) = std::env::var("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BurstPolicy, DateAlbums, DryRunCounts, EditedPolicy, GifPolicy, ImportArgs, IndexEntryKind,
+        LivePhotoIssue, Outcome, ProcessError, ProgressBar, RepairMatch, SELF_CHECK_EVERY,
+        SampleRng, SplitMediaAlbums, UNKNOWN_REPORT_HEADER, VerifyProblem, VerifyProblemReason,
+        VerifySuccessAction, ZipDirGroup, ZipEntry, ZipEntryClass, album_filter_matches,
+        album_for_media, archive_zip, classify_zip_entry, clean_blockers, clean_targets,
+        cmd_repair_manifest, count_live_pairs, create_albums_up_front, date_album_title,
+        date_mismatch, dates_match, discover_sidecar_metadata, dry_run_detail,
+        duplicate_zip_claims, edited_policy_skips, exceeds_max_file_size, extract_retry_files,
+        extract_zip_entry, failure_rate_upper_bound, group_album_files, group_failures,
+        group_retry_entries, icloud_pending_message, import_date_key, import_time_label,
+        live_photo_issue, manifest_totals, metadata, note_earliest_in_album, parse_runtime,
+        parse_sample_pct, process_one_zip, process_zip_streaming, relink_manifest_zip,
+        repair_match, repoint_copies, run_after_part_hook, sample_indices, skip_conflicts,
+        temp_extract_path, verbose_success_due, verify_in_batches, verify_manifest_paths,
+        verify_problem_total, verify_success_action, verify_success_line, write_unknown_report,
+        wrong_date_indices, zip_contains_paths, zip_keep_reason, zip_media_count,
+        zip_media_unaccounted,
+    };
+    use crate::importer;
+    use crate::manifest;
+    use crate::sidecar;
+    use crate::takeout::{self, MediaType};
+    use std::cell::{Cell, RefCell};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    #[test]
+    fn dates_match_normalizes_timezone() {
+        assert!(dates_match(
+            "2026-02-22T10:00:00+08:00",
+            "2026-02-22T02:00:00Z",
+            0
+        ));
+    }
+
+    #[test]
+    fn dates_match_detects_real_difference() {
+        assert!(!dates_match(
+            "2026-02-22T10:00:00Z",
+            "2026-02-22T10:00:01Z",
+            0
+        ));
+    }
+
+    #[test]
+    fn dates_match_falls_back_to_trimmed_string() {
+        assert!(dates_match("not-a-date ", "not-a-date", 0));
+    }
+
+    #[test]
+    fn date_mismatch_is_false_without_expected_date() {
+        assert!(!date_mismatch(None, None, 0));
+        assert!(!date_mismatch(None, Some("2026-02-22T10:00:00Z"), 0));
+    }
+
+    #[test]
+    fn date_mismatch_is_true_when_expected_exists_but_actual_missing() {
+        assert!(date_mismatch(Some("2026-02-22T10:00:00Z"), None, 0));
+    }
+
+    #[test]
+    fn date_mismatch_uses_dates_match_when_both_present() {
+        assert!(!date_mismatch(
+            Some("2026-02-22T10:00:00+08:00"),
+            Some("2026-02-22T02:00:00Z"),
+            0
+        ));
+        assert!(date_mismatch(
+            Some("2026-02-22T10:00:00Z"),
+            Some("2026-02-22T10:00:01Z"),
+            0
+        ));
+    }
+
+    #[test]
+    fn edited_policy_skips_one_half_of_each_pair() {
+        let media: Vec<PathBuf> = ["a.jpg", "a-edited.jpg", "b.jpg", "c_bearbeitet.jpg"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let skipped = |policy| {
+            let mut names: Vec<&str> = edited_policy_skips(&media, policy)
+                .into_iter()
+                .map(|p| p.to_str().unwrap())
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        assert!(skipped(EditedPolicy::Both).is_empty());
+        // Only `a` has both halves; `b` and `c` are kept whatever the policy
+        assert_eq!(skipped(EditedPolicy::Edited), vec!["a.jpg"]);
+        assert_eq!(skipped(EditedPolicy::Original), vec!["a-edited.jpg"]);
+    }
+
+    #[test]
+    fn live_photo_issue_reports_the_missing_half() {
+        let entry = |is_live| manifest::ManifestEntry {
+            path: "IMG_0001.HEIC".to_string(),
+            local_id: "id".to_string(),
+            creation_date: None,
+            is_live_photo: is_live,
+            album: None,
+            sidecar_path: None,
+        };
+        let result = |has_still, has_paired_video| importer::AssetVerifyResult {
+            local_identifier: "id".to_string(),
+            found: true,
+            creation_date: None,
+            has_paired_video,
+            has_still,
+            in_icloud_only: false,
+        };
+        let live = entry(Some(true));
+        assert_eq!(live_photo_issue(&live, &result(true, true)), None);
+        assert_eq!(
+            live_photo_issue(&live, &result(true, false)),
+            Some(LivePhotoIssue::PairMissing)
+        );
+        // Only the video made it in
+        assert_eq!(
+            live_photo_issue(&live, &result(false, true)),
+            Some(LivePhotoIssue::StillMissing)
+        );
+        assert_eq!(
+            live_photo_issue(&entry(Some(false)), &result(true, false)),
+            None
+        );
+        assert_eq!(live_photo_issue(&entry(None), &result(false, true)), None);
+    }
+
+    #[test]
+    fn dates_match_within_tolerance() {
+        assert!(dates_match(
+            "2026-02-22T10:00:00Z",
+            "2026-02-22T10:00:02Z",
+            2
+        ));
+        assert!(dates_match(
+            "2026-02-22T10:00:02Z",
+            "2026-02-22T10:00:00Z",
+            2
+        ));
+        assert!(!dates_match(
+            "2026-02-22T10:00:00Z",
+            "2026-02-22T10:00:03Z",
+            2
+        ));
+        assert!(!dates_match(
+            "2026-02-22T10:00:03Z",
+            "2026-02-22T10:00:00Z",
+            2
+        ));
+        // Tolerance doesn't loosen the string fallback
+        assert!(!dates_match("not-a-date", "not-a-datf", 60));
+        assert!(!date_mismatch(
+            Some("2026-02-22T10:00:00+08:00"),
+            Some("2026-02-22T02:00:01Z"),
+            1
+        ));
+    }
+
+    #[test]
+    fn verify_success_action_deletes_zip_by_default() {
+        assert_eq!(
+            verify_success_action(false, false),
+            VerifySuccessAction::DeleteZipAndMarkCompleted
+        );
+    }
+
+    #[test]
+    fn verify_success_action_keeps_zip_when_keep_zips_set() {
+        assert_eq!(
+            verify_success_action(true, false),
+            VerifySuccessAction::KeepZipAndMarkCompleted
+        );
+    }
+
+    #[test]
+    fn verify_success_action_moves_zip_when_archive_dir_set() {
+        assert_eq!(
+            verify_success_action(false, true),
+            VerifySuccessAction::MoveZipAndMarkCompleted
+        );
+    }
+
+    #[test]
+    fn archive_zip_moves_into_archive_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-001.zip");
+        std::fs::write(&zip_path, b"zip").unwrap();
+        let archive_dir = dir.path().join("done");
+
+        let dest = archive_zip(&zip_path, &archive_dir).unwrap();
+        assert_eq!(dest, archive_dir.join("takeout-001.zip"));
+        assert!(!zip_path.exists());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"zip");
+    }
+
+    #[test]
+    fn archive_zip_refuses_to_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-001.zip");
+        std::fs::write(&zip_path, b"new").unwrap();
+        let archive_dir = dir.path().join("done");
+        std::fs::create_dir_all(&archive_dir).unwrap();
+        std::fs::write(archive_dir.join("takeout-001.zip"), b"old").unwrap();
+
+        assert!(archive_zip(&zip_path, &archive_dir).is_err());
+        assert!(zip_path.exists());
+        assert_eq!(
+            std::fs::read(archive_dir.join("takeout-001.zip")).unwrap(),
+            b"old"
+        );
+    }
+
+    #[test]
+    fn album_for_media_unchanged_without_split() {
+        assert_eq!(
+            album_for_media(Some("Trip"), MediaType::Video, None).as_deref(),
+            Some("Trip")
+        );
+        assert_eq!(album_for_media(None, MediaType::Video, None), None);
+    }
+
+    #[test]
+    fn album_for_media_suffix_only_moves_album_videos() {
+        let split = Some(SplitMediaAlbums::Suffix);
+        assert_eq!(
+            album_for_media(Some("Trip"), MediaType::Video, split).as_deref(),
+            Some("Trip (Videos)")
+        );
+        assert_eq!(
+            album_for_media(Some("Trip"), MediaType::Photo, split).as_deref(),
+            Some("Trip")
+        );
+        assert_eq!(album_for_media(None, MediaType::Video, split), None);
+    }
+
+    #[test]
+    fn album_for_media_single_collects_all_videos() {
+        let split = Some(SplitMediaAlbums::Single);
+        assert_eq!(
+            album_for_media(Some("Trip"), MediaType::Video, split).as_deref(),
+            Some("Imported Videos")
+        );
+        assert_eq!(
+            album_for_media(None, MediaType::Video, split).as_deref(),
+            Some("Imported Videos")
+        );
+        assert_eq!(album_for_media(None, MediaType::Photo, split), None);
+    }
+
+    fn write_zip(path: &Path, entries: &[&str]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for name in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn write_zip_contents(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, contents) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// What the manifest for `zip_path`, kept in `dir`, records as imported.
+    fn manifest_imports(dir: &Path, zip_path: &Path) -> Vec<manifest::ManifestEntry> {
+        let stem = zip_path.file_stem().unwrap().to_string_lossy();
+        manifest::read_manifest(&dir.join(format!(".photoferry-manifest-{stem}.json")))
+            .map(|m| m.imported)
+            .unwrap_or_default()
+    }
+
+    /// An importer that accepts every import and records what it was asked.
+    #[derive(Default)]
+    struct MockImporter {
+        /// (kind, file name, sidecar creation date)
+        imports: RefCell<Vec<(&'static str, String, Option<String>)>>,
+        albums: RefCell<Vec<String>>,
+        /// (album title, file name)
+        album_adds: RefCell<Vec<(String, String)>>,
+        /// Imported ids stop resolving, as with Photos' identifier churn
+        unstable_ids: Cell<bool>,
+        /// Ids passed to `verify_assets`
+        verified: RefCell<Vec<String>>,
+        /// Photos access reports "denied", as after revoking it mid-run
+        access_revoked: Cell<bool>,
+        /// Whether each import's file was on disk when imported
+        on_disk: RefCell<Vec<bool>>,
+        /// `import_motion_photo` fails, so the still is imported instead
+        motion_fails: Cell<bool>,
+    }
+
+    impl MockImporter {
+        fn record(
+            &self,
+            kind: &'static str,
+            path: &str,
+            metadata: Option<&importer::PhotoMetadata>,
+        ) -> anyhow::Result<importer::ImportResult> {
+            self.on_disk.borrow_mut().push(Path::new(path).exists());
+            let mut imports = self.imports.borrow_mut();
+            let name = Path::new(path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            imports.push((kind, name, metadata.and_then(|m| m.creation_date.clone())));
+            Ok(importer::ImportResult {
+                success: true,
+                local_identifier: Some(format!("mock-{}", imports.len())),
+                error: None,
+            })
+        }
+
+        fn imported(&self, name: &str) -> Option<(&'static str, Option<String>)> {
+            self.imports
+                .borrow()
+                .iter()
+                .find(|(_, n, _)| n == name)
+                .map(|(kind, _, date)| (*kind, date.clone()))
+        }
+    }
+
+    impl importer::Importer for MockImporter {
+        fn check_access(&self) -> anyhow::Result<importer::AccessResult> {
+            let revoked = self.access_revoked.get();
+            Ok(importer::AccessResult {
+                authorized: !revoked,
+                status: if revoked { "denied" } else { "authorized" }.to_string(),
+            })
+        }
+
+        fn import_photo(
+            &self,
+            path: &str,
+            metadata: Option<&importer::PhotoMetadata>,
+            is_video: bool,
+        ) -> anyhow::Result<importer::ImportResult> {
+            self.record(if is_video { "video" } else { "photo" }, path, metadata)
+        }
+
+        fn import_live_photo(
+            &self,
+            photo_path: &str,
+            _video_path: &str,
+            metadata: Option<&importer::PhotoMetadata>,
+        ) -> anyhow::Result<importer::ImportResult> {
+            self.record("live", photo_path, metadata)
+        }
+
+        fn import_motion_photo(
+            &self,
+            path: &str,
+            metadata: Option<&importer::PhotoMetadata>,
+        ) -> anyhow::Result<importer::ImportResult> {
+            if self.motion_fails.get() {
+                return Ok(importer::ImportResult {
+                    success: false,
+                    local_identifier: None,
+                    error: Some("not a motion photo".to_string()),
+                });
+            }
+            self.record("motion", path, metadata)
+        }
+
+        fn import_photo_resource(
+            &self,
+            path: &str,
+            metadata: Option<&importer::PhotoMetadata>,
+        ) -> anyhow::Result<importer::ImportResult> {
+            self.record("resource", path, metadata)
+        }
+
+        fn gif_to_video(&self, _gif_path: &str, movie_path: &str) -> anyhow::Result<()> {
+            std::fs::write(movie_path, b"mov")?;
+            Ok(())
+        }
+
+        fn verify_assets(
+            &self,
+            local_ids: &[&str],
+        ) -> anyhow::Result<Vec<importer::AssetVerifyResult>> {
+            let mut verified = self.verified.borrow_mut();
+            verified.extend(local_ids.iter().map(|id| id.to_string()));
+            Ok(local_ids
+                .iter()
+                .map(|id| importer::AssetVerifyResult {
+                    local_identifier: id.to_string(),
+                    found: !self.unstable_ids.get(),
+                    creation_date: None,
+                    has_paired_video: false,
+                    has_still: true,
+                    in_icloud_only: false,
+                })
+                .collect())
+        }
+
+        /// Everywhere north of the equator is Tokyo; the south is unknown
+        fn utc_offset_at(&self, latitude: f64, _: f64, _: i64) -> Option<i32> {
+            (latitude > 0.0).then_some(9 * 3600)
+        }
+
+        fn create_album(&self, title: &str) -> anyhow::Result<String> {
+            let mut albums = self.albums.borrow_mut();
+            albums.push(title.to_string());
+            Ok(format!("album-{}", albums.len()))
+        }
+
+        fn album_title(&self, album_id: &str) -> anyhow::Result<Option<String>> {
+            let index: Option<usize> = album_id.strip_prefix("album-").and_then(|n| n.parse().ok());
+            Ok(index.and_then(|i| self.albums.borrow().get(i.checked_sub(1)?).cloned()))
+        }
+
+        fn add_to_album(&self, album_id: &str, asset_id: &str) -> anyhow::Result<bool> {
+            let index: usize = album_id.trim_start_matches("album-").parse().unwrap();
+            let asset: usize = asset_id.trim_start_matches("mock-").parse().unwrap();
+            let album = self.albums.borrow()[index - 1].clone();
+            let name = self.imports.borrow()[asset - 1].1.clone();
+            self.album_adds.borrow_mut().push((album, name));
+            Ok(true)
+        }
+
+        fn set_album_key_photo(&self, _album_id: &str, _asset_id: &str) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn sidecar_json(timestamp: i64, trashed: bool) -> Vec<u8> {
+        format!(r#"{{"photoTakenTime": {{"timestamp": "{timestamp}"}}, "trashed": {trashed}}}"#)
+            .into_bytes()
+    }
+
+    /// A Takeout export with most of the shapes the heuristics deal with.
+    fn write_synthetic_takeout(path: &Path) {
+        let year = "Takeout/Google Photos/Photos from 2020";
+        let album = "Takeout/Google Photos/Trip to Rome";
+        let long_a = "Screenshot_2020-01-01-12-00-00-000_com.example.app.a.jpg";
+        let long_b = "Screenshot_2020-01-01-12-00-00-000_com.example.app.b.jpg";
+        let entries: Vec<(String, Vec<u8>)> = vec![
+            ("Takeout/archive_browser.html".into(), b"<html>".to_vec()),
+            (format!("{year}/IMG_0001.JPG"), b"jpeg".to_vec()),
+            (
+                format!("{year}/IMG_0001.JPG.supplemental-metadata.json"),
+                sidecar_json(1_577_836_800, false),
+            ),
+            // Live Photo pair
+            (format!("{year}/IMG_0002.HEIC"), b"heic".to_vec()),
+            (format!("{year}/IMG_0002.MOV"), b"mov".to_vec()),
+            (
+                format!("{year}/IMG_0002.HEIC.supplemental-metadata.json"),
+                sidecar_json(1_577_923_200, false),
+            ),
+            (format!("{year}/VID_0003.MP4"), b"mp4".to_vec()),
+            (format!("{year}/deleted.jpg"), b"jpeg".to_vec()),
+            (
+                format!("{year}/deleted.jpg.json"),
+                sidecar_json(1_578_009_600, true),
+            ),
+            // Deduplicated name, index moved into the sidecar's name
+            (format!("{year}/IMG_0005(1).JPG"), b"jpeg".to_vec()),
+            (
+                format!("{year}/IMG_0005.JPG(1).json"),
+                sidecar_json(1_578_096_000, false),
+            ),
+            // Both truncate to the same 46 characters; the sidecar can't be attributed
+            (format!("{year}/{long_a}"), b"jpeg".to_vec()),
+            (format!("{year}/{long_b}"), b"jpeg".to_vec()),
+            (
+                format!("{year}/{}.json", &long_a[..46]),
+                sidecar_json(1_578_182_400, false),
+            ),
+            (
+                format!("{album}/metadata.json"),
+                br#"{"albumData": {"title": "Trip to Rome"}}"#.to_vec(),
+            ),
+            (format!("{album}/IMG_0004.JPG"), b"jpeg".to_vec()),
+            (
+                format!("{album}/IMG_0004.JPG.json"),
+                sidecar_json(1_578_268_800, false),
+            ),
+            // Its media is missing from the export
+            (
+                format!("{album}/IMG_0009.JPG.json"),
+                sidecar_json(1_578_355_200, false),
+            ),
+            (format!("{album}/notes.txt"), b"notes".to_vec()),
+        ];
+        let borrowed: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+        write_zip_contents(path, &borrowed);
+    }
+
+    #[test]
+    fn synthetic_takeout_index_classifies_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+
+        let import = ImportArgs {
+            dump_index: true,
+            ..Default::default()
+        };
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        let kind_of = |name: &str| {
+            summary
+                .index
+                .iter()
+                .find(|e| e.path.ends_with(name))
+                .map(|e| e.kind)
+                .unwrap_or_else(|| panic!("{name} not indexed"))
+        };
+        assert_eq!(kind_of("archive_browser.html"), IndexEntryKind::Bookkeeping);
+        assert_eq!(kind_of("IMG_0002.HEIC"), IndexEntryKind::Photo);
+        assert_eq!(kind_of("IMG_0002.MOV"), IndexEntryKind::Video);
+        assert_eq!(kind_of("Trip to Rome/metadata.json"), IndexEntryKind::Json);
+        assert_eq!(kind_of("notes.txt"), IndexEntryKind::Unknown);
+        let media = summary
+            .index
+            .iter()
+            .filter(|e| matches!(e.kind, IndexEntryKind::Photo | IndexEntryKind::Video))
+            .count();
+        assert_eq!(media, 9);
+    }
+
+    #[test]
+    fn mixed_wrapping_strips_the_prefix_only_where_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-mixed.zip");
+        write_zip_contents(
+            &zip_path,
+            &[
+                ("archive_browser.html", b"<html>"),
+                (
+                    "Takeout/Google Photos/Photos from 2020/IMG_0001.JPG",
+                    b"jpeg",
+                ),
+            ],
+        );
+
+        let import = ImportArgs {
+            dump_index: true,
+            ..Default::default()
+        };
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        let keys: Vec<(&str, &str, IndexEntryKind)> = summary
+            .index
+            .iter()
+            .map(|e| (e.path.as_str(), e.dir.as_str(), e.kind))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("archive_browser.html", "", IndexEntryKind::Bookkeeping),
+                (
+                    "Google Photos/Photos from 2020/IMG_0001.JPG",
+                    "Google Photos/Photos from 2020",
+                    IndexEntryKind::Photo
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn synthetic_takeout_imports_end_to_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+        let library = MockImporter::default();
+
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+
+        assert_eq!(summary.failed, 0, "{:?}", summary.failures);
+        assert_eq!(summary.imported, 7);
+        assert_eq!(summary.ambiguous_truncation_skipped, 2);
+        assert_eq!(summary.orphaned_sidecars, 1);
+        assert_eq!(
+            library.imported("IMG_0001.JPG"),
+            Some(("photo", Some("2020-01-01T00:00:00Z".to_string())))
+        );
+        // The pair's video goes in with the photo, never on its own
+        assert_eq!(
+            library.imported("IMG_0002.HEIC"),
+            Some(("live", Some("2020-01-02T00:00:00Z".to_string())))
+        );
+        assert_eq!(library.imported("IMG_0002.MOV"), None);
+        assert_eq!(library.imported("VID_0003.MP4"), Some(("video", None)));
+        assert_eq!(library.imported("deleted.jpg"), None);
+        assert_eq!(
+            library.imported("IMG_0005(1).JPG"),
+            Some(("photo", Some("2020-01-04T00:00:00Z".to_string())))
+        );
+        assert_eq!(
+            library.imported("Screenshot_2020-01-01-12-00-00-000_com.example.app.a.jpg"),
+            Some(("photo", None))
+        );
+        // Year folders aren't albums; metadata.json names the real one
+        assert_eq!(*library.albums.borrow(), vec!["Trip to Rome".to_string()]);
+        assert_eq!(
+            *library.album_adds.borrow(),
+            vec![("Trip to Rome".to_string(), "IMG_0004.JPG".to_string())]
+        );
+
+        // A second run finds everything in the manifest and imports nothing
+        let manifest =
+            manifest::read_manifest(&dir.path().join(".photoferry-manifest-takeout-test.json"))
+                .unwrap();
+        assert_eq!(manifest.imported.len(), 7);
+        assert!(
+            manifest
+                .imported
+                .iter()
+                .any(|e| e.path.ends_with("IMG_0002.HEIC") && e.is_live_photo == Some(true))
+        );
+        let rerun = MockImporter::default();
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &rerun,
+        )
+        .unwrap();
+        assert_eq!(summary.imported, 0);
+        assert!(rerun.imports.borrow().is_empty());
+    }
+
+    #[test]
+    fn retry_extracts_only_the_recorded_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-retry.zip");
+        let root = "Takeout/Google Photos/Rome";
+        let entries: Vec<(String, Vec<u8>)> = vec![
+            (
+                format!("{root}/metadata.json"),
+                br#"{"albumData": {"title": "Rome"}}"#.to_vec(),
+            ),
+            (format!("{root}/IMG_0001.HEIC"), b"heic".to_vec()),
+            (format!("{root}/IMG_0001.MOV"), b"mov".to_vec()),
+            (
+                format!("{root}/IMG_0001.HEIC.supplemental-metadata.json"),
+                sidecar_json(1_577_836_800, false),
+            ),
+            (format!("{root}/IMG_0002.JPG"), b"jpeg".to_vec()),
+            (
+                format!("{root}/IMG_0002.JPG.json"),
+                sidecar_json(1_600_000_000, false),
+            ),
+        ];
+        let borrowed: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+        write_zip_contents(&zip_path, &borrowed);
+        let library = MockImporter::default();
+        process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        let manifest =
+            manifest::read_manifest(&dir.path().join(".photoferry-manifest-takeout-retry.json"))
+                .unwrap();
+        let live = manifest
+            .imported
+            .iter()
+            .find(|e| e.path.ends_with("IMG_0001.HEIC"))
+            .unwrap();
+        assert_eq!(
+            live.sidecar_path.as_deref(),
+            Some("Google Photos/Rome/IMG_0001.HEIC.supplemental-metadata.json")
+        );
+
+        let extract_dir = dir.path().join("extract");
+        let files = extract_retry_files(&zip_path, std::slice::from_ref(live), &extract_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].album.as_deref(), Some("Rome"));
+        assert!(files[0].metadata.as_ref().unwrap().creation_date.is_some());
+        assert_eq!(
+            files[0].live_photo_pair.as_deref(),
+            Some(
+                extract_dir
+                    .join("Google Photos/Rome/IMG_0001.MOV")
+                    .as_path()
+            )
+        );
+        assert!(!extract_dir.join("Google Photos/Rome/IMG_0002.JPG").exists());
+
+        // Older manifests have no sidecar path: fall back to a full scan
+        let old = manifest::ManifestEntry {
+            sidecar_path: None,
+            ..live.clone()
+        };
+        assert!(
+            extract_retry_files(&zip_path, &[old], &extract_dir)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn two_phase_creates_every_album_before_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = "Takeout/Google Photos";
+        let rome = br#"{"albumData": {"title": "Rome"}}"#.to_vec();
+        let paris = br#"{"albumData": {"title": "Paris"}}"#.to_vec();
+        let first: Vec<(String, Vec<u8>)> = vec![
+            (format!("{root}/Rome/metadata.json"), rome),
+            (format!("{root}/Rome/IMG_0001.HEIC"), b"heic".to_vec()),
+            // Live Photo video: not a video of its own
+            (format!("{root}/Rome/IMG_0001.MOV"), b"mov".to_vec()),
+            (
+                format!("{root}/Photos from 2020/IMG_0002.JPG"),
+                b"jpeg".to_vec(),
+            ),
+        ];
+        let second: Vec<(String, Vec<u8>)> = vec![
+            (format!("{root}/Paris/metadata.json"), paris),
+            (format!("{root}/Paris/clip.mp4"), b"mp4".to_vec()),
+            (format!("{root}/Empty/metadata.json"), b"{}".to_vec()),
+        ];
+        let zips: Vec<PathBuf> = [("takeout-001.zip", first), ("takeout-002.zip", second)]
+            .into_iter()
+            .map(|(name, entries)| {
+                let path = dir.path().join(name);
+                let borrowed: Vec<(&str, &[u8])> = entries
+                    .iter()
+                    .map(|(n, c)| (n.as_str(), c.as_slice()))
+                    .collect();
+                write_zip_contents(&path, &borrowed);
+                path
+            })
+            .collect();
+        let import = ImportArgs {
+            split_media_albums: Some(SplitMediaAlbums::Suffix),
+            two_phase: true,
+            ..Default::default()
+        };
+        let library = MockImporter::default();
+
+        create_albums_up_front(&zips, dir.path(), false, &import, &library).unwrap();
+        assert_eq!(*library.albums.borrow(), vec!["Paris (Videos)", "Rome"]);
 
-    display::print_info(&format!("Imported: {}", summary.imported.len()));
-    display::print_info(&format!("Failed: {}", summary.failed.len()));
-    display::print_info(&format!("Elapsed: {}", elapsed_str));
-    if summary.live_photo_fallbacks > 0 {
-        display::print_warning(&format!(
-            "Live Photo fallbacks (still photo only): {}",
-            summary.live_photo_fallbacks
-        ));
+        // The import reuses them instead of creating its own
+        process_zip_streaming(&zips[0], dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(library.albums.borrow().len(), 2);
+        assert_eq!(
+            *library.album_adds.borrow(),
+            vec![("Rome".to_string(), "IMG_0001.HEIC".to_string())]
+        );
     }
 
-    if !summary.failed.is_empty() {
-        display::print_warning("Failed files:");
-        for failed in &summary.failed {
-            display::print_error(&format!("{} — {}", failed.path, failed.error));
-        }
+    #[test]
+    fn disambiguate_albums_keeps_same_titled_folders_apart() {
+        let root = "Takeout/Google Photos";
+        let untitled = br#"{"albumData": {"title": "Untitled"}}"#.to_vec();
+        let entries: Vec<(String, Vec<u8>)> = vec![
+            (format!("{root}/Untitled/metadata.json"), untitled.clone()),
+            (format!("{root}/Untitled/IMG_0001.JPG"), b"jpeg".to_vec()),
+            (format!("{root}/Untitled(1)/metadata.json"), untitled),
+            (format!("{root}/Untitled(1)/IMG_0002.JPG"), b"jpeg".to_vec()),
+        ];
+        let borrowed: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+
+        let run = |dir: &Path, disambiguate_albums: bool| {
+            let zip_path = dir.join("takeout.zip");
+            write_zip_contents(&zip_path, &borrowed);
+            let library = MockImporter::default();
+            let import = ImportArgs {
+                disambiguate_albums,
+                ..Default::default()
+            };
+            process_zip_streaming(&zip_path, dir, false, false, &import, &library).unwrap();
+            let mut adds = library.album_adds.borrow().clone();
+            adds.sort();
+            adds
+        };
+
+        let merged = tempfile::tempdir().unwrap();
+        assert_eq!(
+            run(merged.path(), false),
+            vec![
+                ("Untitled".to_string(), "IMG_0001.JPG".to_string()),
+                ("Untitled".to_string(), "IMG_0002.JPG".to_string()),
+            ]
+        );
+        let separate = tempfile::tempdir().unwrap();
+        assert_eq!(
+            run(separate.path(), true),
+            vec![
+                ("Untitled".to_string(), "IMG_0001.JPG".to_string()),
+                (
+                    "Untitled (Untitled(1))".to_string(),
+                    "IMG_0002.JPG".to_string()
+                ),
+            ]
+        );
+        let manifest =
+            manifest::read_manifest(&separate.path().join(".photoferry-manifest-takeout.json"))
+                .unwrap();
+        let second = manifest
+            .imported
+            .iter()
+            .find(|e| e.path.ends_with("IMG_0002.JPG"))
+            .unwrap();
+        assert_eq!(second.album.as_deref(), Some("Untitled (Untitled(1))"));
     }
-}
 
-fn cmd_verify(dir: &Path) -> Result<()> {
-    let dir = expand_tilde(dir);
-    display::print_header(&format!("Verifying imports in {}", dir.display()));
+    #[test]
+    fn copies_in_year_folder_and_albums_import_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-dupes.zip");
+        let root = "Takeout/Google Photos";
+        let sidecar = sidecar_json(1_577_836_800, false);
+        let rome_meta = br#"{"albumData": {"title": "Rome"}}"#.to_vec();
+        let best_meta = br#"{"albumData": {"title": "Best of 2020"}}"#.to_vec();
+        let entries: Vec<(String, Vec<u8>)> = vec![
+            (
+                format!("{root}/Photos from 2020/IMG_0001.JPG"),
+                b"jpeg".to_vec(),
+            ),
+            (
+                format!("{root}/Photos from 2020/IMG_0001.JPG.json"),
+                sidecar.clone(),
+            ),
+            (format!("{root}/Rome/metadata.json"), rome_meta),
+            (format!("{root}/Rome/IMG_0001.JPG"), b"jpeg".to_vec()),
+            (format!("{root}/Rome/IMG_0001.JPG.json"), sidecar.clone()),
+            (format!("{root}/Best/metadata.json"), best_meta),
+            (format!("{root}/Best/IMG_0001.JPG"), b"jpeg".to_vec()),
+            // Same name, different file
+            (format!("{root}/Best/IMG_0002.JPG"), b"jpeg".to_vec()),
+            (format!("{root}/Rome/IMG_0002.JPG"), b"other jpeg".to_vec()),
+        ];
+        let borrowed: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+        write_zip_contents(&zip_path, &borrowed);
+        let library = MockImporter::default();
+
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
 
-    let manifests: Vec<PathBuf> = std::fs::read_dir(&dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
-                .unwrap_or(false)
-        })
-        .collect();
+        assert_eq!(summary.duplicate_copies, 2);
+        assert_eq!(summary.imported, 3);
+        let imports = library.imports.borrow();
+        assert_eq!(
+            imports
+                .iter()
+                .filter(|(_, n, _)| n == "IMG_0001.JPG")
+                .count(),
+            1
+        );
+        assert_eq!(
+            imports
+                .iter()
+                .filter(|(_, n, _)| n == "IMG_0002.JPG")
+                .count(),
+            2
+        );
+        let mut adds = library.album_adds.borrow().clone();
+        adds.sort();
+        assert_eq!(
+            adds,
+            vec![
+                ("Best of 2020".to_string(), "IMG_0001.JPG".to_string()),
+                ("Best of 2020".to_string(), "IMG_0002.JPG".to_string()),
+                ("Rome".to_string(), "IMG_0001.JPG".to_string()),
+                ("Rome".to_string(), "IMG_0002.JPG".to_string()),
+            ]
+        );
 
-    if manifests.is_empty() {
-        display::print_info("No manifests found.");
-        return Ok(());
+        // Every copy is in the manifest, so none is imported on a re-run
+        let manifest =
+            manifest::read_manifest(&dir.path().join(".photoferry-manifest-takeout-dupes.json"))
+                .unwrap();
+        assert_eq!(manifest.imported.len(), 5);
+        let ids: HashSet<&str> = manifest
+            .imported
+            .iter()
+            .filter(|e| e.path.ends_with("IMG_0001.JPG"))
+            .map(|e| e.local_id.as_str())
+            .collect();
+        assert_eq!(ids.len(), 1);
     }
 
-    let access = importer::check_access()?;
-    ensure_full_photos_access(&access, "verify")?;
+    #[test]
+    fn same_name_and_size_with_different_contents_are_not_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip_contents(
+            &zip_path,
+            &[
+                (
+                    "Takeout/Google Photos/Photos from 2020/IMG_0001.JPG",
+                    b"jpeg",
+                ),
+                (
+                    "Takeout/Google Photos/Photos from 2021/IMG_0001.JPG",
+                    b"gepj",
+                ),
+            ],
+        );
+        let library = MockImporter::default();
+
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
 
-    let mut total_verified_ok = 0usize;
-    let mut total_missing = 0usize;
-    let mut total_wrong_date = 0usize;
-    let mut total_live_photo_pair_missing = 0usize;
-    let mut total_live_photo_fallback = 0usize;
+        assert_eq!(summary.duplicate_copies, 0);
+        assert_eq!(library.imports.borrow().len(), 2);
+    }
 
-    for manifest_path in &manifests {
-        let manifest = match manifest::read_manifest_strict(manifest_path) {
-            Ok(Some(m)) => m,
-            Ok(None) => {
-                display::print_warning(&format!("Could not read {:?}", manifest_path));
-                continue;
-            }
-            Err(e) => {
-                return Err(e.context(format!(
-                    "Refusing to verify with corrupt manifest {}",
-                    manifest_path.display()
-                )));
-            }
+    #[test]
+    fn preserve_depth_imports_photos_as_original_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+        let library = MockImporter::default();
+
+        let import = ImportArgs {
+            preserve_depth: true,
+            ..Default::default()
         };
+        process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(
+            library.imported("IMG_0001.JPG"),
+            Some(("resource", Some("2020-01-01T00:00:00Z".to_string())))
+        );
+        // Videos and Live Photos keep their own import paths
+        assert_eq!(library.imported("VID_0003.MP4").unwrap().0, "video");
+        assert_eq!(library.imported("IMG_0002.HEIC").unwrap().0, "live");
+    }
 
-        display::print_header(&format!("Verifying {}", manifest.zip));
-        display::print_info(&format!(
-            "Checking {} imported assets...",
-            manifest.imported.len()
+    #[test]
+    fn imports_are_self_checked_for_unstable_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let names: Vec<String> = (0..=SELF_CHECK_EVERY)
+            .map(|i| format!("Takeout/Google Photos/Photos from 2020/IMG_{i:04}.jpg"))
+            .collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), &b"jpeg"[..])).collect();
+        write_zip_contents(&zip_path, &entries);
+
+        let library = MockImporter::default();
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        // The first and the 101st
+        assert_eq!(library.verified.borrow().len(), 2);
+        assert_eq!(summary.unstable_ids, 0);
+
+        let library = MockImporter::default();
+        library.unstable_ids.set(true);
+        let manifest_dir = tempfile::tempdir().unwrap();
+        let summary = process_zip_streaming(
+            &zip_path,
+            manifest_dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        // Still recorded as imported, but flagged
+        assert_eq!(summary.imported, SELF_CHECK_EVERY + 1);
+        assert_eq!(summary.unstable_ids, 2);
+    }
+
+    #[test]
+    fn revoked_photos_access_stays_typed() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip_contents(
+            &zip_path,
+            &[(
+                "Takeout/Google Photos/Photos from 2020/IMG_0001.jpg",
+                b"jpeg",
+            )],
+        );
+
+        let library = MockImporter::default();
+        library.access_revoked.set(true);
+        let err = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap_err();
+        // Callers stop the run on this and exit with the permission code
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(ProcessError::PhotosAccess(_))
         ));
+        assert_eq!(Outcome::error_exit_code(&err), 2);
+        assert!(library.imports.borrow().is_empty());
+    }
 
-        let mut live_photo_paths = HashSet::new();
-        let zip_path = dir.join(&manifest.zip);
-        if zip_path.exists() {
-            match live_photo_paths_from_zip(&zip_path, &dir) {
-                Ok(paths) => live_photo_paths = paths,
-                Err(e) => display::print_warning(&format!(
-                    "Live Photo fallback scan failed for {}: {}",
-                    manifest.zip, e
-                )),
-            }
-        }
+    #[test]
+    fn date_albums_group_year_folder_media_by_month() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        let taken =
+            |timestamp: &str| format!(r#"{{"photoTakenTime": {{"timestamp": "{timestamp}"}}}}"#);
+        let (july, december) = (taken("1594000000"), taken("1608000000"));
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/july.jpg"), b"jpeg"),
+                (&format!("{year}/july.jpg.json"), july.as_bytes()),
+                (&format!("{year}/december.jpg"), b"jpeg"),
+                (&format!("{year}/december.jpg.json"), december.as_bytes()),
+                (&format!("{year}/undated.jpg"), b"jpeg"),
+                (
+                    "Takeout/Google Photos/Trip/metadata.json",
+                    br#"{"albumData": {"title": "Trip"}}"#,
+                ),
+                ("Takeout/Google Photos/Trip/beach.jpg", b"jpeg"),
+            ],
+        );
 
-        let ids: Vec<&str> = manifest
-            .imported
+        let library = MockImporter::default();
+        let import = ImportArgs {
+            date_albums: Some(DateAlbums::Month),
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(summary.imported, 4);
+        assert_eq!(summary.date_album_assigned, 2);
+        let imports = manifest_imports(dir.path(), &zip_path);
+        let album_of = |name: &str| {
+            imports
+                .iter()
+                .find(|f| f.path.ends_with(name))
+                .and_then(|f| f.album.clone())
+        };
+        assert_eq!(album_of("july.jpg").as_deref(), Some("2020-07"));
+        assert_eq!(album_of("december.jpg").as_deref(), Some("2020-12"));
+        assert_eq!(album_of("undated.jpg"), None);
+        // Real albums are untouched
+        assert_eq!(album_of("beach.jpg").as_deref(), Some("Trip"));
+        assert_eq!(
+            date_album_title(DateAlbums::Year, Some("2020-07-06T01:46:40Z")).as_deref(),
+            Some("2020")
+        );
+    }
+
+    #[test]
+    fn limit_albums_caps_album_creation() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+        for album in ["Alps", "Beach", "City"] {
+            let folder = format!("Takeout/Google Photos/{album}");
+            entries.push((
+                format!("{folder}/metadata.json"),
+                format!(r#"{{"albumData": {{"title": "{album}"}}}}"#).into_bytes(),
+            ));
+            entries.push((format!("{folder}/{album}.jpg"), b"jpeg".to_vec()));
+        }
+        let borrowed: Vec<(&str, &[u8])> = entries
             .iter()
-            .map(|e| e.local_id.as_str())
+            .map(|(n, c)| (n.as_str(), c.as_slice()))
             .collect();
-        let results = importer::verify_assets(&ids)?;
+        write_zip_contents(&zip_path, &borrowed);
+        let library = MockImporter::default();
 
-        let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
+        let import = ImportArgs {
+            limit_albums: Some(2),
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(summary.imported, 3);
+        assert_eq!(*library.albums.borrow(), vec!["Alps", "Beach"]);
+        assert_eq!(summary.album_limit_unassigned, 1);
+        let imports = manifest_imports(dir.path(), &zip_path);
+        let city = imports
             .iter()
-            .map(|r| (r.local_identifier.as_str(), r))
+            .find(|f| f.path.ends_with("City.jpg"))
+            .unwrap();
+        assert_eq!(city.album, None);
+    }
+
+    #[test]
+    fn verbose_success_due_prints_every_nth() {
+        assert!((1..=5).all(|n| verbose_success_due(n, None)));
+        let every = std::num::NonZeroUsize::new(3);
+        let printed: Vec<usize> = (1..=10)
+            .filter(|&n| verbose_success_due(n, every))
             .collect();
+        assert_eq!(printed, [3, 6, 9]);
+    }
 
-        let mut missing = vec![];
-        let mut wrong_date = vec![];
-        let mut live_pair_missing = vec![];
-        let mut live_photo_fallback = vec![];
+    #[test]
+    fn overlong_paths_extract_under_a_shortened_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let album = "Ä".repeat(150); // 300 bytes: over the 255-byte component limit
+        let long_name = format!("{}.jpg", "x".repeat(300));
+        let rel = format!("Google Photos/{album}/{long_name}");
+        write_zip_contents(&zip_path, &[(&format!("Takeout/{rel}"), b"jpeg")]);
+
+        let (short, shortened) = temp_extract_path(dir.path(), &rel);
+        assert!(shortened);
+        let short_name = short.file_name().unwrap().to_str().unwrap();
+        assert!(short_name.ends_with(".jpg") && short_name.len() < 255);
+        assert_eq!(
+            temp_extract_path(dir.path(), "Google Photos/Trip/a.jpg"),
+            (dir.path().join("Google Photos/Trip/a.jpg"), false)
+        );
 
-        for entry in &manifest.imported {
-            match result_map.get(entry.local_id.as_str()) {
-                None | Some(importer::AssetVerifyResult { found: false, .. }) => {
-                    missing.push(entry);
-                }
-                Some(result) => {
-                    if entry.is_live_photo == Some(true) && !result.has_paired_video {
-                        live_pair_missing.push(entry);
-                        continue;
-                    }
-                    if date_mismatch(entry.creation_date.as_deref(), result.creation_date.as_deref())
-                    {
-                        wrong_date.push((
-                            entry,
-                            result
-                                .creation_date
-                                .clone()
-                                .unwrap_or_else(|| "<missing>".to_string()),
-                        ));
-                        continue;
-                    }
-                    if entry.is_live_photo == Some(false)
-                        && live_photo_paths.contains(&entry.path)
-                    {
-                        live_photo_fallback.push(entry);
-                    }
-                    total_verified_ok += 1;
-                }
-            }
-        }
+        let library = MockImporter::default();
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        assert_eq!(summary.failed, 0, "{:?}", summary.failures);
+        assert_eq!(summary.shortened_paths, 1);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(manifest_imports(dir.path(), &zip_path)[0].path, rel);
+    }
 
-        for e in &missing {
-            display::print_error(&format!("MISSING: {} ({})", e.path, e.local_id));
-            total_missing += 1;
-        }
-        for (e, actual) in &wrong_date {
-            display::print_warning(&format!(
-                "DATE MISMATCH: {} — expected {} got {}",
-                e.path,
-                e.creation_date.as_deref().unwrap_or("?"),
-                actual
-            ));
-            total_wrong_date += 1;
-        }
-        for e in &live_pair_missing {
-            display::print_warning(&format!(
-                "LIVE PHOTO PAIR MISSING: {} ({})",
-                e.path, e.local_id
-            ));
-            total_live_photo_pair_missing += 1;
-        }
-        for e in &live_photo_fallback {
-            display::print_warning(&format!("LIVE PHOTO FELL BACK: {}", e.path));
-            total_live_photo_fallback += 1;
-        }
+    #[test]
+    fn timezone_from_gps_shifts_dates_with_a_known_zone() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        let sidecar = |lat: f64| {
+            format!(
+                r#"{{"photoTakenTime": {{"timestamp": "1577836800"}}, "geoData": {{"latitude": {lat}, "longitude": 139.7, "altitude": 0.0}}}}"#
+            )
+        };
+        let (tokyo, sydney) = (sidecar(35.6), sidecar(-33.8));
+        let no_gps = r#"{"photoTakenTime": {"timestamp": "1577836800"}}"#;
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/tokyo.jpg"), b"jpeg"),
+                (&format!("{year}/tokyo.jpg.json"), tokyo.as_bytes()),
+                (&format!("{year}/sydney.jpg"), b"jpeg"),
+                (&format!("{year}/sydney.jpg.json"), sydney.as_bytes()),
+                (&format!("{year}/plain.jpg"), b"jpeg"),
+                (&format!("{year}/plain.jpg.json"), no_gps.as_bytes()),
+            ],
+        );
 
-        display::print_info(&format!(
-            "Verified: {} | Missing: {} | Wrong date: {} | Live pair missing: {} | Live fallback: {}",
-            manifest.imported.len()
-                - missing.len()
-                - wrong_date.len()
-                - live_pair_missing.len(),
-            missing.len(),
-            wrong_date.len(),
-            live_pair_missing.len(),
-            live_photo_fallback.len()
-        ));
+        let library = MockImporter::default();
+        let import = ImportArgs {
+            timezone_from_gps: true,
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), true, false, &import, &library).unwrap();
+        assert_eq!(summary.dates_localized, 1);
+        assert_eq!(summary.dates_not_localized, 1);
+        let utc = "2020-01-01T00:00:00Z";
+        let tokyo_date = metadata::shift_to_local_time(utc, 9 * 3600, &chrono::Local).unwrap();
+        assert_eq!(library.imported("tokyo.jpg").unwrap().1, Some(tokyo_date));
+        assert_eq!(
+            library.imported("sydney.jpg").unwrap().1.as_deref(),
+            Some(utc)
+        );
+        assert_eq!(
+            library.imported("plain.jpg").unwrap().1.as_deref(),
+            Some(utc)
+        );
     }
 
-    println!();
-    display::print_header("Total");
-    display::print_info(&format!("Verified OK: {}", total_verified_ok));
-    if total_missing > 0 {
-        display::print_error(&format!("Missing: {}", total_missing));
-    }
-    if total_wrong_date > 0 {
-        display::print_warning(&format!("Wrong date: {}", total_wrong_date));
+    #[test]
+    fn gif_policy_converts_or_skips_animated_gifs() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        // Two 1x1 frames
+        let mut animated = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\x00\x00\x00\xff\xff\xff".to_vec();
+        for _ in 0..2 {
+            animated.extend([0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0, 2, 2, 0x44, 0x01, 0]);
+        }
+        animated.push(0x3B);
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/anim.gif"), &animated),
+                (&format!("{year}/plain.gif"), b"GIF89a"),
+            ],
+        );
+
+        let library = MockImporter::default();
+        let import = ImportArgs {
+            gif_policy: GifPolicy::Video,
+            ..Default::default()
+        };
+        // Its own manifest dir, so the dry run below starts fresh
+        let video_run = tempfile::tempdir().unwrap();
+        let summary =
+            process_zip_streaming(&zip_path, video_run.path(), false, false, &import, &library)
+                .unwrap();
+        assert_eq!(summary.animated_gifs_as_video, 1);
+        assert_eq!(library.imported("anim.gif.mov").unwrap().0, "video");
+        assert_eq!(library.imported("plain.gif").unwrap().0, "photo");
+        // Recorded under the GIF's own path, so re-runs skip it
+        assert!(
+            manifest_imports(video_run.path(), &zip_path)
+                .iter()
+                .any(|f| f.path.ends_with("anim.gif"))
+        );
+
+        let library = MockImporter::default();
+        let import = ImportArgs {
+            gif_policy: GifPolicy::Skip,
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), true, false, &import, &library).unwrap();
+        assert_eq!(summary.skipped_animated_gifs, 1);
+        assert!(zip_keep_reason(&summary).is_some_and(|r| r.contains("1 by --gif-policy skip")));
+        assert_eq!(summary.imported, 1);
+        assert!(library.imported("anim.gif").is_none());
     }
-    if total_live_photo_pair_missing > 0 {
-        display::print_warning(&format!(
-            "Live Photo pair missing: {}",
-            total_live_photo_pair_missing
-        ));
+
+    #[test]
+    fn dry_run_reads_zip_entries_instead_of_extracting() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        write_zip_contents(
+            &zip_path,
+            &[
+                (
+                    &format!("{year}/PXL_0001.MP.jpg"),
+                    b"\xff\xd8 GCamera:MotionPhoto=\"1\" \xff\xd9",
+                ),
+                (&format!("{year}/IMG_0002.jpg"), b"jpeg"),
+            ],
+        );
+
+        let library = MockImporter::default();
+        process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            true,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        assert_eq!(*library.on_disk.borrow(), vec![false, false]);
+        // Still recognised from the entry itself
+        assert_eq!(library.imported("PXL_0001.MP.jpg").unwrap().0, "motion");
+        assert_eq!(library.imported("IMG_0002.jpg").unwrap().0, "photo");
+
+        let library = MockImporter::default();
+        process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        assert_eq!(*library.on_disk.borrow(), vec![true, true]);
     }
-    if total_live_photo_fallback > 0 {
-        display::print_warning(&format!(
-            "Live Photo fallbacks (still photo only): {}",
-            total_live_photo_fallback
-        ));
+
+    #[test]
+    fn motion_photo_fallbacks_are_counted_apart_from_live_photos() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-dupes.zip");
+        write_zip_contents(
+            &zip_path,
+            &[(
+                "Takeout/Google Photos/Photos from 2020/PXL_0001.MP.jpg",
+                b"\xff\xd8 GCamera:MotionPhoto=\"1\" \xff\xd9",
+            )],
+        );
+        let library = MockImporter::default();
+        library.motion_fails.set(true);
+
+        let summary = process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            false,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        assert_eq!(summary.motion_photo_fallbacks, 1);
+        assert_eq!(summary.live_photo_fallbacks, 0);
+        assert_eq!(library.imported("PXL_0001.MP.jpg").unwrap().0, "photo");
+        let manifest =
+            manifest::read_manifest(&dir.path().join(".photoferry-manifest-takeout-dupes.json"))
+                .unwrap();
+        assert_eq!(manifest.imported[0].is_live_photo, Some(false));
+        assert!(manifest.live_photo_fallbacks.is_empty());
     }
-    if total_missing == 0 && total_wrong_date == 0 && total_live_photo_pair_missing == 0 {
-        display::print_success("All assets verified successfully");
+
+    #[test]
+    fn import_albums_only_skips_year_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+        let library = MockImporter::default();
+
+        let import = ImportArgs {
+            import_albums_only: true,
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        // Everything but the album's one photo is in `Photos from 2020`
+        assert_eq!(summary.imported, 1);
+        assert_eq!(
+            library.imported("IMG_0004.JPG"),
+            Some(("photo", Some("2020-01-06T00:00:00Z".to_string())))
+        );
+        assert_eq!(summary.skipped_year_folder, 8);
+        assert!(zip_keep_reason(&summary).is_some_and(|r| r.contains("8 by --import-albums-only")));
+        assert!(library.imported("IMG_0001.JPG").is_none());
     }
 
-    Ok(())
-}
+    #[test]
+    fn into_album_id_adds_everything_to_the_existing_album() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+        let library = MockImporter::default();
+        let album_id = importer::Importer::create_album(&library, "From Google").unwrap();
+
+        let missing = ImportArgs {
+            into_album_id: Some("album-9".to_string()),
+            ..Default::default()
+        };
+        let err = process_zip_streaming(&zip_path, dir.path(), false, false, &missing, &library)
+            .unwrap_err();
+        assert!(err.to_string().contains("no such album"), "{err}");
+        assert!(library.imports.borrow().is_empty());
+
+        let import = ImportArgs {
+            into_album_id: Some(album_id),
+            ..Default::default()
+        };
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(summary.imported, 7);
+        // No albums created from the Takeout folders
+        assert_eq!(*library.albums.borrow(), vec!["From Google".to_string()]);
+        let adds = library.album_adds.borrow();
+        assert_eq!(adds.len(), 7);
+        assert!(adds.iter().all(|(album, _)| album == "From Google"));
+        assert!(
+            manifest_imports(dir.path(), &zip_path)
+                .iter()
+                .all(|f| f.album.as_deref() == Some("From Google"))
+        );
+    }
 
-fn cmd_retry_missing(dir: &Path, verbose: bool) -> Result<()> {
-    let dir = expand_tilde(dir);
-    display::print_header(&format!("Retrying missing assets in {}", dir.display()));
+    #[test]
+    fn dry_run_runs_the_full_pipeline_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_synthetic_takeout(&zip_path);
+
+        let summary =
+            process_one_zip(&zip_path, dir.path(), true, false, &ImportArgs::default()).unwrap();
+        assert!(summary.simulated);
+        // Same outcome the mocked real run gets, trashed and paired video excluded
+        assert_eq!(summary.imported, 7);
+        assert_eq!(summary.dry_run.would_import, 9);
+        // Albums are "created" and added to, so routing runs as it would
+        let library = MockImporter::default();
+        process_zip_streaming(
+            &zip_path,
+            dir.path(),
+            true,
+            false,
+            &ImportArgs::default(),
+            &library,
+        )
+        .unwrap();
+        assert!(
+            library
+                .album_adds
+                .borrow()
+                .iter()
+                .any(|(album, _)| album == "Trip to Rome")
+        );
+        let dry: &dyn importer::Importer = &importer::DryRunImporter::default();
+        let album_id = dry.create_album("Trip to Rome").unwrap();
+        assert!(dry.add_to_album(&album_id, "dry-run-asset-1").unwrap());
+        assert!(
+            !dir.path()
+                .join(".photoferry-manifest-takeout-test.json")
+                .exists()
+        );
+        assert!(!dir.path().join(".photoferry-albums.json").exists());
+    }
 
-    let manifests: Vec<PathBuf> = std::fs::read_dir(&dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
-                .unwrap_or(false)
-        })
-        .collect();
+    #[test]
+    fn min_people_skips_photos_without_enough_tagged_people() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        let (family, family_json) = (
+            format!("{year}/family.jpg"),
+            format!("{year}/family.jpg.json"),
+        );
+        let (solo, solo_json) = (format!("{year}/solo.jpg"), format!("{year}/solo.jpg.json"));
+        let scenery = format!("{year}/scenery.jpg");
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&family, b"jpeg"),
+                (
+                    &family_json,
+                    br#"{"people": [{"name": "Ada"}, {"name": "Grace"}]}"#,
+                ),
+                (&solo, b"jpeg"),
+                (&solo_json, br#"{"people": [{"name": "Ada"}]}"#),
+                (&scenery, b"jpeg"),
+            ],
+        );
 
-    if manifests.is_empty() {
-        display::print_info("No manifests found.");
-        return Ok(());
+        let import = ImportArgs {
+            min_people: Some(2),
+            ..Default::default()
+        };
+        let library = MockImporter::default();
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        let imported: Vec<String> = manifest_imports(dir.path(), &zip_path)
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(imported, vec!["Google Photos/Photos from 2020/family.jpg"]);
+        assert_eq!(summary.skipped_by_people, 2);
+        assert!(zip_keep_reason(&summary).is_some_and(|r| r.contains("2 by --min-people")));
     }
 
-    let access = importer::check_access()?;
-    ensure_full_photos_access(&access, "retry-missing verification")?;
-
-    let mut total_reimported = 0usize;
-    let mut total_retry_failed = 0usize;
-    let mut total_missing_unresolved = 0usize;
+    #[test]
+    fn favorites_only_needs_a_strong_favorited_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        let starred = br#"{"title": "starred.jpg", "favorited": true}"#;
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/starred.jpg"), b"jpeg"),
+                (&format!("{year}/starred.jpg.json"), starred),
+                (&format!("{year}/plain.jpg"), b"jpeg"),
+                (
+                    &format!("{year}/plain.jpg.json"),
+                    br#"{"favorited": false}"#,
+                ),
+                (&format!("{year}/bare.jpg"), b"jpeg"),
+            ],
+        );
 
-    for manifest_path in &manifests {
-        let manifest = match manifest::read_manifest_strict(manifest_path) {
-            Ok(Some(m)) => m,
-            Ok(None) => {
-                display::print_warning(&format!("Could not read {:?}", manifest_path));
-                continue;
-            }
-            Err(e) => {
-                return Err(e.context(format!(
-                    "Refusing retry-missing with corrupt manifest {}",
-                    manifest_path.display()
-                )));
-            }
+        let import = ImportArgs {
+            favorites_only: true,
+            ..Default::default()
         };
-        if manifest.imported.is_empty() {
-            continue;
-        }
-
-        let ids: Vec<&str> = manifest
-            .imported
-            .iter()
-            .map(|e| e.local_id.as_str())
-            .collect();
-        let results = importer::verify_assets(&ids)?;
-        let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
-            .iter()
-            .map(|r| (r.local_identifier.as_str(), r))
-            .collect();
-        let retry_entries: Vec<&manifest::ManifestEntry> = manifest
-            .imported
-            .iter()
-            .filter(|entry| match result_map.get(entry.local_id.as_str()) {
-                None | Some(importer::AssetVerifyResult { found: false, .. }) => true,
-                Some(result) => {
-                    if entry.is_live_photo == Some(true) && !result.has_paired_video {
-                        return true;
-                    }
-                    date_mismatch(entry.creation_date.as_deref(), result.creation_date.as_deref())
-                }
-            })
+        let library = MockImporter::default();
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        let imported: Vec<String> = manifest_imports(dir.path(), &zip_path)
+            .into_iter()
+            .map(|f| f.path)
             .collect();
+        assert_eq!(imported, vec!["Google Photos/Photos from 2020/starred.jpg"]);
+        assert_eq!(summary.skipped_not_favorite, 2);
+        // The non-favorites exist only in the zip: `download` must keep it
+        assert_eq!(
+            zip_keep_reason(&summary).as_deref(),
+            Some("Files left out of the import (2 by --favorites-only) — zip kept")
+        );
+        let everything =
+            process_one_zip(&zip_path, dir.path(), true, false, &ImportArgs::default()).unwrap();
+        assert_eq!(zip_keep_reason(&everything), None);
+    }
 
-        if retry_entries.is_empty() {
-            display::print_info(&format!("{}: no retry-needed assets", manifest.zip));
-            continue;
+    #[test]
+    fn parse_runtime_accepts_unit_suffixes() {
+        assert_eq!(parse_runtime("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_runtime("90m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_runtime("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_runtime("1d"), Ok(Duration::from_secs(86_400)));
+        for bad in ["", "90", "0m", "h", "1x", "1.5h"] {
+            assert!(parse_runtime(bad).is_err(), "{bad:?} should be rejected");
         }
+    }
 
-        let zip_path = dir.join(&manifest.zip);
-        if !zip_path.exists() {
-            display::print_warning(&format!(
-                "{}: {} missing assets but zip not found at {}",
-                manifest.zip,
-                retry_entries.len(),
-                zip_path.display()
-            ));
-            total_missing_unresolved += retry_entries.len();
-            continue;
+    #[test]
+    fn parse_sample_pct_accepts_only_zero_to_a_hundred() {
+        assert_eq!(parse_sample_pct("5"), Ok(5.0));
+        assert_eq!(parse_sample_pct("0.5"), Ok(0.5));
+        assert_eq!(parse_sample_pct("100"), Ok(100.0));
+        for bad in ["0", "-1", "100.1", "NaN", "inf", "five", ""] {
+            assert!(parse_sample_pct(bad).is_err(), "{bad:?} should be rejected");
         }
+    }
 
-        display::print_header(&format!(
-            "{}: retrying {} assets",
-            manifest.zip,
-            retry_entries.len()
-        ));
+    #[test]
+    fn max_runtime_stops_before_the_next_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/a.jpg"), b"jpeg"),
+                (&format!("{year}/b.jpg"), b"jpeg"),
+            ],
+        );
+        let import = ImportArgs {
+            deadline: Some(std::time::Instant::now()),
+            ..Default::default()
+        };
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.deadline_remaining, 2);
+    }
 
-        let extract_dir = dir.join(format!(
-            ".photoferry-retry-extract-{}",
-            zip_path.file_stem().unwrap_or_default().to_string_lossy()
-        ));
-        if extract_dir.exists() {
-            std::fs::remove_dir_all(&extract_dir)?;
-        }
-        std::fs::create_dir_all(&extract_dir)?;
+    #[test]
+    fn burst_policy_cover_only_imports_one_frame_per_burst() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2019";
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/IMG_20190101_120000_BURST001.jpg"), b"one"),
+                (
+                    &format!("{year}/IMG_20190101_120000_BURST002_COVER.jpg"),
+                    b"two",
+                ),
+                (
+                    &format!("{year}/IMG_20190101_120000_BURST003.jpg"),
+                    b"three",
+                ),
+                (&format!("{year}/IMG_5678.jpg"), b"other"),
+            ],
+        );
 
-        let content_root = match takeout::extract_zip(&zip_path, &extract_dir) {
-            Ok(root) => root,
-            Err(e) => {
-                let _ = std::fs::remove_dir_all(&extract_dir);
-                return Err(e.context(format!("Failed to extract {}", zip_path.display())));
-            }
-        };
-        let inventory = match takeout::scan_directory(&content_root, &takeout::ScanOptions::default()) {
-            Ok(inv) => inv,
-            Err(e) => {
-                let _ = std::fs::remove_dir_all(&extract_dir);
-                return Err(e.context(format!(
-                    "Failed to scan extracted content for {}",
-                    zip_path.display()
-                )));
-            }
+        let import = ImportArgs {
+            burst_policy: BurstPolicy::CoverOnly,
+            ..Default::default()
         };
+        let library = MockImporter::default();
+        let summary =
+            process_zip_streaming(&zip_path, dir.path(), false, false, &import, &library).unwrap();
+        assert_eq!(summary.skipped_burst_frames, 2);
+        assert!(
+            zip_keep_reason(&summary).is_some_and(|r| r.contains("2 by --burst-policy cover-only"))
+        );
+        let imports = manifest_imports(dir.path(), &zip_path);
+        let mut imported: Vec<&str> = imports.iter().map(|f| f.path.as_str()).collect();
+        imported.sort_unstable();
+        assert_eq!(
+            imported,
+            vec![
+                "Google Photos/Photos from 2019/IMG_20190101_120000_BURST002_COVER.jpg",
+                "Google Photos/Photos from 2019/IMG_5678.jpg",
+            ]
+        );
+    }
 
-        let mut by_relative: HashMap<String, takeout::MediaFile> = HashMap::new();
-        for file in &inventory.files {
-            let rel = file
-                .path
-                .strip_prefix(&content_root)
-                .unwrap_or(&file.path)
-                .to_string_lossy()
-                .to_string();
-            by_relative.insert(rel, file.clone());
-        }
+    #[test]
+    fn edited_policy_drops_one_half_and_keeps_the_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2019";
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/a.jpg"), b"original"),
+                (&format!("{year}/a-edited.jpg"), b"edited"),
+                (&format!("{year}/b.jpg"), b"other"),
+            ],
+        );
 
-        let mut retry_files = Vec::new();
-        let mut unresolved = 0usize;
-        for entry in &retry_entries {
-            if let Some(file) = by_relative.get(&entry.path) {
-                retry_files.push(file.clone());
-            } else {
-                display::print_warning(&format!(
-                    "Missing in zip content (cannot retry): {}",
-                    entry.path
-                ));
-                unresolved += 1;
-            }
-        }
+        let import = ImportArgs {
+            edited_policy: EditedPolicy::Edited,
+            ..Default::default()
+        };
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        assert_eq!(summary.skipped_by_edited_policy, 1);
+        assert_eq!(summary.imported, 2);
+        assert!(zip_keep_reason(&summary).is_some_and(|r| r.contains("1 by --edited-policy")));
+    }
 
-        if retry_files.is_empty() {
-            total_missing_unresolved += retry_entries.len();
-            let _ = std::fs::remove_dir_all(&extract_dir);
-            continue;
-        }
+    #[test]
+    fn classify_zip_entry_sorts_entries_by_kind() {
+        let root = takeout::ContentRoot {
+            prefix: "Takeout/Google Photos/".to_string(),
+            other_products: true,
+        };
+        let skip_ext = vec!["gif".to_string()];
+        let kind = |name: &str| match classify_zip_entry(name, &root, &skip_ext) {
+            Some(ZipEntryClass::Entry(entry)) => Some(entry.kind),
+            Some(ZipEntryClass::OtherProduct) => None,
+            None => panic!("{name} has no file name"),
+        };
+        assert_eq!(
+            kind("Takeout/Google Photos/A/IMG.JPG"),
+            Some(IndexEntryKind::Photo)
+        );
+        assert_eq!(
+            kind("Takeout/Google Photos/A/clip.mp4"),
+            Some(IndexEntryKind::Video)
+        );
+        assert_eq!(
+            kind("Takeout/Google Photos/A/IMG.JPG.json"),
+            Some(IndexEntryKind::Json)
+        );
+        assert_eq!(
+            kind("Takeout/Google Photos/A/anim.gif"),
+            Some(IndexEntryKind::Excluded)
+        );
+        assert_eq!(
+            kind("Takeout/Google Photos/A/notes.xyz"),
+            Some(IndexEntryKind::Unknown)
+        );
+        assert_eq!(kind("Takeout/Drive/doc.pdf"), None);
 
-        let retry_albums: Vec<String> = retry_files
-            .iter()
-            .filter_map(|f| f.album.clone())
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        let retry_inventory = takeout::TakeoutInventory {
-            files: retry_files,
-            albums: retry_albums,
-            stats: Default::default(),
+        let Some(ZipEntryClass::Entry(entry)) =
+            classify_zip_entry("Takeout/Google Photos/A/IMG.JPG", &root, &skip_ext)
+        else {
+            panic!("expected an entry");
         };
+        assert_eq!(entry.relative, "A/IMG.JPG");
+        assert_eq!(entry.dir_key, "A");
+        assert_eq!(entry.filename, "IMG.JPG");
+        assert_eq!(entry.ext, "jpg");
+    }
 
-        let summary = import_inventory(&retry_inventory, verbose);
-        print_import_summary(&summary);
+    #[test]
+    fn dry_run_detail_lists_sidecar_and_metadata() {
+        let metadata = importer::PhotoMetadata {
+            creation_date: Some("2020-01-01T00:00:00Z".to_string()),
+            latitude: Some(51.5),
+            longitude: Some(-0.125),
+            altitude: None,
+            title: None,
+            description: None,
+            is_favorite: Some(true),
+        };
+        assert_eq!(
+            dry_run_detail(
+                Some(("A/IMG.jpg.json", sidecar::SidecarMatchStrength::Fuzzy)),
+                Some(&metadata),
+                Some("Trip")
+            ),
+            "sidecar A/IMG.jpg.json (fuzzy) | date 2020-01-01T00:00:00Z | GPS 51.50000,-0.12500 | favorite | album \"Trip\""
+        );
+        assert_eq!(dry_run_detail(None, None, None), "no sidecar | date none");
+    }
 
-        let new_imported: Vec<(String, String, Option<String>, bool)> = summary
-            .imported
-            .iter()
-            .map(|file| {
-                (
-                    file.path
-                        .strip_prefix(&content_root)
-                        .unwrap_or(&file.path)
-                        .to_string_lossy()
-                        .to_string(),
-                    file.local_id.clone(),
-                    file.creation_date.clone(),
-                    file.is_live_photo,
-                )
-            })
-            .collect();
-        let new_failed: Vec<(String, String)> = summary
-            .failed
-            .iter()
-            .map(|file| {
-                let p = std::path::Path::new(&file.path);
-                (
-                    p.strip_prefix(&content_root)
-                        .unwrap_or(p)
-                        .to_string_lossy()
-                        .to_string(),
-                    file.error.clone(),
-                )
-            })
-            .collect();
-        manifest::merge_and_write(
-            manifest_path,
-            &manifest.zip,
-            &new_imported,
-            &new_failed,
-            &[],
-        )?;
+    #[test]
+    fn discover_sidecar_metadata_reads_the_matching_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("IMG_0001.jpg");
+        std::fs::write(&photo, b"jpeg").unwrap();
+        std::fs::write(
+            dir.path().join("IMG_0001.jpg.supplemental-metadata.json"),
+            r#"{"photoTakenTime": {"timestamp": "1577836800"}, "favorited": true}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("IMG_0002.jpg.json"), "{}").unwrap();
 
-        total_reimported += summary.imported.len();
-        total_retry_failed += summary.failed.len();
-        total_missing_unresolved += unresolved;
-        std::fs::remove_dir_all(&extract_dir)?;
+        let (found, parsed) = discover_sidecar_metadata(&photo).unwrap().unwrap();
+        assert_eq!(
+            found.path,
+            dir.path().join("IMG_0001.jpg.supplemental-metadata.json")
+        );
+        assert_eq!(found.strength, sidecar::SidecarMatchStrength::Strong);
+        let meta = parsed.unwrap();
+        assert_eq!(meta.creation_date.as_deref(), Some("2020-01-01T00:00:00Z"));
+        assert_eq!(meta.is_favorite, Some(true));
+
+        let lonely = dir.path().join("IMG_0003.jpg");
+        std::fs::write(&lonely, b"jpeg").unwrap();
+        assert!(discover_sidecar_metadata(&lonely).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_report_refuses_to_append_to_unrelated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let unknown = [takeout::UnknownFile {
+            path: PathBuf::from("Photos from 2020/x.abc"),
+            ext: "abc".to_string(),
+            size_bytes: 3,
+        }];
+
+        let report = dir.path().join("unknown.csv");
+        write_unknown_report(&report, "takeout-1.zip", &unknown, false).unwrap();
+        write_unknown_report(&report, "takeout-2.zip", &unknown, false).unwrap();
+        let text = std::fs::read_to_string(&report).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.starts_with(UNKNOWN_REPORT_HEADER));
+
+        let other = dir.path().join("budget.csv");
+        std::fs::write(&other, "month,amount\njan,10\n").unwrap();
+        let err = write_unknown_report(&other, "takeout-1.zip", &unknown, false).unwrap_err();
+        assert!(err.to_string().contains("--force-unknown-report"));
+        assert_eq!(
+            std::fs::read_to_string(&other).unwrap(),
+            "month,amount\njan,10\n"
+        );
+        write_unknown_report(&other, "takeout-1.zip", &unknown, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&other).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn duplicate_zip_claims_lists_every_manifest_claiming_a_zip() {
+        let claims = vec![
+            (
+                "takeout-1.zip".to_string(),
+                PathBuf::from(".photoferry-manifest-takeout-1.json"),
+            ),
+            (
+                "takeout-2.zip".to_string(),
+                PathBuf::from(".photoferry-manifest-takeout-2.json"),
+            ),
+            (
+                "takeout-1.zip".to_string(),
+                PathBuf::from(".photoferry-manifest-takeout-1-copy.json"),
+            ),
+        ];
+        assert_eq!(
+            duplicate_zip_claims(&claims),
+            vec![(
+                "takeout-1.zip",
+                vec![
+                    Path::new(".photoferry-manifest-takeout-1.json"),
+                    Path::new(".photoferry-manifest-takeout-1-copy.json"),
+                ]
+            )]
+        );
+        assert!(duplicate_zip_claims(&claims[..2]).is_empty());
+    }
+
+    #[test]
+    fn icloud_pending_message_only_when_uploads_are_outstanding() {
+        let status = |available, synced, pending| importer::ICloudUploadStatus {
+            available,
+            synced,
+            pending,
+        };
+        let message = icloud_pending_message(&status(true, 8, 2)).unwrap();
+        assert!(
+            message.starts_with("2 of 10 imports not in iCloud yet"),
+            "{message}"
+        );
+        assert_eq!(icloud_pending_message(&status(true, 10, 0)), None);
+        // Pre-macOS 12: no signal, so no warning
+        assert_eq!(icloud_pending_message(&status(false, 0, 10)), None);
+    }
+
+    #[test]
+    fn live_still_missing_suppresses_verify_success() {
+        assert!(verify_success_line(verify_problem_total(0, 0, 0, 0, 0), false).is_some());
+        let still_missing = verify_problem_total(0, 0, 0, 1, 0);
+        assert_eq!(still_missing, 1);
+        assert_eq!(verify_success_line(still_missing, false), None);
+        assert_eq!(verify_success_line(still_missing, true), None);
+    }
+
+    #[test]
+    fn verify_in_batches_advances_progress_and_stops_on_errors() {
+        let ids: Vec<String> = (0..1200).map(|i| format!("id-{i}")).collect();
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let pb = ProgressBar::hidden();
+        let mut calls = 0;
+        let (results, unverifiable) = verify_in_batches(&ids, &pb, |batch| {
+            calls += 1;
+            // Isolated rejects are reported per asset
+            let (rejected, verified) = batch.split_at(if calls == 2 { 1 } else { 0 });
+            Ok((
+                verified.iter().map(|id| id.to_string()).collect(),
+                rejected.iter().map(|id| id.to_string()).collect(),
+            ))
+        })
+        .unwrap();
+        assert_eq!(calls, 3);
+        assert_eq!(pb.position(), 1200);
+        assert_eq!(results.len(), 1199);
+        assert_eq!(unverifiable, vec!["id-500".to_string()]);
+
+        // A batch failing outright (e.g. access lost mid-run) is an error,
+        // even after earlier batches verified
+        let mut calls = 0;
+        let pb = ProgressBar::hidden();
+        let failed = verify_in_batches(&ids, &pb, |batch| {
+            calls += 1;
+            if calls == 2 {
+                anyhow::bail!("no access");
+            }
+            Ok((
+                batch.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+                Vec::new(),
+            ))
+        });
+        let err = failed.unwrap_err();
+        assert!(format!("{err:#}").contains("after 500 of 1200 assets: no access"));
+        assert_eq!(calls, 2);
     }
 
-    println!();
-    display::print_header("Retry missing summary");
-    display::print_info(&format!("Re-imported: {}", total_reimported));
-    if total_retry_failed > 0 {
-        display::print_warning(&format!("Retry import failures: {}", total_retry_failed));
-    }
-    if total_missing_unresolved > 0 {
-        display::print_warning(&format!(
-            "Still unresolved missing assets: {}",
-            total_missing_unresolved
-        ));
+    #[test]
+    fn verify_problem_serializes_for_export() {
+        let entry = manifest::ManifestEntry {
+            path: "Photos from 2020/IMG_0001.JPG".to_string(),
+            local_id: "ABC/L0/001".to_string(),
+            creation_date: None,
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        };
+        let problem = VerifyProblem::new(
+            "takeout-1.zip",
+            &entry,
+            VerifyProblemReason::LivePairMissing,
+        );
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            serde_json::json!({
+                "zip": "takeout-1.zip",
+                "path": "Photos from 2020/IMG_0001.JPG",
+                "local_id": "ABC/L0/001",
+                "reason": "live_pair_missing",
+            })
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn import_time_label_flags_slow_imports() {
+        assert_eq!(import_time_label(Duration::from_millis(250)), "(250 ms)");
+        assert_eq!(
+            import_time_label(Duration::from_secs(12)),
+            "(12000 ms, slow)"
+        );
+    }
 
-fn cmd_retry_live_photo_fallbacks(dir: &Path, verbose: bool) -> Result<()> {
-    let dir = expand_tilde(dir);
-    display::print_header(&format!(
-        "Retrying Live Photo fallbacks in {}",
-        dir.display()
-    ));
+    #[test]
+    fn strict_metadata_fails_media_with_unparseable_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        let year = "Takeout/Google Photos/Photos from 2020";
+        write_zip_contents(
+            &zip_path,
+            &[
+                (&format!("{year}/good.jpg"), b"jpeg"),
+                (
+                    &format!("{year}/good.jpg.json"),
+                    &sidecar_json(1_577_836_800, false),
+                ),
+                (&format!("{year}/bad.jpg"), b"jpeg"),
+                (&format!("{year}/bad.jpg.json"), b"{\"photoTakenTime\": "),
+            ],
+        );
 
-    let manifests: Vec<PathBuf> = std::fs::read_dir(&dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with(".photoferry-manifest-") && n.ends_with(".json"))
-                .unwrap_or(false)
-        })
-        .collect();
+        let lenient =
+            process_one_zip(&zip_path, dir.path(), true, false, &ImportArgs::default()).unwrap();
+        assert_eq!(lenient.imported, 2);
+        assert_eq!(lenient.unparseable_sidecars, 1);
 
-    if manifests.is_empty() {
-        display::print_info("No manifests found.");
-        return Ok(());
+        let import = ImportArgs {
+            strict_metadata: true,
+            ..Default::default()
+        };
+        let strict = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        assert_eq!(strict.imported, 1);
+        assert_eq!(strict.unparseable_sidecars, 1);
+        assert_eq!(strict.failed, 1);
+        assert_eq!(
+            strict.failures[0].path,
+            "Google Photos/Photos from 2020/bad.jpg"
+        );
+        assert!(strict.failures[0].error.starts_with("Unparseable sidecar"));
     }
 
-    let access = importer::check_access()?;
-    ensure_full_photos_access(&access, "retry-live-photo-fallbacks")?;
+    #[test]
+    fn strict_extensions_ignores_takeout_bookkeeping_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/archive_browser.html",
+                "Takeout/Google Photos/Photos from 2024/a.jpg",
+            ],
+        );
 
-    let mut total_reimported = 0usize;
-    let mut total_failed = 0usize;
-    let mut total_unresolved = 0usize;
+        let import = ImportArgs {
+            strict_extensions: true,
+            ..Default::default()
+        };
+        assert!(process_one_zip(&zip_path, dir.path(), true, false, &import).is_ok());
+    }
 
-    for manifest_path in &manifests {
-        let mut manifest = match manifest::read_manifest_strict(manifest_path) {
-            Ok(Some(m)) => m,
-            Ok(None) => {
-                display::print_warning(&format!("Could not read {:?}", manifest_path));
-                continue;
-            }
-            Err(e) => {
-                return Err(e.context(format!(
-                    "Refusing retry-live-photo-fallbacks with corrupt manifest {}",
-                    manifest_path.display()
-                )));
+    #[test]
+    fn note_earliest_in_album_picks_oldest_dated_asset() {
+        let imported: Vec<(&str, Option<&str>, Option<&str>)> = vec![
+            ("b", Some("Trip"), Some("2021-06-02T10:00:00Z")),
+            ("a", Some("Trip"), Some("2021-06-01T09:00:00Z")),
+            ("undated", Some("Trip"), None),
+            ("c", Some("Party"), Some("2022-01-01T00:00:00Z")),
+            ("loose", None, Some("2000-01-01T00:00:00Z")),
+            // Compared as instants, not strings: 09:00Z is first, and a
+            // whole second comes before the same second plus a fraction
+            ("utc", Some("Dinner"), Some("2021-06-01T10:30:00Z")),
+            ("offset", Some("Dinner"), Some("2021-06-01T17:00:00+08:00")),
+            ("bad", Some("Dinner"), Some("not-a-date")),
+            ("frac", Some("Burst"), Some("2021-06-01T10:00:00.500Z")),
+            ("whole", Some("Burst"), Some("2021-06-01T10:00:00Z")),
+        ];
+
+        let mut earliest = BTreeMap::new();
+        for (local_id, album, date) in imported {
+            if let Some(album) = album {
+                note_earliest_in_album(&mut earliest, album, date, local_id);
             }
+        }
+        let key_photo = |album: &str| earliest[album].1.as_str();
+        assert_eq!(earliest.len(), 4);
+        assert_eq!(key_photo("Trip"), "a");
+        assert_eq!(key_photo("Party"), "c");
+        assert_eq!(key_photo("Dinner"), "offset");
+        assert_eq!(key_photo("Burst"), "whole");
+    }
+
+    #[test]
+    fn dump_index_records_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/archive_browser.html",
+                "Takeout/Google Photos/Photos from 2024/a.jpg",
+                "Takeout/Google Photos/Photos from 2024/a.jpg.json",
+                "Takeout/Google Photos/Photos from 2024/b.mp4",
+                "Takeout/Google Photos/Photos from 2024/c.xyz",
+            ],
+        );
+        let import = ImportArgs {
+            // Dumping must not be blocked by the strict check
+            strict_extensions: true,
+            dump_index: true,
+            ..Default::default()
         };
 
-        if manifest.live_photo_fallbacks.is_empty() {
-            continue;
-        }
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        let kinds: Vec<(&str, IndexEntryKind, bool)> = summary
+            .index
+            .iter()
+            .map(|e| (e.path.as_str(), e.kind, e.should_import))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("archive_browser.html", IndexEntryKind::Bookkeeping, false),
+                (
+                    "Google Photos/Photos from 2024/a.jpg",
+                    IndexEntryKind::Photo,
+                    true
+                ),
+                (
+                    "Google Photos/Photos from 2024/a.jpg.json",
+                    IndexEntryKind::Json,
+                    false
+                ),
+                (
+                    "Google Photos/Photos from 2024/b.mp4",
+                    IndexEntryKind::Video,
+                    true
+                ),
+                (
+                    "Google Photos/Photos from 2024/c.xyz",
+                    IndexEntryKind::Unknown,
+                    false
+                ),
+            ]
+        );
+        assert_eq!(summary.index[1].dir, "Google Photos/Photos from 2024");
+    }
 
-        let zip_path = dir.join(&manifest.zip);
-        if !zip_path.exists() {
-            display::print_warning(&format!(
-                "{}: {} live photo fallbacks but zip not found at {}",
-                manifest.zip,
-                manifest.live_photo_fallbacks.len(),
-                zip_path.display()
-            ));
-            total_unresolved += manifest.live_photo_fallbacks.len();
-            continue;
-        }
+    #[test]
+    fn split_export_uses_photos_folder_as_content_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/archive_browser.html",
+                "Takeout/Drive/My Drive/scan.jpg",
+                "Takeout/Google Photos/Photos from 2020/a.jpg",
+                "Takeout/Google Photos/Photos from 2020/a.jpg.json",
+            ],
+        );
+        let import = ImportArgs {
+            dump_index: true,
+            ..Default::default()
+        };
 
-        display::print_header(&format!(
-            "{}: retrying {} live photo fallbacks",
-            manifest.zip,
-            manifest.live_photo_fallbacks.len()
-        ));
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        let paths: Vec<&str> = summary.index.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["Photos from 2020/a.jpg", "Photos from 2020/a.jpg.json"]
+        );
+        assert!(crate::takeout::is_year_folder(Path::new(
+            &summary.index[0].dir
+        )));
+    }
 
-        let extract_dir = dir.join(format!(
-            ".photoferry-live-retry-extract-{}",
-            zip_path.file_stem().unwrap_or_default().to_string_lossy()
-        ));
-        if extract_dir.exists() {
-            std::fs::remove_dir_all(&extract_dir)?;
-        }
-        std::fs::create_dir_all(&extract_dir)?;
+    #[test]
+    fn skip_ext_excludes_known_media_without_counting_unknown() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/Google Photos/Photos from 2024/a.jpg",
+                "Takeout/Google Photos/Photos from 2024/b.GIF",
+                "Takeout/Google Photos/Photos from 2024/c.dng",
+            ],
+        );
+        let mut import = ImportArgs {
+            strict_extensions: true,
+            skip_ext: vec!["gif".to_string(), ".DNG".to_string()],
+            ..Default::default()
+        };
+        // Excluded files don't trip --strict-extensions
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        assert_eq!(summary.skipped_by_ext, 2);
+        assert!(zip_keep_reason(&summary).is_some_and(|r| r.contains("2 by --skip-ext")));
+
+        import.dump_index = true;
+        let summary = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap();
+        let kinds: Vec<(IndexEntryKind, bool)> = summary
+            .index
+            .iter()
+            .map(|e| (e.kind, e.should_import))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (IndexEntryKind::Photo, true),
+                (IndexEntryKind::Excluded, false),
+                (IndexEntryKind::Excluded, false),
+            ]
+        );
+    }
 
-        let content_root = match takeout::extract_zip(&zip_path, &extract_dir) {
-            Ok(root) => root,
-            Err(e) => {
-                let _ = std::fs::remove_dir_all(&extract_dir);
-                return Err(e.context(format!("Failed to extract {}", zip_path.display())));
-            }
+    #[test]
+    fn count_live_pairs_counts_per_directory() {
+        let entry = |name: &str| ZipEntry {
+            index: 0,
+            relative_path: name.to_string(),
+            filename: name.to_string(),
+            size: 0,
+            crc32: 0,
+            should_import: true,
         };
-        let inventory = match takeout::scan_directory(&content_root, &takeout::ScanOptions::default()) {
-            Ok(inv) => inv,
-            Err(e) => {
-                let _ = std::fs::remove_dir_all(&extract_dir);
-                return Err(e.context(format!(
-                    "Failed to scan extracted content for {}",
-                    zip_path.display()
-                )));
-            }
+        let group = |names: &[&str]| ZipDirGroup {
+            media: names.iter().map(|n| entry(n)).collect(),
+            json: Vec::new(),
         };
+        let dirs = std::collections::HashMap::from([
+            (
+                "Photos from 2024".to_string(),
+                group(&["IMG_1.HEIC", "IMG_1.MOV", "IMG_2.JPG", "IMG_3.MP4"]),
+            ),
+            // Same stem in another directory is not a pair
+            (
+                "Trip".to_string(),
+                group(&["IMG_2.MOV", "IMG_4.HEIC", "img_4.mov"]),
+            ),
+        ]);
+        assert_eq!(count_live_pairs(&dirs), 2);
+    }
 
-        let mut by_relative: HashMap<String, takeout::MediaFile> = HashMap::new();
-        for file in &inventory.files {
-            let rel = file
-                .path
-                .strip_prefix(&content_root)
-                .unwrap_or(&file.path)
-                .to_string_lossy()
-                .to_string();
-            by_relative.insert(rel, file.clone());
-        }
-
-        let mut resolved_paths = HashSet::new();
-        let mut updated_imports: HashMap<String, String> = HashMap::new();
+    #[test]
+    fn dry_run_diffs_zip_against_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/Google Photos/Trip/done.jpg",
+                "Takeout/Google Photos/Trip/failed.jpg",
+                "Takeout/Google Photos/Trip/new.jpg",
+                "Takeout/Google Photos/Trip/new.mp4",
+            ],
+        );
+        manifest::write_manifest(
+            &dir.path().join(".photoferry-manifest-takeout-test.json"),
+            "takeout-test.zip",
+            &[manifest::ManifestEntry {
+                path: "Google Photos/Trip/done.jpg".to_string(),
+                local_id: "A".to_string(),
+                creation_date: None,
+                is_live_photo: None,
+                album: None,
+                sidecar_path: None,
+            }],
+            &[manifest::ManifestFailure {
+                path: "Google Photos/Trip/failed.jpg".to_string(),
+                error: "boom".to_string(),
+            }],
+            &[],
+        )
+        .unwrap();
 
-        for fallback in &manifest.live_photo_fallbacks {
-            let Some(photo_file) = by_relative.get(&fallback.photo_path) else {
-                display::print_warning(&format!(
-                    "Missing photo in zip content: {}",
-                    fallback.photo_path
-                ));
-                total_unresolved += 1;
-                continue;
-            };
-            let video_abs = content_root.join(&fallback.video_path);
-            if !video_abs.exists() {
-                display::print_warning(&format!(
-                    "Missing video in zip content: {}",
-                    fallback.video_path
-                ));
-                total_unresolved += 1;
-                continue;
+        let summary =
+            process_one_zip(&zip_path, dir.path(), true, false, &ImportArgs::default()).unwrap();
+        assert_eq!(
+            summary.dry_run,
+            DryRunCounts {
+                would_import: 2,
+                already_done: 1,
+                would_retry_failed: 1,
             }
+        );
 
-            let photo_abs = &photo_file.path;
-            let import_result = importer::import_live_photo(
-                photo_abs.to_str().unwrap_or_default(),
-                video_abs.to_str().unwrap_or_default(),
-                photo_file.metadata.as_ref(),
-            );
-
-            match import_result {
-                Ok(result) if result.success => {
-                    total_reimported += 1;
-                    resolved_paths.insert(fallback.photo_path.clone());
-                    if let Some(local_id) = result.local_identifier {
-                        updated_imports.insert(fallback.photo_path.clone(), local_id);
-                    }
-                    if verbose {
-                        display::print_success(&format!(
-                            "Re-imported Live Photo: {}",
-                            fallback.photo_path
-                        ));
-                    }
-                }
-                Ok(result) => {
-                    total_failed += 1;
-                    let err = result.error.unwrap_or_else(|| "unknown error".to_string());
-                    display::print_warning(&format!(
-                        "Live Photo retry failed: {} — {}",
-                        fallback.photo_path, err
-                    ));
-                }
-                Err(err) => {
-                    total_failed += 1;
-                    display::print_warning(&format!(
-                        "Live Photo retry error: {} — {}",
-                        fallback.photo_path, err
-                    ));
-                }
+        // --retry-failed only touches the failures
+        let summary =
+            process_one_zip(&zip_path, dir.path(), true, true, &ImportArgs::default()).unwrap();
+        assert_eq!(
+            summary.dry_run,
+            DryRunCounts {
+                would_import: 0,
+                already_done: 1,
+                would_retry_failed: 1,
             }
-        }
+        );
+    }
 
-        if !resolved_paths.is_empty() {
-            // Update manifest: remove resolved fallbacks and update imported entry to live photo
-            manifest.live_photo_fallbacks.retain(|f| !resolved_paths.contains(&f.photo_path));
-            for entry in &mut manifest.imported {
-                if let Some(new_id) = updated_imports.get(&entry.path) {
-                    entry.local_id = new_id.clone();
-                    entry.is_live_photo = Some(true);
-                }
-            }
-            // Write updated manifest
-            let imported: Vec<(String, String, Option<String>, bool)> = manifest
-                .imported
-                .iter()
-                .map(|e| {
-                    (
-                        e.path.clone(),
-                        e.local_id.clone(),
-                        e.creation_date.clone(),
-                        e.is_live_photo.unwrap_or(false),
-                    )
-                })
-                .collect();
-            let failed: Vec<(String, String)> = manifest
-                .failed
-                .iter()
-                .map(|e| (e.path.clone(), e.error.clone()))
-                .collect();
-            let live_photo_fallbacks: Vec<(String, String, String)> = manifest
-                .live_photo_fallbacks
-                .iter()
-                .map(|e| (e.photo_path.clone(), e.video_path.clone(), e.local_id.clone()))
-                .collect();
-            manifest::write_manifest(manifest_path, &manifest.zip, &imported, &failed, &live_photo_fallbacks)?;
+    #[test]
+    fn strict_extensions_returns_structured_abort() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-test.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/Google Photos/Photos from 2024/a.jpg",
+                "Takeout/Google Photos/Photos from 2024/b.xyz",
+            ],
+        );
+        let import = ImportArgs {
+            strict_extensions: true,
+            ..Default::default()
+        };
 
-            if !updated_imports.is_empty() {
-                display::print_warning(
-                    "Live Photo retries create new assets; check Photos.app for duplicates.",
-                );
+        let err = process_one_zip(&zip_path, dir.path(), true, false, &import).unwrap_err();
+        match err.downcast_ref::<ProcessError>() {
+            Some(ProcessError::StrictExtensionsAbort { count, examples }) => {
+                assert_eq!(*count, 1);
+                assert_eq!(examples, &["Google Photos/Photos from 2024/b.xyz"]);
             }
+            _ => panic!("expected StrictExtensionsAbort, got {err}"),
         }
+    }
 
-        std::fs::remove_dir_all(&extract_dir)?;
+    #[test]
+    fn exit_codes_distinguish_outcomes() {
+        assert_eq!(Outcome::for_download(0, 0), Outcome::Success);
+        assert_eq!(Outcome::for_download(0, 2), Outcome::CompletedWithFailures);
+        assert_eq!(Outcome::for_download(1, 2), Outcome::DownloadFailures);
+        assert_eq!(Outcome::DownloadFailures.exit_code(), 5);
+
+        let abort = anyhow::Error::from(ProcessError::StrictExtensionsAbort {
+            count: 1,
+            examples: vec![],
+        });
+        assert_eq!(Outcome::error_exit_code(&abort), 3);
+        let denied = anyhow::Error::from(ProcessError::PhotosAccess("denied".to_string()))
+            .context("checking access");
+        assert_eq!(Outcome::error_exit_code(&denied), 2);
+        assert_eq!(Outcome::error_exit_code(&anyhow::anyhow!("boom")), 1);
     }
 
-    println!();
-    display::print_header("Retry Live Photo fallbacks summary");
-    display::print_info(&format!("Re-imported: {}", total_reimported));
-    if total_failed > 0 {
-        display::print_warning(&format!("Retry failures: {}", total_failed));
+    #[test]
+    fn import_date_key_orders_oldest_first_and_undated_last() {
+        let mut dates = vec![
+            None,
+            Some("2021-06-01T00:00:00Z"),
+            Some("not-a-date"),
+            Some("2019-01-01T08:00:00+08:00"),
+            Some("2019-01-01T01:00:00Z"),
+        ];
+        dates.sort_by_key(|d| import_date_key(*d));
+        assert_eq!(
+            dates,
+            vec![
+                Some("2019-01-01T08:00:00+08:00"),
+                Some("2019-01-01T01:00:00Z"),
+                Some("2021-06-01T00:00:00Z"),
+                None,
+                Some("not-a-date"),
+            ]
+        );
     }
-    if total_unresolved > 0 {
-        display::print_warning(&format!(
-            "Unresolved fallbacks (missing in zip): {}",
-            total_unresolved
-        ));
+
+    #[test]
+    fn zip_media_count_counts_only_media() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-001.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/archive_browser.html",
+                "Takeout/Google Photos/Trip/metadata.json",
+                "Takeout/Google Photos/Trip/a.jpg",
+                "Takeout/Google Photos/Trip/a.jpg.json",
+                "Takeout/Google Photos/Trip/b.MOV",
+            ],
+        );
+        assert_eq!(zip_media_count(&zip_path).unwrap(), 2);
     }
 
-    Ok(())
-}
+    #[test]
+    fn zip_media_unaccounted_counts_media_missing_from_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-001.zip");
+        write_zip(
+            &zip_path,
+            &[
+                "Takeout/Google Photos/Trip/a.jpg",
+                "Takeout/Google Photos/Trip/b.HEIC",
+                "Takeout/Google Photos/Trip/b.MOV",
+                "Takeout/Google Photos/Trip/c.jpg",
+                "Takeout/Google Photos/Trip/c.jpg.json",
+            ],
+        );
+        let entry = |path: &str, is_live| manifest::ManifestEntry {
+            path: path.to_string(),
+            local_id: path.to_string(),
+            creation_date: None,
+            is_live_photo: Some(is_live),
+            album: None,
+            sidecar_path: None,
+        };
+        let mut manifest = manifest::ImportManifest {
+            zip: "takeout-001.zip".to_string(),
+            processed_at: String::new(),
+            imported: vec![
+                entry("Google Photos/Trip/a.jpg", false),
+                entry("Google Photos/Trip/b.HEIC", true),
+            ],
+            failed: Vec::new(),
+            live_photo_fallbacks: Vec::new(),
+            layout: None,
+        };
+        // `c.jpg` was never imported (e.g. stopped at --max-runtime)
+        assert_eq!(zip_media_unaccounted(&manifest, &zip_path).unwrap(), 1);
 
-fn dates_match(a: &str, b: &str) -> bool {
-    let parsed_a = chrono::DateTime::parse_from_rfc3339(a)
-        .ok()
-        .map(|dt| dt.with_timezone(&chrono::Utc));
-    let parsed_b = chrono::DateTime::parse_from_rfc3339(b)
-        .ok()
-        .map(|dt| dt.with_timezone(&chrono::Utc));
-    match (parsed_a, parsed_b) {
-        (Some(da), Some(db)) => da == db,
-        _ => a.trim() == b.trim(),
+        manifest
+            .imported
+            .push(entry("Google Photos/Trip/c.jpg", false));
+        assert_eq!(zip_media_unaccounted(&manifest, &zip_path).unwrap(), 0);
     }
-}
 
-fn date_mismatch(expected: Option<&str>, actual: Option<&str>) -> bool {
-    match expected {
-        None => false,
-        Some(expected_value) => match actual {
-            Some(actual_value) => !dates_match(expected_value, actual_value),
-            None => true,
-        },
-    }
-}
+    #[test]
+    fn zip_contains_paths_strips_takeout_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("renamed.zip");
+        write_zip(&zip_path, &["Takeout/Google Photos/Trip/a.jpg"]);
 
+        assert!(zip_contains_paths(&zip_path, &["Google Photos/Trip/a.jpg"]).unwrap());
+        assert!(!zip_contains_paths(&zip_path, &["Google Photos/Trip/b.jpg"]).unwrap());
+    }
 
-/// Batch-verify all assets recorded in a zip's manifest exist in Photos Library.
-/// Returns true if all present (safe to delete zip), false if any missing.
-fn verify_zip_manifest(zip_path: &Path, manifest_dir: &Path) -> bool {
-    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
-    let manifest_path = manifest_dir.join(format!(".photoferry-manifest-{}.json", zip_stem));
-    let manifest = match manifest::read_manifest_strict(&manifest_path) {
-        Ok(Some(m)) => m,
-        Ok(None) => {
-            display::print_warning("  Verify: manifest missing — refusing to delete zip");
-            return false;
-        }
-        Err(e) => {
-            display::print_warning(&format!(
-                "  Verify: manifest unreadable/corrupt ({e}) — refusing to delete zip"
-            ));
-            return false;
-        }
-    };
-    if manifest.imported.is_empty() {
-        if !manifest.failed.is_empty() {
-            display::print_warning(&format!(
-                "  Verify: {} failed imports — keeping zip",
-                manifest.failed.len()
-            ));
-            return false;
+    #[test]
+    fn extract_zip_entry_fails_only_the_corrupt_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-corrupt.zip");
+        {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("Takeout/good.jpg", options).unwrap();
+            zip.write_all(b"good-bytes").unwrap();
+            zip.start_file("Takeout/bad.jpg", options).unwrap();
+            zip.write_all(b"corrupt-me").unwrap();
+            zip.finish().unwrap();
         }
-        return true;
+        // Flip a byte in the stored data so its CRC no longer matches
+        let mut bytes = std::fs::read(&zip_path).unwrap();
+        let pos = bytes
+            .windows(b"corrupt-me".len())
+            .position(|w| w == b"corrupt-me")
+            .unwrap();
+        bytes[pos] = b'C';
+        std::fs::write(&zip_path, bytes).unwrap();
+
+        let file = std::fs::File::open(&zip_path).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file)).unwrap();
+        let out = dir.path().join("out");
+        assert!(extract_zip_entry(&mut archive, 1, &out.join("bad.jpg")).is_err());
+        extract_zip_entry(&mut archive, 0, &out.join("good.jpg")).unwrap();
+        assert_eq!(std::fs::read(out.join("good.jpg")).unwrap(), b"good-bytes");
     }
-    let ids: Vec<&str> = manifest
-        .imported
-        .iter()
-        .map(|e| e.local_id.as_str())
-        .collect();
-    match importer::verify_assets(&ids) {
-        Ok(results) => {
-            let result_map: HashMap<&str, &importer::AssetVerifyResult> = results
-                .iter()
-                .map(|r| (r.local_identifier.as_str(), r))
-                .collect();
-            let mut missing = 0usize;
-            let mut wrong_date = 0usize;
-            let mut live_pair_missing = 0usize;
-            let mut confirmed = 0usize;
-            for entry in &manifest.imported {
-                let Some(result) = result_map.get(entry.local_id.as_str()) else {
-                    missing += 1;
-                    continue;
-                };
-                if !result.found {
-                    missing += 1;
-                    continue;
-                }
-                if entry.is_live_photo == Some(true) && !result.has_paired_video {
-                    live_pair_missing += 1;
-                    continue;
-                }
-                if date_mismatch(entry.creation_date.as_deref(), result.creation_date.as_deref()) {
-                    wrong_date += 1;
-                    continue;
-                }
-                confirmed += 1;
-            }
-            if missing > 0 || live_pair_missing > 0 || wrong_date > 0 {
-                display::print_warning(&format!(
-                    "  Verify: {}/{} confirmed — {} missing, {} wrong date, {} live pair missing; keeping zip",
-                    confirmed,
-                    manifest.imported.len(),
-                    missing,
-                    wrong_date,
-                    live_pair_missing
-                ));
-                false
-            } else {
-                display::print_success(&format!(
-                    "  Verify: all {} assets confirmed in Photos Library",
-                    confirmed
-                ));
-                true
-            }
-        }
-        Err(e) => {
-            display::print_warning(&format!("  Verify error: {e} — keeping zip as precaution"));
-            false
-        }
+
+    #[test]
+    fn after_part_hook_sees_part_and_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("hook.txt");
+        let cmd = format!(
+            "echo \"$PHOTOFERRY_PART $PHOTOFERRY_STATUS $PHOTOFERRY_DIR\" > '{}'",
+            out.display()
+        );
+        run_after_part_hook(Some(&cmd), 7, "verified", dir.path());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap().trim(),
+            format!("7 verified {}", dir.path().display())
+        );
+
+        // A failing hook only warns
+        run_after_part_hook(Some("exit 3"), 7, "kept", dir.path());
     }
-}
 
-fn live_photo_paths_from_zip(zip_path: &Path, manifest_dir: &Path) -> Result<HashSet<String>> {
-    let zip_stem = zip_path.file_stem().unwrap_or_default().to_string_lossy();
-    let extract_dir = manifest_dir.join(format!(
-        ".photoferry-verify-extract-{}",
-        zip_stem
-    ));
-    if extract_dir.exists() {
-        std::fs::remove_dir_all(&extract_dir)?;
+    #[test]
+    fn wrong_date_indices_skips_missing_and_matching_assets() {
+        let entry = |id: &str| manifest::ManifestEntry {
+            path: format!("Google Photos/A/{id}.JPG"),
+            local_id: id.to_string(),
+            creation_date: Some("2020-01-01T00:00:00Z".to_string()),
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        };
+        let result = |id: &str, found: bool, date: &str| importer::AssetVerifyResult {
+            local_identifier: id.to_string(),
+            found,
+            creation_date: Some(date.to_string()),
+            has_paired_video: false,
+            has_still: true,
+            in_icloud_only: false,
+        };
+        let wrong = result("wrong", true, "2019-06-01T12:00:00.000Z");
+        let right = result("right", true, "2020-01-01T00:00:00.000Z");
+        let gone = result("gone", false, "2019-06-01T12:00:00.000Z");
+        let verified = HashMap::from([("wrong", &wrong), ("right", &right), ("gone", &gone)]);
+        let entries = vec![
+            entry("right"),
+            entry("wrong"),
+            entry("gone"),
+            entry("unknown"),
+        ];
+
+        assert_eq!(wrong_date_indices(&entries, &verified, 0), vec![1]);
+
+        // A second off, within --date-tolerance-secs
+        let close = result("close", true, "2020-01-01T00:00:01.000Z");
+        let verified = HashMap::from([("close", &close)]);
+        assert_eq!(wrong_date_indices(&[entry("close")], &verified, 0), vec![0]);
+        assert!(wrong_date_indices(&[entry("close")], &verified, 1).is_empty());
     }
-    std::fs::create_dir_all(&extract_dir)?;
 
-    let result = (|| -> Result<HashSet<String>> {
-        let content_root = takeout::extract_zip(zip_path, &extract_dir)?;
-        let inventory = takeout::scan_directory(&content_root, &takeout::ScanOptions::default())?;
+    #[test]
+    fn skip_conflicts_keeps_present_assets_and_relinks_found_ones() {
+        let entry = |path: &str, id: &str| manifest::ManifestEntry {
+            path: path.to_string(),
+            local_id: id.to_string(),
+            creation_date: Some("2020-01-01T00:00:00Z".to_string()),
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        };
+        let wrong_date = importer::AssetVerifyResult {
+            local_identifier: "present".to_string(),
+            found: true,
+            creation_date: Some("2019-01-01T00:00:00.000Z".to_string()),
+            has_paired_video: false,
+            has_still: true,
+            in_icloud_only: false,
+        };
+        let verified = HashMap::from([("present", &wrong_date)]);
+        let assets = vec![importer::FilenameAsset {
+            filename: "IMG_0002.JPG".to_string(),
+            local_identifier: "new-id".to_string(),
+            creation_date: Some("2020-01-01T00:00:00.000Z".to_string()),
+        }];
+
+        let skip = skip_conflicts(
+            vec![
+                entry("Google Photos/A/IMG_0001.JPG", "present"),
+                entry("Google Photos/A/IMG_0002.JPG", "gone"),
+                entry("Google Photos/A/IMG_0003.JPG", "gone-too"),
+            ],
+            &verified,
+            &assets,
+        );
+        assert_eq!(skip.kept, 1);
+        assert_eq!(
+            skip.relinks
+                .get("Google Photos/A/IMG_0002.JPG")
+                .map(String::as_str),
+            Some("new-id")
+        );
+        let retry: Vec<&str> = skip.retry.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(retry, vec!["Google Photos/A/IMG_0003.JPG"]);
+    }
 
-        let mut live_paths = HashSet::new();
-        for file in &inventory.files {
-            if file.live_photo_pair.is_some() {
-                let rel = file
-                    .path
-                    .strip_prefix(&content_root)
-                    .unwrap_or(&file.path)
-                    .to_string_lossy()
-                    .to_string();
-                live_paths.insert(rel);
-            }
-        }
-        Ok(live_paths)
-    })();
+    #[test]
+    fn retry_imports_copies_once_and_repoints_them() {
+        let entry = |path: &str, id: &str, album: Option<&str>| manifest::ManifestEntry {
+            path: path.to_string(),
+            local_id: id.to_string(),
+            creation_date: None,
+            is_live_photo: Some(false),
+            album: album.map(str::to_string),
+            sidecar_path: None,
+        };
+        let (retry, copies) = group_retry_entries(vec![
+            entry("Photos from 2020/IMG_0001.JPG", "old-1", None),
+            entry("Rome/IMG_0001.JPG", "old-1", Some("Rome")),
+            entry("Best/IMG_0001.JPG", "old-1", Some("Best")),
+            entry("Photos from 2020/IMG_0002.JPG", "old-2", None),
+        ]);
+        let retry: Vec<&str> = retry.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            retry,
+            vec![
+                "Photos from 2020/IMG_0001.JPG",
+                "Photos from 2020/IMG_0002.JPG"
+            ]
+        );
 
-    let _ = std::fs::remove_dir_all(&extract_dir);
-    result
-}
+        let mut copies = copies.get("Photos from 2020/IMG_0001.JPG").unwrap().clone();
+        let library = MockImporter::default();
+        library.record("photo", "IMG_0001.JPG", None).unwrap();
+        let registry_dir = tempfile::tempdir().unwrap();
+        let mut registry = manifest::AlbumRegistry::load(registry_dir.path()).unwrap();
+        repoint_copies(
+            &library,
+            &mut HashMap::new(),
+            &mut registry,
+            "mock-1",
+            Some(true),
+            &mut copies,
+            HashSet::from(["Best".to_string()]),
+        );
 
-fn ensure_full_photos_access(access: &importer::AccessResult, action: &str) -> Result<()> {
-    if !access.authorized {
-        bail!(
-            "Photos access: {} — grant in System Settings > Privacy & Security > Photos",
-            access.status
+        assert!(copies.iter().all(|c| c.local_id == "mock-1"));
+        assert!(copies.iter().all(|c| c.is_live_photo == Some(true)));
+        // Already in "Best"; added to "Rome"
+        assert_eq!(
+            *library.album_adds.borrow(),
+            vec![("Rome".to_string(), "IMG_0001.JPG".to_string())]
         );
+        let albums: Vec<Option<&str>> = copies.iter().map(|c| c.album.as_deref()).collect();
+        assert_eq!(albums, vec![Some("Rome"), Some("Best")]);
     }
-    if access.status == "limited" {
-        bail!(
-            "Photos access is limited — {} requires full library access for reliable results",
-            action
+
+    #[test]
+    fn group_album_files_skips_files_outside_albums() {
+        let root = Path::new("/tmp/extract/Takeout");
+        let file = |path: &str, album: Option<&str>, video: Option<&str>| takeout::MediaFile {
+            path: root.join(path),
+            media_type: MediaType::Photo,
+            metadata: None,
+            album: album.map(str::to_string),
+            live_photo_pair: video.map(|v| root.join(v)),
+        };
+        let mut albums = BTreeMap::new();
+        group_album_files(
+            &[
+                file("Google Photos/Trip/a.jpg", Some("Trip"), None),
+                file(
+                    "Google Photos/Trip/b.heic",
+                    Some("Trip"),
+                    Some("Google Photos/Trip/b.mov"),
+                ),
+                file("Google Photos/Photos from 2020/c.jpg", None, None),
+            ],
+            root,
+            &mut albums,
+        );
+        assert_eq!(albums.len(), 1);
+        assert_eq!(
+            albums["Trip"],
+            vec![
+                "Google Photos/Trip/a.jpg",
+                "Google Photos/Trip/b.heic",
+                "Google Photos/Trip/b.mov"
+            ]
         );
     }
-    Ok(())
-}
 
-fn expand_tilde(path: &Path) -> PathBuf {
-    if let Some(rest) = path.to_str().and_then(|s: &str| s.strip_prefix("~/"))
-        && let Ok(home) = std::env::var("HOME")
-    {
-        return PathBuf::from(home).join(rest);
+    #[test]
+    fn repair_match_uses_filename_and_date() {
+        let asset = |name: &str, id: &str, date: Option<&str>| importer::FilenameAsset {
+            filename: name.to_string(),
+            local_identifier: id.to_string(),
+            creation_date: date.map(str::to_string),
+        };
+        let assets = vec![
+            asset("IMG_0001.JPG", "a", Some("2020-01-01T00:00:00.000Z")),
+            asset("img_0001.jpg", "b", Some("2021-06-01T00:00:00.000Z")),
+            asset("IMG_0002.JPG", "d", Some("2020-01-01T00:00:00.000Z")),
+            asset("IMG_0002.JPG", "c", Some("2020-01-01T00:00:00.000Z")),
+        ];
+
+        assert_eq!(
+            repair_match("IMG_0001.jpg", Some("2021-06-01T00:00:00Z"), &assets),
+            RepairMatch::Found("b")
+        );
+        // No sidecar date: every asset with the name is a candidate
+        assert_eq!(
+            repair_match("IMG_0001.jpg", None, &assets),
+            RepairMatch::Ambiguous(vec!["a", "b"])
+        );
+        assert_eq!(
+            repair_match("IMG_0002.JPG", Some("2020-01-01T00:00:00Z"), &assets),
+            RepairMatch::Ambiguous(vec!["c", "d"])
+        );
+        assert_eq!(
+            repair_match("IMG_0003.JPG", None, &assets),
+            RepairMatch::Missing
+        );
     }
-    path.to_path_buf()
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{VerifySuccessAction, date_mismatch, dates_match, verify_success_action};
+    #[test]
+    fn repair_manifest_refuses_to_overwrite_readable_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("takeout-001.zip");
+        write_zip(&zip_path, &["Takeout/Google Photos/Trip/a.jpg"]);
+        let manifest_path = dir.path().join(".photoferry-manifest-takeout-001.json");
+        manifest::write_manifest(&manifest_path, "takeout-001.zip", &[], &[], &[]).unwrap();
+
+        let err = cmd_repair_manifest(&zip_path, None).unwrap_err();
+        assert!(err.to_string().contains("is readable"));
+    }
 
     #[test]
-    fn dates_match_normalizes_timezone() {
-        assert!(dates_match(
-            "2026-02-22T10:00:00+08:00",
-            "2026-02-22T02:00:00Z"
-        ));
+    fn relink_finds_renamed_zip_and_renames_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        write_zip(
+            &dir.path().join("other.zip"),
+            &["Takeout/Google Photos/x.jpg"],
+        );
+        write_zip(
+            &dir.path().join("2024 trip.zip"),
+            &["Takeout/Google Photos/Trip/a.jpg"],
+        );
+        let old_manifest = dir.path().join(".photoferry-manifest-takeout-001.json");
+        let imported = [manifest::ManifestEntry {
+            path: "Google Photos/Trip/a.jpg".to_string(),
+            local_id: "ID1".to_string(),
+            creation_date: None,
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        }];
+        manifest::write_manifest(&old_manifest, "takeout-001.zip", &imported, &[], &[]).unwrap();
+        let mut m = manifest::read_manifest_strict(&old_manifest)
+            .unwrap()
+            .unwrap();
+
+        let relinked = relink_manifest_zip(dir.path(), &old_manifest, &mut m)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(m.zip, "2024 trip.zip");
+        assert_eq!(
+            relinked,
+            dir.path().join(".photoferry-manifest-2024 trip.json")
+        );
+        assert!(!old_manifest.exists());
+        let on_disk = manifest::read_manifest_strict(&relinked).unwrap().unwrap();
+        assert_eq!(on_disk.zip, "2024 trip.zip");
     }
 
     #[test]
-    fn dates_match_detects_real_difference() {
-        assert!(!dates_match("2026-02-22T10:00:00Z", "2026-02-22T10:00:01Z"));
+    fn manifest_totals_sums_all_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = |path: &str| manifest::ManifestEntry {
+            path: path.to_string(),
+            local_id: format!("id-{path}"),
+            creation_date: None,
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        };
+        let failed = [manifest::ManifestFailure {
+            path: "c.jpg".to_string(),
+            error: "boom".to_string(),
+        }];
+        manifest::write_manifest(
+            &dir.path().join(".photoferry-manifest-a.json"),
+            "a.zip",
+            &[entry("a.jpg"), entry("b.jpg")],
+            &failed,
+            &[],
+        )
+        .unwrap();
+        manifest::write_manifest(
+            &dir.path().join(".photoferry-manifest-b.json"),
+            "b.zip",
+            &[entry("d.jpg")],
+            &[],
+            &[],
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("unrelated.json"), "{}").unwrap();
+
+        let totals = manifest_totals(dir.path());
+        assert_eq!(totals.manifests, 2);
+        assert_eq!(totals.imported, 3);
+        assert_eq!(totals.failed, 1);
     }
 
     #[test]
-    fn dates_match_falls_back_to_trimmed_string() {
-        assert!(dates_match("not-a-date ", "not-a-date"));
+    fn group_failures_clusters_by_error_most_frequent_first() {
+        let failure = |path: &str, error: &str| manifest::ManifestFailure {
+            path: path.to_string(),
+            error: error.to_string(),
+        };
+        let manifest =
+            |zip: &str, failed: Vec<manifest::ManifestFailure>| manifest::ImportManifest {
+                zip: zip.to_string(),
+                processed_at: String::new(),
+                imported: Vec::new(),
+                failed,
+                live_photo_fallbacks: Vec::new(),
+                layout: None,
+            };
+        let manifests = [
+            manifest(
+                "a.zip",
+                vec![
+                    failure("1.jpg", "timeout"),
+                    failure("2.heic", "unsupported format"),
+                ],
+            ),
+            manifest("b.zip", vec![failure("3.jpg", "timeout")]),
+        ];
+
+        let groups = group_failures(&manifests, None);
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    "timeout".to_string(),
+                    vec!["a.zip: 1.jpg".to_string(), "b.zip: 3.jpg".to_string()]
+                ),
+                (
+                    "unsupported format".to_string(),
+                    vec!["a.zip: 2.heic".to_string()]
+                ),
+            ]
+        );
+
+        let filtered = group_failures(&manifests, Some("format"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, "unsupported format");
     }
 
     #[test]
-    fn date_mismatch_is_false_without_expected_date() {
-        assert!(!date_mismatch(None, None));
-        assert!(!date_mismatch(None, Some("2026-02-22T10:00:00Z")));
+    fn clean_blocks_on_failed_imports_and_incomplete_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let failed = [manifest::ManifestFailure {
+            path: "c.jpg".to_string(),
+            error: "boom".to_string(),
+        }];
+        manifest::write_manifest(
+            &dir.path().join(".photoferry-manifest-a.json"),
+            "a.zip",
+            &[],
+            &[],
+            &[],
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join(".photoferry-stream-tmp")).unwrap();
+        std::fs::write(dir.path().join("takeout-001.zip"), "zip").unwrap();
+        let mut progress = crate::downloader::DownloadProgress {
+            job_id: "job".to_string(),
+            completed: vec![0, 1],
+            ..Default::default()
+        };
+        progress.save(dir.path()).unwrap();
+
+        let targets = clean_targets(dir.path()).unwrap();
+        assert_eq!(targets.len(), 3);
+        assert!(targets.iter().all(|p| !p.ends_with("takeout-001.zip")));
+        assert!(clean_blockers(&targets).is_empty());
+
+        manifest::write_manifest(
+            &dir.path().join(".photoferry-manifest-b.json"),
+            "b.zip",
+            &[],
+            &failed,
+            &[],
+        )
+        .unwrap();
+        progress.failed = vec![3, 2];
+        progress.save(dir.path()).unwrap();
+        let blockers = clean_blockers(&clean_targets(dir.path()).unwrap());
+        assert_eq!(blockers.len(), 2);
+        assert!(
+            blockers
+                .iter()
+                .any(|b| b.ends_with("incomplete parts 2, 3"))
+        );
+        assert!(blockers.iter().any(|b| b.ends_with("1 failed imports")));
     }
 
     #[test]
-    fn date_mismatch_is_true_when_expected_exists_but_actual_missing() {
-        assert!(date_mismatch(Some("2026-02-22T10:00:00Z"), None));
+    fn exceeds_max_file_size_compares_in_gb() {
+        let gb = 1024 * 1024 * 1024;
+        assert!(!exceeds_max_file_size(100 * gb, None));
+        assert!(!exceeds_max_file_size(2 * gb, Some(2.0)));
+        assert!(exceeds_max_file_size(2 * gb + 1, Some(2.0)));
+        assert!(exceeds_max_file_size(gb, Some(0.5)));
     }
 
     #[test]
-    fn date_mismatch_uses_dates_match_when_both_present() {
-        assert!(!date_mismatch(
-            Some("2026-02-22T10:00:00+08:00"),
-            Some("2026-02-22T02:00:00Z")
-        ));
-        assert!(date_mismatch(
-            Some("2026-02-22T10:00:00Z"),
-            Some("2026-02-22T10:00:01Z")
-        ));
+    fn sample_indices_is_deterministic_for_a_seed() {
+        let a = sample_indices(1000, 5.0, &mut SampleRng(42));
+        let b = sample_indices(1000, 5.0, &mut SampleRng(42));
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 50);
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+        assert_ne!(a, sample_indices(1000, 5.0, &mut SampleRng(7)));
     }
 
     #[test]
-    fn verify_success_action_deletes_zip_by_default() {
+    fn sample_indices_bounds() {
         assert_eq!(
-            verify_success_action(false),
-            VerifySuccessAction::DeleteZipAndMarkCompleted
+            sample_indices(0, 5.0, &mut SampleRng(1)),
+            Vec::<usize>::new()
+        );
+        assert_eq!(sample_indices(10, 1.0, &mut SampleRng(1)).len(), 1);
+        assert_eq!(
+            sample_indices(4, 100.0, &mut SampleRng(1)),
+            vec![0, 1, 2, 3]
         );
     }
 
     #[test]
-    fn verify_success_action_keeps_zip_when_keep_zips_set() {
+    fn failure_rate_upper_bound_shrinks_with_sample_size() {
+        let small = failure_rate_upper_bound(0, 100);
+        let large = failure_rate_upper_bound(0, 10_000);
+        assert!(small > 0.03 && small < 0.04);
+        assert!(large < 0.001);
+        assert!(failure_rate_upper_bound(5, 100) > 0.05);
+    }
+
+    #[test]
+    fn verify_manifest_paths_named_manifest_skips_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b"] {
+            manifest::write_manifest(
+                &dir.path().join(format!(".photoferry-manifest-{name}.json")),
+                &format!("{name}.zip"),
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        }
+        assert_eq!(verify_manifest_paths(dir.path(), None).unwrap().len(), 2);
+
+        let named = dir.path().join(".photoferry-manifest-b.json");
         assert_eq!(
-            verify_success_action(true),
-            VerifySuccessAction::KeepZipAndMarkCompleted
+            verify_manifest_paths(dir.path(), Some(&named)).unwrap(),
+            vec![named]
         );
     }
+
+    #[test]
+    fn verify_manifest_paths_named_manifest_must_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let corrupt = dir.path().join(".photoferry-manifest-bad.json");
+        std::fs::write(&corrupt, "{not json").unwrap();
+        assert!(verify_manifest_paths(dir.path(), Some(&corrupt)).is_err());
+
+        let missing = dir.path().join(".photoferry-manifest-missing.json");
+        assert!(verify_manifest_paths(dir.path(), Some(&missing)).is_err());
+    }
+
+    #[test]
+    fn album_filter_matches_case_insensitive_and_trimmed() {
+        let filter = vec!["  wedding ".to_string()];
+        assert!(album_filter_matches(&filter, Some("Wedding"), None));
+        assert!(!album_filter_matches(&filter, Some("Vacation"), None));
+        assert!(!album_filter_matches(&filter, None, None));
+        assert!(album_filter_matches(&[], None, None));
+    }
+
+    #[test]
+    fn album_filter_excludes_year_folders_unless_named() {
+        let year = Path::new("Google Photos/Photos from 2024");
+        assert!(!album_filter_matches(
+            &["Wedding".to_string()],
+            None,
+            Some(year)
+        ));
+        assert!(album_filter_matches(
+            &["photos from 2024".to_string()],
+            None,
+            Some(year)
+        ));
+    }
 }
@@ -1,6 +1,7 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -14,6 +15,13 @@ pub struct ManifestEntry {
     pub creation_date: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_live_photo: Option<bool>,
+    /// Album the asset was actually added to (after any album routing).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    /// Sidecar JSON the metadata came from (same root as `path`), so a retry
+    /// reads just that file instead of re-matching sidecars.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecar_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,16 +45,45 @@ pub struct ImportManifest {
     pub failed: Vec<ManifestFailure>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub live_photo_fallbacks: Vec<ManifestLivePhotoFallback>,
+    /// How the zip's layout was read, for debugging album or path issues
+    /// later. Absent in manifests from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<LayoutInfo>,
+}
+
+/// What Phase 1 detected about a zip's layout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutInfo {
+    /// Prefix stripped from entry names (`""`, `Takeout/`, ...)
+    pub content_prefix: String,
+    /// Directories holding media or sidecars
+    pub directories: usize,
+    /// Wrapped in `Takeout/` with everything under a Google Photos folder
+    pub standard_takeout: bool,
+}
+
+/// A record [`ManifestAppender`] appends to a manifest's journal.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalRecord {
+    Imported(ManifestEntry),
+    Failed(ManifestFailure),
+    LivePhotoFallback(ManifestLivePhotoFallback),
+}
+
+/// Records appended since the manifest at `path` was last rewritten.
+fn journal_path(path: &Path) -> PathBuf {
+    path.with_extension("journal")
 }
 
 /// Read an existing manifest file leniently. Returns None on any error.
 #[cfg(test)]
 pub fn read_manifest(path: &Path) -> Option<ImportManifest> {
-    let contents = fs::read_to_string(path).ok()?;
-    serde_json::from_str(&contents).ok()
+    read_manifest_strict(path).ok().flatten()
 }
 
-/// Read an existing manifest file strictly.
+/// Read an existing manifest file strictly, with any journal a crashed run
+/// left behind folded in.
 /// Returns Ok(None) when missing, and Err when unreadable/corrupt.
 pub fn read_manifest_strict(path: &Path) -> Result<Option<ImportManifest>> {
     let contents = match fs::read_to_string(path) {
@@ -54,11 +91,45 @@ pub fn read_manifest_strict(path: &Path) -> Result<Option<ImportManifest>> {
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
         Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
     };
-    let manifest = serde_json::from_str::<ImportManifest>(&contents)
+    let mut manifest = serde_json::from_str::<ImportManifest>(&contents)
         .with_context(|| format!("Corrupt manifest JSON at {}", path.display()))?;
+    replay_journal(path, &mut manifest)?;
     Ok(Some(manifest))
 }
 
+/// Fold the journal beside `path` into `manifest`. A last line without its
+/// newline was being written when the run died, and is dropped.
+fn replay_journal(path: &Path, manifest: &mut ImportManifest) -> Result<()> {
+    let journal = journal_path(path);
+    let contents = match fs::read_to_string(&journal) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", journal.display())),
+    };
+    let mut imported = Vec::new();
+    let mut failed = Vec::new();
+    let mut live_photo_fallbacks = Vec::new();
+    let complete = contents.ends_with('\n');
+    let mut lines = contents.lines().peekable();
+    while let Some(line) = lines.next() {
+        let record = match serde_json::from_str::<JournalRecord>(line) {
+            Ok(record) => record,
+            Err(_) if !complete && lines.peek().is_none() => break,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Corrupt manifest journal at {}", journal.display()));
+            }
+        };
+        match record {
+            JournalRecord::Imported(entry) => imported.push(entry),
+            JournalRecord::Failed(failure) => failed.push(failure),
+            JournalRecord::LivePhotoFallback(fallback) => live_photo_fallbacks.push(fallback),
+        }
+    }
+    merge_records(manifest, &imported, &failed, &live_photo_fallbacks);
+    Ok(())
+}
+
 /// Build a set of already-imported paths from a manifest.
 #[cfg(test)]
 pub fn already_imported(manifest: &ImportManifest) -> HashSet<String> {
@@ -66,46 +137,88 @@ pub fn already_imported(manifest: &ImportManifest) -> HashSet<String> {
 }
 
 /// Write a manifest to disk. Uses write-to-tmp-then-rename for atomicity.
+/// The layout recorded in the existing manifest, if any, is kept.
 pub fn write_manifest(
     path: &Path,
     zip_name: &str,
-    imported: &[(String, String, Option<String>, bool)], // (relative_path, local_id, creation_date, is_live_photo)
-    failed: &[(String, String)],                         // (relative_path, error)
-    live_photo_fallbacks: &[(String, String, String)],   // (photo_path, video_path, local_id)
+    imported: &[ManifestEntry],
+    failed: &[ManifestFailure],
+    live_photo_fallbacks: &[ManifestLivePhotoFallback],
+) -> Result<()> {
+    let layout = read_manifest_strict(path)
+        .ok()
+        .flatten()
+        .and_then(|m| m.layout);
+    write_entries(
+        path,
+        zip_name,
+        imported,
+        failed,
+        live_photo_fallbacks,
+        layout,
+    )
+}
+
+fn write_entries(
+    path: &Path,
+    zip_name: &str,
+    imported: &[ManifestEntry],
+    failed: &[ManifestFailure],
+    live_photo_fallbacks: &[ManifestLivePhotoFallback],
+    layout: Option<LayoutInfo>,
 ) -> Result<()> {
     let manifest = ImportManifest {
         zip: zip_name.to_string(),
         processed_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
         imported: imported
             .iter()
-            .map(|(p, id, date, is_live_photo)| ManifestEntry {
-                path: p.clone(),
-                local_id: id.clone(),
-                creation_date: date.clone(),
-                is_live_photo: Some(*is_live_photo),
-            })
-            .collect(),
-        failed: failed
-            .iter()
-            .map(|(p, e)| ManifestFailure {
-                path: p.clone(),
-                error: e.clone(),
-            })
-            .collect(),
-        live_photo_fallbacks: live_photo_fallbacks
-            .iter()
-            .map(|(photo_path, video_path, local_id)| ManifestLivePhotoFallback {
-                photo_path: photo_path.clone(),
-                video_path: video_path.clone(),
-                local_id: local_id.clone(),
+            .map(|e| ManifestEntry {
+                is_live_photo: Some(e.is_live_photo.unwrap_or(false)),
+                ..e.clone()
             })
             .collect(),
+        failed: failed.to_vec(),
+        live_photo_fallbacks: live_photo_fallbacks.to_vec(),
+        layout,
     };
+    save(path, &manifest)
+}
+
+/// Point an existing manifest at a renamed zip, keeping everything else as-is.
+pub fn relink(path: &Path, zip_name: &str) -> Result<()> {
+    let mut manifest = read_manifest_strict(path)?
+        .with_context(|| format!("Manifest missing: {}", path.display()))?;
+    manifest.zip = zip_name.to_string();
+    save(path, &manifest)
+}
 
-    let json = serde_json::to_string_pretty(&manifest)?;
+/// Write-to-tmp-then-rename, fsynced so a crash or power loss right after
+/// can't leave a manifest that forgets imports (and re-imports them).
+/// `manifest` holds everything, so the journal beside it goes.
+fn save(path: &Path, manifest: &ImportManifest) -> Result<()> {
+    let json = serde_json::to_string_pretty(manifest)?;
     let tmp_path = path.with_extension("json.tmp");
-    fs::write(&tmp_path, json)?;
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+    }
     fs::rename(&tmp_path, path)?;
+    sync_parent(path)?;
+    match fs::remove_file(journal_path(path)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Persist a rename or a newly created file in `path`'s directory.
+fn sync_parent(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()?;
     Ok(())
 }
 
@@ -114,64 +227,344 @@ pub fn write_manifest(
 pub fn merge_and_write(
     path: &Path,
     zip_name: &str,
-    new_imported: &[(String, String, Option<String>, bool)],
-    new_failed: &[(String, String)],
-    new_live_photo_fallbacks: &[(String, String, String)],
+    new_imported: &[ManifestEntry],
+    new_failed: &[ManifestFailure],
+    new_live_photo_fallbacks: &[ManifestLivePhotoFallback],
 ) -> Result<()> {
-    let mut imported: Vec<(String, String, Option<String>, bool)> = Vec::new();
-    let mut failed: Vec<(String, String)> = Vec::new();
-    let mut live_photo_fallbacks: Vec<(String, String, String)> = Vec::new();
-
-    if let Some(existing) = read_manifest_strict(path)? {
-        imported.extend(existing.imported.into_iter().map(|e| {
-            (
-                e.path,
-                e.local_id,
-                e.creation_date,
-                e.is_live_photo.unwrap_or(false),
-            )
-        }));
-        failed.extend(existing.failed.into_iter().map(|e| (e.path, e.error)));
-        live_photo_fallbacks.extend(existing.live_photo_fallbacks.into_iter().map(|e| {
-            (e.photo_path, e.video_path, e.local_id)
-        }));
-    }
+    merge_with_layout(
+        path,
+        zip_name,
+        new_imported,
+        new_failed,
+        new_live_photo_fallbacks,
+        None,
+    )
+}
 
+/// [`merge_and_write`], replacing the recorded layout when `new_layout` is set.
+fn merge_with_layout(
+    path: &Path,
+    zip_name: &str,
+    new_imported: &[ManifestEntry],
+    new_failed: &[ManifestFailure],
+    new_live_photo_fallbacks: &[ManifestLivePhotoFallback],
+    new_layout: Option<&LayoutInfo>,
+) -> Result<()> {
+    let mut manifest = read_manifest_strict(path)?.unwrap_or_else(|| ImportManifest {
+        zip: zip_name.to_string(),
+        processed_at: String::new(),
+        imported: Vec::new(),
+        failed: Vec::new(),
+        live_photo_fallbacks: Vec::new(),
+        layout: None,
+    });
+    merge_records(
+        &mut manifest,
+        new_imported,
+        new_failed,
+        new_live_photo_fallbacks,
+    );
+    let layout = new_layout.cloned().or(manifest.layout);
+    write_entries(
+        path,
+        zip_name,
+        &manifest.imported,
+        &manifest.failed,
+        &manifest.live_photo_fallbacks,
+        layout,
+    )
+}
+
+/// Append new results to `manifest`, keeping the newest entry per path.
+/// Previously-failed entries that succeeded this time are removed from failed.
+fn merge_records(
+    manifest: &mut ImportManifest,
+    new_imported: &[ManifestEntry],
+    new_failed: &[ManifestFailure],
+    new_live_photo_fallbacks: &[ManifestLivePhotoFallback],
+) {
     // Remove old failures that succeeded on retry
     let newly_imported_paths: HashSet<&str> =
-        new_imported.iter().map(|(p, _, _, _)| p.as_str()).collect();
-    failed.retain(|(p, _)| !newly_imported_paths.contains(p.as_str()));
+        new_imported.iter().map(|e| e.path.as_str()).collect();
+    manifest
+        .failed
+        .retain(|f| !newly_imported_paths.contains(f.path.as_str()));
 
+    let mut imported = std::mem::take(&mut manifest.imported);
     imported.extend_from_slice(new_imported);
     let mut seen = std::collections::HashSet::new();
     let mut deduped = Vec::new();
     for entry in imported.into_iter().rev() {
-        if seen.insert(entry.0.clone()) {
+        if seen.insert(entry.path.clone()) {
             deduped.push(entry);
         }
     }
     deduped.reverse();
-    let imported = deduped;
-    failed.extend_from_slice(new_failed);
+    manifest.imported = deduped;
+    manifest.failed.extend_from_slice(new_failed);
 
+    let mut live_photo_fallbacks = std::mem::take(&mut manifest.live_photo_fallbacks);
     live_photo_fallbacks.extend_from_slice(new_live_photo_fallbacks);
     let mut seen_fb = std::collections::HashSet::new();
     let mut deduped_fb = Vec::new();
     for entry in live_photo_fallbacks.into_iter().rev() {
-        if seen_fb.insert(entry.0.clone()) {
+        if seen_fb.insert(entry.photo_path.clone()) {
             deduped_fb.push(entry);
         }
     }
     deduped_fb.reverse();
-    let live_photo_fallbacks = deduped_fb;
+    manifest.live_photo_fallbacks = deduped_fb;
+}
 
-    write_manifest(path, zip_name, &imported, &failed, &live_photo_fallbacks)
+/// A run's manifest records, saved every `interval` records instead of once
+/// at the end, so a crash loses at most one batch. The first batch is merged
+/// into the manifest (see [`merge_and_write`]); later ones are appended to its
+/// journal, so each save costs one batch rather than a rewrite of the whole
+/// manifest, and [`finish`](Self::finish) folds the journal in once. Readers
+/// fold in a journal a crash left behind. A dry run never writes.
+pub struct ManifestAppender {
+    path: PathBuf,
+    zip_name: String,
+    interval: usize,
+    write: bool,
+    imported: Vec<ManifestEntry>,
+    failed: Vec<ManifestFailure>,
+    live_photo_fallbacks: Vec<ManifestLivePhotoFallback>,
+    layout: Option<LayoutInfo>,
+    /// The manifest has been merged this run; later batches go to the journal
+    merged: bool,
+    journaled: bool,
+}
+
+impl ManifestAppender {
+    pub fn new(path: &Path, zip_name: &str, interval: usize, write: bool) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            zip_name: zip_name.to_string(),
+            interval,
+            write,
+            imported: Vec::new(),
+            failed: Vec::new(),
+            live_photo_fallbacks: Vec::new(),
+            layout: None,
+            merged: false,
+            journaled: false,
+        }
+    }
+
+    /// Record this run's layout in the manifest on the next flush.
+    pub fn set_layout(&mut self, layout: LayoutInfo) {
+        self.layout = Some(layout);
+    }
+
+    pub fn imported(&mut self, entry: ManifestEntry) -> Result<()> {
+        self.imported.push(entry);
+        self.flush_if_due()
+    }
+
+    pub fn failed(&mut self, failure: ManifestFailure) -> Result<()> {
+        self.failed.push(failure);
+        self.flush_if_due()
+    }
+
+    pub fn live_photo_fallback(&mut self, fallback: ManifestLivePhotoFallback) -> Result<()> {
+        self.live_photo_fallbacks.push(fallback);
+        self.flush_if_due()
+    }
+
+    fn flush_if_due(&mut self) -> Result<()> {
+        let pending = self.imported.len() + self.failed.len() + self.live_photo_fallbacks.len();
+        if pending >= self.interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Save everything buffered. Call it before bailing out early, so the
+    /// last partial batch is kept.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.write && !self.merged {
+            merge_with_layout(
+                &self.path,
+                &self.zip_name,
+                &self.imported,
+                &self.failed,
+                &self.live_photo_fallbacks,
+                self.layout.as_ref(),
+            )?;
+            self.merged = true;
+        } else if self.write {
+            self.append_to_journal()?;
+        }
+        self.imported.clear();
+        self.failed.clear();
+        self.live_photo_fallbacks.clear();
+        Ok(())
+    }
+
+    /// [`flush`](Self::flush), then fold the journal into the manifest.
+    pub fn finish(&mut self) -> Result<()> {
+        self.flush()?;
+        if self.journaled {
+            merge_with_layout(&self.path, &self.zip_name, &[], &[], &[], None)?;
+            self.journaled = false;
+        }
+        Ok(())
+    }
+
+    fn append_to_journal(&mut self) -> Result<()> {
+        let records = self
+            .imported
+            .iter()
+            .cloned()
+            .map(JournalRecord::Imported)
+            .chain(self.failed.iter().cloned().map(JournalRecord::Failed))
+            .chain(
+                self.live_photo_fallbacks
+                    .iter()
+                    .cloned()
+                    .map(JournalRecord::LivePhotoFallback),
+            );
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(&record)?);
+            lines.push('\n');
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let journal = journal_path(&self.path);
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal)
+            .with_context(|| format!("Failed to open {}", journal.display()))?;
+        file.write_all(lines.as_bytes())?;
+        file.sync_all()?;
+        if !self.journaled {
+            sync_parent(&journal)?;
+            self.journaled = true;
+        }
+        Ok(())
+    }
+}
+
+// MARK: - Album registry
+
+/// Album title → Photos album local identifier, persisted as
+/// `.photoferry-albums.json` so every zip and run reuses the same album
+/// instead of creating a duplicate with the same title.
+#[derive(Debug)]
+pub struct AlbumRegistry {
+    path: PathBuf,
+    ids: BTreeMap<String, String>,
+    /// `--disambiguate-albums`: album title → the Takeout folder that first
+    /// used it, persisted as `.photoferry-album-folders.json`
+    folders_path: PathBuf,
+    folders: BTreeMap<String, String>,
+    /// Dry run: inserts stay in memory
+    read_only: bool,
+}
+
+fn load_json_map(path: &Path, what: &str) -> Result<BTreeMap<String, String>> {
+    match fs::read_to_string(path) {
+        Ok(c) => serde_json::from_str(&c)
+            .with_context(|| format!("Corrupt {what} JSON at {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn write_json_map(path: &Path, map: &BTreeMap<String, String>) -> Result<()> {
+    let json = serde_json::to_string_pretty(map)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+impl AlbumRegistry {
+    /// Load the registry from `dir`. Missing file = empty; corrupt = Err.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(".photoferry-albums.json");
+        let ids = load_json_map(&path, "album registry")?;
+        let folders_path = dir.join(".photoferry-album-folders.json");
+        let folders = load_json_map(&folders_path, "album folder")?;
+        Ok(Self {
+            path,
+            ids,
+            folders_path,
+            folders,
+            read_only: false,
+        })
+    }
+
+    /// Load for a dry run, whose placeholder album ids must not be saved.
+    pub fn load_read_only(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            read_only: true,
+            ..Self::load(dir)?
+        })
+    }
+
+    pub fn get(&self, title: &str) -> Option<&str> {
+        self.ids.get(title).map(String::as_str)
+    }
+
+    /// Albums photoferry has created in this directory, across runs.
+    pub fn album_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Record a newly created album and write the registry to disk.
+    pub fn insert(&mut self, title: &str, id: &str) -> Result<()> {
+        self.ids.insert(title.to_string(), id.to_string());
+        if self.read_only {
+            return Ok(());
+        }
+        write_json_map(&self.path, &self.ids)
+    }
+
+    /// The album title to use for `folder`: `title` for the first folder
+    /// that presents it, `"{title} ({folder})"` for any other folder with
+    /// the same title, so the two stay separate albums.
+    pub fn disambiguate(&mut self, title: &str, folder: &str) -> Result<String> {
+        match self.folders.get(title) {
+            Some(first) if first == folder => return Ok(title.to_string()),
+            Some(_) => return Ok(format!("{title} ({folder})")),
+            None => {}
+        }
+        self.folders.insert(title.to_string(), folder.to_string());
+        if self.read_only {
+            return Ok(title.to_string());
+        }
+        write_json_map(&self.folders_path, &self.folders)?;
+        Ok(title.to_string())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn entry(path: &str, local_id: &str) -> ManifestEntry {
+        ManifestEntry {
+            path: path.to_string(),
+            local_id: local_id.to_string(),
+            creation_date: None,
+            is_live_photo: None,
+            album: None,
+            sidecar_path: None,
+        }
+    }
+
+    fn failure(path: &str, error: &str) -> ManifestFailure {
+        ManifestFailure {
+            path: path.to_string(),
+            error: error.to_string(),
+        }
+    }
+
     #[test]
     fn test_read_nonexistent() {
         assert!(read_manifest(Path::new("/nonexistent/manifest.json")).is_none());
@@ -185,13 +578,11 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("manifest.json");
 
-        let imported = vec![
-            ("photo.jpg".to_string(), "ABC123".to_string(), None, false),
-            ("sunset.png".to_string(), "DEF456".to_string(), None, false),
-        ];
-        let failed = vec![("corrupt.jpg".to_string(), "bad data".to_string())];
+        let imported = vec![entry("photo.jpg", "ABC123"), entry("sunset.png", "DEF456")];
+        let failed = vec![failure("corrupt.jpg", "bad data")];
 
         write_manifest(&path, "takeout-20240101.zip", &imported, &failed, &[]).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
 
         let manifest = read_manifest(&path).unwrap();
         assert_eq!(manifest.zip, "takeout-20240101.zip");
@@ -202,15 +593,213 @@ mod tests {
         assert_eq!(manifest.imported[0].is_live_photo, Some(false));
     }
 
+    #[test]
+    fn test_album_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+
+        let mut clip = entry("clip.mp4", "VID1");
+        clip.album = Some("Trip (Videos)".to_string());
+        write_manifest(&path, "test.zip", &[clip, entry("a.jpg", "1")], &[], &[]).unwrap();
+
+        let manifest = read_manifest(&path).unwrap();
+        assert_eq!(manifest.imported[0].album.as_deref(), Some("Trip (Videos)"));
+        assert_eq!(manifest.imported[1].album, None);
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(raw.matches("\"album\"").count(), 1);
+    }
+
+    #[test]
+    fn test_reads_manifest_without_album_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        std::fs::write(
+            &path,
+            r#"{"zip":"old.zip","processed_at":"2026-01-01T00:00:00Z",
+                "imported":[{"path":"a.jpg","local_id":"1"}],"failed":[]}"#,
+        )
+        .unwrap();
+
+        let manifest = read_manifest_strict(&path).unwrap().unwrap();
+        assert_eq!(manifest.imported[0].album, None);
+    }
+
+    #[test]
+    fn test_relink_updates_zip_and_keeps_processed_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        write_manifest(&path, "takeout-001.zip", &[entry("a.jpg", "1")], &[], &[]).unwrap();
+        let before = read_manifest(&path).unwrap();
+
+        relink(&path, "2024 photos.zip").unwrap();
+
+        let after = read_manifest(&path).unwrap();
+        assert_eq!(after.zip, "2024 photos.zip");
+        assert_eq!(after.processed_at, before.processed_at);
+        assert_eq!(after.imported.len(), 1);
+    }
+
+    #[test]
+    fn test_album_registry_persists_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = AlbumRegistry::load(dir.path()).unwrap();
+        assert_eq!(registry.get("Vacation"), None);
+
+        registry.insert("Vacation", "ALBUM-1").unwrap();
+
+        let reloaded = AlbumRegistry::load(dir.path()).unwrap();
+        assert_eq!(reloaded.get("Vacation"), Some("ALBUM-1"));
+    }
+
+    #[test]
+    fn test_read_only_album_registry_does_not_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = AlbumRegistry::load_read_only(dir.path()).unwrap();
+        registry.insert("Vacation", "dry-run-album-1").unwrap();
+        assert_eq!(registry.get("Vacation"), Some("dry-run-album-1"));
+        assert!(!dir.path().join(".photoferry-albums.json").exists());
+    }
+
+    #[test]
+    fn test_disambiguate_separates_folders_with_the_same_title() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = AlbumRegistry::load(dir.path()).unwrap();
+        assert_eq!(registry.disambiguate("Untitled", "Untitled").unwrap(), "Untitled");
+        assert_eq!(registry.disambiguate("Untitled", "Untitled").unwrap(), "Untitled");
+        assert_eq!(
+            registry.disambiguate("Untitled", "Untitled(1)").unwrap(),
+            "Untitled (Untitled(1))"
+        );
+
+        // A later zip holding the rest of the second folder gets the same name
+        let mut reloaded = AlbumRegistry::load(dir.path()).unwrap();
+        assert_eq!(
+            reloaded.disambiguate("Untitled", "Untitled(1)").unwrap(),
+            "Untitled (Untitled(1))"
+        );
+        assert_eq!(reloaded.disambiguate("Untitled", "Untitled").unwrap(), "Untitled");
+    }
+
+    #[test]
+    fn test_album_registry_errors_on_corrupt_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".photoferry-albums.json"), "{oops").unwrap();
+        assert!(AlbumRegistry::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_manifest_appender_writes_each_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".photoferry-manifest-takeout-001.json");
+        let mut appender = ManifestAppender::new(&path, "takeout-001.zip", 2, true);
+
+        appender.imported(entry("a.jpg", "A")).unwrap();
+        assert!(read_manifest(&path).is_none());
+        appender
+            .failed(ManifestFailure {
+                path: "b.jpg".to_string(),
+                error: "nope".to_string(),
+            })
+            .unwrap();
+        // Batch of two written: a crash now keeps both records
+        let manifest = read_manifest(&path).unwrap();
+        assert_eq!(manifest.imported.len(), 1);
+        assert_eq!(manifest.failed.len(), 1);
+
+        appender.imported(entry("b.jpg", "B")).unwrap();
+        appender.flush().unwrap();
+        let manifest = read_manifest(&path).unwrap();
+        assert_eq!(manifest.imported.len(), 2);
+        // The retry's success clears the failure from the earlier batch
+        assert!(manifest.failed.is_empty());
+
+        let dry = dir.path().join(".photoferry-manifest-dry.json");
+        let mut appender = ManifestAppender::new(&dry, "dry.zip", 1, false);
+        appender.imported(entry("a.jpg", "A")).unwrap();
+        appender.flush().unwrap();
+        assert!(!dry.exists());
+    }
+
+    #[test]
+    fn test_manifest_appender_journals_later_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".photoferry-manifest-takeout-001.json");
+        let journal = journal_path(&path);
+        let mut appender = ManifestAppender::new(&path, "takeout-001.zip", 1, true);
+        appender.imported(entry("a.jpg", "A")).unwrap();
+        appender.imported(entry("b.jpg", "B")).unwrap();
+        appender.imported(entry("c.jpg", "C")).unwrap();
+
+        // Only the first batch rewrote the manifest; the rest were appended
+        let on_disk: ImportManifest =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk.imported.len(), 1);
+        assert_eq!(fs::read_to_string(&journal).unwrap().lines().count(), 2);
+        // ...and readers see all three, as they would after a crash
+        assert_eq!(
+            read_manifest_strict(&path).unwrap().unwrap().imported.len(),
+            3
+        );
+
+        // A record torn by the crash is dropped; earlier ones survive
+        let intact = fs::read_to_string(&journal).unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&journal).unwrap();
+        file.write_all(br#"{"imported":{"path":"d.j"#).unwrap();
+        let manifest = read_manifest_strict(&path).unwrap().unwrap();
+        assert_eq!(manifest.imported.len(), 3);
+
+        // A corrupt record before the end isn't a torn write
+        file.write_all(b"\n{}\n").unwrap();
+        assert!(read_manifest_strict(&path).is_err());
+        fs::write(&journal, intact).unwrap();
+
+        appender.imported(entry("d.jpg", "D")).unwrap();
+        appender.finish().unwrap();
+        assert!(!journal.exists());
+        let on_disk: ImportManifest =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let paths: Vec<&str> = on_disk.imported.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+    }
+
+    #[test]
+    fn test_layout_is_recorded_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".photoferry-manifest-takeout-001.json");
+        // Older manifests have no layout
+        fs::write(
+            &path,
+            r#"{"zip":"takeout-001.zip","processed_at":"","imported":[],"failed":[]}"#,
+        )
+        .unwrap();
+        assert!(read_manifest(&path).unwrap().layout.is_none());
+
+        let layout = LayoutInfo {
+            content_prefix: "Takeout/".to_string(),
+            directories: 3,
+            standard_takeout: true,
+        };
+        let mut appender = ManifestAppender::new(&path, "takeout-001.zip", 10, true);
+        appender.set_layout(layout.clone());
+        appender.imported(entry("a.jpg", "A")).unwrap();
+        appender.flush().unwrap();
+        assert_eq!(read_manifest(&path).unwrap().layout.as_ref(), Some(&layout));
+
+        // Rewrites by other commands keep it
+        write_manifest(&path, "takeout-001.zip", &[], &[], &[]).unwrap();
+        merge_and_write(&path, "takeout-001.zip", &[entry("b.jpg", "B")], &[], &[]).unwrap();
+        assert_eq!(read_manifest(&path).unwrap().layout, Some(layout));
+    }
+
     #[test]
     fn test_merge_removes_retried_failures() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("manifest.json");
 
-        let failed = vec![("retry.jpg".to_string(), "timeout".to_string())];
+        let failed = vec![failure("retry.jpg", "timeout")];
         write_manifest(&path, "test.zip", &[], &failed, &[]).unwrap();
 
-        let new_imported = vec![("retry.jpg".to_string(), "XYZ789".to_string(), None, false)];
+        let new_imported = vec![entry("retry.jpg", "XYZ789")];
         merge_and_write(&path, "test.zip", &new_imported, &[], &[]).unwrap();
 
         let manifest = read_manifest(&path).unwrap();
@@ -239,22 +828,29 @@ mod tests {
                     local_id: "1".to_string(),
                     creation_date: None,
                     is_live_photo: None,
+                    album: None,
+                    sidecar_path: None,
                 },
                 ManifestEntry {
                     path: "b.jpg".to_string(),
                     local_id: "2".to_string(),
                     creation_date: None,
                     is_live_photo: None,
+                    album: None,
+                    sidecar_path: None,
                 },
                 ManifestEntry {
                     path: "c.jpg".to_string(),
                     local_id: "3".to_string(),
                     creation_date: None,
                     is_live_photo: Some(false),
+                    album: None,
+                    sidecar_path: None,
                 },
             ],
             failed: vec![],
             live_photo_fallbacks: vec![],
+            layout: None,
         };
 
         let set = already_imported(&manifest);
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use serde::Deserialize;
 
 use crate::importer::PhotoMetadata;
@@ -24,13 +24,19 @@ pub(crate) struct TakeoutJson {
 
 #[derive(Debug, Deserialize)]
 struct TimestampField {
+    #[serde(default)]
     timestamp: String,
+    /// Human-readable UTC time (`"Nov 14, 2023, 10:13:20 PM UTC"`); used when
+    /// `timestamp` is empty or garbage
+    formatted: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct GeoData {
     latitude: f64,
     longitude: f64,
+    /// Meters, signed. Takeout has no separate altitude-reference field (EXIF
+    /// `GPSAltitudeRef`); below-sea-level locations arrive as negative values.
     altitude: f64,
 }
 
@@ -47,7 +53,10 @@ pub(crate) struct AlbumData {
 
 // MARK: - Parsing
 
+/// Parse a Takeout JSON file (media sidecar or album `metadata.json`).
+/// A leading UTF-8 byte-order mark, which some exports emit, is ignored.
 pub(crate) fn parse_sidecar(json_bytes: &[u8]) -> Result<TakeoutJson> {
+    let json_bytes = json_bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json_bytes);
     let parsed: TakeoutJson = serde_json::from_slice(json_bytes)?;
     Ok(parsed)
 }
@@ -71,15 +80,24 @@ impl TakeoutJson {
         self.trashed.unwrap_or(false)
     }
 
+    /// People tagged in the photo; no `people` array counts as none.
+    pub(crate) fn people_count(&self) -> usize {
+        self.people.as_ref().map_or(0, Vec::len)
+    }
+
     fn parse_timestamp(&self) -> Option<String> {
-        let ts_str = self.photo_taken_time.as_ref()?.timestamp.as_str();
+        let field = self.photo_taken_time.as_ref()?;
+        let ts_str = field.timestamp.as_str();
 
-        // Empty or zero = no timestamp
-        if ts_str.is_empty() || ts_str == "0" {
+        // Zero = no timestamp
+        if ts_str == "0" {
             return None;
         }
 
-        let epoch: i64 = ts_str.parse().ok()?;
+        let epoch = match ts_str.parse::<i64>() {
+            Ok(epoch) => epoch,
+            Err(_) => parse_formatted_utc(field.formatted.as_deref()?)?,
+        };
         if epoch == 0 {
             return None;
         }
@@ -105,11 +123,53 @@ impl TakeoutJson {
         self.best_geo().map(|g| g.longitude)
     }
 
+    /// Passed through unchanged. If an export ever carries an unsigned
+    /// magnitude (reference byte dropped), there's nothing left to recover
+    /// the sign from, so such altitudes import as above sea level.
     fn best_altitude(&self) -> Option<f64> {
         self.best_geo().map(|g| g.altitude)
     }
 }
 
+/// `--timezone-from-gps`: move a UTC creation date so that, shown in
+/// `display` (this Mac's zone, as Photos shows it), it reads the wall-clock
+/// time where the photo was taken, `utc_offset` seconds east of UTC. None if
+/// the date doesn't parse or that wall-clock time doesn't exist in `display`.
+pub(crate) fn shift_to_local_time<Tz: TimeZone>(
+    creation_date: &str,
+    utc_offset: i32,
+    display: &Tz,
+) -> Option<String> {
+    let utc = DateTime::parse_from_rfc3339(creation_date)
+        .ok()?
+        .naive_utc();
+    let wall = utc + chrono::Duration::seconds(utc_offset.into());
+    let shown = display.from_local_datetime(&wall).earliest()?;
+    Some(
+        shown
+            .with_timezone(&Utc)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string(),
+    )
+}
+
+/// Epoch seconds from a `formatted` time. Google writes these in UTC, in a
+/// few layouts depending on export age and locale; newer exports put a
+/// narrow no-break space before AM/PM.
+fn parse_formatted_utc(formatted: &str) -> Option<i64> {
+    const LAYOUTS: &[&str] = &[
+        "%b %d, %Y, %I:%M:%S %p",
+        "%b %d, %Y, %H:%M:%S",
+        "%d %b %Y, %H:%M:%S",
+    ];
+    let normalized = formatted.replace(['\u{202f}', '\u{a0}'], " ");
+    let text = normalized.trim().strip_suffix("UTC")?.trim_end();
+    LAYOUTS
+        .iter()
+        .find_map(|layout| NaiveDateTime::parse_from_str(text, layout).ok())
+        .map(|dt| dt.and_utc().timestamp())
+}
+
 fn is_zero_gps(geo: &GeoData) -> bool {
     geo.latitude == 0.0 && geo.longitude == 0.0
 }
@@ -120,6 +180,24 @@ fn is_zero_gps(geo: &GeoData) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_shift_to_local_time() {
+        let hong_kong = 8 * 3600;
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        // 22:13 UTC is 06:13 the next morning in Hong Kong
+        assert_eq!(
+            shift_to_local_time("2023-11-14T22:13:20Z", hong_kong, &utc).as_deref(),
+            Some("2023-11-15T06:13:20Z")
+        );
+        // Viewed from New York (UTC-5), 06:13 local is 11:13 UTC
+        let new_york = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(
+            shift_to_local_time("2023-11-14T22:13:20Z", hong_kong, &new_york).as_deref(),
+            Some("2023-11-15T11:13:20Z")
+        );
+        assert_eq!(shift_to_local_time("yesterday", hong_kong, &utc), None);
+    }
+
     #[test]
     fn test_basic_metadata_conversion() {
         let json = r#"{
@@ -141,6 +219,14 @@ mod tests {
         assert_eq!(meta.is_favorite, Some(true));
     }
 
+    #[test]
+    fn test_parse_sidecar_with_bom() {
+        let mut bytes = b"\xEF\xBB\xBF".to_vec();
+        bytes.extend_from_slice(br#"{ "photoTakenTime": { "timestamp": "1700000000" } }"#);
+        let meta = parse_sidecar(&bytes).unwrap().to_photo_metadata();
+        assert_eq!(meta.creation_date.as_deref(), Some("2023-11-14T22:13:20Z"));
+    }
+
     #[test]
     fn test_zero_timestamp() {
         let json = r#"{ "photoTakenTime": { "timestamp": "0" } }"#;
@@ -155,6 +241,36 @@ mod tests {
         assert_eq!(takeout.to_photo_metadata().creation_date, None);
     }
 
+    #[test]
+    fn test_formatted_date_when_timestamp_is_garbage() {
+        for json in [
+            r#"{ "photoTakenTime": { "timestamp": "", "formatted": "Nov 14, 2023, 10:13:20 PM UTC" } }"#,
+            r#"{ "photoTakenTime": { "timestamp": "x17e", "formatted": "Nov 14, 2023, 10:13:20\u202fPM UTC" } }"#,
+            r#"{ "photoTakenTime": { "formatted": "14 Nov 2023, 22:13:20 UTC" } }"#,
+        ] {
+            let takeout: TakeoutJson = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                takeout.to_photo_metadata().creation_date.as_deref(),
+                Some("2023-11-14T22:13:20Z"),
+                "{json}"
+            );
+        }
+        // A valid epoch wins; an unparseable or non-UTC formatted string is no date
+        let json = r#"{ "photoTakenTime": { "timestamp": "1700000000", "formatted": "Jan 1, 2000, 1:00:00 AM UTC" } }"#;
+        let takeout: TakeoutJson = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            takeout.to_photo_metadata().creation_date.as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        for formatted in ["yesterday", "Nov 14, 2023, 10:13:20 PM PST"] {
+            let json = format!(
+                r#"{{ "photoTakenTime": {{ "timestamp": "", "formatted": "{formatted}" }} }}"#
+            );
+            let takeout: TakeoutJson = serde_json::from_str(&json).unwrap();
+            assert_eq!(takeout.to_photo_metadata().creation_date, None);
+        }
+    }
+
     #[test]
     fn test_negative_timestamp() {
         // 1960-01-01 00:00:00 UTC = -315619200
@@ -209,6 +325,16 @@ mod tests {
         assert!(!takeout.is_trashed());
     }
 
+    #[test]
+    fn test_people_count() {
+        let json = r#"{ "people": [{ "name": "Ada" }, { "name": "Grace" }] }"#;
+        let takeout: TakeoutJson = serde_json::from_str(json).unwrap();
+        assert_eq!(takeout.people_count(), 2);
+
+        let takeout: TakeoutJson = serde_json::from_str("{}").unwrap();
+        assert_eq!(takeout.people_count(), 0);
+    }
+
     #[test]
     fn test_geo_data_exif_preferred_over_geo_data() {
         let json = r#"{
@@ -220,4 +346,16 @@ mod tests {
         assert_eq!(meta.latitude, Some(1.0));
         assert_eq!(meta.longitude, Some(2.0));
     }
+
+    #[test]
+    fn test_below_sea_level_altitude_keeps_sign() {
+        // Dead Sea shore
+        let json = r#"{
+            "geoDataExif": { "latitude": 31.5590, "longitude": 35.4732, "altitude": -430.5 }
+        }"#;
+        let takeout: TakeoutJson = serde_json::from_str(json).unwrap();
+        let meta = takeout.to_photo_metadata();
+        assert_eq!(meta.latitude, Some(31.5590));
+        assert_eq!(meta.altitude, Some(-430.5));
+    }
 }
@@ -57,10 +57,19 @@ pub struct PipelineStats {
 }
 
 struct PartStat {
+    part: usize,
     bytes: u64,
     wall_duration: Duration,
 }
 
+fn mb_per_sec(bytes: u64, secs: f64) -> f64 {
+    if secs > 0.0 {
+        (bytes as f64 / 1024.0 / 1024.0) / secs
+    } else {
+        0.0
+    }
+}
+
 impl PipelineStats {
     pub fn new(total_parts: usize) -> Self {
         Self {
@@ -71,9 +80,10 @@ impl PipelineStats {
     }
 
     /// Record completion of one part (download + import wall time).
-    pub fn record_part(&self, bytes: u64, duration: Duration) {
+    pub fn record_part(&self, part: usize, bytes: u64, duration: Duration) {
         let mut parts = self.completed.lock().unwrap();
         parts.push(PartStat {
+            part,
             bytes,
             wall_duration: duration,
         });
@@ -91,11 +101,7 @@ impl PipelineStats {
         let total_secs: f64 = parts.iter().map(|p| p.wall_duration.as_secs_f64()).sum();
 
         let avg_secs = total_secs / done as f64;
-        let avg_mbps = if total_secs > 0.0 {
-            (total_bytes as f64 / 1024.0 / 1024.0) / total_secs
-        } else {
-            0.0
-        };
+        let avg_mbps = mb_per_sec(total_bytes, total_secs);
         let eta_secs = avg_secs * remaining as f64;
         let eta_h = (eta_secs / 3600.0) as u64;
         let eta_m = ((eta_secs % 3600.0) / 60.0) as u64;
@@ -109,4 +115,69 @@ impl PipelineStats {
             self.total_parts
         )
     }
+
+    /// `(part, MB/s)` for each recorded part, in part order.
+    pub fn part_throughput(&self) -> Vec<(usize, f64)> {
+        let parts = self.completed.lock().unwrap();
+        let mut rates: Vec<(usize, f64)> = parts
+            .iter()
+            .map(|p| (p.part, mb_per_sec(p.bytes, p.wall_duration.as_secs_f64())))
+            .collect();
+        rates.sort_by_key(|(part, _)| *part);
+        rates
+    }
+
+    /// Average and peak per-part MB/s, and overall MB/s across the run's wall
+    /// time (which parallel downloads push above the per-part average).
+    pub fn throughput_summary(&self) -> Option<String> {
+        let parts = self.completed.lock().unwrap();
+        throughput_line(&parts, self.pipeline_start.elapsed())
+    }
+}
+
+fn throughput_line(parts: &[PartStat], elapsed: Duration) -> Option<String> {
+    let peak = parts
+        .iter()
+        .map(|p| (p.part, mb_per_sec(p.bytes, p.wall_duration.as_secs_f64())))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let total_bytes: u64 = parts.iter().map(|p| p.bytes).sum();
+    let total_secs: f64 = parts.iter().map(|p| p.wall_duration.as_secs_f64()).sum();
+    Some(format!(
+        "Throughput: avg {:.1} MB/s | peak {:.1} MB/s (part {}) | overall {:.1} MB/s",
+        mb_per_sec(total_bytes, total_secs),
+        peak.1,
+        peak.0,
+        mb_per_sec(total_bytes, elapsed.as_secs_f64())
+    ))
+}
+
+// MARK: - Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn throughput_line_reports_avg_peak_and_overall() {
+        let parts = [
+            PartStat {
+                part: 0,
+                bytes: 100 * MB,
+                wall_duration: Duration::from_secs(10),
+            },
+            PartStat {
+                part: 1,
+                bytes: 300 * MB,
+                wall_duration: Duration::from_secs(10),
+            },
+        ];
+        // Two 10s parts downloaded side by side in 10s overall
+        assert_eq!(
+            throughput_line(&parts, Duration::from_secs(10)).unwrap(),
+            "Throughput: avg 20.0 MB/s | peak 30.0 MB/s (part 1) | overall 40.0 MB/s"
+        );
+        assert_eq!(throughput_line(&[], Duration::from_secs(10)), None);
+    }
 }
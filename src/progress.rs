@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+static SINK: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// One line of `--progress-fd` output. `current`/`total` are bytes; `total`
+/// is 0 when unknown (Chrome downloads).
+#[derive(Debug, Serialize)]
+struct ProgressEvent<'a> {
+    phase: &'a str,
+    current: u64,
+    total: u64,
+    current_file: Option<&'a str>,
+}
+
+/// `--progress-fd`: send progress events to an inherited file descriptor.
+/// Opened through `/dev/fd` so the descriptor is duplicated, not adopted.
+pub fn set_progress_fd(fd: u32) -> Result<()> {
+    let file = File::options()
+        .append(true)
+        .open(format!("/dev/fd/{fd}"))
+        .with_context(|| format!("--progress-fd {fd}: descriptor is not open for writing"))?;
+    let _ = SINK.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Write one event as a JSON line. No-op without `--progress-fd`; write
+/// errors (wrapper went away) are ignored so they never fail an import.
+pub fn emit(phase: &str, current: u64, total: u64, current_file: Option<&str>) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let line = event_line(&ProgressEvent {
+        phase,
+        current,
+        total,
+        current_file,
+    });
+    if let Ok(mut file) = sink.lock() {
+        let _ = file.write_all(line.as_bytes());
+    }
+}
+
+fn event_line(event: &ProgressEvent) -> String {
+    let mut line = serde_json::to_string(event).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_line_is_one_json_object_per_line() {
+        let line = event_line(&ProgressEvent {
+            phase: "import",
+            current: 5,
+            total: 10,
+            current_file: Some("IMG_0001.jpg"),
+        });
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["phase"], "import");
+        assert_eq!(value["current"], 5);
+        assert_eq!(value["total"], 10);
+        assert_eq!(value["current_file"], "IMG_0001.jpg");
+    }
+}
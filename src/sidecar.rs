@@ -1,7 +1,11 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 const TRUNCATION_LIMIT: usize = 46;
 
+/// Suffixes Google Photos adds to the stem of an edited copy (English, German).
+const EDITED_SUFFIXES: [&str; 4] = ["-edited", "_edited", "-bearbeitet", "_bearbeitet"];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SidecarMatchStrength {
     Strong,
@@ -72,10 +76,8 @@ fn match_normal(media: &Path, candidates: &[PathBuf]) -> Option<PathBuf> {
             continue;
         };
 
-        // Strip .supplemental-metadata or .supplemental-meta* prefix variants
-        let without_supplemental = strip_supplemental_suffix(without_json);
-
-        let (cand_base, cand_dedup) = strip_dedup_index(without_supplemental);
+        // Strip .supplemental-meta* and the dedup index, in whichever order Google used
+        let (cand_base, cand_dedup) = strip_sidecar_suffixes(without_json);
 
         // Dedup indices must match (both None, or both same value)
         if media_dedup != cand_dedup {
@@ -145,13 +147,8 @@ fn match_edited(media: &Path, candidates: &[PathBuf]) -> Option<PathBuf> {
         let json_base = strip_supplemental_suffix(without_json);
         let json_stem = strip_last_extension(json_base);
 
-        if !json_stem.is_empty() && media_stem != json_stem {
-            let edited_suffixes = ["-edited", "_edited", "-bearbeitet", "_bearbeitet"];
-            for suffix in edited_suffixes {
-                if media_stem == format!("{json_stem}{suffix}") {
-                    return Some(candidate.clone());
-                }
-            }
+        if !json_stem.is_empty() && edited_original_stem(media_stem) == Some(json_stem) {
+            return Some(candidate.clone());
         }
     }
 
@@ -160,6 +157,14 @@ fn match_edited(media: &Path, candidates: &[PathBuf]) -> Option<PathBuf> {
 
 // MARK: - Helpers
 
+/// The original's stem if `stem` names an edited copy (`photo-edited` → `photo`).
+pub fn edited_original_stem(stem: &str) -> Option<&str> {
+    EDITED_SUFFIXES
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .filter(|base| !base.is_empty())
+}
+
 /// Strip `(N)` dedup index from filename. Returns (base_without_index, Option<index>).
 ///
 /// Google Takeout adds `(N)` in two positions:
@@ -189,6 +194,25 @@ fn strip_supplemental_suffix(name: &str) -> &str {
     }
 }
 
+/// Strip both the supplemental-metadata suffix and the dedup index from a JSON
+/// name (`.json` already removed). Google emits them in either order:
+/// - `photo.jpg(1).supplemental-metadata` → index before the suffix
+/// - `photo.jpg.supplemental-metadata(1)` → index after the suffix
+///
+/// Stripping the suffix first drops everything after it, including a trailing
+/// `(1)`, so try both orders and keep the one that saw the index.
+fn strip_sidecar_suffixes(name: &str) -> (String, Option<u32>) {
+    let suffix_first = strip_dedup_index(strip_supplemental_suffix(name));
+    if suffix_first.1.is_some() {
+        return suffix_first;
+    }
+    let (deduped, index) = strip_dedup_index(name);
+    if index.is_some() {
+        return (strip_supplemental_suffix(&deduped).to_string(), index);
+    }
+    suffix_first
+}
+
 /// Truncate a string to at most `max_chars` Unicode characters.
 fn truncate_utf8(s: &str, max_chars: usize) -> String {
     s.chars().take(max_chars).collect()
@@ -203,7 +227,26 @@ fn strip_last_extension(name: &str) -> &str {
     }
 }
 
+/// Whether a sidecar's `title` plausibly names `media_name`. Case-insensitive
+/// and ignores extensions and dedup indices; either name may be a prefix of the
+/// other (truncation, `-edited`). An empty side can't contradict the other.
+pub fn title_matches_media(title: &str, media_name: &str) -> bool {
+    let normalize = |name: &str| {
+        let (base, _) = strip_dedup_index(name);
+        strip_last_extension(&base).to_lowercase()
+    };
+    let title = normalize(title);
+    let media = normalize(media_name);
+    if title.is_empty() || media.is_empty() {
+        return true;
+    }
+    title.starts_with(&media) || media.starts_with(&title)
+}
+
 /// Collect all `.json` files from a flat list of paths (for a single directory).
+/// `metadata.json` describes the directory (album or not), never one photo,
+/// so it is left out — the prefix patterns would otherwise match it to any
+/// `metadata*` media.
 pub fn collect_json_candidates(files: &[PathBuf]) -> Vec<PathBuf> {
     files
         .iter()
@@ -212,10 +255,31 @@ pub fn collect_json_candidates(files: &[PathBuf]) -> Vec<PathBuf> {
                 .and_then(|e| e.to_str())
                 .is_some_and(|e| e.eq_ignore_ascii_case("json"))
         })
+        .filter(|f| {
+            !f.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.eq_ignore_ascii_case("metadata.json"))
+        })
         .cloned()
         .collect()
 }
 
+/// Sidecars in a directory that no media file matches. A directory full of
+/// them usually means the matcher doesn't know that naming pattern.
+pub fn orphaned_sidecars<'a>(
+    media: &[PathBuf],
+    json_candidates: &'a [PathBuf],
+) -> Vec<&'a PathBuf> {
+    let matched: HashSet<PathBuf> = media
+        .iter()
+        .filter_map(|m| find_sidecar_with_strength(m, json_candidates).map(|s| s.path))
+        .collect();
+    json_candidates
+        .iter()
+        .filter(|j| !matched.contains(*j))
+        .collect()
+}
+
 // MARK: - Tests
 
 #[cfg(test)]
@@ -348,6 +412,43 @@ mod tests {
         assert_eq!(match_fast_track(Path::new("photo.jpg"), &candidates), None);
     }
 
+    #[test]
+    fn test_dedup_supplemental_index_before_suffix() {
+        let candidates = pbs(&[
+            "photo.jpg.supplemental-metadata.json",
+            "photo.jpg(1).supplemental-metadata.json",
+        ]);
+        assert_eq!(
+            find_sidecar(Path::new("photo(1).jpg"), &candidates),
+            Some(pb("photo.jpg(1).supplemental-metadata.json"))
+        );
+    }
+
+    #[test]
+    fn test_dedup_supplemental_index_after_suffix() {
+        let candidates = pbs(&[
+            "photo.jpg.supplemental-metadata.json",
+            "photo.jpg.supplemental-metadata(1).json",
+        ]);
+        assert_eq!(
+            find_sidecar(Path::new("photo(1).jpg"), &candidates),
+            Some(pb("photo.jpg.supplemental-metadata(1).json"))
+        );
+    }
+
+    #[test]
+    fn test_undeduped_media_skips_deduped_supplemental() {
+        // The `(1)` sidecar belongs to photo(1).jpg, even though it's listed first
+        let candidates = pbs(&[
+            "photo.jpg.supplemental-metadata(1).json",
+            "photo.jpg.supplemental-metadata.json",
+        ]);
+        assert_eq!(
+            find_sidecar(Path::new("photo.jpg"), &candidates),
+            Some(pb("photo.jpg.supplemental-metadata.json"))
+        );
+    }
+
     // -- forgotten_duplicates --
 
     #[test]
@@ -395,6 +496,20 @@ mod tests {
         assert_eq!(matched.strength, SidecarMatchStrength::Fuzzy);
     }
 
+    #[test]
+    fn test_title_matches_media() {
+        assert!(title_matches_media("IMG_1234.HEIC", "img_1234.jpg"));
+        assert!(title_matches_media("photo.jpg", "photo-edited.jpg"));
+        assert!(title_matches_media("photo.jpg", "photo(1).jpg"));
+        assert!(title_matches_media(
+            "a_very_long_original_google_photos_filename_here.jpg",
+            "a_very_long_original_google_photos_filena.jpg"
+        ));
+        assert!(title_matches_media("", "photo.jpg"));
+        assert!(!title_matches_media("IMG_5678.jpg", "IMG_1234.jpg"));
+        assert!(!title_matches_media("beach.jpg", "photo-edited.jpg"));
+    }
+
     // -- no match --
 
     #[test]
@@ -419,6 +534,18 @@ mod tests {
         assert_eq!(idx, None);
     }
 
+    #[test]
+    fn test_strip_sidecar_suffixes_either_order() {
+        let expected = ("photo.jpg".to_string(), Some(1));
+        assert_eq!(strip_sidecar_suffixes("photo.jpg(1).supplemental-metadata"), expected);
+        assert_eq!(strip_sidecar_suffixes("photo.jpg.supplemental-metadata(1)"), expected);
+        assert_eq!(strip_sidecar_suffixes("photo.jpg.supplemental-metad(1)"), expected);
+        assert_eq!(
+            strip_sidecar_suffixes("photo.jpg.supplemental-metadata"),
+            ("photo.jpg".to_string(), None)
+        );
+    }
+
     #[test]
     fn test_strip_dedup_index_not_a_number() {
         let (base, idx) = strip_dedup_index("photo(abc).jpg");
@@ -448,4 +575,40 @@ mod tests {
         let jsons = collect_json_candidates(&files);
         assert_eq!(jsons.len(), 2);
     }
+
+    #[test]
+    fn test_directory_metadata_json_is_not_a_sidecar() {
+        // An album holding one photo and the album's (or an older export's
+        // album-less) metadata.json
+        let files = pbs(&["Album/metadata(1).jpg", "Album/metadata.json"]);
+        let candidates = collect_json_candidates(&files);
+        assert!(candidates.is_empty());
+        assert!(find_sidecar_with_strength(&files[0], &candidates).is_none());
+
+        // Without the filter, the forgotten-duplicate pattern would take it
+        assert!(find_sidecar_with_strength(&files[0], &pbs(&["Album/metadata.json"])).is_some());
+    }
+
+    #[test]
+    fn test_edited_original_stem() {
+        assert_eq!(edited_original_stem("photo-edited"), Some("photo"));
+        assert_eq!(
+            edited_original_stem("IMG_0001_bearbeitet"),
+            Some("IMG_0001")
+        );
+        assert_eq!(edited_original_stem("photo"), None);
+        assert_eq!(edited_original_stem("-edited"), None);
+    }
+
+    #[test]
+    fn test_orphaned_sidecars() {
+        let media = pbs(&["Album/IMG_0001.jpg", "Album/IMG_0002.jpg"]);
+        let json = pbs(&[
+            "Album/IMG_0001.jpg.json",
+            "Album/IMG_0003.jpg.json",
+            "Album/IMG_0002.jpg.supplemental-metadata.json",
+        ]);
+        assert_eq!(orphaned_sidecars(&media, &json), vec![&json[1]]);
+        assert!(orphaned_sidecars(&media, &[]).is_empty());
+    }
 }
@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
@@ -62,6 +62,9 @@ pub struct InventoryStats {
 
 const PHOTO_EXTENSIONS: &[&str] = &[
     "jpg", "jpeg", "png", "gif", "heic", "heif", "webp", "tiff", "tif", "bmp",
+    // Newer formats. Photos only accepts these on recent macOS; elsewhere the
+    // import fails and lands in the manifest's `failed` list (zip kept)
+    "avif", "jxl",
     // RAW formats
     "raw", "cr2", "cr3", "nef", "arw", "sr2", "dng", "orf", "rw2", "raf", "srw", "x3f", "3fr",
     "pef", "mos", "iiq", "erf", "mef", "nrw", "kdc",
@@ -83,6 +86,14 @@ pub(crate) fn classify_extension(ext: &str) -> Option<MediaType> {
     }
 }
 
+/// Non-media files Google adds to every Takeout export. These are neither
+/// importable nor "unknown", so they shouldn't trip `--strict-extensions`.
+const TAKEOUT_BOOKKEEPING_FILES: &[&str] = &["archive_browser.html", "readme.txt", "readme.html"];
+
+pub(crate) fn is_takeout_bookkeeping(filename: &str) -> bool {
+    TAKEOUT_BOOKKEEPING_FILES.contains(&filename.to_ascii_lowercase().as_str())
+}
+
 pub fn media_type_from_path(path: &Path) -> Option<MediaType> {
     let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
     classify_extension(ext)
@@ -90,7 +101,9 @@ pub fn media_type_from_path(path: &Path) -> Option<MediaType> {
 
 // MARK: - ZIP discovery
 
-/// Find Takeout ZIP files in a directory.
+/// Find Takeout ZIP files in a directory. Takeout `.tgz`/`.tar.gz` exports
+/// are listed too, unless their repacked ZIP (see [`repack_tarball_as_zip`])
+/// already sits beside them.
 pub fn find_takeout_zips(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut zips = Vec::new();
 
@@ -115,11 +128,14 @@ pub fn find_takeout_zips(dir: &Path) -> Result<Vec<PathBuf>> {
         }
 
         let name_lower = name.to_ascii_lowercase();
-        if name_lower.ends_with(".zip")
-            && (name_lower.starts_with("takeout-")
-                || name_lower.starts_with("takeout ")
-                || name_lower.contains("-takeout-"))
-        {
+        let is_takeout = name_lower.starts_with("takeout-")
+            || name_lower.starts_with("takeout ")
+            || name_lower.contains("-takeout-");
+        if !is_takeout {
+            continue;
+        }
+        let is_zip = name_lower.ends_with(".zip");
+        if is_zip || (is_tarball(&path) && !tarball_zip_path(&path).exists()) {
             zips.push(path);
         }
     }
@@ -130,28 +146,313 @@ pub fn find_takeout_zips(dir: &Path) -> Result<Vec<PathBuf>> {
 
 // MARK: - ZIP extraction
 
+/// Name of the Google Photos folder inside `Takeout/`, per export language.
+const PHOTOS_PRODUCT_DIRS: &[&str] = &[
+    "Google Photos",
+    "Google Fotos",
+    "Google Foto",
+    "Google Foto's",
+    "Google Фото",
+    "Google フォト",
+    "Google 포토",
+    "Google 相簿",
+    "Google 相册",
+    "Zdjęcia Google",
+];
+
+/// Where Photos content starts inside a Takeout ZIP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContentRoot {
+    /// Prefix stripped from entry names: `""`, `Takeout/`, or
+    /// `Takeout/Google Photos/` for a multi-product export
+    pub prefix: String,
+    /// Other Takeout products (Drive, Mail, ...) sit beside Google Photos;
+    /// entries outside `prefix` are theirs and must not be imported
+    pub other_products: bool,
+}
+
+/// Detect the content root from a ZIP's entry names.
+///
+/// Google wraps everything in `Takeout/`. A Photos-only export keeps its
+/// `Google Photos/` folder in relative paths (existing manifests rely on
+/// that); only when another product shares the export does the Photos folder
+/// itself become the root.
+pub(crate) fn detect_content_root<'a>(names: impl IntoIterator<Item = &'a str>) -> ContentRoot {
+    let mut wrapped = false;
+    let mut products: HashSet<&str> = HashSet::new();
+    for name in names {
+        let Some(rest) = name.strip_prefix("Takeout/") else {
+            continue;
+        };
+        wrapped = true;
+        if let Some((product, _)) = rest.split_once('/') {
+            products.insert(product);
+        }
+    }
+    if !wrapped {
+        return ContentRoot {
+            prefix: String::new(),
+            other_products: false,
+        };
+    }
+    let photos = products
+        .iter()
+        .find(|p| PHOTOS_PRODUCT_DIRS.contains(p))
+        .filter(|_| products.len() > 1);
+    match photos {
+        Some(photos) => ContentRoot {
+            prefix: format!("Takeout/{photos}/"),
+            other_products: true,
+        },
+        None => ContentRoot {
+            prefix: "Takeout/".to_string(),
+            other_products: false,
+        },
+    }
+}
+
+/// Whether a zip looks like an ordinary Takeout export: wrapped in
+/// `Takeout/`, with every directory (relative to the content root) inside a
+/// Google Photos folder.
+pub(crate) fn is_standard_takeout<'a>(
+    root: &ContentRoot,
+    dirs: impl IntoIterator<Item = &'a str>,
+) -> bool {
+    if root.prefix.is_empty() {
+        return false;
+    }
+    // The multi-product root is the Photos folder itself
+    root.other_products
+        || dirs.into_iter().all(|dir| {
+            let product = dir.split('/').next().unwrap_or(dir);
+            PHOTOS_PRODUCT_DIRS.contains(&product)
+        })
+}
+
 /// Extract a Takeout ZIP to a destination directory. Returns the content root
-/// (handles the `Takeout/` wrapper subfolder Google adds).
+/// (see [`detect_content_root`]).
 pub fn extract_zip(zip_path: &Path, dest: &Path) -> Result<PathBuf> {
     let file = fs::File::open(zip_path)
         .with_context(|| format!("Cannot open ZIP: {}", zip_path.display()))?;
     let reader = BufReader::new(file);
     let mut archive = zip::ZipArchive::new(reader)
         .with_context(|| format!("Invalid ZIP: {}", zip_path.display()))?;
+    let root = detect_content_root(archive.file_names());
 
     archive
         .extract(dest)
         .with_context(|| format!("Failed to extract ZIP: {}", zip_path.display()))?;
 
-    // Google Takeout wraps everything in a `Takeout/` subfolder
-    let takeout_dir = dest.join("Takeout");
-    if takeout_dir.is_dir() {
-        Ok(takeout_dir)
+    let content_dir = dest.join(root.prefix.trim_end_matches('/'));
+    if !root.prefix.is_empty() && content_dir.is_dir() {
+        Ok(content_dir)
     } else {
         Ok(dest.to_path_buf())
     }
 }
 
+// MARK: - Tarball exports
+
+/// Whether `path` is a `.tgz`/`.tar.gz` Takeout export rather than a ZIP.
+pub fn is_tarball(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    name.ends_with(".tgz") || name.ends_with(".tar.gz")
+}
+
+/// The ZIP a tarball is repacked into: `takeout-001.tgz` → `takeout-001.zip`.
+pub fn tarball_zip_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    let stem_len = [".tar.gz", ".tgz"]
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map_or(name.len(), |ext| name.len() - ext.len());
+    path.with_file_name(format!("{}.zip", &name[..stem_len]))
+}
+
+/// Repack a Takeout tarball into a ZIP beside it and return the ZIP's path.
+///
+/// The import pipeline reads entries by index and extracts them one at a
+/// time, which a gzip stream can't do. Repacking once (stored, no
+/// recompression — media is already compressed) lets tarball exports use the
+/// same pipeline, manifests, and resume logic as ZIPs. The tarball is kept;
+/// it can be deleted once the ZIP exists. Returns the existing ZIP if the
+/// tarball was already repacked.
+pub fn repack_tarball_as_zip(tar_path: &Path) -> Result<PathBuf> {
+    let zip_path = tarball_zip_path(tar_path);
+    if zip_path.exists() {
+        return Ok(zip_path);
+    }
+    let partial = zip_path.with_extension("zip.partial");
+    let file = fs::File::open(tar_path)
+        .with_context(|| format!("Cannot open tarball: {}", tar_path.display()))?;
+    let gz = flate2::read::MultiGzDecoder::new(BufReader::new(file));
+    let out = fs::File::create(&partial)
+        .with_context(|| format!("Cannot create {}", partial.display()))?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(out));
+
+    let result = for_each_tar_file(gz, |name, size, data| {
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .large_file(size >= u64::from(u32::MAX));
+        zip.start_file(name, options)?;
+        std::io::copy(data, &mut zip)?;
+        Ok(())
+    })
+    .and_then(|()| zip.finish().map(|_| ()).map_err(Into::into))
+    .with_context(|| format!("Failed to repack tarball: {}", tar_path.display()));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &zip_path)
+        .with_context(|| format!("Cannot rename to {}", zip_path.display()))?;
+    Ok(zip_path)
+}
+
+const TAR_BLOCK: usize = 512;
+
+/// Call `f` with the path, size, and contents of each regular file in a tar
+/// stream. Handles ustar prefixes, GNU long names, and pax `path` records —
+/// Takeout paths routinely exceed the 100-byte name field — and pax `size`
+/// records, the only place POSIX tars store sizes of 8 GiB and up.
+fn for_each_tar_file<R: Read>(
+    mut reader: R,
+    mut f: impl FnMut(&str, u64, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let mut header = [0u8; TAR_BLOCK];
+    let mut long_name: Option<String> = None;
+    let mut pax_size: Option<u64> = None;
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+        let checksum = tar_number(&header[148..156])?;
+        let actual: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum();
+        if checksum != actual {
+            anyhow::bail!("Corrupt tar header (checksum mismatch)");
+        }
+        let header_size = tar_number(&header[124..136])?;
+        let size = match header[156] {
+            b'L' | b'x' => header_size,
+            _ => pax_size.take().unwrap_or(header_size),
+        };
+        let padding = (TAR_BLOCK as u64 - size % TAR_BLOCK as u64) % TAR_BLOCK as u64;
+        let mut data = (&mut reader).take(size);
+        match header[156] {
+            // GNU long name, or pax extended header: names the next entry
+            b'L' | b'x' => {
+                let mut bytes = Vec::new();
+                data.read_to_end(&mut bytes)?;
+                if header[156] == b'L' {
+                    long_name = Some(tar_str(&bytes).to_string());
+                } else {
+                    long_name = pax_record(&bytes, "path")
+                        .and_then(|v| String::from_utf8(v.to_vec()).ok())
+                        .or(long_name);
+                    pax_size = pax_record(&bytes, "size")
+                        .and_then(|v| std::str::from_utf8(v).ok()?.parse().ok());
+                }
+            }
+            b'0' | 0 => {
+                let name = match long_name.take() {
+                    Some(name) => name,
+                    None => tar_header_path(&header),
+                };
+                f(&name, size, &mut data)?;
+            }
+            _ => long_name = None,
+        }
+        // Drain whatever `f` didn't read, then the block padding
+        std::io::copy(&mut data, &mut std::io::sink())?;
+        std::io::copy(&mut (&mut reader).take(padding), &mut std::io::sink())?;
+    }
+}
+
+/// Fill one block; `false` at a clean end of stream.
+fn read_block(reader: &mut impl Read, block: &mut [u8; TAR_BLOCK]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < TAR_BLOCK {
+        match reader.read(&mut block[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => anyhow::bail!("Truncated tar stream"),
+            n => filled += n,
+        }
+    }
+    Ok(true)
+}
+
+/// A NUL-terminated header field.
+fn tar_str(field: &[u8]) -> &str {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    std::str::from_utf8(&field[..end]).unwrap_or_default()
+}
+
+/// An octal header field, or GNU base-256 for sizes past 8 GB.
+fn tar_number(field: &[u8]) -> Result<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Ok(field[1..]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 8) | u64::from(b)));
+    }
+    let text = tar_str(field).trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).with_context(|| format!("Bad tar number field: {text:?}"))
+}
+
+/// `prefix/name` from a ustar header.
+fn tar_header_path(header: &[u8; TAR_BLOCK]) -> String {
+    let name = tar_str(&header[0..100]);
+    let prefix = if &header[257..262] == b"ustar" {
+        tar_str(&header[345..500])
+    } else {
+        ""
+    };
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+/// The value of `key`'s record in a pax extended header
+/// (`"<len> <key>=<value>\n"`).
+fn pax_record<'a>(records: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    let mut rest = records;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?;
+        if let Some(value) = record
+            .strip_prefix(key.as_bytes())
+            .and_then(|r| r.strip_prefix(b"="))
+        {
+            return Some(value.strip_suffix(b"\n").unwrap_or(value));
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
 // MARK: - Directory scanning
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -356,6 +657,12 @@ fn collect_directory_contents(root: &Path) -> Result<HashMap<PathBuf, DirectoryE
             dir_entry.json_files.push(path);
         } else if classify_extension(&ext).is_some() {
             dir_entry.media_files.push(path);
+        } else if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_takeout_bookkeeping)
+        {
+            continue;
         } else {
             dir_entry.unknown_files.push(path);
         }
@@ -364,18 +671,152 @@ fn collect_directory_contents(root: &Path) -> Result<HashMap<PathBuf, DirectoryE
     Ok(dirs)
 }
 
+// MARK: - Motion photo detection
+
+/// XMP flags Google (Pixel `.MP.jpg`, older `MVIMG_`) and Samsung cameras set
+/// on a still that has an MP4 appended after the image data.
+const MOTION_PHOTO_MARKERS: &[&[u8]] = &[
+    b"MotionPhoto=\"1\"",
+    b"MicroVideo=\"1\"",
+    b"MotionPhoto>1<",
+    b"MicroVideo>1<",
+];
+
+/// JPEG keeps XMP in APP1 near the start, so the video itself is never read.
+/// HEIC motion photos with XMP further in are still imported as stills.
+const MOTION_PHOTO_SCAN_BYTES: u64 = 256 * 1024;
+
+/// Whether a photo is a single-file motion photo (still plus embedded video).
+pub(crate) fn is_motion_photo(path: &Path) -> bool {
+    fs::File::open(path).is_ok_and(is_motion_photo_in)
+}
+
+/// [`is_motion_photo`] for a photo read from `reader`, e.g. a ZIP entry.
+pub(crate) fn is_motion_photo_in(reader: impl Read) -> bool {
+    let mut head = Vec::new();
+    if reader
+        .take(MOTION_PHOTO_SCAN_BYTES)
+        .read_to_end(&mut head)
+        .is_err()
+    {
+        return false;
+    }
+    MOTION_PHOTO_MARKERS
+        .iter()
+        .any(|marker| head.windows(marker.len()).any(|w| w == *marker))
+}
+
+// MARK: - Animated GIF detection
+
+/// Whether a file is a GIF with more than one frame. Some macOS versions
+/// import those as a still of the first frame.
+pub(crate) fn is_animated_gif(path: &Path) -> bool {
+    is_gif_path(path) && fs::File::open(path).is_ok_and(|file| is_animated_gif_in(path, file))
+}
+
+/// [`is_animated_gif`] for a file named `path` read from `reader`, e.g. a
+/// ZIP entry.
+pub(crate) fn is_animated_gif_in(path: &Path, mut reader: impl Read) -> bool {
+    let mut bytes = Vec::new();
+    is_gif_path(path) && reader.read_to_end(&mut bytes).is_ok() && gif_frame_count(&bytes) > 1
+}
+
+fn is_gif_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("gif"))
+}
+
+/// Image descriptors in a GIF stream, walking its blocks. Counts what it
+/// reached if the file is truncated; 0 if it isn't a GIF.
+fn gif_frame_count(bytes: &[u8]) -> usize {
+    /// Bytes in a color table whose size field is `packed & 7`
+    fn color_table(packed: u8) -> usize {
+        if packed & 0x80 == 0 {
+            0
+        } else {
+            3 << ((packed & 7) + 1)
+        }
+    }
+    /// Position after a run of data sub-blocks starting at `pos`
+    fn skip_sub_blocks(bytes: &[u8], mut pos: usize) -> Option<usize> {
+        loop {
+            let len = usize::from(*bytes.get(pos)?);
+            pos += 1;
+            if len == 0 {
+                return Some(pos);
+            }
+            pos += len;
+        }
+    }
+
+    if !bytes.starts_with(b"GIF87a") && !bytes.starts_with(b"GIF89a") {
+        return 0;
+    }
+    let Some(&screen_packed) = bytes.get(10) else {
+        return 0;
+    };
+    let mut pos = 13 + color_table(screen_packed);
+    let mut frames = 0;
+    loop {
+        let next = match bytes.get(pos) {
+            // Extension: introducer, label, data sub-blocks
+            Some(0x21) => skip_sub_blocks(bytes, pos + 2),
+            // Image: 10-byte descriptor, local color table, LZW code size, data
+            Some(0x2C) => {
+                frames += 1;
+                bytes
+                    .get(pos + 9)
+                    .and_then(|&packed| skip_sub_blocks(bytes, pos + 11 + color_table(packed)))
+            }
+            // Trailer, or not a block we understand
+            _ => None,
+        };
+        match next {
+            Some(p) => pos = p,
+            None => return frames,
+        }
+    }
+}
+
 // MARK: - Album detection
 
 /// Check if a directory is an album folder by looking for a `metadata.json` with album data.
-pub(crate) fn detect_album(_dir: &Path, json_files: &[PathBuf]) -> Option<String> {
+/// The title is sanitized (see [`sanitize_album_title`]).
+pub(crate) fn detect_album(dir: &Path, json_files: &[PathBuf]) -> Option<String> {
+    detect_album_with_original(dir, json_files).map(|(title, _)| title)
+}
+
+/// [`detect_album`], plus the title as Takeout spelled it.
+pub(crate) fn detect_album_with_original(
+    _dir: &Path,
+    json_files: &[PathBuf],
+) -> Option<(String, String)> {
     // First check: directory-level metadata.json
     let metadata_path = json_files
         .iter()
         .find(|p| p.file_name().and_then(|n| n.to_str()) == Some("metadata.json"))?;
 
     let bytes = fs::read(metadata_path).ok()?;
-    let parsed: metadata::TakeoutJson = serde_json::from_slice(&bytes).ok()?;
-    parsed.album_data.map(|a| a.title)
+    let parsed = metadata::parse_sidecar(&bytes).ok()?;
+    let original = parsed.album_data?.title;
+    let title = sanitize_album_title(&original);
+    (!title.is_empty()).then_some((title, original))
+}
+
+/// Make an album title safe to hand to Photos: path separators become `-`,
+/// control characters become spaces, runs of whitespace collapse to one
+/// space, and the ends are trimmed.
+pub(crate) fn sanitize_album_title(title: &str) -> String {
+    let replaced: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    replaced.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 /// Check if directory name matches `Photos from YYYY` pattern — these aren't albums.
@@ -428,6 +869,50 @@ pub(crate) fn detect_live_photo_pairs(media_files: &[PathBuf]) -> HashMap<PathBu
     pairs
 }
 
+// MARK: - Burst detection
+
+/// Which burst a file belongs to and whether it's the cover, from Takeout's
+/// `_BURST` names: Pixel numbers frames (`IMG_20190101_120000_BURST001`),
+/// older exports number the prefix and share a timestamp
+/// (`00001IMG_00001_BURST20190101120000`). Google marks the cover `_COVER`.
+fn burst_frame(path: &Path) -> Option<(String, bool)> {
+    let stem = path.file_stem()?.to_str()?.to_ascii_uppercase();
+    let at = stem.find("_BURST")?;
+    let (prefix, rest) = (&stem[..at], &stem[at + "_BURST".len()..]);
+    let digits_len = rest.chars().take_while(char::is_ascii_digit).count();
+    if digits_len == 0 || prefix.is_empty() {
+        return None;
+    }
+    let (digits, suffix) = rest.split_at(digits_len);
+    let cover = match suffix {
+        "" => false,
+        "_COVER" => true,
+        _ => return None,
+    };
+    let key = if digits_len >= 14 { digits } else { prefix };
+    Some((key.to_string(), cover))
+}
+
+/// Burst sequences (two or more frames) among one directory's media, keyed by
+/// burst id. Each sequence lists the cover first (the `_COVER` frame, else the
+/// first by name), then the other frames in name order.
+pub(crate) fn detect_burst_sequences(media_files: &[PathBuf]) -> BTreeMap<String, Vec<&PathBuf>> {
+    let mut bursts: BTreeMap<String, Vec<(bool, &PathBuf)>> = BTreeMap::new();
+    for path in media_files {
+        if let Some((key, cover)) = burst_frame(path) {
+            bursts.entry(key).or_default().push((cover, path));
+        }
+    }
+    bursts
+        .into_iter()
+        .filter(|(_, frames)| frames.len() > 1)
+        .map(|(key, mut frames)| {
+            frames.sort_by(|(a_cover, a), (b_cover, b)| b_cover.cmp(a_cover).then(a.cmp(b)));
+            (key, frames.into_iter().map(|(_, p)| p).collect())
+        })
+        .collect()
+}
+
 // MARK: - Tests
 
 #[cfg(test)]
@@ -445,10 +930,136 @@ mod tests {
         assert_eq!(classify_extension("JPEG"), Some(MediaType::Photo));
         assert_eq!(classify_extension("mp4"), Some(MediaType::Video));
         assert_eq!(classify_extension("MOV"), Some(MediaType::Video));
+        assert_eq!(classify_extension("avif"), Some(MediaType::Photo));
+        assert_eq!(classify_extension("JXL"), Some(MediaType::Photo));
         assert_eq!(classify_extension("json"), None);
         assert_eq!(classify_extension("txt"), None);
     }
 
+    #[test]
+    fn test_takeout_bookkeeping_files_not_unknown() {
+        let dir = setup_test_dir();
+        let base = dir.path();
+        fs::write(base.join("archive_browser.html"), b"<html></html>").unwrap();
+        fs::write(base.join("README.txt"), b"readme").unwrap();
+        fs::write(base.join("notes.txt"), b"not google's").unwrap();
+
+        let inventory = scan_directory(base, &ScanOptions::default()).unwrap();
+        assert_eq!(inventory.stats.unknown_extensions, 1);
+        assert!(inventory.stats.unknown_examples[0].ends_with("notes.txt"));
+    }
+
+    #[test]
+    fn test_detect_content_root() {
+        let photos_only = detect_content_root([
+            "Takeout/archive_browser.html",
+            "Takeout/Google Photos/Photos from 2020/a.jpg",
+        ]);
+        assert_eq!(photos_only.prefix, "Takeout/");
+        assert!(!photos_only.other_products);
+
+        let split = detect_content_root([
+            "Takeout/archive_browser.html",
+            "Takeout/Drive/notes.jpg",
+            "Takeout/Google Fotos/Photos from 2020/a.jpg",
+        ]);
+        assert_eq!(split.prefix, "Takeout/Google Fotos/");
+        assert!(split.other_products);
+
+        // Drive alone: nothing to treat as a Photos root
+        let drive_only = detect_content_root(["Takeout/Drive/a.jpg", "Takeout/Mail/x.mbox"]);
+        assert_eq!(drive_only.prefix, "Takeout/");
+
+        assert_eq!(detect_content_root(["Photos from 2020/a.jpg"]).prefix, "");
+    }
+
+    #[test]
+    fn test_split_export_extracts_photos_root() {
+        let dir = setup_test_dir();
+        let zip_path = dir.path().join("takeout-split.zip");
+        {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, data) in [
+                ("Takeout/Drive/scan.jpg", "drive"),
+                ("Takeout/Google Photos/Photos from 2020/a.jpg", "jpg"),
+                ("Takeout/Google Photos/Trip/b.jpg", "jpg"),
+                (
+                    "Takeout/Google Photos/Trip/metadata.json",
+                    r#"{ "albumData": { "title": "Trip" } }"#,
+                ),
+            ] {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(data.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let dest = dir.path().join("out");
+        let root = extract_zip(&zip_path, &dest).unwrap();
+        assert_eq!(root, dest.join("Takeout/Google Photos"));
+
+        let inventory = scan_directory(&root, &ScanOptions::default()).unwrap();
+        let mut relative: Vec<PathBuf> = inventory
+            .files
+            .iter()
+            .map(|f| f.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        relative.sort();
+        assert_eq!(
+            relative,
+            vec![
+                PathBuf::from("Photos from 2020/a.jpg"),
+                PathBuf::from("Trip/b.jpg"),
+            ]
+        );
+        assert_eq!(inventory.albums, vec!["Trip"]);
+    }
+
+    #[test]
+    fn test_is_motion_photo() {
+        let dir = setup_test_dir();
+        let base = dir.path();
+        let pixel = base.join("PXL_20230101_000000000.MP.jpg");
+        let mut bytes = b"\xFF\xD8\xFF\xE1<x:xmpmeta GCamera:MotionPhoto=\"1\" />".to_vec();
+        bytes.extend_from_slice(b"\0\0\0\x18ftypmp42");
+        fs::write(&pixel, bytes).unwrap();
+        let mvimg = base.join("MVIMG_0001.jpg");
+        fs::write(&mvimg, b"<GCamera:MicroVideo>1</GCamera:MicroVideo>").unwrap();
+        let still = base.join("IMG_0001.jpg");
+        fs::write(&still, b"\xFF\xD8 GCamera:MotionPhoto=\"0\"").unwrap();
+
+        assert!(is_motion_photo(&pixel));
+        assert!(is_motion_photo(&mvimg));
+        assert!(!is_motion_photo(&still));
+        assert!(!is_motion_photo(&base.join("missing.jpg")));
+    }
+
+    #[test]
+    fn test_sanitize_album_title() {
+        assert_eq!(sanitize_album_title("2020/2021 Trip"), "2020-2021 Trip");
+        assert_eq!(sanitize_album_title("C:\\Photos"), "C:-Photos");
+        assert_eq!(sanitize_album_title("Summer\nin\tRome"), "Summer in Rome");
+        assert_eq!(sanitize_album_title("  Beach   day  "), "Beach day");
+        assert_eq!(sanitize_album_title("Rome"), "Rome");
+        assert_eq!(sanitize_album_title(" \n "), "");
+    }
+
+    #[test]
+    fn test_detect_album_sanitizes_title() {
+        let dir = setup_test_dir();
+        let metadata = dir.path().join("metadata.json");
+        fs::write(&metadata, r#"{"albumData": {"title": " 2020/2021\nTrip "}}"#).unwrap();
+        assert_eq!(
+            detect_album_with_original(dir.path(), std::slice::from_ref(&metadata)),
+            Some(("2020-2021 Trip".to_string(), " 2020/2021\nTrip ".to_string()))
+        );
+
+        fs::write(&metadata, r#"{"albumData": {"title": "\t"}}"#).unwrap();
+        assert_eq!(detect_album(dir.path(), &[metadata]), None);
+    }
+
     #[test]
     fn test_is_year_folder() {
         assert!(is_year_folder(Path::new("/tmp/Takeout/Photos from 2024")));
@@ -479,6 +1090,153 @@ mod tests {
         assert!(first_name.contains("takeout"));
     }
 
+    /// One tar entry: header block (a `typeflag` entry named `name`) plus
+    /// padded data.
+    fn tar_entry(name: &str, typeflag: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        let mut out = header.to_vec();
+        out.extend_from_slice(data);
+        out.resize(out.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        out
+    }
+
+    #[test]
+    fn test_tar_pax_size_outranks_header() {
+        // Writers leave the header size at 0 when it won't fit in 8 GiB
+        let record = "size=600\n";
+        let pax = format!("{} {record}", record.len() + 3);
+        let mut tar = Vec::new();
+        tar.extend(tar_entry("PaxHeaders/big.mp4", b'x', pax.as_bytes()));
+        tar.extend(tar_entry("big.mp4", b'0', b""));
+        tar.extend([7u8; 600]);
+        tar.resize(tar.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        tar.extend(tar_entry("next.jpg", b'0', b"jpeg"));
+        tar.extend([0u8; TAR_BLOCK * 2]);
+
+        let mut seen = Vec::new();
+        for_each_tar_file(tar.as_slice(), |name, size, data| {
+            let mut bytes = Vec::new();
+            data.read_to_end(&mut bytes)?;
+            seen.push((name.to_string(), size, bytes));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            seen,
+            vec![
+                ("big.mp4".to_string(), 600, vec![7u8; 600]),
+                ("next.jpg".to_string(), 4, b"jpeg".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repack_tarball_as_zip() {
+        use std::io::Write;
+
+        let dir = setup_test_dir();
+        let long = format!("Takeout/Google Photos/{}/IMG_0001.jpg", "a".repeat(90));
+        let record = format!("path={long}\n");
+        // A pax length counts its own digits
+        let pax = format!("{} {record}", record.len() + 4);
+        let mut tar = Vec::new();
+        tar.extend(tar_entry("Takeout/", b'5', b""));
+        tar.extend(tar_entry("Takeout/a.json", b'0', b"{}"));
+        tar.extend(tar_entry("PaxHeaders/x", b'x', pax.as_bytes()));
+        tar.extend(tar_entry("ignored-short-name", b'0', &[7u8; 600]));
+        tar.extend([0u8; TAR_BLOCK * 2]);
+        let tgz = dir.path().join("takeout-001.tgz");
+        let mut gz = flate2::write::GzEncoder::new(
+            fs::File::create(&tgz).unwrap(),
+            flate2::Compression::fast(),
+        );
+        gz.write_all(&tar).unwrap();
+        gz.finish().unwrap();
+
+        assert_eq!(find_takeout_zips(dir.path()).unwrap(), vec![tgz.clone()]);
+        let zip_path = repack_tarball_as_zip(&tgz).unwrap();
+        assert_eq!(zip_path, dir.path().join("takeout-001.zip"));
+        // The repacked zip now stands in for the tarball
+        assert_eq!(
+            find_takeout_zips(dir.path()).unwrap(),
+            vec![zip_path.clone()]
+        );
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 2);
+        let mut media = Vec::new();
+        archive
+            .by_name(&long)
+            .unwrap()
+            .read_to_end(&mut media)
+            .unwrap();
+        assert_eq!(media, vec![7u8; 600]);
+        assert_eq!(
+            tarball_zip_path(Path::new("/x/Takeout-002.TAR.GZ")),
+            Path::new("/x/Takeout-002.zip")
+        );
+    }
+
+    /// A GIF with `frames` 1x1 images.
+    fn gif_with_frames(frames: usize) -> Vec<u8> {
+        let mut gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00".to_vec();
+        gif.extend([0, 0, 0, 255, 255, 255]); // global color table
+        for _ in 0..frames {
+            // Graphic control extension (100 ms), then the image
+            gif.extend([0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+            gif.extend([0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+            gif.extend([2, 2, 0x44, 0x01, 0]);
+        }
+        gif.push(0x3B);
+        gif
+    }
+
+    #[test]
+    fn test_is_standard_takeout() {
+        let root = |prefix: &str, other_products| ContentRoot {
+            prefix: prefix.to_string(),
+            other_products,
+        };
+        let dirs = ["Google Photos/Photos from 2020", "Google Photos/Trip"];
+        assert!(is_standard_takeout(&root("Takeout/", false), dirs));
+        assert!(is_standard_takeout(
+            &root("Takeout/Google Photos/", true),
+            ["Photos from 2020"]
+        ));
+        // Unwrapped, or with stray folders beside the Photos folder
+        assert!(!is_standard_takeout(&root("", false), dirs));
+        assert!(!is_standard_takeout(
+            &root("Takeout/", false),
+            ["Google Photos/Trip", "Misc"]
+        ));
+    }
+
+    #[test]
+    fn test_is_animated_gif() {
+        assert_eq!(gif_frame_count(&gif_with_frames(1)), 1);
+        assert_eq!(gif_frame_count(&gif_with_frames(3)), 3);
+        assert_eq!(gif_frame_count(b"\xFF\xD8\xFF"), 0);
+        let truncated = gif_with_frames(2);
+        assert_eq!(gif_frame_count(&truncated[..truncated.len() - 10]), 2);
+
+        let dir = setup_test_dir();
+        let animated = dir.path().join("anim.GIF");
+        fs::write(&animated, gif_with_frames(2)).unwrap();
+        let still = dir.path().join("still.gif");
+        fs::write(&still, gif_with_frames(1)).unwrap();
+        assert!(is_animated_gif(&animated));
+        assert!(!is_animated_gif(&still));
+    }
+
     #[test]
     fn test_find_takeout_zips_empty_dir() {
         let dir = setup_test_dir();
@@ -511,6 +1269,42 @@ mod tests {
         assert!(pairs.is_empty());
     }
 
+    #[test]
+    fn burst_sequences_group_frames_cover_first() {
+        let files: Vec<PathBuf> = [
+            "IMG_20190101_120000_BURST001.jpg",
+            "IMG_20190101_120000_BURST002_COVER.jpg",
+            "IMG_20190101_120000_BURST003.jpg",
+            "00000IMG_00000_BURST20200202080000.jpg",
+            "00001IMG_00001_BURST20200202080000.jpg",
+            "IMG_20190101_130000_BURST001.jpg",
+            "IMG_5678.jpg",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let bursts = detect_burst_sequences(&files);
+        // The lone 13:00 frame isn't a sequence
+        assert_eq!(bursts.len(), 2);
+        let pixel: Vec<&str> = bursts["IMG_20190101_120000"]
+            .iter()
+            .map(|p| p.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            pixel,
+            vec![
+                "IMG_20190101_120000_BURST002_COVER.jpg",
+                "IMG_20190101_120000_BURST001.jpg",
+                "IMG_20190101_120000_BURST003.jpg",
+            ]
+        );
+        assert_eq!(bursts["20200202080000"].len(), 2);
+        assert_eq!(
+            bursts["20200202080000"][0],
+            &PathBuf::from("00000IMG_00000_BURST20200202080000.jpg")
+        );
+    }
+
     #[test]
     fn test_scan_mock_takeout() {
         let dir = setup_test_dir();
@@ -1,6 +1,8 @@
 import Foundation
 import Photos
 import CoreLocation
+import AVFoundation
+import ImageIO
 import SwiftRs
 
 // MARK: - Data Types
@@ -39,6 +41,16 @@ private func authStatusString(_ status: PHAuthorizationStatus) -> String {
     }
 }
 
+/// The identifier a just-created asset keeps once its change block has
+/// committed. Usually the placeholder's, but some macOS versions persist a
+/// different form; fetching by the placeholder returns the asset under the
+/// one that lasts. Falls back to the placeholder's (the Rust self-check
+/// reports it) if the fetch finds nothing.
+private func stableIdentifier(_ placeholderID: String) -> String {
+    let assets = PHAsset.fetchAssets(withLocalIdentifiers: [placeholderID], options: nil)
+    return assets.firstObject?.localIdentifier ?? placeholderID
+}
+
 private func toJSON<T: Encodable>(_ value: T) -> String {
     let encoder = JSONEncoder()
     guard let data = try? encoder.encode(value),
@@ -48,6 +60,43 @@ private func toJSON<T: Encodable>(_ value: T) -> String {
     return str
 }
 
+/// Set the sidecar's creation date, location and favorite flag on a new
+/// asset. Called from each import's change block; `PHAssetCreationRequest`
+/// is a `PHAssetChangeRequest` too.
+private func apply(_ meta: PhotoMetadata, to req: PHAssetChangeRequest) {
+    if let dateStr = meta.creationDate {
+        let formatter = ISO8601DateFormatter()
+        formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
+        if let date = formatter.date(from: dateStr) {
+            req.creationDate = date
+        } else {
+            formatter.formatOptions = [.withInternetDateTime]
+            if let date = formatter.date(from: dateStr) {
+                req.creationDate = date
+            }
+        }
+    }
+
+    if let lat = meta.latitude, let lon = meta.longitude,
+       !(lat == 0.0 && lon == 0.0) {
+        if let alt = meta.altitude {
+            req.location = CLLocation(
+                coordinate: CLLocationCoordinate2D(latitude: lat, longitude: lon),
+                altitude: alt,
+                horizontalAccuracy: 0,
+                verticalAccuracy: 0,
+                timestamp: Date()
+            )
+        } else {
+            req.location = CLLocation(latitude: lat, longitude: lon)
+        }
+    }
+
+    if let favorite = meta.isFavorite {
+        req.isFavorite = favorite
+    }
+}
+
 // MARK: - Check Access
 
 @_cdecl("photoferry_check_access")
@@ -126,42 +175,74 @@ public func importPhoto(path: SRString, metadataJSON: SRString, isVideo: Bool) -
             creationRequest = req
         }
 
-        // Apply metadata
         if let meta = metadata {
-            if let dateStr = meta.creationDate {
-                let formatter = ISO8601DateFormatter()
-                formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
-                if let date = formatter.date(from: dateStr) {
-                    creationRequest.creationDate = date
-                } else {
-                    formatter.formatOptions = [.withInternetDateTime]
-                    if let date = formatter.date(from: dateStr) {
-                        creationRequest.creationDate = date
-                    }
-                }
-            }
+            apply(meta, to: creationRequest)
+        }
 
-            if let lat = meta.latitude, let lon = meta.longitude,
-               !(lat == 0.0 && lon == 0.0) {
-                if let alt = meta.altitude {
-                    creationRequest.location = CLLocation(
-                        coordinate: CLLocationCoordinate2D(latitude: lat, longitude: lon),
-                        altitude: alt,
-                        horizontalAccuracy: 0,
-                        verticalAccuracy: 0,
-                        timestamp: Date()
-                    )
-                } else {
-                    creationRequest.location = CLLocation(latitude: lat, longitude: lon)
-                }
-            }
+        localIdentifier = creationRequest.placeholderForCreatedAsset?.localIdentifier
+    }) { success, error in
+        if !success {
+            importError = error?.localizedDescription ?? "Unknown PhotoKit error"
+        }
+        semaphore.signal()
+    }
 
-            if let favorite = meta.isFavorite {
-                creationRequest.isFavorite = favorite
-            }
+    semaphore.wait()
+
+    if let err = importError {
+        let result = ImportResult(success: false, localIdentifier: nil, error: err)
+        return SRString(toJSON(result))
+    }
+
+    let result = ImportResult(
+        success: true,
+        localIdentifier: localIdentifier.map(stableIdentifier),
+        error: nil
+    )
+    return SRString(toJSON(result))
+}
+
+/// `--preserve-depth`: add the file as the asset's original photo resource
+/// instead of going through `creationRequestForAssetFromImage`, so Photos
+/// stores it byte for byte — depth maps and portrait matte auxiliary images
+/// included, keeping Portrait effects editable. Live Photos already import
+/// this way.
+@_cdecl("photoferry_import_photo_resource")
+public func importPhotoResource(path: SRString, metadataJSON: SRString) -> SRString {
+    let filePath = path.toString()
+    let fileURL = URL(fileURLWithPath: filePath)
+
+    guard FileManager.default.fileExists(atPath: filePath) else {
+        let result = ImportResult(
+            success: false,
+            localIdentifier: nil,
+            error: "File not found: \(filePath)"
+        )
+        return SRString(toJSON(result))
+    }
+
+    // Parse metadata
+    var metadata: PhotoMetadata? = nil
+    let metaStr = metadataJSON.toString()
+    if !metaStr.isEmpty, let data = metaStr.data(using: .utf8) {
+        metadata = try? JSONDecoder().decode(PhotoMetadata.self, from: data)
+    }
+
+    let semaphore = DispatchSemaphore(value: 0)
+    var localIdentifier: String? = nil
+    var importError: String? = nil
+
+    PHPhotoLibrary.shared().performChanges({
+        let req = PHAssetCreationRequest.forAsset()
+        let options = PHAssetResourceCreationOptions()
+        options.originalFilename = fileURL.lastPathComponent
+        req.addResource(with: .photo, fileURL: fileURL, options: options)
+
+        if let meta = metadata {
+            apply(meta, to: req)
         }
 
-        localIdentifier = creationRequest.placeholderForCreatedAsset?.localIdentifier
+        localIdentifier = req.placeholderForCreatedAsset?.localIdentifier
     }) { success, error in
         if !success {
             importError = error?.localizedDescription ?? "Unknown PhotoKit error"
@@ -176,7 +257,11 @@ public func importPhoto(path: SRString, metadataJSON: SRString, isVideo: Bool) -
         return SRString(toJSON(result))
     }
 
-    let result = ImportResult(success: true, localIdentifier: localIdentifier, error: nil)
+    let result = ImportResult(
+        success: true,
+        localIdentifier: localIdentifier.map(stableIdentifier),
+        error: nil
+    )
     return SRString(toJSON(result))
 }
 
@@ -221,39 +306,8 @@ public func importLivePhoto(photoPath: SRString, videoPath: SRString, metadataJS
         req.addResource(with: .photo, fileURL: photoURL, options: nil)
         req.addResource(with: .pairedVideo, fileURL: videoURL, options: nil)
 
-        // Apply metadata
         if let meta = metadata {
-            if let dateStr = meta.creationDate {
-                let formatter = ISO8601DateFormatter()
-                formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
-                if let date = formatter.date(from: dateStr) {
-                    req.creationDate = date
-                } else {
-                    formatter.formatOptions = [.withInternetDateTime]
-                    if let date = formatter.date(from: dateStr) {
-                        req.creationDate = date
-                    }
-                }
-            }
-
-            if let lat = meta.latitude, let lon = meta.longitude,
-               !(lat == 0.0 && lon == 0.0) {
-                if let alt = meta.altitude {
-                    req.location = CLLocation(
-                        coordinate: CLLocationCoordinate2D(latitude: lat, longitude: lon),
-                        altitude: alt,
-                        horizontalAccuracy: 0,
-                        verticalAccuracy: 0,
-                        timestamp: Date()
-                    )
-                } else {
-                    req.location = CLLocation(latitude: lat, longitude: lon)
-                }
-            }
-
-            if let favorite = meta.isFavorite {
-                req.isFavorite = favorite
-            }
+            apply(meta, to: req)
         }
 
         localIdentifier = req.placeholderForCreatedAsset?.localIdentifier
@@ -271,10 +325,291 @@ public func importLivePhoto(photoPath: SRString, videoPath: SRString, metadataJS
         return SRString(toJSON(result))
     }
 
-    let result = ImportResult(success: true, localIdentifier: localIdentifier, error: nil)
+    let result = ImportResult(
+        success: true,
+        localIdentifier: localIdentifier.map(stableIdentifier),
+        error: nil
+    )
     return SRString(toJSON(result))
 }
 
+// MARK: - Import Motion Photo
+
+/// Major brands of the MP4/QuickTime a motion photo appends after its image.
+private let motionVideoBrands: Set<String> = [
+    "mp41", "mp42", "isom", "iso2", "iso4", "iso5", "iso6", "avc1", "qt  ", "M4V ",
+]
+
+/// Byte offset of the appended video: the first `ftyp` box with a video brand
+/// after the image's own header (HEIC starts with an `ftyp` at byte 4).
+private func embeddedVideoOffset(_ data: Data) -> Int? {
+    let ftyp = Data("ftyp".utf8)
+    var searchStart = data.startIndex + 8
+    while searchStart < data.endIndex,
+          let range = data.range(of: ftyp, in: searchStart..<data.endIndex) {
+        let brandEnd = min(range.upperBound + 4, data.endIndex)
+        let brand = String(decoding: data.subdata(in: range.upperBound..<brandEnd), as: UTF8.self)
+        if motionVideoBrands.contains(brand) {
+            return range.lowerBound - 4 - data.startIndex
+        }
+        searchStart = range.upperBound
+    }
+    return nil
+}
+
+/// Copy the still with the Live Photo content identifier in its Apple maker note.
+private func writeLivePhotoStill(_ stillData: Data, to url: URL, identifier: String) -> Bool {
+    guard let source = CGImageSourceCreateWithData(stillData as CFData, nil),
+          let type = CGImageSourceGetType(source),
+          let dest = CGImageDestinationCreateWithURL(url as CFURL, type, 1, nil)
+    else { return false }
+
+    var properties = CGImageSourceCopyPropertiesAtIndex(source, 0, nil) as? [String: Any] ?? [:]
+    var makerApple = properties[kCGImagePropertyMakerAppleDictionary as String] as? [String: Any] ?? [:]
+    makerApple["17"] = identifier
+    properties[kCGImagePropertyMakerAppleDictionary as String] = makerApple
+    CGImageDestinationAddImageFromSource(dest, source, 0, properties as CFDictionary)
+    return CGImageDestinationFinalize(dest)
+}
+
+/// Re-encode the video as a QuickTime movie carrying the same content identifier
+/// and a still-image-time marker, which Photos needs to pair it. Audio is dropped.
+private func writeLivePhotoVideo(from source: URL, to dest: URL, identifier: String) -> Bool {
+    let asset = AVURLAsset(url: source)
+    guard let videoTrack = asset.tracks(withMediaType: .video).first,
+          let reader = try? AVAssetReader(asset: asset),
+          let writer = try? AVAssetWriter(outputURL: dest, fileType: .mov)
+    else { return false }
+
+    let readerOutput = AVAssetReaderTrackOutput(
+        track: videoTrack,
+        outputSettings: [kCVPixelBufferPixelFormatTypeKey as String: kCVPixelFormatType_32BGRA]
+    )
+    reader.add(readerOutput)
+
+    let videoInput = AVAssetWriterInput(mediaType: .video, outputSettings: [
+        AVVideoCodecKey: AVVideoCodecType.h264,
+        AVVideoWidthKey: videoTrack.naturalSize.width,
+        AVVideoHeightKey: videoTrack.naturalSize.height,
+    ])
+    videoInput.transform = videoTrack.preferredTransform
+    videoInput.expectsMediaDataInRealTime = false
+    writer.add(videoInput)
+
+    let identifierItem = AVMutableMetadataItem()
+    identifierItem.keySpace = .quickTimeMetadata
+    identifierItem.key = "com.apple.quicktime.content.identifier" as NSString
+    identifierItem.value = identifier as NSString
+    identifierItem.dataType = "com.apple.metadata.datatype.UTF-8"
+    writer.metadata = [identifierItem]
+
+    let spec: [String: Any] = [
+        kCMMetadataFormatDescriptionMetadataSpecificationKey_Identifier as String:
+            "mdta/com.apple.quicktime.still-image-time",
+        kCMMetadataFormatDescriptionMetadataSpecificationKey_DataType as String:
+            "com.apple.metadata.datatype.int8",
+    ]
+    var formatDescription: CMFormatDescription?
+    CMMetadataFormatDescriptionCreateWithMetadataSpecifications(
+        allocator: kCFAllocatorDefault,
+        metadataType: kCMMetadataFormatType_Boxed,
+        metadataSpecifications: [spec] as CFArray,
+        formatDescriptionOut: &formatDescription
+    )
+    let metadataInput = AVAssetWriterInput(
+        mediaType: .metadata, outputSettings: nil, sourceFormatHint: formatDescription
+    )
+    let metadataAdaptor = AVAssetWriterInputMetadataAdaptor(assetWriterInput: metadataInput)
+    writer.add(metadataInput)
+
+    guard writer.startWriting(), reader.startReading() else { return false }
+    writer.startSession(atSourceTime: .zero)
+
+    let stillTimeItem = AVMutableMetadataItem()
+    stillTimeItem.keySpace = .quickTimeMetadata
+    stillTimeItem.key = "com.apple.quicktime.still-image-time" as NSString
+    stillTimeItem.value = 0 as NSNumber
+    stillTimeItem.dataType = "com.apple.metadata.datatype.int8"
+    metadataAdaptor.append(AVTimedMetadataGroup(
+        items: [stillTimeItem],
+        timeRange: CMTimeRange(start: .zero, duration: CMTime(value: 1, timescale: 100))
+    ))
+    metadataInput.markAsFinished()
+
+    let semaphore = DispatchSemaphore(value: 0)
+    videoInput.requestMediaDataWhenReady(on: DispatchQueue(label: "photoferry.motion-photo")) {
+        while videoInput.isReadyForMoreMediaData {
+            if let buffer = readerOutput.copyNextSampleBuffer() {
+                videoInput.append(buffer)
+            } else {
+                videoInput.markAsFinished()
+                writer.finishWriting { semaphore.signal() }
+                break
+            }
+        }
+    }
+    semaphore.wait()
+    return writer.status == .completed && reader.status == .completed
+}
+
+/// Google/Samsung motion photos are one JPEG/HEIC with an MP4 appended. Photos
+/// only pairs a still and video that share a content identifier, so split the
+/// file, stamp a fresh identifier into both halves, and import them as a pair.
+@_cdecl("photoferry_import_motion_photo")
+public func importMotionPhoto(path: SRString, metadataJSON: SRString) -> SRString {
+    let filePath = path.toString()
+    let fileURL = URL(fileURLWithPath: filePath)
+
+    guard let data = FileManager.default.contents(atPath: filePath) else {
+        let result = ImportResult(
+            success: false,
+            localIdentifier: nil,
+            error: "File not found: \(filePath)"
+        )
+        return SRString(toJSON(result))
+    }
+    guard let videoOffset = embeddedVideoOffset(data) else {
+        let result = ImportResult(
+            success: false,
+            localIdentifier: nil,
+            error: "No embedded video found in \(filePath)"
+        )
+        return SRString(toJSON(result))
+    }
+
+    let workDir = fileURL.deletingLastPathComponent()
+        .appendingPathComponent(".motion-\(UUID().uuidString)")
+    defer { try? FileManager.default.removeItem(at: workDir) }
+    // Keep the original file name: Photos records it as the asset's filename
+    let stillURL = workDir.appendingPathComponent(fileURL.lastPathComponent)
+    let rawVideoURL = workDir.appendingPathComponent("embedded.mp4")
+    let videoURL = workDir.appendingPathComponent(
+        fileURL.deletingPathExtension().lastPathComponent + ".MOV"
+    )
+    let identifier = UUID().uuidString
+
+    do {
+        try FileManager.default.createDirectory(at: workDir, withIntermediateDirectories: true)
+        try data.subdata(in: (data.startIndex + videoOffset)..<data.endIndex).write(to: rawVideoURL)
+    } catch {
+        let result = ImportResult(success: false, localIdentifier: nil, error: error.localizedDescription)
+        return SRString(toJSON(result))
+    }
+
+    let stillData = data.subdata(in: data.startIndex..<(data.startIndex + videoOffset))
+    guard writeLivePhotoStill(stillData, to: stillURL, identifier: identifier) else {
+        let result = ImportResult(
+            success: false,
+            localIdentifier: nil,
+            error: "Failed to write motion photo still for \(filePath)"
+        )
+        return SRString(toJSON(result))
+    }
+    guard writeLivePhotoVideo(from: rawVideoURL, to: videoURL, identifier: identifier) else {
+        let result = ImportResult(
+            success: false,
+            localIdentifier: nil,
+            error: "Failed to convert motion photo video for \(filePath)"
+        )
+        return SRString(toJSON(result))
+    }
+
+    return importLivePhoto(
+        photoPath: SRString(stillURL.path),
+        videoPath: SRString(videoURL.path),
+        metadataJSON: metadataJSON
+    )
+}
+
+// MARK: - Animated GIF to Video
+
+/// Seconds a GIF frame is shown. Browsers treat delays under 20 ms as 100 ms;
+/// do the same so "fast" GIFs don't collapse to a flash.
+private func gifFrameDelay(_ source: CGImageSource, _ index: Int) -> Double {
+    let properties = CGImageSourceCopyPropertiesAtIndex(source, index, nil) as? [String: Any]
+    let gif = properties?[kCGImagePropertyGIFDictionary as String] as? [String: Any]
+    let delay = (gif?[kCGImagePropertyGIFUnclampedDelayTime as String] as? Double)
+        ?? (gif?[kCGImagePropertyGIFDelayTime as String] as? Double)
+        ?? 0.1
+    return delay < 0.02 ? 0.1 : delay
+}
+
+/// `--gif-policy video`: render every frame of an animated GIF, with its
+/// own delay, into an H.264 QuickTime movie Photos plays as a video.
+@_cdecl("photoferry_gif_to_video")
+public func gifToVideo(gifPath: SRString, moviePath: SRString) -> Bool {
+    let sourceURL = URL(fileURLWithPath: gifPath.toString())
+    let destURL = URL(fileURLWithPath: moviePath.toString())
+    try? FileManager.default.removeItem(at: destURL)
+
+    guard let source = CGImageSourceCreateWithURL(sourceURL as CFURL, nil),
+          CGImageSourceGetCount(source) > 1,
+          let first = CGImageSourceCreateImageAtIndex(source, 0, nil),
+          let writer = try? AVAssetWriter(outputURL: destURL, fileType: .mov)
+    else { return false }
+
+    // H.264 needs even dimensions
+    let width = max(2, first.width & ~1)
+    let height = max(2, first.height & ~1)
+    let input = AVAssetWriterInput(mediaType: .video, outputSettings: [
+        AVVideoCodecKey: AVVideoCodecType.h264,
+        AVVideoWidthKey: width,
+        AVVideoHeightKey: height,
+    ])
+    input.expectsMediaDataInRealTime = false
+    let adaptor = AVAssetWriterInputPixelBufferAdaptor(
+        assetWriterInput: input,
+        sourcePixelBufferAttributes: [
+            kCVPixelBufferPixelFormatTypeKey as String: kCVPixelFormatType_32ARGB,
+            kCVPixelBufferWidthKey as String: width,
+            kCVPixelBufferHeightKey as String: height,
+        ]
+    )
+    writer.add(input)
+    guard writer.startWriting() else { return false }
+    writer.startSession(atSourceTime: .zero)
+
+    let timescale: CMTimeScale = 600
+    var time = CMTime.zero
+    for index in 0..<CGImageSourceGetCount(source) {
+        guard let image = CGImageSourceCreateImageAtIndex(source, index, nil),
+              let pool = adaptor.pixelBufferPool
+        else { return false }
+        var buffer: CVPixelBuffer?
+        CVPixelBufferPoolCreatePixelBuffer(nil, pool, &buffer)
+        guard let pixelBuffer = buffer else { return false }
+
+        CVPixelBufferLockBaseAddress(pixelBuffer, [])
+        let context = CGContext(
+            data: CVPixelBufferGetBaseAddress(pixelBuffer),
+            width: width,
+            height: height,
+            bitsPerComponent: 8,
+            bytesPerRow: CVPixelBufferGetBytesPerRow(pixelBuffer),
+            space: CGColorSpaceCreateDeviceRGB(),
+            bitmapInfo: CGImageAlphaInfo.premultipliedFirst.rawValue
+        )
+        // Transparent GIF pixels become white rather than black
+        context?.setFillColor(CGColor(red: 1, green: 1, blue: 1, alpha: 1))
+        context?.fill(CGRect(x: 0, y: 0, width: width, height: height))
+        context?.draw(image, in: CGRect(x: 0, y: 0, width: width, height: height))
+        CVPixelBufferUnlockBaseAddress(pixelBuffer, [])
+
+        while !input.isReadyForMoreMediaData {
+            Thread.sleep(forTimeInterval: 0.01)
+        }
+        guard adaptor.append(pixelBuffer, withPresentationTime: time) else { return false }
+        time = time + CMTime(seconds: gifFrameDelay(source, index), preferredTimescale: timescale)
+    }
+
+    input.markAsFinished()
+    writer.endSession(atSourceTime: time)
+    let semaphore = DispatchSemaphore(value: 0)
+    writer.finishWriting { semaphore.signal() }
+    semaphore.wait()
+    return writer.status == .completed
+}
+
 // MARK: - Create Album
 
 @_cdecl("photoferry_create_album")
@@ -315,6 +650,26 @@ public func createAlbum(title: SRString) -> SRString {
     return SRString("{\"album_id\":\"\(albumIdentifier ?? "")\"}")
 }
 
+// MARK: - Album Exists
+
+struct AlbumLookup: Codable {
+    let exists: Bool
+    let title: String?
+}
+
+/// Look up a user album by local identifier (`--into-album-id`).
+@_cdecl("photoferry_album_exists")
+public func albumExists(albumId: SRString) -> SRString {
+    let albums = PHAssetCollection.fetchAssetCollections(
+        withLocalIdentifiers: [albumId.toString()],
+        options: nil
+    )
+    guard let album = albums.firstObject else {
+        return SRString(toJSON(AlbumLookup(exists: false, title: nil)))
+    }
+    return SRString(toJSON(AlbumLookup(exists: true, title: album.localizedTitle)))
+}
+
 // MARK: - Verify Assets
 
 struct AssetVerifyResult: Codable {
@@ -322,6 +677,8 @@ struct AssetVerifyResult: Codable {
     let found: Bool
     let creationDate: String?
     let hasPairedVideo: Bool
+    let hasStill: Bool
+    let inICloudOnly: Bool
 }
 
 @_cdecl("photoferry_verify_assets")
@@ -347,12 +704,25 @@ public func verifyAssets(identifiersJSON: SRString) -> SRString {
         let hasPaired = resources.contains {
             $0.type == .pairedVideo || $0.type == .fullSizePairedVideo
         }
+        let hasStill = resources.contains {
+            $0.type == .photo || $0.type == .fullSizePhoto
+        }
+        // With Optimize Storage the originals can live only in iCloud. The
+        // asset is still in the library; PhotoKit has no public flag for this,
+        // so read the resource's `locallyAvailable` key (absent = local).
+        let locallyAvailable = Selector(("locallyAvailable"))
+        let inICloudOnly = !resources.isEmpty && resources.allSatisfy {
+            $0.responds(to: locallyAvailable)
+                && ($0.value(forKey: "locallyAvailable") as? Bool) == false
+        }
         let dateStr = asset.creationDate.map { formatter.string(from: $0) }
         results.append(AssetVerifyResult(
             localIdentifier: asset.localIdentifier,
             found: true,
             creationDate: dateStr,
-            hasPairedVideo: hasPaired
+            hasPairedVideo: hasPaired,
+            hasStill: hasStill,
+            inICloudOnly: inICloudOnly
         ))
     }
 
@@ -362,13 +732,134 @@ public func verifyAssets(identifiersJSON: SRString) -> SRString {
             localIdentifier: id,
             found: false,
             creationDate: nil,
-            hasPairedVideo: false
+            hasPairedVideo: false,
+            hasStill: false,
+            inICloudOnly: false
         ))
     }
 
     return SRString(toJSON(results))
 }
 
+// MARK: - iCloud Upload Status
+
+struct ICloudUploadStatus: Codable {
+    /// False before macOS 12, where cloud identifiers don't exist
+    let available: Bool
+    let synced: Int
+    let pending: Int
+}
+
+/// Best-effort iCloud upload check: an asset gets a cloud identifier once
+/// iCloud Photos knows about it, so identifiers that don't map yet are still
+/// waiting to upload (or iCloud Photos is off). PhotoKit has no public
+/// upload-queue API.
+@_cdecl("photoferry_icloud_upload_status")
+public func iCloudUploadStatus(identifiersJSON: SRString) -> SRString {
+    let json = identifiersJSON.toString()
+    guard let data = json.data(using: .utf8),
+          let identifiers = try? JSONDecoder().decode([String].self, from: data)
+    else {
+        return SRString("{\"error\":\"invalid_input\"}")
+    }
+
+    guard #available(macOS 12, *) else {
+        return SRString(toJSON(ICloudUploadStatus(available: false, synced: 0, pending: 0)))
+    }
+
+    let mappings = PHPhotoLibrary.shared().cloudIdentifierMappings(forLocalIdentifiers: identifiers)
+    var synced = 0
+    for id in identifiers {
+        if case .success = mappings[id] {
+            synced += 1
+        }
+    }
+    let status = ICloudUploadStatus(
+        available: true,
+        synced: synced,
+        pending: identifiers.count - synced
+    )
+    return SRString(toJSON(status))
+}
+
+// MARK: - Find by Filename
+
+struct FilenameAsset: Codable {
+    let filename: String
+    let localIdentifier: String
+    let creationDate: String?
+}
+
+/// Every asset whose original filename is one of the requested names
+/// (case-insensitive). Walks the whole library, so batch the names.
+@_cdecl("photoferry_find_by_filename")
+public func findByFilename(filenamesJSON: SRString) -> SRString {
+    let json = filenamesJSON.toString()
+    guard let data = json.data(using: .utf8),
+          let filenames = try? JSONDecoder().decode([String].self, from: data)
+    else {
+        return SRString("{\"error\":\"invalid_input\"}")
+    }
+    let wanted = Set(filenames.map { $0.lowercased() })
+
+    let formatter = ISO8601DateFormatter()
+    formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
+
+    var results: [FilenameAsset] = []
+    let fetchResult = PHAsset.fetchAssets(with: nil)
+    fetchResult.enumerateObjects { asset, _, _ in
+        let resources = PHAssetResource.assetResources(for: asset)
+        guard let primary = resources.first(where: { $0.type == .photo || $0.type == .video }),
+              wanted.contains(primary.originalFilename.lowercased())
+        else { return }
+        results.append(FilenameAsset(
+            filename: primary.originalFilename,
+            localIdentifier: asset.localIdentifier,
+            creationDate: asset.creationDate.map { formatter.string(from: $0) }
+        ))
+    }
+
+    return SRString(toJSON(results))
+}
+
+// MARK: - Delete Assets
+
+/// Move assets to Recently Deleted in one change, so Photos asks for
+/// confirmation once. Identifiers no longer in the library are ignored.
+@_cdecl("photoferry_delete_assets")
+public func deleteAssets(identifiersJSON: SRString) -> SRString {
+    let json = identifiersJSON.toString()
+    guard let data = json.data(using: .utf8),
+          let identifiers = try? JSONDecoder().decode([String].self, from: data)
+    else {
+        return SRString("{\"error\":\"invalid_input\"}")
+    }
+
+    let assets = PHAsset.fetchAssets(withLocalIdentifiers: identifiers, options: nil)
+    if assets.count == 0 {
+        return SRString("{\"deleted\":0}")
+    }
+
+    let semaphore = DispatchSemaphore(value: 0)
+    var deleteError: String?
+
+    PHPhotoLibrary.shared().performChanges({
+        PHAssetChangeRequest.deleteAssets(assets)
+    }) { success, error in
+        if !success {
+            deleteError = error?.localizedDescription ?? "unknown"
+        }
+        semaphore.signal()
+    }
+
+    semaphore.wait()
+
+    if let err = deleteError {
+        return SRString("{\"error\":\"\(err)\"}")
+    }
+    return SRString("{\"deleted\":\(assets.count)}")
+}
+
 // MARK: - Add to Album
 
 @_cdecl("photoferry_add_to_album")
@@ -400,3 +891,122 @@ public func addToAlbum(albumID: SRString, assetID: SRString) -> Bool {
     semaphore.wait()
     return success
 }
+
+// MARK: - Album Key Photo
+
+/// PhotoKit has no key-photo setter; Photos shows the first asset of a manually
+/// ordered album, so move the asset to the front. Keeps the current front asset
+/// if it is already dated no later (albums span Takeout zips).
+@_cdecl("photoferry_set_album_key_photo")
+public func setAlbumKeyPhoto(albumID: SRString, assetID: SRString) -> Bool {
+    let albumIdStr = albumID.toString()
+    let assetIdStr = assetID.toString()
+
+    let albums = PHAssetCollection.fetchAssetCollections(
+        withLocalIdentifiers: [albumIdStr], options: nil
+    )
+    guard let album = albums.firstObject else { return false }
+
+    let assets = PHAsset.fetchAssets(
+        withLocalIdentifiers: [assetIdStr], options: nil
+    )
+    guard let asset = assets.firstObject else { return false }
+
+    let contents = PHAsset.fetchAssets(in: album, options: nil)
+    let index = contents.index(of: asset)
+    if index == NSNotFound { return false }
+    if index == 0 { return true }
+    if let front = contents.firstObject,
+       let frontDate = front.creationDate,
+       let assetDate = asset.creationDate,
+       frontDate <= assetDate {
+        return true
+    }
+
+    let semaphore = DispatchSemaphore(value: 0)
+    var success = false
+
+    PHPhotoLibrary.shared().performChanges({
+        guard let request = PHAssetCollectionChangeRequest(for: album, assets: contents) else { return }
+        request.moveAssets(at: IndexSet(integer: index), to: 0)
+    }) { result, _ in
+        success = result
+        semaphore.signal()
+    }
+
+    semaphore.wait()
+    return success
+}
+
+// MARK: - Time Zone Lookup
+
+/// IANA time zone at a coordinate, by reverse geocoding (needs network;
+/// Apple throttles bursts, so the caller caches). Empty string on failure.
+@_cdecl("photoferry_timezone_at")
+public func timezoneAt(latitude: Double, longitude: Double) -> SRString {
+    let location = CLLocation(latitude: latitude, longitude: longitude)
+    var identifier = ""
+    var done = false
+    let semaphore = DispatchSemaphore(value: 0)
+
+    CLGeocoder().reverseGeocodeLocation(location) { placemarks, _ in
+        identifier = placemarks?.first?.timeZone?.identifier ?? ""
+        done = true
+        semaphore.signal()
+    }
+
+    let deadline = Date(timeIntervalSinceNow: 30)
+    if Thread.isMainThread {
+        // The completion handler is delivered on the main queue: keep its run
+        // loop turning instead of blocking it
+        while !done && Date() < deadline {
+            RunLoop.current.run(mode: .default, before: Date(timeIntervalSinceNow: 0.05))
+        }
+    } else {
+        _ = semaphore.wait(timeout: .now() + 30)
+    }
+    return SRString(identifier)
+}
+
+/// Seconds east of UTC in a time zone at a Unix time (daylight saving
+/// included), or `Int.min` for an unknown zone.
+@_cdecl("photoferry_timezone_offset")
+public func timezoneOffset(identifier: SRString, epoch: Int) -> Int {
+    guard let zone = TimeZone(identifier: identifier.toString()) else { return Int.min }
+    return zone.secondsFromGMT(for: Date(timeIntervalSince1970: TimeInterval(epoch)))
+}
+
+// MARK: - Set Creation Date
+
+/// Change an existing asset's creation date in place (`fix-dates`), so a
+/// wrong date is corrected without importing a duplicate.
+@_cdecl("photoferry_set_creation_date")
+public func setCreationDate(assetID: SRString, date: SRString) -> Bool {
+    let dateStr = date.toString()
+    let formatter = ISO8601DateFormatter()
+    formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
+    var parsed = formatter.date(from: dateStr)
+    if parsed == nil {
+        formatter.formatOptions = [.withInternetDateTime]
+        parsed = formatter.date(from: dateStr)
+    }
+    guard let newDate = parsed else { return false }
+
+    let assets = PHAsset.fetchAssets(
+        withLocalIdentifiers: [assetID.toString()], options: nil
+    )
+    guard let asset = assets.firstObject else { return false }
+
+    let semaphore = DispatchSemaphore(value: 0)
+    var success = false
+
+    PHPhotoLibrary.shared().performChanges({
+        PHAssetChangeRequest(for: asset).creationDate = newDate
+    }) { result, _ in
+        success = result
+        semaphore.signal()
+    }
+
+    semaphore.wait()
+    return success
+}